@@ -1,9 +1,93 @@
 //! Microphone input capture using cpal
+//!
+//! Besides input devices, the default output device can be recorded in
+//! loopback mode so voices coming out of the speakers (e.g. a voice chat)
+//! reach detection too. cpal only supports this through WASAPI, so loopback
+//! is Windows-only; elsewhere starting it fails with `CaptureError::ConfigError`.
 
+use crate::dsp::processing;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
-use cpal::{Device, SampleFormat, Stream, StreamConfig};
+use cpal::{BufferSize, Device, SampleFormat, Stream, StreamConfig, SupportedBufferSize, SupportedStreamConfig};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
 use thiserror::Error;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
+
+/// Settings key holding the name of the chosen input device
+pub const INPUT_DEVICE_SETTING: &str = "input_device";
+
+/// Event emitted with the device name when an input device appears
+pub const DEVICE_CONNECTED_EVENT: &str = "device_connected";
+
+/// Event emitted with the device name when an input device goes away
+pub const DEVICE_DISCONNECTED_EVENT: &str = "device_disconnected";
+
+/// Event emitted with the new default input device (or null)
+pub const DEFAULT_DEVICE_CHANGED_EVENT: &str = "default_device_changed";
+
+/// How often the device list is polled (cpal has no change callbacks)
+const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What a session records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureSource {
+    /// The selected input device
+    #[default]
+    Microphone,
+    /// The default output device (system audio)
+    Loopback,
+    /// Both, kept in separate buffers so loopback audio stays out of speaker verification
+    Both,
+}
+
+impl CaptureSource {
+    /// Check if the input device is recorded
+    pub fn includes_microphone(&self) -> bool {
+        matches!(self, CaptureSource::Microphone | CaptureSource::Both)
+    }
+
+    /// Check if system audio is recorded
+    pub fn includes_loopback(&self) -> bool {
+        matches!(self, CaptureSource::Loopback | CaptureSource::Both)
+    }
+}
+
+/// How much buffering to ask the device for; smaller buffers let VAD react sooner
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LatencyMode {
+    /// 10 ms buffers
+    Low,
+    /// 20 ms buffers
+    #[default]
+    Balanced,
+    /// 50 ms buffers, for devices that glitch with smaller ones
+    Relaxed,
+}
+
+impl LatencyMode {
+    /// Buffer length in milliseconds
+    pub fn buffer_ms(&self) -> u32 {
+        match self {
+            LatencyMode::Low => 10,
+            LatencyMode::Balanced => 20,
+            LatencyMode::Relaxed => 50,
+        }
+    }
+
+    /// Buffer length in frames at `sample_rate`
+    pub fn buffer_frames(&self, sample_rate: u32) -> u32 {
+        (sample_rate * self.buffer_ms() / 1000).max(1)
+    }
+}
 
 #[derive(Error, Debug)]
 pub enum CaptureError {
@@ -15,6 +99,12 @@ pub enum CaptureError {
     StreamBuildError(String),
     #[error("Stream play error: {0}")]
     StreamPlayError(String),
+    #[error("Capture thread error: {0}")]
+    ThreadError(String),
+    #[error("Input device lost: {0}")]
+    DeviceLost(String),
+    #[error("Session recording error: {0}")]
+    RecordingError(String),
 }
 
 /// Audio capture state
@@ -23,6 +113,16 @@ pub struct AudioCapture {
     is_recording: bool,
     sample_rate: u32,
     channels: u16,
+    /// Average interleaved frames to mono before the callback sees them
+    downmix_to_mono: bool,
+    /// Where stream errors are reported (they are only logged otherwise)
+    error_tx: Option<flume::Sender<CaptureError>>,
+    /// Where the dB level of each incoming chunk is sent
+    peak_tx: Option<flume::Sender<f32>>,
+    /// Requested buffering (None leaves it to the device)
+    latency_mode: Option<LatencyMode>,
+    /// Buffer size the running stream was built with (None is the device default)
+    buffer_frames: Option<u32>,
 }
 
 impl AudioCapture {
@@ -33,9 +133,42 @@ impl AudioCapture {
             is_recording: false,
             sample_rate: 16000,
             channels: 1,
+            downmix_to_mono: false,
+            error_tx: None,
+            peak_tx: None,
+            latency_mode: None,
+            buffer_frames: None,
         }
     }
 
+    /// Ask for a fixed buffer size; devices that reject it use their default
+    ///
+    /// Takes effect on the next `start_recording`.
+    pub fn set_latency_mode(&mut self, mode: LatencyMode) {
+        self.latency_mode = Some(mode);
+    }
+
+    /// Send the dB level of every incoming chunk, for the input peak meter
+    ///
+    /// Takes effect on the next `start_recording`.
+    pub fn set_peak_sender(&mut self, tx: flume::Sender<f32>) {
+        self.peak_tx = Some(tx);
+    }
+
+    /// Report stream errors (e.g. an unplugged device) as `CaptureError::DeviceLost`
+    ///
+    /// Takes effect on the next `start_recording`.
+    pub fn set_error_sender(&mut self, tx: flume::Sender<CaptureError>) {
+        self.error_tx = Some(tx);
+    }
+
+    /// Deliver mono audio to the callback regardless of the device channel count
+    ///
+    /// Takes effect on the next `start_recording`.
+    pub fn set_downmix_to_mono(&mut self, downmix: bool) {
+        self.downmix_to_mono = downmix;
+    }
+
     /// Get the default input device
     fn get_default_input_device() -> Result<Device, CaptureError> {
         let host = cpal::default_host();
@@ -43,6 +176,44 @@ impl AudioCapture {
             .ok_or(CaptureError::NoInputDevice)
     }
 
+    /// Find an input device by name, falling back to the default device
+    fn find_input_device(device_name: &str) -> Result<Device, CaptureError> {
+        let host = cpal::default_host();
+        let found = host
+            .input_devices()
+            .map_err(|e| CaptureError::ConfigError(e.to_string()))?
+            .find(|device| device.name().map(|name| name == device_name).unwrap_or(false));
+
+        match found {
+            Some(device) => Ok(device),
+            None => {
+                warn!("Input device '{}' not found, using default", device_name);
+                Self::get_default_input_device()
+            }
+        }
+    }
+
+    /// Get the output device recorded in loopback mode
+    #[cfg(target_os = "windows")]
+    fn get_loopback_device() -> Result<Device, CaptureError> {
+        let host = cpal::default_host();
+        host.default_output_device()
+            .ok_or_else(|| CaptureError::ConfigError("No output device for loopback capture".to_string()))
+    }
+
+    /// Get the output device recorded in loopback mode
+    #[cfg(not(target_os = "windows"))]
+    fn get_loopback_device() -> Result<Device, CaptureError> {
+        Err(CaptureError::ConfigError(
+            "Loopback capture is only supported on Windows (WASAPI)".to_string(),
+        ))
+    }
+
+    /// Name of the output device loopback capture would record, if supported
+    pub fn loopback_device_name() -> Option<String> {
+        Self::get_loopback_device().ok().and_then(|device| device.name().ok())
+    }
+
     /// List all available input devices
     pub fn list_devices() -> Result<Vec<String>, CaptureError> {
         let host = cpal::default_host();
@@ -57,63 +228,85 @@ impl AudioCapture {
         Ok(devices)
     }
 
-    /// Start recording audio
-    pub fn start_recording<F>(&mut self, mut callback: F) -> Result<(), CaptureError>
+    /// Start recording audio from the default input device
+    pub fn start_recording<F>(&mut self, callback: F) -> Result<(), CaptureError>
     where
         F: FnMut(Vec<f32>) + Send + 'static,
     {
         let device = Self::get_default_input_device()?;
+        self.start_on_device(device, callback)
+    }
+
+    /// Start recording audio from the named input device
+    ///
+    /// Falls back to the default device if no device has that name.
+    pub fn start_recording_on<F>(&mut self, device_name: &str, callback: F) -> Result<(), CaptureError>
+    where
+        F: FnMut(Vec<f32>) + Send + 'static,
+    {
+        let device = Self::find_input_device(device_name)?;
+        self.start_on_device(device, callback)
+    }
+
+    /// Start recording what the default output device plays (Windows only)
+    pub fn start_loopback<F>(&mut self, callback: F) -> Result<(), CaptureError>
+    where
+        F: FnMut(Vec<f32>) + Send + 'static,
+    {
+        let device = Self::get_loopback_device()?;
+        info!("Using loopback device: {:?}", device.name());
+
+        // WASAPI opens an input stream on an output device in loopback mode
+        let config = device
+            .default_output_config()
+            .map_err(|e| CaptureError::ConfigError(e.to_string()))?;
+
+        self.start_stream(device, config, callback)
+    }
+
+    fn start_on_device<F>(&mut self, device: Device, callback: F) -> Result<(), CaptureError>
+    where
+        F: FnMut(Vec<f32>) + Send + 'static,
+    {
         info!("Using input device: {:?}", device.name());
 
         let config = device
             .default_input_config()
             .map_err(|e| CaptureError::ConfigError(e.to_string()))?;
 
+        self.start_stream(device, config, callback)
+    }
+
+    fn start_stream<F>(&mut self, device: Device, config: SupportedStreamConfig, callback: F) -> Result<(), CaptureError>
+    where
+        F: FnMut(Vec<f32>) + Send + 'static,
+    {
         debug!("Input config: {:?}", config);
 
         let sample_rate = config.sample_rate().0;
         let channels = config.channels();
+        let mut downmixed = downmixing(channels, self.downmix_to_mono, callback);
+        let peak_tx = self.peak_tx.clone();
+        // Shared so the stream can be rebuilt if the device rejects the buffer size
+        let callback = Arc::new(Mutex::new(move |samples: Vec<f32>| {
+            if let Some(tx) = &peak_tx {
+                let _ = tx.send(processing::calculate_db(&samples));
+            }
+            downmixed(samples);
+        }));
 
-        let err_fn = |err| error!("Audio stream error: {}", err);
-
-        let stream = match config.sample_format() {
-            SampleFormat::F32 => device.build_input_stream(
-                &config.into(),
-                move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                    callback(data.to_vec());
-                },
-                err_fn,
-                None,
-            ),
-            SampleFormat::I16 => device.build_input_stream(
-                &config.into(),
-                move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                    let float_data: Vec<f32> =
-                        data.iter().map(|&s| s as f32 / i16::MAX as f32).collect();
-                    callback(float_data);
-                },
-                err_fn,
-                None,
-            ),
-            SampleFormat::U16 => device.build_input_stream(
-                &config.into(),
-                move |data: &[u16], _: &cpal::InputCallbackInfo| {
-                    let float_data: Vec<f32> = data
-                        .iter()
-                        .map(|&s| (s as f32 / u16::MAX as f32) - 0.5)
-                        .collect();
-                    callback(float_data);
-                },
-                err_fn,
-                None,
-            ),
-            _ => {
-                return Err(CaptureError::StreamBuildError(
-                    "Unsupported sample format".to_string(),
-                ))
+        let requested = stream_config(&config, self.latency_mode);
+        let built = build_input_stream(&device, &requested, config.sample_format(), callback.clone(), self.error_tx.clone());
+        let (stream, stream_config) = match built {
+            Ok(stream) => (stream, requested),
+            Err(e) if matches!(requested.buffer_size, BufferSize::Fixed(_)) => {
+                warn!("Device rejected {:?}, using its default buffer: {}", requested.buffer_size, e);
+                let fallback = config.config();
+                let stream = build_input_stream(&device, &fallback, config.sample_format(), callback, self.error_tx.clone())?;
+                (stream, fallback)
             }
-        }
-        .map_err(|e| CaptureError::StreamBuildError(e.to_string()))?;
+            Err(e) => return Err(e),
+        };
 
         stream
             .play()
@@ -123,10 +316,14 @@ impl AudioCapture {
         self.is_recording = true;
         self.sample_rate = sample_rate;
         self.channels = channels;
+        self.buffer_frames = match stream_config.buffer_size {
+            BufferSize::Fixed(frames) => Some(frames),
+            BufferSize::Default => None,
+        };
 
         info!(
-            "Recording started: {} Hz, {} channels",
-            sample_rate, channels
+            "Recording started: {} Hz, {} channels, buffer {:?}",
+            sample_rate, channels, stream_config.buffer_size
         );
 
         Ok(())
@@ -152,10 +349,113 @@ impl AudioCapture {
         self.sample_rate
     }
 
-    /// Get number of channels
+    /// Get number of channels the device records
     pub fn channels(&self) -> u16 {
         self.channels
     }
+
+    /// Get number of channels delivered to the callback
+    pub fn output_channels(&self) -> u16 {
+        if self.downmix_to_mono {
+            1
+        } else {
+            self.channels
+        }
+    }
+
+    /// Get the format and buffer size the stream actually got
+    pub fn capture_info(&self) -> CaptureInfo {
+        CaptureInfo {
+            sample_rate: self.sample_rate,
+            channels: self.output_channels(),
+            buffer_frames: self.buffer_frames,
+        }
+    }
+}
+
+/// Stream config for `supported`, with the buffer size of `mode` clamped to the device's range
+fn stream_config(supported: &SupportedStreamConfig, mode: Option<LatencyMode>) -> StreamConfig {
+    let mut config = supported.config();
+    if let Some(mode) = mode {
+        let frames = mode.buffer_frames(supported.sample_rate().0);
+        config.buffer_size = match supported.buffer_size() {
+            SupportedBufferSize::Range { min, max } => BufferSize::Fixed(frames.max(*min).min(*max)),
+            SupportedBufferSize::Unknown => BufferSize::Fixed(frames),
+        };
+    }
+    config
+}
+
+/// Build an input stream converting any supported sample format to f32
+fn build_input_stream<F>(
+    device: &Device,
+    config: &StreamConfig,
+    sample_format: SampleFormat,
+    callback: Arc<Mutex<F>>,
+    error_tx: Option<flume::Sender<CaptureError>>,
+) -> Result<Stream, CaptureError>
+where
+    F: FnMut(Vec<f32>) + Send + 'static,
+{
+    let err_fn = move |err: cpal::StreamError| {
+        error!("Audio stream error: {}", err);
+        if let Some(tx) = &error_tx {
+            let _ = tx.send(CaptureError::DeviceLost(err.to_string()));
+        }
+    };
+
+    match sample_format {
+        SampleFormat::F32 => device.build_input_stream(
+            config,
+            move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                (callback.lock())(data.to_vec());
+            },
+            err_fn,
+            None,
+        ),
+        SampleFormat::I16 => device.build_input_stream(
+            config,
+            move |data: &[i16], _: &cpal::InputCallbackInfo| {
+                let float_data: Vec<f32> =
+                    data.iter().map(|&s| s as f32 / i16::MAX as f32).collect();
+                (callback.lock())(float_data);
+            },
+            err_fn,
+            None,
+        ),
+        SampleFormat::U16 => device.build_input_stream(
+            config,
+            move |data: &[u16], _: &cpal::InputCallbackInfo| {
+                let float_data: Vec<f32> = data
+                    .iter()
+                    .map(|&s| (s as f32 / u16::MAX as f32) - 0.5)
+                    .collect();
+                (callback.lock())(float_data);
+            },
+            err_fn,
+            None,
+        ),
+        _ => {
+            return Err(CaptureError::StreamBuildError(
+                "Unsupported sample format".to_string(),
+            ))
+        }
+    }
+    .map_err(|e| CaptureError::StreamBuildError(e.to_string()))
+}
+
+/// Wrap `callback` so interleaved frames are averaged to mono first when `downmix` is set
+fn downmixing<F>(channels: u16, downmix: bool, mut callback: F) -> impl FnMut(Vec<f32>) + Send + 'static
+where
+    F: FnMut(Vec<f32>) + Send + 'static,
+{
+    move |samples| {
+        if downmix && channels > 1 {
+            callback(processing::stereo_to_mono(&samples, channels));
+        } else {
+            callback(samples);
+        }
+    }
 }
 
 impl Default for AudioCapture {
@@ -163,3 +463,564 @@ impl Default for AudioCapture {
         Self::new()
     }
 }
+
+/// Sample rate and channel count the device actually records at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Format and buffering a running capture actually got from the device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureInfo {
+    pub sample_rate: u32,
+    /// Channels delivered to the callback
+    pub channels: u16,
+    /// Frames per device buffer (None when the device chose)
+    pub buffer_frames: Option<u32>,
+}
+
+impl CaptureInfo {
+    /// Sample rate and channel count only
+    pub fn format(&self) -> CaptureFormat {
+        CaptureFormat {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+        }
+    }
+
+    /// Latency added by one device buffer
+    pub fn buffer_ms(&self) -> Option<f32> {
+        self.buffer_frames
+            .map(|frames| frames as f32 * 1000.0 / self.sample_rate.max(1) as f32)
+    }
+}
+
+impl From<CaptureFormat> for CaptureInfo {
+    fn from(format: CaptureFormat) -> Self {
+        Self {
+            sample_rate: format.sample_rate,
+            channels: format.channels,
+            buffer_frames: None,
+        }
+    }
+}
+
+/// What a `CaptureThread` records from and reports
+#[derive(Debug, Clone, Default)]
+pub struct CaptureOptions {
+    /// Input device name (None uses the default device)
+    pub device_name: Option<String>,
+    /// Record the default output device instead of an input device
+    pub loopback: bool,
+    /// Requested buffering (None leaves it to the device)
+    pub latency_mode: Option<LatencyMode>,
+    /// Receives the dB level of every incoming chunk
+    pub peak_tx: Option<flume::Sender<f32>>,
+}
+
+/// Microphone capture running on its own thread until stopped
+///
+/// cpal streams cannot move between threads, so the stream is created, kept
+/// alive and dropped on the capture thread; `stop` signals it and joins.
+pub struct CaptureThread {
+    stop_tx: flume::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+    info: CaptureInfo,
+    errors: flume::Receiver<CaptureError>,
+}
+
+impl CaptureThread {
+    /// Start recording mono audio as described by `options`
+    pub fn spawn<F>(options: CaptureOptions, callback: F) -> Result<Self, CaptureError>
+    where
+        F: FnMut(Vec<f32>) + Send + 'static,
+    {
+        Self::spawn_with(move |error_tx| {
+            let mut capture = AudioCapture::new();
+            capture.set_downmix_to_mono(true);
+            capture.set_error_sender(error_tx);
+            if let Some(peak_tx) = options.peak_tx {
+                capture.set_peak_sender(peak_tx);
+            }
+            if let Some(mode) = options.latency_mode {
+                capture.set_latency_mode(mode);
+            }
+            match options.device_name {
+                _ if options.loopback => capture.start_loopback(callback)?,
+                Some(name) => capture.start_recording_on(&name, callback)?,
+                None => capture.start_recording(callback)?,
+            }
+            let info = capture.capture_info();
+            Ok((capture, info))
+        })
+    }
+
+    /// Run `start` on a new thread and keep what it returns alive until stopped
+    ///
+    /// `start` gets the sender for errors reported while running.
+    fn spawn_with<S, G, I>(start: S) -> Result<Self, CaptureError>
+    where
+        S: FnOnce(flume::Sender<CaptureError>) -> Result<(G, I), CaptureError> + Send + 'static,
+        I: Into<CaptureInfo>,
+        G: 'static,
+    {
+        let (stop_tx, stop_rx) = flume::bounded::<()>(1);
+        let (ready_tx, ready_rx) = flume::bounded(1);
+        let (error_tx, errors) = flume::unbounded();
+
+        let handle = std::thread::Builder::new()
+            .name("audio-capture".to_string())
+            .spawn(move || {
+                let capture = match start(error_tx) {
+                    Ok((capture, info)) => {
+                        let _ = ready_tx.send(Ok(info.into()));
+                        capture
+                    }
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+
+                // Returns on stop() or when the handle is dropped
+                let _ = stop_rx.recv();
+                drop(capture);
+                debug!("Capture thread stopped");
+            })
+            .map_err(|e| CaptureError::ThreadError(e.to_string()))?;
+
+        let mut thread = Self {
+            stop_tx,
+            handle: Some(handle),
+            info: CaptureInfo {
+                sample_rate: 0,
+                channels: 0,
+                buffer_frames: None,
+            },
+            errors,
+        };
+
+        match ready_rx.recv() {
+            Ok(Ok(info)) => {
+                thread.info = info;
+                Ok(thread)
+            }
+            Ok(Err(e)) => {
+                thread.stop();
+                Err(e)
+            }
+            Err(_) => {
+                thread.stop();
+                Err(CaptureError::ThreadError("capture thread exited".to_string()))
+            }
+        }
+    }
+
+    /// Stop recording and wait for the capture thread to exit
+    pub fn stop(&mut self) {
+        let _ = self.stop_tx.try_send(());
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                warn!("Capture thread panicked");
+            }
+        }
+    }
+
+    /// Format the device is recording in
+    pub fn format(&self) -> CaptureFormat {
+        self.info.format()
+    }
+
+    /// Format and buffer size the device is recording with
+    pub fn info(&self) -> CaptureInfo {
+        self.info
+    }
+
+    /// Errors reported by the running stream; disconnects once capture stops
+    pub fn errors(&self) -> flume::Receiver<CaptureError> {
+        self.errors.clone()
+    }
+
+    /// Check if the capture thread is still running
+    pub fn is_running(&self) -> bool {
+        self.handle.as_ref().is_some_and(|h| !h.is_finished())
+    }
+}
+
+impl Drop for CaptureThread {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Writes the raw captured audio of a session to a 16-bit WAV file
+pub struct SessionRecorder {
+    writer: hound::WavWriter<BufWriter<File>>,
+    path: PathBuf,
+}
+
+impl SessionRecorder {
+    /// Create the WAV file (and its directory) for audio in `format`
+    pub fn create(path: PathBuf, format: CaptureFormat) -> Result<Self, CaptureError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| CaptureError::RecordingError(e.to_string()))?;
+        }
+
+        let spec = hound::WavSpec {
+            channels: format.channels,
+            sample_rate: format.sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let writer = hound::WavWriter::create(&path, spec).map_err(|e| CaptureError::RecordingError(e.to_string()))?;
+
+        info!("Recording session audio to {:?}", path);
+        Ok(Self { writer, path })
+    }
+
+    /// Append interleaved samples
+    pub fn write(&mut self, samples: &[f32]) -> Result<(), CaptureError> {
+        for &sample in samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.writer
+                .write_sample(value)
+                .map_err(|e| CaptureError::RecordingError(e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Write the WAV header and close the file
+    pub fn finalize(self) -> Result<PathBuf, CaptureError> {
+        self.writer
+            .finalize()
+            .map_err(|e| CaptureError::RecordingError(e.to_string()))?;
+        Ok(self.path)
+    }
+
+    /// Get the file being written
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Wrap `callback` so each chunk is appended to the session recording first, while one is set
+///
+/// A failed write ends the recording; capture carries on.
+pub fn recording<F>(recorder: Arc<Mutex<Option<SessionRecorder>>>, mut callback: F) -> impl FnMut(Vec<f32>) + Send + 'static
+where
+    F: FnMut(Vec<f32>) + Send + 'static,
+{
+    move |samples| {
+        let mut slot = recorder.lock();
+        if let Some(active) = slot.as_mut() {
+            if let Err(e) = active.write(&samples) {
+                warn!("Stopping session recording: {}", e);
+                slot.take();
+            }
+        }
+        drop(slot);
+        callback(samples);
+    }
+}
+
+/// A change in the set of input devices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceChange {
+    Connected(String),
+    Disconnected(String),
+    DefaultChanged(Option<String>),
+}
+
+impl DeviceChange {
+    /// Frontend event announcing this change
+    pub fn event_name(&self) -> &'static str {
+        match self {
+            DeviceChange::Connected(_) => DEVICE_CONNECTED_EVENT,
+            DeviceChange::Disconnected(_) => DEVICE_DISCONNECTED_EVENT,
+            DeviceChange::DefaultChanged(_) => DEFAULT_DEVICE_CHANGED_EVENT,
+        }
+    }
+
+    /// Device the change is about (None when there is no longer a default device)
+    pub fn device_name(&self) -> Option<&str> {
+        match self {
+            DeviceChange::Connected(name) | DeviceChange::Disconnected(name) => Some(name),
+            DeviceChange::DefaultChanged(name) => name.as_deref(),
+        }
+    }
+}
+
+/// Input devices present at one poll
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceSnapshot {
+    pub devices: BTreeSet<String>,
+    pub default: Option<String>,
+}
+
+impl DeviceSnapshot {
+    /// Read the current input devices from cpal
+    pub fn current() -> Self {
+        let host = cpal::default_host();
+        let devices = host
+            .input_devices()
+            .map(|devices| devices.filter_map(|d| d.name().ok()).collect())
+            .unwrap_or_default();
+        let default = host.default_input_device().and_then(|d| d.name().ok());
+
+        Self { devices, default }
+    }
+
+    /// Changes needed to get from this snapshot to `next`
+    pub fn diff(&self, next: &DeviceSnapshot) -> Vec<DeviceChange> {
+        let mut changes: Vec<DeviceChange> = next
+            .devices
+            .difference(&self.devices)
+            .map(|name| DeviceChange::Connected(name.clone()))
+            .collect();
+        changes.extend(
+            self.devices
+                .difference(&next.devices)
+                .map(|name| DeviceChange::Disconnected(name.clone())),
+        );
+        if self.default != next.default {
+            changes.push(DeviceChange::DefaultChanged(next.default.clone()));
+        }
+        changes
+    }
+}
+
+/// Polls the input device list on a background thread and reports changes
+pub struct DeviceWatcher {
+    stop_tx: flume::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl DeviceWatcher {
+    /// Start polling; `on_change` runs on the watcher thread for every change
+    pub fn start<F>(on_change: F) -> Result<Self, CaptureError>
+    where
+        F: Fn(DeviceChange) + Send + 'static,
+    {
+        let (stop_tx, stop_rx) = flume::bounded::<()>(1);
+
+        let handle = std::thread::Builder::new()
+            .name("device-watcher".to_string())
+            .spawn(move || {
+                let mut previous = DeviceSnapshot::current();
+
+                while let Err(flume::RecvTimeoutError::Timeout) = stop_rx.recv_timeout(DEVICE_POLL_INTERVAL) {
+                    let current = DeviceSnapshot::current();
+                    for change in previous.diff(&current) {
+                        debug!("Input device change: {:?}", change);
+                        on_change(change);
+                    }
+                    previous = current;
+                }
+                debug!("Device watcher stopped");
+            })
+            .map_err(|e| CaptureError::ThreadError(e.to_string()))?;
+
+        info!("Watching for input device changes");
+
+        Ok(Self {
+            stop_tx,
+            handle: Some(handle),
+        })
+    }
+
+    /// Stop polling and wait for the thread to exit
+    pub fn stop(&mut self) {
+        let _ = self.stop_tx.try_send(());
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for DeviceWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parking_lot::RwLock;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Stand-in for a cpal stream: feeds the buffer until dropped
+    struct FakeStream {
+        running: Arc<AtomicBool>,
+        feeder: Option<JoinHandle<()>>,
+    }
+
+    impl FakeStream {
+        fn start(buffer: Arc<RwLock<Vec<f32>>>) -> Self {
+            let running = Arc::new(AtomicBool::new(true));
+            let flag = running.clone();
+            let feeder = std::thread::spawn(move || {
+                while flag.load(Ordering::SeqCst) {
+                    buffer.write().extend_from_slice(&[0.1; 16]);
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+            });
+            Self {
+                running,
+                feeder: Some(feeder),
+            }
+        }
+    }
+
+    impl Drop for FakeStream {
+        fn drop(&mut self) {
+            self.running.store(false, Ordering::SeqCst);
+            if let Some(feeder) = self.feeder.take() {
+                feeder.join().unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_stop_ends_capture_repeatedly() {
+        let buffer = Arc::new(RwLock::new(Vec::new()));
+
+        for _ in 0..2 {
+            let feed = buffer.clone();
+            let format = CaptureFormat {
+                sample_rate: 48000,
+                channels: 2,
+            };
+            let mut capture = CaptureThread::spawn_with(move |_| Ok((FakeStream::start(feed), format))).unwrap();
+            std::thread::sleep(Duration::from_millis(20));
+            assert!(capture.is_running());
+            assert_eq!(capture.format(), format);
+
+            capture.stop();
+            assert!(!capture.is_running());
+
+            let len = buffer.read().len();
+            assert!(len > 0);
+            std::thread::sleep(Duration::from_millis(20));
+            assert_eq!(buffer.read().len(), len);
+        }
+    }
+
+    #[test]
+    fn test_downmix_halves_identical_stereo() {
+        let received = Arc::new(RwLock::new(Vec::new()));
+        let sink = received.clone();
+        let mut callback = downmixing(2, true, move |samples| sink.write().extend(samples));
+
+        let mono = [0.1, -0.2, 0.3, 0.4];
+        callback(mono.iter().flat_map(|&s| [s, s]).collect());
+
+        assert_eq!(*received.read(), mono.to_vec());
+    }
+
+    #[test]
+    fn test_device_snapshot_diff() {
+        let before = DeviceSnapshot {
+            devices: ["Built-in".to_string(), "USB Mic".to_string()].into(),
+            default: Some("USB Mic".to_string()),
+        };
+        let after = DeviceSnapshot {
+            devices: ["Built-in".to_string(), "Headset".to_string()].into(),
+            default: Some("Built-in".to_string()),
+        };
+
+        assert_eq!(
+            before.diff(&after),
+            vec![
+                DeviceChange::Connected("Headset".to_string()),
+                DeviceChange::Disconnected("USB Mic".to_string()),
+                DeviceChange::DefaultChanged(Some("Built-in".to_string())),
+            ]
+        );
+        assert!(after.diff(&after).is_empty());
+    }
+
+    #[test]
+    fn test_stream_errors_reach_the_owner() {
+        let format = CaptureFormat {
+            sample_rate: 48000,
+            channels: 1,
+        };
+        let mut capture = CaptureThread::spawn_with(move |error_tx| {
+            error_tx.send(CaptureError::DeviceLost("unplugged".to_string())).unwrap();
+            // The sender lives as long as the "stream", like cpal's error callback
+            Ok((error_tx, format))
+        })
+        .unwrap();
+
+        let errors = capture.errors();
+        assert!(matches!(errors.recv(), Ok(CaptureError::DeviceLost(_))));
+
+        capture.stop();
+        assert!(errors.recv().is_err());
+    }
+
+    #[test]
+    fn test_capture_source_parts() {
+        assert!(CaptureSource::Both.includes_microphone() && CaptureSource::Both.includes_loopback());
+        assert!(!CaptureSource::default().includes_loopback());
+        assert!(!CaptureSource::Loopback.includes_microphone());
+
+        #[cfg(not(target_os = "windows"))]
+        assert!(matches!(
+            AudioCapture::new().start_loopback(|_| {}),
+            Err(CaptureError::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_recording_wraps_callback() {
+        let path = std::env::temp_dir().join(format!("session-{}.wav", uuid::Uuid::new_v4()));
+        let format = CaptureFormat {
+            sample_rate: 16000,
+            channels: 1,
+        };
+        let recorder = Arc::new(Mutex::new(Some(SessionRecorder::create(path.clone(), format).unwrap())));
+
+        let received = Arc::new(RwLock::new(0usize));
+        let counter = received.clone();
+        let mut callback = recording(recorder.clone(), move |samples| *counter.write() += samples.len());
+        callback(vec![0.5; 160]);
+        callback(vec![-0.5; 160]);
+
+        let finished = recorder.lock().take().unwrap().finalize().unwrap();
+        let reader = hound::WavReader::open(&finished).unwrap();
+        assert_eq!(reader.len(), 320);
+        assert_eq!(*received.read(), 320);
+        std::fs::remove_file(&finished).ok();
+    }
+
+    #[test]
+    fn test_latency_mode_sets_stream_buffer_size() {
+        let supported = |buffer_size| {
+            SupportedStreamConfig::new(1, cpal::SampleRate(48000), buffer_size, SampleFormat::F32)
+        };
+
+        let config = stream_config(&supported(SupportedBufferSize::Unknown), Some(LatencyMode::Low));
+        assert_eq!(config.buffer_size, BufferSize::Fixed(480));
+        assert_eq!(config.sample_rate.0, 48000);
+
+        // Clamped to what the device supports
+        let range = SupportedBufferSize::Range { min: 1024, max: 4096 };
+        let config = stream_config(&supported(range), Some(LatencyMode::Balanced));
+        assert_eq!(config.buffer_size, BufferSize::Fixed(1024));
+
+        let config = stream_config(&supported(SupportedBufferSize::Unknown), None);
+        assert_eq!(config.buffer_size, BufferSize::Default);
+    }
+
+    #[test]
+    fn test_failed_start_is_reported() {
+        let result = CaptureThread::spawn_with(|_| Err::<((), CaptureFormat), _>(CaptureError::NoInputDevice));
+        assert!(matches!(result, Err(CaptureError::NoInputDevice)));
+    }
+}
@@ -0,0 +1,274 @@
+//! Audio controller - owns the AudioEngine on a dedicated thread
+//!
+//! rodio's output stream cannot be shared between threads, so the engine
+//! lives on its own thread and callers send it closures to run. The thread
+//! also ticks the mood playlist that rotates background music.
+
+use crate::audio::engine::{AudioEngine, PlaybackEvent, SoundEffect, Track};
+use crate::audio::resume::{self, PlaybackSnapshot, SNAPSHOT_INTERVAL};
+use crate::db::{Repository, Sfx};
+use crate::detection::DetectionLogger;
+use crate::error::AppError;
+use crate::orchestrator::MoodPlaylist;
+use parking_lot::{Mutex, RwLock};
+use rand::seq::SliceRandom;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// How often the engine gets housekeeping ticks while idle
+const TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+type Job = Box<dyn FnOnce(&mut AudioEngine) + Send>;
+
+/// Handle to the audio thread
+pub struct AudioController {
+    tx: flume::Sender<Job>,
+    /// Where playback snapshots are saved (None until the database is ready)
+    repository: Arc<RwLock<Option<Repository>>>,
+    /// Background music rotation (None until the database is ready)
+    playlist: Arc<Mutex<Option<MoodPlaylist>>>,
+}
+
+impl AudioController {
+    /// Start the audio thread, sharing `music_level` with the engine's meter
+    /// and `track_bpm` with its tempo analysis
+    ///
+    /// Track plays are also logged to `detection_logger` for the session report.
+    pub fn spawn(
+        music_level: Arc<RwLock<f32>>,
+        track_bpm: Arc<RwLock<HashMap<String, f32>>>,
+        detection_logger: Arc<Mutex<DetectionLogger>>,
+    ) -> Self {
+        let (tx, rx) = flume::unbounded::<Job>();
+        let repository: Arc<RwLock<Option<Repository>>> = Arc::new(RwLock::new(None));
+        let snapshot_repo = repository.clone();
+        let playlist: Arc<Mutex<Option<MoodPlaylist>>> = Arc::new(Mutex::new(None));
+        let rotation = playlist.clone();
+        let playback_events = spawn_track_stats(repository.clone(), detection_logger);
+
+        let spawned = std::thread::Builder::new()
+            .name("audio-engine".to_string())
+            .spawn(move || {
+                let mut engine = AudioEngine::default();
+                engine.set_level_meter(music_level);
+                engine.set_bpm_cache(track_bpm);
+                if let Some(tx) = playback_events {
+                    engine.set_playback_events(tx);
+                }
+                info!("Audio thread started (output available: {})", engine.is_available());
+
+                let mut last_snapshot: Option<(String, Instant)> = None;
+
+                loop {
+                    match rx.recv_timeout(TICK_INTERVAL) {
+                        Ok(job) => job(&mut engine),
+                        Err(flume::RecvTimeoutError::Timeout) => {}
+                        Err(flume::RecvTimeoutError::Disconnected) => break,
+                    }
+                    engine.tick();
+
+                    if let Some(playlist) = rotation.lock().as_mut() {
+                        if let Err(e) = playlist.tick(&mut engine) {
+                            warn!("Mood playlist failed to advance: {}", e);
+                        }
+                    }
+
+                    if let Some(repo) = snapshot_repo.read().as_ref() {
+                        save_snapshot_if_due(&engine, repo, &mut last_snapshot);
+                    }
+                }
+
+                engine.stop_all();
+                debug!("Audio thread stopped");
+            });
+
+        if let Err(e) = spawned {
+            warn!("Failed to start audio thread: {}", e);
+        }
+
+        Self {
+            tx,
+            repository,
+            playlist,
+        }
+    }
+
+    /// Start saving playback snapshots to the database and enable the mood playlist
+    pub fn set_repository(&self, repo: Repository) {
+        *self.playlist.lock() = Some(MoodPlaylist::new(repo.clone()));
+        *self.repository.write() = Some(repo);
+    }
+
+    /// Crossfade to `track`, then keep rotating the tracks of `mood`
+    pub fn play_for_mood(&self, mood: &str, track: Track) -> Result<(), AppError> {
+        let playlist = self.playlist.clone();
+        let mood = mood.to_string();
+        self.run(move |engine| match playlist.lock().as_mut() {
+            Some(playlist) => playlist.play(&mood, &track, engine),
+            None => engine.crossfade_to(&track),
+        })
+    }
+
+    /// Run a closure on the audio thread and wait for its result
+    pub fn run<R, F>(&self, f: F) -> Result<R, AppError>
+    where
+        F: FnOnce(&mut AudioEngine) -> Result<R, AppError> + Send + 'static,
+        R: Send + 'static,
+    {
+        let (reply_tx, reply_rx) = flume::bounded(1);
+
+        self.tx
+            .send(Box::new(move |engine| {
+                let _ = reply_tx.send(f(engine));
+            }))
+            .map_err(|_| AppError::Playback("audio thread not running".to_string()))?;
+
+        reply_rx
+            .recv()
+            .map_err(|_| AppError::Playback("audio thread not running".to_string()))?
+    }
+
+    /// Fail with a clear error when there is no audio output to play to
+    pub fn require_output(&self) -> Result<(), AppError> {
+        if self.run(|engine| Ok(engine.is_available()))? {
+            Ok(())
+        } else {
+            Err(AppError::Playback("no audio output device".to_string()))
+        }
+    }
+
+    /// Play a stored SFX at its own volume
+    pub fn play_sfx(&self, sfx: &Sfx) -> Result<(), AppError> {
+        let effect = SoundEffect::from(sfx);
+        let volume = sfx.volume as f32;
+        self.run(move |engine| engine.play_sfx_at_volume(&effect, volume))
+    }
+
+    /// Play a random SFX from a category (e.g. when a "danger" keyword fires)
+    ///
+    /// Returns the SFX that was played, or None if the category is empty.
+    pub fn play_random_sfx_in_category(
+        &self,
+        repo: &Repository,
+        category: &str,
+    ) -> Result<Option<Sfx>, AppError> {
+        let sfx = repo.get_sfx_by_category(category)?;
+        let Some(choice) = sfx.choose(&mut rand::thread_rng()) else {
+            return Ok(None);
+        };
+
+        self.play_sfx(choice)?;
+        Ok(Some(choice.clone()))
+    }
+
+    /// Reload the last snapshot and resume playback near its stored position
+    ///
+    /// Returns the snapshot that was resumed, or None if there was nothing to
+    /// resume or the track is no longer in the library.
+    pub fn resume_last_track(&self, repo: &Repository) -> Result<Option<PlaybackSnapshot>, AppError> {
+        let Some(snapshot) = resume::load_snapshot(repo)? else {
+            return Ok(None);
+        };
+        let Some(stored) = repo.get_track(&snapshot.track_id)? else {
+            debug!("Last track {} is no longer in the library", snapshot.track_id);
+            return Ok(None);
+        };
+
+        let track = Track::from(&stored);
+        let position_ms = match track.duration_ms {
+            Some(duration) if snapshot.position_ms >= duration as u64 => 0,
+            _ => snapshot.position_ms,
+        };
+
+        self.run(move |engine| engine.play_track_from(&track, position_ms))?;
+        info!("Resumed {} at {}ms", snapshot.track_id, position_ms);
+        Ok(Some(snapshot))
+    }
+}
+
+/// Record track plays in the database as the engine reports them
+///
+/// The thread exits once the engine drops its sender.
+fn spawn_track_stats(
+    repository: Arc<RwLock<Option<Repository>>>,
+    detection_logger: Arc<Mutex<DetectionLogger>>,
+) -> Option<flume::Sender<PlaybackEvent>> {
+    let (tx, rx) = flume::unbounded::<PlaybackEvent>();
+
+    let spawned = std::thread::Builder::new()
+        .name("track-stats".to_string())
+        .spawn(move || {
+            for event in rx.iter() {
+                if let PlaybackEvent::TrackPlayed { track_id, .. } = &event {
+                    detection_logger.lock().log_track_played(track_id);
+                }
+                let Some(repo) = repository.read().clone() else {
+                    continue;
+                };
+                let result = match &event {
+                    PlaybackEvent::TrackPlayed { track_id, started_at } => repo.record_track_play(track_id, started_at),
+                    PlaybackEvent::TrackStopped { track_id, played_ms } => {
+                        repo.add_track_played_time(track_id, *played_ms as i64)
+                    }
+                };
+                if let Err(e) = result {
+                    warn!("Failed to update track stats: {}", e);
+                }
+            }
+        });
+
+    match spawned {
+        Ok(_) => Some(tx),
+        Err(e) => {
+            warn!("Failed to start track stats thread: {}", e);
+            None
+        }
+    }
+}
+
+/// Save the playing track when it changes or every `SNAPSHOT_INTERVAL`
+fn save_snapshot_if_due(engine: &AudioEngine, repo: &Repository, last: &mut Option<(String, Instant)>) {
+    let Some(playing) = engine.current_track() else {
+        return;
+    };
+
+    let due = match last {
+        Some((id, at)) => *id != playing.track.id || at.elapsed() >= SNAPSHOT_INTERVAL,
+        None => true,
+    };
+    if !due {
+        return;
+    }
+
+    if let Err(e) = resume::save_snapshot(repo, &PlaybackSnapshot::of(&playing)) {
+        warn!("Failed to save playback position: {}", e);
+    }
+    *last = Some((playing.track.id, Instant::now()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_random_sfx_in_empty_category() {
+        let controller = AudioController::spawn(
+            Arc::new(RwLock::new(0.0)),
+            Arc::new(RwLock::new(HashMap::new())),
+            Arc::new(Mutex::new(DetectionLogger::new(String::new()))),
+        );
+        assert!(controller.run(|engine| Ok(engine.active_stingers())).is_ok());
+        let available = controller.run(|engine| Ok(engine.is_available())).unwrap();
+        assert_eq!(controller.require_output().is_ok(), available);
+
+        let db = Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+        assert!(controller
+            .play_random_sfx_in_category(&repo, "danger")
+            .unwrap()
+            .is_none());
+    }
+}
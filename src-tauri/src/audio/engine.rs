@@ -6,13 +6,16 @@
 //! - SFX layering on top of background music
 //! - Volume ducking for voice-overs
 
+use crate::audio::meter::MeteredSource;
 use crate::error::AppError;
 use parking_lot::RwLock;
 use rodio::{OutputStream, OutputStreamHandle, Sink, Source};
 use serde::{Deserialize, Serialize};
 use std::fs::File;
 use std::io::BufReader;
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
 use tracing::{debug, info, warn};
 
 /// Crossfade types
@@ -45,6 +48,30 @@ impl CrossfadeType {
             CrossfadeType::Long => 5000,
         }
     }
+
+    /// Name as stored in settings and the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CrossfadeType::Instant => "instant",
+            CrossfadeType::Quick => "quick",
+            CrossfadeType::Musical => "musical",
+            CrossfadeType::Long => "long",
+        }
+    }
+}
+
+impl std::str::FromStr for CrossfadeType {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "instant" => Ok(CrossfadeType::Instant),
+            "quick" => Ok(CrossfadeType::Quick),
+            "musical" => Ok(CrossfadeType::Musical),
+            "long" => Ok(CrossfadeType::Long),
+            other => Err(AppError::Config(format!("Unknown crossfade type: {}", other))),
+        }
+    }
 }
 
 /// Track info for playback
@@ -60,6 +87,21 @@ pub struct Track {
     pub bpm: Option<f32>,
 }
 
+impl From<&crate::db::Track> for Track {
+    fn from(track: &crate::db::Track) -> Self {
+        Self {
+            id: track.id.clone(),
+            name: track.name.clone(),
+            file_path: track.file_path.clone(),
+            genre: track.genre.clone(),
+            mood: track.mood.clone(),
+            is_looping: track.is_looping,
+            duration_ms: track.duration_ms.map(|ms| ms as u32),
+            bpm: track.bpm.map(|bpm| bpm as f32),
+        }
+    }
+}
+
 /// SFX info
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SoundEffect {
@@ -70,6 +112,18 @@ pub struct SoundEffect {
     pub duration_ms: Option<u32>,
 }
 
+impl From<&crate::db::Sfx> for SoundEffect {
+    fn from(sfx: &crate::db::Sfx) -> Self {
+        Self {
+            id: sfx.id.clone(),
+            name: sfx.name.clone(),
+            file_path: sfx.file_path.clone(),
+            category: sfx.category.clone(),
+            duration_ms: sfx.duration_ms.map(|ms| ms as u32),
+        }
+    }
+}
+
 /// Audio engine configuration
 #[derive(Debug, Clone)]
 pub struct EngineConfig {
@@ -106,6 +160,83 @@ pub struct PlayingTrack {
     pub track: Track,
     pub started_at_ms: u64,
     pub is_looping: bool,
+    /// Position the track was started from
+    pub start_offset_ms: u64,
+    /// Total time spent paused, excluding the current pause
+    pub paused_ms: u64,
+    /// When the current pause began
+    pub paused_at_ms: Option<u64>,
+}
+
+impl PlayingTrack {
+    /// Start tracking a track that begins at `start_offset_ms`
+    pub fn new(track: Track, start_offset_ms: u64) -> Self {
+        Self {
+            is_looping: track.is_looping,
+            track,
+            started_at_ms: now_ms(),
+            start_offset_ms,
+            paused_ms: 0,
+            paused_at_ms: None,
+        }
+    }
+
+    /// Mark the track as paused
+    pub fn pause(&mut self, now_ms: u64) {
+        if self.paused_at_ms.is_none() {
+            self.paused_at_ms = Some(now_ms);
+        }
+    }
+
+    /// Mark the track as resumed, accumulating the pause
+    pub fn resume(&mut self, now_ms: u64) {
+        if let Some(paused_at) = self.paused_at_ms.take() {
+            self.paused_ms += now_ms.saturating_sub(paused_at);
+        }
+    }
+
+    /// Time actually spent playing up to `now_ms`, excluding pauses
+    ///
+    /// A track that doesn't loop stops counting once it reaches its end.
+    pub fn played_ms(&self, now_ms: u64) -> u64 {
+        let end = self.paused_at_ms.unwrap_or(now_ms);
+        let played = end.saturating_sub(self.started_at_ms).saturating_sub(self.paused_ms);
+
+        match self.track.duration_ms {
+            Some(duration) if !self.is_looping => played.min((duration as u64).saturating_sub(self.start_offset_ms)),
+            _ => played,
+        }
+    }
+
+    /// Playback position at `now_ms`, excluding time spent paused
+    pub fn position_ms(&self, now_ms: u64) -> u64 {
+        let position = self.start_offset_ms + self.played_ms(now_ms);
+
+        match self.track.duration_ms {
+            Some(duration) if self.is_looping && duration > 0 => position % duration as u64,
+            _ => position,
+        }
+    }
+}
+
+/// Current wall-clock time in milliseconds
+pub fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// Fixed volume for track previews (independent of the session mix)
+pub const PREVIEW_VOLUME: f32 = 0.3;
+
+/// Music playback notifications, used to keep per-track statistics
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlaybackEvent {
+    /// A track started playing (RFC 3339 start time)
+    TrackPlayed { track_id: String, started_at: String },
+    /// A track was stopped or replaced after playing for `played_ms`
+    TrackStopped { track_id: String, played_ms: u64 },
 }
 
 /// State of the audio engine
@@ -140,6 +271,20 @@ pub struct AudioEngine {
     current_track: RwLock<Option<PlayingTrack>>,
     /// Is ducking active
     is_ducking: RwLock<bool>,
+    /// Stinger SFX sinks currently holding the duck
+    stinger_sinks: Vec<Sink>,
+    /// Whether the active duck was started by a stinger
+    stinger_ducked: bool,
+    /// Linear RMS level of the music bus
+    music_level: Arc<RwLock<f32>>,
+    /// Track preview sink (outside the session mix)
+    preview_sink: Option<Sink>,
+    /// Crossfade type per (from genre, to genre), overriding the default
+    crossfade_overrides: HashMap<(String, String), CrossfadeType>,
+    /// Receiver of track play/stop events (None when nobody listens)
+    playback_events: Option<flume::Sender<PlaybackEvent>>,
+    /// Detected tempo per track id, filled in the background as tracks load
+    track_bpm: Arc<RwLock<HashMap<String, f32>>>,
 }
 
 impl AudioEngine {
@@ -156,23 +301,126 @@ impl AudioEngine {
             state: RwLock::new(EngineState::Idle),
             current_track: RwLock::new(None),
             is_ducking: RwLock::new(false),
+            stinger_sinks: Vec::new(),
+            stinger_ducked: false,
+            music_level: Arc::new(RwLock::new(0.0)),
+            preview_sink: None,
+            crossfade_overrides: HashMap::new(),
+            playback_events: None,
+            track_bpm: Arc::new(RwLock::new(HashMap::new())),
         })
     }
 
+    /// Create an engine without an output device
+    ///
+    /// Every playback call on a headless engine returns an error.
+    pub fn headless() -> Self {
+        Self {
+            _stream: None,
+            stream_handle: None,
+            music_sink: None,
+            config: RwLock::new(EngineConfig::default()),
+            state: RwLock::new(EngineState::Idle),
+            current_track: RwLock::new(None),
+            is_ducking: RwLock::new(false),
+            stinger_sinks: Vec::new(),
+            stinger_ducked: false,
+            music_level: Arc::new(RwLock::new(0.0)),
+            preview_sink: None,
+            crossfade_overrides: HashMap::new(),
+            playback_events: None,
+            track_bpm: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Check whether an output device is available
+    pub fn is_available(&self) -> bool {
+        self.stream_handle.is_some()
+    }
+
+    /// Send track play/stop events to `tx`
+    pub fn set_playback_events(&mut self, tx: flume::Sender<PlaybackEvent>) {
+        self.playback_events = Some(tx);
+    }
+
+    /// Share the detected tempo of each track with `cache`
+    pub fn set_bpm_cache(&mut self, cache: Arc<RwLock<HashMap<String, f32>>>) {
+        self.track_bpm = cache;
+    }
+
+    /// Detected tempo of a track, if analysis has finished
+    pub fn track_bpm(&self, track_id: &str) -> Option<f32> {
+        self.track_bpm.read().get(track_id).copied()
+    }
+
+    /// Estimate the tempo of a newly loaded track without delaying playback
+    fn detect_bpm(&self, track: &Track) {
+        if track.bpm.is_some() || self.track_bpm.read().contains_key(&track.id) {
+            return;
+        }
+
+        let cache = self.track_bpm.clone();
+        let track_id = track.id.clone();
+        let path = track.file_path.clone();
+        let spawned = std::thread::Builder::new()
+            .name("bpm-analysis".to_string())
+            .spawn(move || match crate::audio::metadata::detect_track_bpm(Path::new(&path)) {
+                Ok(Some(bpm)) => {
+                    debug!("Track {} is {:.1} BPM", track_id, bpm);
+                    cache.write().insert(track_id, bpm);
+                }
+                Ok(None) => debug!("No tempo found for track {}", track_id),
+                Err(e) => warn!("Tempo analysis failed for {}: {}", track_id, e),
+            });
+        if let Err(e) = spawned {
+            warn!("Failed to start tempo analysis: {}", e);
+        }
+    }
+
+    fn emit_playback(&self, event: PlaybackEvent) {
+        if let Some(tx) = &self.playback_events {
+            let _ = tx.send(event);
+        }
+    }
+
+    /// Report how long the current track played before it is replaced
+    fn emit_track_stopped(&self) {
+        if let Some(playing) = self.current_track.read().as_ref() {
+            self.emit_playback(PlaybackEvent::TrackStopped {
+                track_id: playing.track.id.clone(),
+                played_ms: playing.played_ms(now_ms()),
+            });
+        }
+    }
+
+    fn emit_track_played(&self, track: &Track) {
+        self.emit_playback(PlaybackEvent::TrackPlayed {
+            track_id: track.id.clone(),
+            started_at: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
     /// Get stream handle
-    fn stream_handle(&self) -> &OutputStreamHandle {
-        self.stream_handle.as_ref().unwrap()
+    fn stream_handle(&self) -> Result<&OutputStreamHandle, AppError> {
+        self.stream_handle
+            .as_ref()
+            .ok_or_else(|| AppError::Playback("no output device".to_string()))
     }
 
     /// Play a track (stops current playback first)
     pub fn play_track(&mut self, track: &Track) -> Result<(), AppError> {
-        info!("Playing track: {}", track.name);
+        self.play_track_from(track, 0)
+    }
+
+    /// Play a track starting at `position_ms` (stops current playback first)
+    pub fn play_track_from(&mut self, track: &Track, position_ms: u64) -> Result<(), AppError> {
+        info!("Playing track: {} from {}ms", track.name, position_ms);
 
         // Stop current playback
         self.stop_music();
 
         // Load and play the track
-        let sink = Sink::try_new(self.stream_handle())
+        let sink = Sink::try_new(self.stream_handle()?)
             .map_err(|e| AppError::Playback(e.to_string()))?;
 
         let file = File::open(&track.file_path)
@@ -183,10 +431,12 @@ impl AudioEngine {
             .map_err(|e| AppError::Audio(format!("Failed to decode: {}", e)))?;
 
         // Apply looping if needed
+        let level = self.music_level.clone();
+        let skip = std::time::Duration::from_millis(position_ms);
         if track.is_looping {
-            sink.append(source.repeat_infinite());
+            sink.append(MeteredSource::new(source.repeat_infinite().skip_duration(skip).convert_samples(), level));
         } else {
-            sink.append(source);
+            sink.append(MeteredSource::new(source.skip_duration(skip).convert_samples(), level));
         }
 
         // Apply volume
@@ -195,21 +445,21 @@ impl AudioEngine {
 
         self.music_sink = Some(sink);
         *self.state.write() = EngineState::Playing;
-        *self.current_track.write() = Some(PlayingTrack {
-            track: track.clone(),
-            started_at_ms: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64,
-            is_looping: track.is_looping,
-        });
+        *self.current_track.write() = Some(PlayingTrack::new(track.clone(), position_ms));
+        self.emit_track_played(track);
+        self.detect_bpm(track);
 
         Ok(())
     }
 
     /// Crossfade to a new track
     pub fn crossfade_to(&mut self, track: &Track) -> Result<(), AppError> {
-        let crossfade_type = self.config.read().crossfade_type;
+        let current_genre = self
+            .current_track
+            .read()
+            .as_ref()
+            .and_then(|playing| playing.track.genre.clone());
+        let crossfade_type = self.crossfade_type_between(current_genre.as_deref(), track.genre.as_deref());
 
         info!("Crossfading to: {} ({:?})", track.name, crossfade_type);
 
@@ -218,7 +468,7 @@ impl AudioEngine {
         }
 
         // Create next sink for crossfade
-        let next_sink = Sink::try_new(self.stream_handle())
+        let next_sink = Sink::try_new(self.stream_handle()?)
             .map_err(|e| AppError::Playback(e.to_string()))?;
 
         let file = File::open(&track.file_path)
@@ -228,10 +478,11 @@ impl AudioEngine {
         let source = rodio::Decoder::new(reader)
             .map_err(|e| AppError::Audio(format!("Failed to decode: {}", e)))?;
 
+        let level = self.music_level.clone();
         if track.is_looping {
-            next_sink.append(source.repeat_infinite());
+            next_sink.append(MeteredSource::new(source.repeat_infinite().convert_samples(), level));
         } else {
-            next_sink.append(source);
+            next_sink.append(MeteredSource::new(source.convert_samples(), level));
         }
 
         next_sink.set_volume(0.0);
@@ -247,14 +498,10 @@ impl AudioEngine {
         // Perform instant crossfade - simplified
         // (Proper crossfade would require Arc<Sink> for thread safety)
         let volume = config.music_volume * config.master_volume;
-        *self.current_track.write() = Some(PlayingTrack {
-            track: track.clone(),
-            started_at_ms: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64,
-            is_looping: track.is_looping,
-        });
+        self.emit_track_stopped();
+        *self.current_track.write() = Some(PlayingTrack::new(track.clone(), 0));
+        self.emit_track_played(track);
+        self.detect_bpm(track);
 
         *self.state.write() = EngineState::Playing;
 
@@ -263,9 +510,14 @@ impl AudioEngine {
 
     /// Play a sound effect (layered on top of music)
     pub fn play_sfx(&mut self, sfx: &SoundEffect) -> Result<(), AppError> {
+        self.play_sfx_at_volume(sfx, 1.0)
+    }
+
+    /// Play a sound effect scaled by its own volume (0.0 - 1.0)
+    pub fn play_sfx_at_volume(&mut self, sfx: &SoundEffect, volume: f32) -> Result<(), AppError> {
         info!("Playing SFX: {}", sfx.name);
 
-        let sink = Sink::try_new(self.stream_handle())
+        let sink = Sink::try_new(self.stream_handle()?)
             .map_err(|e| AppError::Playback(e.to_string()))?;
 
         let file = File::open(&sfx.file_path)
@@ -277,7 +529,7 @@ impl AudioEngine {
 
         sink.append(source);
 
-        let volume = self.config.read().sfx_volume * self.config.read().master_volume;
+        let volume = volume.clamp(0.0, 1.0) * self.config.read().sfx_volume * self.config.read().master_volume;
         sink.set_volume(volume);
 
         // Detach sink to play independently
@@ -286,11 +538,133 @@ impl AudioEngine {
         Ok(())
     }
 
+    /// Play raw mono samples as a sound effect (e.g. a generated test tone)
+    pub fn play_sfx_samples(&mut self, samples: &[f32], sample_rate: u32) -> Result<(), AppError> {
+        debug!("Playing {} SFX samples at {} Hz", samples.len(), sample_rate);
+
+        let sink = Sink::try_new(self.stream_handle()?)
+            .map_err(|e| AppError::Playback(e.to_string()))?;
+
+        sink.append(rodio::buffer::SamplesBuffer::new(1, sample_rate, samples.to_vec()));
+
+        let volume = self.config.read().sfx_volume * self.config.read().master_volume;
+        sink.set_volume(volume);
+        sink.detach();
+
+        Ok(())
+    }
+
+    /// Play a stinger: an SFX that ducks the music until it finishes
+    ///
+    /// The duck is released from [`AudioEngine::tick`] once every active
+    /// stinger has finished, so overlapping stingers extend the duck window.
+    pub fn play_stinger(&mut self, sfx: &SoundEffect) -> Result<(), AppError> {
+        info!("Playing stinger: {}", sfx.name);
+
+        let sink = Sink::try_new(self.stream_handle()?)
+            .map_err(|e| AppError::Playback(e.to_string()))?;
+
+        let file = File::open(&sfx.file_path)
+            .map_err(|e| AppError::Audio(format!("Failed to open SFX: {}", e)))?;
+
+        let reader = BufReader::new(file);
+        let source = rodio::Decoder::new(reader)
+            .map_err(|e| AppError::Audio(format!("Failed to decode SFX: {}", e)))?;
+
+        sink.append(source);
+
+        let volume = self.config.read().sfx_volume * self.config.read().master_volume;
+        sink.set_volume(volume);
+        self.hold_stinger(sink);
+
+        Ok(())
+    }
+
+    /// Keep a playing stinger's sink until it finishes, ducking the music
+    fn hold_stinger(&mut self, sink: Sink) {
+        // Only take ownership of the duck if nothing else is ducking already
+        if !*self.is_ducking.read() {
+            self.duck();
+            self.stinger_ducked = true;
+        }
+
+        self.stinger_sinks.push(sink);
+    }
+
+    /// Audition the first `duration_ms` of a track at the preview volume
+    ///
+    /// Plays on its own sink, so the current music, engine state and ducking
+    /// are untouched. Any previous preview is stopped first.
+    pub fn preview(&mut self, track: &Track, duration_ms: u64) -> Result<(), AppError> {
+        info!("Previewing track: {} ({}ms)", track.name, duration_ms);
+
+        self.stop_preview();
+
+        let sink = Sink::try_new(self.stream_handle()?)
+            .map_err(|e| AppError::Playback(e.to_string()))?;
+
+        let file = File::open(&track.file_path)
+            .map_err(|e| AppError::Audio(format!("Failed to open file: {}", e)))?;
+
+        let reader = BufReader::new(file);
+        let source = rodio::Decoder::new(reader)
+            .map_err(|e| AppError::Audio(format!("Failed to decode: {}", e)))?;
+
+        sink.append(source.take_duration(std::time::Duration::from_millis(duration_ms)));
+        sink.set_volume(PREVIEW_VOLUME);
+        self.preview_sink = Some(sink);
+
+        Ok(())
+    }
+
+    /// Stop the current preview, if any
+    pub fn stop_preview(&mut self) {
+        if let Some(sink) = self.preview_sink.take() {
+            sink.stop();
+            debug!("Preview stopped");
+        }
+    }
+
+    /// Check if a preview is playing
+    pub fn is_previewing(&self) -> bool {
+        self.preview_sink.as_ref().is_some_and(|sink| !sink.empty())
+    }
+
+    /// Periodic housekeeping: drops finished stingers and releases their duck
+    pub fn tick(&mut self) {
+        self.stinger_sinks.retain(|sink| !sink.empty());
+        if self.preview_sink.as_ref().is_some_and(|sink| sink.empty()) {
+            self.preview_sink = None;
+        }
+
+        if self.stinger_sinks.is_empty() && self.stinger_ducked {
+            self.stinger_ducked = false;
+            self.release_duck();
+        }
+    }
+
+    /// Number of stingers still playing
+    pub fn active_stingers(&self) -> usize {
+        self.stinger_sinks.iter().filter(|sink| !sink.empty()).count()
+    }
+
+    /// Share the music level meter (linear RMS, updated every 20ms while playing)
+    pub fn set_level_meter(&mut self, level: Arc<RwLock<f32>>) {
+        self.music_level = level;
+    }
+
+    /// Current linear RMS level of the music bus
+    pub fn music_level(&self) -> f32 {
+        *self.music_level.read()
+    }
+
     /// Stop music playback
     pub fn stop_music(&mut self) {
         if let Some(sink) = self.music_sink.take() {
             sink.stop();
         }
+        self.emit_track_stopped();
+        *self.music_level.write() = 0.0;
         *self.state.write() = EngineState::Idle;
         *self.current_track.write() = None;
         info!("Music stopped");
@@ -299,6 +673,14 @@ impl AudioEngine {
     /// Stop all playback
     pub fn stop_all(&mut self) {
         self.stop_music();
+        self.stop_preview();
+        for sink in self.stinger_sinks.drain(..) {
+            sink.stop();
+        }
+        if self.stinger_ducked {
+            self.stinger_ducked = false;
+            self.release_duck();
+        }
         info!("All playback stopped");
     }
 
@@ -306,6 +688,9 @@ impl AudioEngine {
     pub fn pause(&mut self) {
         if let Some(ref sink) = self.music_sink {
             sink.pause();
+            if let Some(playing) = self.current_track.write().as_mut() {
+                playing.pause(now_ms());
+            }
             *self.state.write() = EngineState::Paused;
             debug!("Playback paused");
         }
@@ -315,6 +700,9 @@ impl AudioEngine {
     pub fn resume(&mut self) {
         if let Some(ref sink) = self.music_sink {
             sink.play();
+            if let Some(playing) = self.current_track.write().as_mut() {
+                playing.resume(now_ms());
+            }
             *self.state.write() = EngineState::Playing;
             debug!("Playback resumed");
         }
@@ -351,6 +739,11 @@ impl AudioEngine {
         debug!("Ducking released");
     }
 
+    /// Music volume before the master volume is applied
+    pub fn music_volume(&self) -> f32 {
+        self.config.read().music_volume
+    }
+
     /// Set music volume
     pub fn set_music_volume(&mut self, volume: f32) {
         self.config.write().music_volume = volume.clamp(0.0, 1.0);
@@ -373,6 +766,23 @@ impl AudioEngine {
         self.config.write().crossfade_type = crossfade_type;
     }
 
+    /// Replace the genre transition matrix
+    pub fn set_crossfade_overrides(&mut self, overrides: HashMap<(String, String), CrossfadeType>) {
+        self.crossfade_overrides = overrides;
+    }
+
+    /// Crossfade for a genre change, falling back to the configured default
+    pub fn crossfade_type_between(&self, from_genre: Option<&str>, to_genre: Option<&str>) -> CrossfadeType {
+        from_genre
+            .zip(to_genre)
+            .and_then(|(from, to)| {
+                self.crossfade_overrides
+                    .get(&(from.to_string(), to.to_string()))
+                    .copied()
+            })
+            .unwrap_or(self.config.read().crossfade_type)
+    }
+
     /// Get current state
     pub fn state(&self) -> EngineState {
         *self.state.read()
@@ -380,7 +790,11 @@ impl AudioEngine {
 
     /// Get current track
     pub fn current_track(&self) -> Option<PlayingTrack> {
-        self.current_track.read().clone()
+        let mut playing = self.current_track.read().clone()?;
+        if playing.track.bpm.is_none() {
+            playing.track.bpm = self.track_bpm(&playing.track.id);
+        }
+        Some(playing)
     }
 
     /// Calculate music volume based on config and ducking
@@ -416,14 +830,9 @@ impl AudioEngine {
 
 impl Default for AudioEngine {
     fn default() -> Self {
-        Self::new().unwrap_or_else(|_| Self {
-            _stream: None,
-            stream_handle: None,
-            music_sink: None,
-            config: RwLock::new(EngineConfig::default()),
-            state: RwLock::new(EngineState::Idle),
-            current_track: RwLock::new(None),
-            is_ducking: RwLock::new(false),
+        Self::new().unwrap_or_else(|e| {
+            warn!("No audio output available, running headless: {}", e);
+            Self::headless()
         })
     }
 }
@@ -440,6 +849,39 @@ mod tests {
         assert_eq!(CrossfadeType::Long.duration_ms(), 5000);
     }
 
+    #[test]
+    fn test_stop_music_reports_played_time() {
+        let (tx, rx) = flume::unbounded();
+        let mut engine = AudioEngine::headless();
+        engine.set_playback_events(tx);
+
+        engine.stop_music();
+        assert!(rx.is_empty());
+
+        let track = Track {
+            id: "t1".to_string(),
+            name: "Tavern".to_string(),
+            file_path: "tavern.ogg".to_string(),
+            genre: None,
+            mood: None,
+            is_looping: false,
+            duration_ms: None,
+            bpm: None,
+        };
+        let mut playing = PlayingTrack::new(track, 0);
+        playing.started_at_ms -= 1500;
+        *engine.current_track.write() = Some(playing);
+        engine.stop_music();
+
+        match rx.try_recv().unwrap() {
+            PlaybackEvent::TrackStopped { track_id, played_ms } => {
+                assert_eq!(track_id, "t1");
+                assert!(played_ms >= 1500);
+            }
+            other => panic!("unexpected event: {:?}", other),
+        }
+    }
+
     #[test]
     fn test_engine_config() {
         let config = EngineConfig::default();
@@ -447,4 +889,137 @@ mod tests {
         assert_eq!(config.sfx_volume, 0.8);
         assert_eq!(config.crossfade_type, CrossfadeType::Musical);
     }
+
+    #[test]
+    fn test_crossfade_overrides() {
+        let mut engine = AudioEngine::headless();
+        engine.set_crossfade_type(CrossfadeType::Musical);
+
+        let mut overrides = HashMap::new();
+        overrides.insert(("exploration".to_string(), "combat".to_string()), CrossfadeType::Quick);
+        overrides.insert(("combat".to_string(), "exploration".to_string()), CrossfadeType::Long);
+        engine.set_crossfade_overrides(overrides);
+
+        assert_eq!(engine.crossfade_type_between(Some("exploration"), Some("combat")), CrossfadeType::Quick);
+        assert_eq!(engine.crossfade_type_between(Some("combat"), Some("exploration")), CrossfadeType::Long);
+        assert_eq!(engine.crossfade_type_between(Some("combat"), Some("social")), CrossfadeType::Musical);
+        assert_eq!(engine.crossfade_type_between(None, Some("combat")), CrossfadeType::Musical);
+        assert_eq!("quick".parse::<CrossfadeType>().unwrap(), CrossfadeType::Quick);
+    }
+
+    #[test]
+    fn test_position_excludes_paused_time() {
+        let track = Track {
+            id: "t1".to_string(),
+            name: "Test".to_string(),
+            file_path: "test.ogg".to_string(),
+            genre: None,
+            mood: None,
+            is_looping: false,
+            duration_ms: Some(60_000),
+            bpm: None,
+        };
+
+        let mut playing = PlayingTrack::new(track, 5_000);
+        let start = playing.started_at_ms;
+
+        playing.pause(start + 10_000);
+        assert_eq!(playing.position_ms(start + 30_000), 15_000);
+
+        playing.resume(start + 30_000);
+        assert_eq!(playing.position_ms(start + 35_000), 20_000);
+
+        // Long after the end, only the rest of the track counts as played
+        assert_eq!(playing.played_ms(start + 600_000), 55_000);
+        assert_eq!(playing.position_ms(start + 600_000), 60_000);
+    }
+
+    #[test]
+    fn test_headless_engine_errors_instead_of_panicking() {
+        let mut engine = AudioEngine::headless();
+        assert!(!engine.is_available());
+
+        let track = Track {
+            id: "t1".to_string(),
+            name: "Test".to_string(),
+            file_path: "missing.ogg".to_string(),
+            genre: None,
+            mood: None,
+            is_looping: false,
+            duration_ms: None,
+            bpm: None,
+        };
+
+        assert!(matches!(engine.play_track(&track), Err(AppError::Playback(_))));
+        assert!(matches!(engine.crossfade_to(&track), Err(AppError::Playback(_))));
+        assert!(matches!(engine.play_sfx_samples(&[0.0; 16], 16000), Err(AppError::Playback(_))));
+        assert!(matches!(engine.preview(&track, 5000), Err(AppError::Playback(_))));
+        assert!(!engine.is_previewing());
+        assert_eq!(engine.state(), EngineState::Idle);
+        assert!(engine.current_track().is_none());
+    }
+
+    /// An unplugged sink playing `samples` samples, and the output that plays it
+    fn idle_sink(samples: usize) -> (Sink, rodio::queue::SourcesQueueOutput<f32>) {
+        let (sink, output) = Sink::new_idle();
+        sink.append(rodio::buffer::SamplesBuffer::new(1, 1000, vec![0.5f32; samples]));
+        (sink, output)
+    }
+
+    #[test]
+    fn test_stinger_ducks_music_until_it_finishes() {
+        let mut engine = AudioEngine::headless();
+        let (music, _music_output) = idle_sink(1000);
+        music.set_volume(1.0);
+        engine.music_sink = Some(music);
+
+        let (stinger, mut output) = idle_sink(10);
+        engine.hold_stinger(stinger);
+        let ducked = engine.music_sink.as_ref().unwrap().volume();
+        assert!((ducked - engine.config.read().ducking_amount).abs() < 1e-6);
+
+        engine.tick();
+        assert_eq!(engine.active_stingers(), 1);
+        assert!(*engine.is_ducking.read());
+
+        // Play the stinger out; the next tick releases the duck
+        output.by_ref().take(20).count();
+        engine.tick();
+        assert_eq!(engine.active_stingers(), 0);
+        assert!(!*engine.is_ducking.read());
+        let released = engine.music_sink.as_ref().unwrap().volume();
+        assert_eq!(released, engine.calculate_music_volume());
+    }
+
+    #[test]
+    fn test_overlapping_stingers_extend_the_duck() {
+        let mut engine = AudioEngine::headless();
+        let (first, mut first_output) = idle_sink(10);
+        let (second, mut second_output) = idle_sink(10);
+        engine.hold_stinger(first);
+        engine.hold_stinger(second);
+        assert_eq!(engine.active_stingers(), 2);
+
+        first_output.by_ref().take(20).count();
+        engine.tick();
+        assert_eq!(engine.active_stingers(), 1);
+        assert!(*engine.is_ducking.read());
+
+        second_output.by_ref().take(20).count();
+        engine.tick();
+        assert!(!*engine.is_ducking.read());
+    }
+
+    #[test]
+    fn test_stinger_leaves_an_existing_duck_alone() {
+        let mut engine = AudioEngine::headless();
+        engine.duck();
+        let (stinger, mut output) = idle_sink(10);
+        engine.hold_stinger(stinger);
+
+        output.by_ref().take(20).count();
+        engine.tick();
+        assert_eq!(engine.active_stingers(), 0);
+        assert!(*engine.is_ducking.read());
+    }
 }
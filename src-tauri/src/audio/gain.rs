@@ -0,0 +1,116 @@
+//! Microphone input gain with a clipping indicator
+
+use std::time::{Duration, Instant};
+
+/// Lowest accepted input gain
+pub const MIN_INPUT_GAIN: f32 = 0.1;
+
+/// Highest accepted input gain
+pub const MAX_INPUT_GAIN: f32 = 4.0;
+
+/// How long a clipped sample keeps the indicator lit
+const CLIP_HOLD: Duration = Duration::from_secs(1);
+
+/// Clamp a requested gain to the accepted range
+pub fn clamp_gain(gain: f32) -> f32 {
+    if gain.is_finite() {
+        gain.clamp(MIN_INPUT_GAIN, MAX_INPUT_GAIN)
+    } else {
+        1.0
+    }
+}
+
+/// Gain applied in the capture callback, shared so it can change while recording
+#[derive(Debug, Clone)]
+pub struct InputGain {
+    gain: f32,
+    clipped_samples: u64,
+    last_clip: Option<Instant>,
+}
+
+impl InputGain {
+    /// Create with a gain (clamped to the accepted range)
+    pub fn new(gain: f32) -> Self {
+        Self {
+            gain: clamp_gain(gain),
+            clipped_samples: 0,
+            last_clip: None,
+        }
+    }
+
+    /// Apply the gain in place, hard-limiting and counting samples that reach full scale
+    pub fn apply(&mut self, samples: &mut [f32]) {
+        let mut clipped = 0u64;
+        for sample in samples.iter_mut() {
+            let amplified = *sample * self.gain;
+            if amplified.abs() >= 1.0 {
+                clipped += 1;
+            }
+            *sample = amplified.clamp(-1.0, 1.0);
+        }
+
+        if clipped > 0 {
+            self.clipped_samples += clipped;
+            self.last_clip = Some(Instant::now());
+        }
+    }
+
+    /// Set the gain (clamped to the accepted range)
+    pub fn set_gain(&mut self, gain: f32) {
+        self.gain = clamp_gain(gain);
+    }
+
+    /// Current gain
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Samples that reached full scale since the last reset
+    pub fn clipped_samples(&self) -> u64 {
+        self.clipped_samples
+    }
+
+    /// Check if any sample clipped within the last second
+    pub fn clipping_detected(&self) -> bool {
+        self.last_clip.is_some_and(|at| at.elapsed() < CLIP_HOLD)
+    }
+
+    /// Clear the clipping counter and indicator
+    pub fn reset_clipping(&mut self) {
+        self.clipped_samples = 0;
+        self.last_clip = None;
+    }
+}
+
+impl Default for InputGain {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gain_is_clamped_and_clipping_counted() {
+        assert_eq!(clamp_gain(10.0), MAX_INPUT_GAIN);
+        assert_eq!(clamp_gain(0.0), MIN_INPUT_GAIN);
+        assert_eq!(clamp_gain(f32::NAN), 1.0);
+
+        let mut gain = InputGain::new(2.0);
+        let mut quiet = [0.1, -0.2];
+        gain.apply(&mut quiet);
+        assert_eq!(quiet, [0.2, -0.4]);
+        assert!(!gain.clipping_detected());
+
+        let mut loud = [0.6, -0.5, 0.3];
+        gain.apply(&mut loud);
+        assert_eq!(loud, [1.0, -1.0, 0.6]);
+        assert_eq!(gain.clipped_samples(), 2);
+        assert!(gain.clipping_detected());
+
+        gain.reset_clipping();
+        assert!(!gain.clipping_detected());
+    }
+}
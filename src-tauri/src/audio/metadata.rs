@@ -0,0 +1,319 @@
+//! Track metadata extraction for library imports
+
+use crate::dsp::processing;
+use crate::error::AppError;
+use rodio::Source;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::{MetadataOptions, StandardTagKey, Tag};
+use symphonia::core::probe::Hint;
+
+/// Only the start of a file is analyzed, so huge files cannot hang an import
+const MAX_ANALYSIS_SECS: u64 = 600;
+
+/// Seconds decoded to estimate a track's tempo
+const BPM_ANALYSIS_SECS: u64 = 30;
+
+/// Offset of the preview used to tag a track's mood, past any intro
+const MOOD_PREVIEW_START_SECS: u64 = 30;
+
+/// Length of the mood preview
+const MOOD_PREVIEW_SECS: u64 = 10;
+
+/// Digital silence at the start or end longer than this is flagged
+const SILENCE_WARNING_SECS: f32 = 2.0;
+
+/// A jump between consecutive samples this large is heard as a click
+const CLICK_THRESHOLD: f32 = 1.0;
+
+/// Metadata read from an audio file
+#[derive(Debug, Clone)]
+pub struct TrackMetadata {
+    /// Title tag, or the file stem if the file has none
+    pub title: String,
+    /// Duration in milliseconds (None when neither the decoder nor the
+    /// container header knows it)
+    pub duration_ms: Option<u64>,
+    /// Problems found by the analysis pass
+    pub warnings: Vec<String>,
+}
+
+/// Validate that a file decodes and read its title and duration
+pub fn probe_track(path: &Path) -> Result<TrackMetadata, AppError> {
+    let duration_ms = read_duration_ms(path)?;
+    let warnings = analyze_track(path)?;
+
+    let title = read_title_tag(path).unwrap_or_else(|| {
+        path.file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string_lossy().to_string())
+    });
+
+    Ok(TrackMetadata {
+        title,
+        duration_ms,
+        warnings,
+    })
+}
+
+/// Open the file with rodio and take its duration, falling back to the
+/// container header; the file is never decoded in full
+fn read_duration_ms(path: &Path) -> Result<Option<u64>, AppError> {
+    let file = File::open(path).map_err(|e| AppError::Audio(format!("Failed to open file: {}", e)))?;
+    let decoder = rodio::Decoder::new(BufReader::new(file))
+        .map_err(|e| AppError::Audio(format!("Failed to decode: {}", e)))?;
+
+    if let Some(duration) = decoder.total_duration() {
+        return Ok(Some(duration.as_millis() as u64));
+    }
+    Ok(header_duration_ms(path))
+}
+
+/// Duration from the frame count and sample rate in the container header
+fn header_duration_ms(path: &Path) -> Option<u64> {
+    let file = File::open(path).ok()?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .ok()?;
+    let params = &probed.format.default_track()?.codec_params;
+    let sample_rate = params.sample_rate.filter(|&rate| rate > 0)?;
+    Some(params.n_frames? * 1000 / sample_rate as u64)
+}
+
+/// Estimate a track's tempo from its first `BPM_ANALYSIS_SECS`
+pub fn detect_track_bpm(path: &Path) -> Result<Option<f32>, AppError> {
+    let file = File::open(path).map_err(|e| AppError::Audio(format!("Failed to open file: {}", e)))?;
+    let decoder = rodio::Decoder::new(BufReader::new(file))
+        .map_err(|e| AppError::Audio(format!("Failed to decode: {}", e)))?;
+
+    let sample_rate = decoder.sample_rate();
+    let channels = decoder.channels().max(1);
+    let limit = (sample_rate as u64 * channels as u64 * BPM_ANALYSIS_SECS) as usize;
+
+    let samples: Vec<f32> = decoder.convert_samples::<f32>().take(limit).collect();
+    let mono = processing::stereo_to_mono(&samples, channels);
+    Ok(processing::detect_bpm(&mono, sample_rate))
+}
+
+/// Decode the mono preview a track's mood is tagged from, with its sample rate
+///
+/// Tracks shorter than the preview offset are previewed from the start.
+pub fn mood_preview(path: &Path) -> Result<(Vec<f32>, u32), AppError> {
+    let file = File::open(path).map_err(|e| AppError::Audio(format!("Failed to open file: {}", e)))?;
+    let decoder = rodio::Decoder::new(BufReader::new(file))
+        .map_err(|e| AppError::Audio(format!("Failed to decode: {}", e)))?;
+
+    let sample_rate = decoder.sample_rate();
+    let channels = decoder.channels().max(1);
+    let frame = sample_rate as u64 * channels as u64;
+    let limit = (frame * (MOOD_PREVIEW_START_SECS + MOOD_PREVIEW_SECS)) as usize;
+
+    let samples: Vec<f32> = decoder.convert_samples::<f32>().take(limit).collect();
+    let mono = processing::stereo_to_mono(&samples, channels);
+
+    let start = (sample_rate as u64 * MOOD_PREVIEW_START_SECS) as usize;
+    let preview_len = (sample_rate as u64 * MOOD_PREVIEW_SECS) as usize;
+    let preview = if mono.len() > start {
+        mono[start..].to_vec()
+    } else {
+        mono.into_iter().take(preview_len).collect()
+    };
+    Ok((preview, sample_rate))
+}
+
+/// Decode up to `MAX_ANALYSIS_SECS` of a file and look for damage
+fn analyze_track(path: &Path) -> Result<Vec<String>, AppError> {
+    let file = File::open(path).map_err(|e| AppError::Audio(format!("Failed to open file: {}", e)))?;
+    let decoder = rodio::Decoder::new(BufReader::new(file))
+        .map_err(|e| AppError::Audio(format!("Failed to decode: {}", e)))?;
+
+    let sample_rate = decoder.sample_rate();
+    let channels = decoder.channels().max(1);
+    let limit = (sample_rate as u64 * channels as u64 * MAX_ANALYSIS_SECS) as usize;
+
+    Ok(analyze_samples(
+        decoder.convert_samples::<f32>().take(limit),
+        sample_rate,
+        channels,
+    ))
+}
+
+/// Scan interleaved samples for clicks, out-of-range or non-finite values and
+/// long stretches of digital silence at either end
+pub fn analyze_samples<I>(samples: I, sample_rate: u32, channels: u16) -> Vec<String>
+where
+    I: IntoIterator<Item = f32>,
+{
+    let channels = channels.max(1) as usize;
+    let mut previous = vec![0.0f32; channels];
+
+    let mut non_finite = 0usize;
+    let mut out_of_range = 0usize;
+    let mut clicks = 0usize;
+    let mut leading_silence: Option<usize> = None;
+    let mut trailing_silence = 0usize;
+    let mut total = 0usize;
+
+    for (i, sample) in samples.into_iter().enumerate() {
+        total += 1;
+
+        if !sample.is_finite() {
+            non_finite += 1;
+            continue;
+        }
+        if sample.abs() > 1.0 {
+            out_of_range += 1;
+        }
+
+        let channel = i % channels;
+        if i >= channels && (sample - previous[channel]).abs() > CLICK_THRESHOLD {
+            clicks += 1;
+        }
+        previous[channel] = sample;
+
+        if sample == 0.0 {
+            trailing_silence += 1;
+        } else {
+            leading_silence.get_or_insert(i);
+            trailing_silence = 0;
+        }
+    }
+
+    let samples_per_sec = sample_rate.max(1) as f32 * channels as f32;
+    // An all-silent file counts as leading silence only
+    let leading_silence = leading_silence.unwrap_or(total);
+    let trailing_silence = if leading_silence == total { 0 } else { trailing_silence };
+
+    let mut warnings = Vec::new();
+    if non_finite > 0 {
+        warnings.push(format!("{} NaN/infinite samples", non_finite));
+    }
+    if out_of_range > 0 {
+        warnings.push(format!("{} samples exceed ±1.0", out_of_range));
+    }
+    if clicks > 0 {
+        warnings.push(format!("{} clicks", clicks));
+    }
+    for (position, run) in [("start", leading_silence), ("end", trailing_silence)] {
+        let secs = run as f32 / samples_per_sec;
+        if secs > SILENCE_WARNING_SECS {
+            warnings.push(format!("{:.1}s of digital silence at the {}", secs, position));
+        }
+    }
+
+    warnings
+}
+
+/// Read the title tag with symphonia
+fn read_title_tag(path: &Path) -> Option<String> {
+    let file = File::open(path).ok()?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let mut probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .ok()?;
+
+    let from_probe = probed
+        .metadata
+        .get()
+        .and_then(|m| m.current().and_then(|rev| find_title(rev.tags())));
+
+    from_probe.or_else(|| {
+        probed
+            .format
+            .metadata()
+            .current()
+            .and_then(|rev| find_title(rev.tags()))
+    })
+}
+
+fn find_title(tags: &[Tag]) -> Option<String> {
+    tags.iter()
+        .find(|tag| tag.std_key == Some(StandardTagKey::TrackTitle))
+        .map(|tag| tag.value.to_string())
+        .filter(|title| !title.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_wav_falls_back_to_file_stem() {
+        let path = std::env::temp_dir().join(format!("probe-{}.wav", uuid::Uuid::new_v4()));
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..8000 {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let metadata = probe_track(&path).unwrap();
+        let from_header = header_duration_ms(&path);
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(metadata.duration_ms, Some(1000));
+        assert_eq!(from_header, Some(1000));
+        assert!(metadata.title.starts_with("probe-"));
+        assert!(metadata.warnings.is_empty());
+    }
+
+    #[test]
+    fn test_mood_preview_of_short_track_starts_at_zero() {
+        let path = std::env::temp_dir().join(format!("preview-{}.wav", uuid::Uuid::new_v4()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        for _ in 0..8000 * 2 {
+            writer.write_sample(1000i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let (preview, sample_rate) = mood_preview(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(sample_rate, 8000);
+        assert_eq!(preview.len(), 8000);
+    }
+
+    #[test]
+    fn test_analyze_samples_flags_damage() {
+        let tone: Vec<f32> = (0..8000).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        assert!(analyze_samples(tone.clone(), 8000, 1).is_empty());
+
+        let mut damaged = vec![0.0f32; 8000 * 3];
+        damaged.extend(&tone);
+        damaged.extend([0.9, -0.9, f32::NAN, 1.5]);
+
+        let warnings = analyze_samples(damaged, 8000, 1);
+        assert!(warnings.iter().any(|w| w.contains("NaN")));
+        assert!(warnings.iter().any(|w| w.contains("exceed")));
+        assert!(warnings.iter().any(|w| w.contains("clicks")));
+        assert!(warnings.iter().any(|w| w.contains("at the start")));
+        assert!(!warnings.iter().any(|w| w.contains("at the end")));
+    }
+}
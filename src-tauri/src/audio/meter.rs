@@ -0,0 +1,164 @@
+//! Level metering
+//!
+//! `MeteredSource` wraps a playback source and publishes the RMS of what
+//! passed through it every 20ms, without allocating on the audio thread.
+//! `spawn_peak_meter` turns per-chunk input levels into a steady 60 Hz feed.
+
+use parking_lot::RwLock;
+use rodio::Source;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Metering window length in milliseconds (50 updates per second)
+const METER_WINDOW_MS: u32 = 20;
+
+/// Input peak update interval (60 updates per second)
+const PEAK_INTERVAL: Duration = Duration::from_micros(16_667);
+
+/// Level reported for silence, matching `processing::calculate_db`
+pub const SILENCE_DB: f32 = -96.0;
+
+/// Source adapter that measures the RMS level of the samples it yields
+pub struct MeteredSource<S> {
+    inner: S,
+    level: Arc<RwLock<f32>>,
+    window_len: usize,
+    count: usize,
+    sum_squares: f32,
+}
+
+impl<S> MeteredSource<S>
+where
+    S: Source<Item = f32>,
+{
+    /// Wrap a source, publishing its linear RMS level into `level`
+    pub fn new(inner: S, level: Arc<RwLock<f32>>) -> Self {
+        let samples_per_second = inner.sample_rate() as usize * inner.channels().max(1) as usize;
+        let window_len = (samples_per_second * METER_WINDOW_MS as usize / 1000).max(1);
+
+        Self {
+            inner,
+            level,
+            window_len,
+            count: 0,
+            sum_squares: 0.0,
+        }
+    }
+}
+
+impl<S> Iterator for MeteredSource<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let Some(sample) = self.inner.next() else {
+            *self.level.write() = 0.0;
+            return None;
+        };
+
+        self.sum_squares += sample * sample;
+        self.count += 1;
+
+        if self.count >= self.window_len {
+            *self.level.write() = (self.sum_squares / self.count as f32).sqrt();
+            self.sum_squares = 0.0;
+            self.count = 0;
+        }
+
+        Some(sample)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<S> Source for MeteredSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+/// Take the loudest level queued since the last call, if any
+pub fn drain_peak(levels: &flume::Receiver<f32>) -> Option<f32> {
+    levels.try_iter().reduce(f32::max)
+}
+
+/// Publish the peak of the dB levels sent on `levels` 60 times a second
+///
+/// `on_peak` runs on the meter thread for every window that received levels.
+/// The thread ends once every sender is dropped, leaving `peak` at silence.
+pub fn spawn_peak_meter<F>(levels: flume::Receiver<f32>, peak: Arc<RwLock<f32>>, on_peak: F)
+where
+    F: Fn(f32) + Send + 'static,
+{
+    let spawned = std::thread::Builder::new()
+        .name("peak-meter".to_string())
+        .spawn(move || {
+            while !levels.is_disconnected() || !levels.is_empty() {
+                if let Some(db) = drain_peak(&levels) {
+                    *peak.write() = db;
+                    on_peak(db);
+                }
+                std::thread::sleep(PEAK_INTERVAL);
+            }
+            *peak.write() = SILENCE_DB;
+        });
+
+    if let Err(e) = spawned {
+        tracing::warn!("Failed to start peak meter: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    #[test]
+    fn test_metered_source_publishes_rms() {
+        let level = Arc::new(RwLock::new(0.0));
+        let samples = vec![0.5f32; 1600];
+        let mut source = MeteredSource::new(SamplesBuffer::new(1, 16000, samples), level.clone());
+
+        // One 20ms window at 16kHz mono
+        for _ in 0..320 {
+            source.next();
+        }
+        assert!((*level.read() - 0.5).abs() < 1e-4);
+
+        // Level drops to silence once the source is exhausted
+        for _ in source.by_ref() {}
+        assert_eq!(*level.read(), 0.0);
+    }
+
+    #[test]
+    fn test_drain_peak_takes_window_max() {
+        let (tx, rx) = flume::unbounded();
+        assert_eq!(drain_peak(&rx), None);
+
+        for db in [-40.0, -12.5, -30.0] {
+            tx.send(db).unwrap();
+        }
+        assert_eq!(drain_peak(&rx), Some(-12.5));
+        assert_eq!(drain_peak(&rx), None);
+    }
+}
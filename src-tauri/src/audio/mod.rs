@@ -1,7 +1,15 @@
 //! Audio module - handles microphone input and audio playback
 
 pub mod capture;
+pub mod controller;
 pub mod engine;
+pub mod gain;
+pub mod metadata;
+pub mod meter;
 pub mod playback;
+pub mod resume;
+pub mod ring_buffer;
 
+pub use controller::AudioController;
+pub use ring_buffer::AudioRingBuffer;
 pub use engine::*;
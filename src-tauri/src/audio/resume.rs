@@ -0,0 +1,82 @@
+//! Playback snapshots - remembers what was playing so it can resume on startup
+
+use crate::audio::engine::{now_ms, PlayingTrack};
+use crate::db::Repository;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Settings key holding the last playback snapshot (JSON)
+pub const LAST_PLAYBACK_SETTING: &str = "last_playback";
+
+/// Settings key for the opt-in resume-on-startup behaviour ("true"/"false")
+pub const RESUME_LAST_TRACK_SETTING: &str = "resume_last_track";
+
+/// How often the position of a playing track is saved
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// What was playing, and where
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlaybackSnapshot {
+    pub track_id: String,
+    pub position_ms: u64,
+    pub mood: Option<String>,
+}
+
+impl PlaybackSnapshot {
+    /// Capture the current position of a playing track
+    pub fn of(playing: &PlayingTrack) -> Self {
+        Self {
+            track_id: playing.track.id.clone(),
+            position_ms: playing.position_ms(now_ms()),
+            mood: playing.track.mood.clone(),
+        }
+    }
+}
+
+/// Store a snapshot in the settings table
+pub fn save_snapshot(repo: &Repository, snapshot: &PlaybackSnapshot) -> Result<(), AppError> {
+    let json = serde_json::to_string(snapshot).map_err(|e| AppError::Serialization(e.to_string()))?;
+    repo.set_setting(LAST_PLAYBACK_SETTING, &json)
+}
+
+/// Load the last stored snapshot, if any
+pub fn load_snapshot(repo: &Repository) -> Result<Option<PlaybackSnapshot>, AppError> {
+    let Some(json) = repo.get_setting(LAST_PLAYBACK_SETTING)? else {
+        return Ok(None);
+    };
+
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| AppError::Serialization(e.to_string()))
+}
+
+/// Check if the user opted in to resuming the last track on startup
+pub fn resume_enabled(repo: &Repository) -> bool {
+    matches!(repo.get_setting(RESUME_LAST_TRACK_SETTING), Ok(Some(value)) if value == "true")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let db = Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+        assert!(load_snapshot(&repo).unwrap().is_none());
+        assert!(!resume_enabled(&repo));
+
+        let snapshot = PlaybackSnapshot {
+            track_id: "t1".to_string(),
+            position_ms: 42_000,
+            mood: Some("tense".to_string()),
+        };
+        save_snapshot(&repo, &snapshot).unwrap();
+        assert_eq!(load_snapshot(&repo).unwrap(), Some(snapshot));
+
+        repo.set_setting(RESUME_LAST_TRACK_SETTING, "true").unwrap();
+        assert!(resume_enabled(&repo));
+    }
+}
@@ -0,0 +1,143 @@
+//! Fixed-capacity buffer for captured audio
+//!
+//! Capture keeps writing for the whole session, so the buffer is a
+//! `ringbuf::HeapRb` holding only the most recent `capacity` samples; the
+//! oldest ones are overwritten. Streaming consumers pop fixed-size chunks from
+//! the consumer end, the transcription path drains everything at once and
+//! calibration peeks at the last few seconds without consuming them.
+
+use ringbuf::traits::{Consumer, Observer, RingBuffer};
+use ringbuf::HeapRb;
+
+/// Ring buffer of interleaved f32 samples
+pub struct AudioRingBuffer {
+    ring: HeapRb<f32>,
+    /// Interleaved samples per second, used to convert seconds to samples
+    sample_rate: u32,
+}
+
+impl std::fmt::Debug for AudioRingBuffer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AudioRingBuffer")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity())
+            .field("sample_rate", &self.sample_rate)
+            .finish()
+    }
+}
+
+impl AudioRingBuffer {
+    /// Create an empty buffer holding at most `capacity` samples
+    pub fn new(capacity: usize, sample_rate: u32) -> Self {
+        Self {
+            ring: HeapRb::new(capacity.max(1)),
+            sample_rate,
+        }
+    }
+
+    /// Append samples, overwriting the oldest ones once full
+    pub fn push_slice(&mut self, samples: &[f32]) {
+        self.ring.push_slice_overwrite(samples);
+    }
+
+    /// Pop every complete chunk of `chunk_size` samples
+    ///
+    /// Samples that were overwritten before being popped are lost; a partial
+    /// chunk stays until it fills up.
+    pub fn drain_chunks(&mut self, chunk_size: usize) -> Vec<Vec<f32>> {
+        if chunk_size == 0 {
+            return Vec::new();
+        }
+
+        let count = self.ring.occupied_len() / chunk_size;
+        (0..count)
+            .map(|_| {
+                let mut chunk = vec![0.0; chunk_size];
+                self.ring.pop_slice(&mut chunk);
+                chunk
+            })
+            .collect()
+    }
+
+    /// Pop all buffered samples as one contiguous block
+    pub fn drain_all(&mut self) -> Vec<f32> {
+        let mut samples = vec![0.0; self.ring.occupied_len()];
+        self.ring.pop_slice(&mut samples);
+        samples
+    }
+
+    /// Copy the most recent `seconds` of audio (or everything, if less is buffered)
+    pub fn snapshot_last(&self, seconds: f32) -> Vec<f32> {
+        let wanted = (seconds.max(0.0) * self.sample_rate as f32) as usize;
+        let skip = self.ring.occupied_len().saturating_sub(wanted);
+        self.ring.iter().skip(skip).copied().collect()
+    }
+
+    /// Drop all samples
+    pub fn clear(&mut self) {
+        self.ring.clear();
+    }
+
+    /// Set the rate used by `snapshot_last`
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Interleaved samples per second
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Number of buffered samples
+    pub fn len(&self) -> usize {
+        self.ring.occupied_len()
+    }
+
+    /// Check if nothing is buffered
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    /// Maximum number of samples kept
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity().get()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_stays_bounded_over_long_session() {
+        let mut buffer = AudioRingBuffer::new(48_000, 16_000);
+        let block = vec![0.1f32; 4800];
+
+        // One simulated hour of 100 ms blocks at 48 kHz
+        for _ in 0..(60 * 60 * 10) {
+            buffer.push_slice(&block);
+            assert!(buffer.len() <= buffer.capacity());
+        }
+
+        assert_eq!(buffer.len(), 48_000);
+        assert_eq!(buffer.snapshot_last(1.0).len(), 16_000);
+        assert_eq!(buffer.snapshot_last(60.0).len(), 48_000);
+        assert_eq!(buffer.drain_all().len(), 48_000);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_drain_chunks_is_incremental() {
+        let mut buffer = AudioRingBuffer::new(10, 16_000);
+        buffer.push_slice(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        assert_eq!(buffer.drain_chunks(2), vec![vec![1.0, 2.0], vec![3.0, 4.0]]);
+        assert!(buffer.drain_chunks(2).is_empty());
+
+        // 5.0 is still pending; overwriting drops the oldest unread samples
+        buffer.push_slice(&(6..=20).map(|i| i as f32).collect::<Vec<_>>());
+        assert_eq!(buffer.drain_chunks(4), vec![vec![11.0, 12.0, 13.0, 14.0], vec![15.0, 16.0, 17.0, 18.0]]);
+        assert_eq!(buffer.snapshot_last(0.0), Vec::<f32>::new());
+        assert_eq!(buffer.drain_all(), vec![19.0, 20.0]);
+    }
+}
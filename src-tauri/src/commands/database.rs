@@ -0,0 +1,108 @@
+//! Database maintenance commands
+
+use crate::commands::repository;
+use crate::db::{Database, Repository};
+use crate::error::CommandError;
+use crate::state::SessionState;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::{AppHandle, Emitter, State};
+use tracing::info;
+
+/// Event emitted after the database has been replaced from a backup
+pub const DATABASE_RESTORED_EVENT: &str = "database_restored";
+
+/// Database size around a vacuum
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VacuumReport {
+    pub size_before: u64,
+    pub size_after: u64,
+    pub freed_bytes: u64,
+}
+
+/// Compact the database file, reclaiming pages left by deleted rows
+#[tauri::command]
+pub fn vacuum_database(state: State<'_, AppState>) -> Result<VacuumReport, CommandError> {
+    let repo = repository(&state)?;
+
+    let size_before = repo.database_size()?;
+    repo.vacuum()?;
+    let size_after = repo.database_size()?;
+
+    let freed_bytes = size_before.saturating_sub(size_after);
+    info!("Vacuumed database: {} -> {} bytes", size_before, size_after);
+
+    Ok(VacuumReport {
+        size_before,
+        size_after,
+        freed_bytes,
+    })
+}
+
+/// Check the database file for corruption
+#[tauri::command]
+pub fn check_database_integrity(state: State<'_, AppState>) -> Result<bool, CommandError> {
+    repository(&state)?.integrity_check().map_err(CommandError::from)
+}
+
+/// Write a consistent copy of the database to `dest_path`
+#[tauri::command]
+pub fn backup_database(app: AppHandle, dest_path: String) -> Result<(), CommandError> {
+    let db = open_database(&app)?;
+    db.backup(Path::new(&dest_path)).map_err(CommandError::from)
+}
+
+/// Replace the database with the backup at `src_path`
+///
+/// Startup is marked incomplete while the pool is rebuilt, then everything
+/// loaded from the database at startup is reloaded.
+#[tauri::command]
+pub fn restore_database(app: AppHandle, state: State<'_, AppState>, src_path: String) -> Result<(), CommandError> {
+    if *state.session_state.read() != SessionState::Idle {
+        return Err(CommandError::invalid_state("Stop the session before restoring the database"));
+    }
+
+    *state.startup_complete.write() = false;
+    let result = replace_database(&app, &state, Path::new(&src_path));
+    if result.is_err() && state.db_pool.read().is_none() {
+        // Keep serving whatever is on disk rather than leaving the app without a database
+        if let Ok(db) = open_database(&app) {
+            state.db_pool.write().replace(db.pool().clone());
+        }
+    }
+    *state.startup_complete.write() = true;
+    result?;
+
+    let _ = app.emit(DATABASE_RESTORED_EVENT, &src_path);
+    Ok(())
+}
+
+fn replace_database(app: &AppHandle, state: &AppState, src_path: &Path) -> Result<(), CommandError> {
+    // Drop the managed pool first so no connection writes over the restored file
+    state.db_pool.write().take();
+
+    let db = open_database(app)?;
+    db.restore(src_path)?;
+
+    // Connections opened before the copy may hold stale pages
+    let db = open_database(app)?;
+    let pool = db.pool().clone();
+    state.db_pool.write().replace(pool.clone());
+
+    crate::commands::library::restore_library_watcher(app);
+    crate::commands::training::restore_emotion_baseline(state);
+    crate::commands::keywords::restore_keywords(state);
+    crate::commands::session::restore_app_mode(state);
+    crate::commands::settings::restore_input_gain(state);
+    crate::commands::hotkeys::restore_hotkeys(state);
+    crate::restore_playback(state, Repository::new(pool));
+
+    info!("Database restored from {:?}", src_path);
+    Ok(())
+}
+
+fn open_database(app: &AppHandle) -> Result<Database, CommandError> {
+    let path = crate::database_path(app)?;
+    Database::new(&path.to_string_lossy()).map_err(CommandError::from)
+}
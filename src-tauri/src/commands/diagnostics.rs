@@ -0,0 +1,291 @@
+//! App health report for support requests
+//!
+//! Every section is gathered independently and degrades to an error string
+//! or `None` instead of failing the whole report, so it can be collected
+//! even when the database or the models are missing.
+
+use crate::audio::capture::{AudioCapture, CaptureSource};
+use crate::commands::session;
+use crate::db::DbPool;
+use crate::error::{AppError, CommandError};
+use crate::inference::whisper;
+use crate::ml::{get_onnx_env, ModelPaths};
+use crate::startup::StartupManager;
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::State;
+
+/// Number of log lines included in the report
+const LOG_TAIL_LINES: usize = 50;
+
+/// How long to wait for a pooled connection before reporting the pool as busy
+const POOL_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Model file and whether it is on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelStatus {
+    pub path: String,
+    pub exists: bool,
+}
+
+impl ModelStatus {
+    fn of(path: &Path) -> Self {
+        Self {
+            path: path.display().to_string(),
+            exists: path.exists(),
+        }
+    }
+}
+
+/// Inference models and runtime
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelDiagnostics {
+    pub whisper: ModelStatus,
+    /// Built with the `whisper` feature
+    pub whisper_enabled: bool,
+    pub onnx_initialized: bool,
+    pub vad: Option<ModelStatus>,
+    pub speaker: Option<ModelStatus>,
+    pub emotion: Option<ModelStatus>,
+    /// Live detection is running with its models loaded
+    pub detection_ready: bool,
+}
+
+/// Database file, schema and connection pool
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DatabaseDiagnostics {
+    pub available: bool,
+    pub path: Option<String>,
+    pub schema_version: Option<i64>,
+    pub max_connections: Option<u32>,
+    pub open_connections: Option<u32>,
+    pub idle_connections: Option<u32>,
+    pub error: Option<String>,
+}
+
+/// Devices the next session records from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioDiagnostics {
+    /// None uses the system default input
+    pub input_device: Option<String>,
+    pub capture_source: CaptureSource,
+    pub loopback_device: Option<String>,
+}
+
+/// Startup phase, how long each phase took and which model files were usable
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StartupDiagnostics {
+    pub phase: String,
+    pub ui_ready_ms: Option<u64>,
+    pub detection_ready_ms: Option<u64>,
+    /// Model files found usable at startup; the models load where they are used
+    pub vad_verified: bool,
+    pub speaker_model_verified: bool,
+    pub whisper_verified: bool,
+    /// Model files to download before detection can run
+    pub missing_models: Vec<String>,
+    /// Phase failures and missing models, oldest first
+    pub errors: Vec<String>,
+    /// Phases still not ready past their startup timeout
+    pub timeouts: Vec<String>,
+}
+
+/// Everything support needs to triage a problem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostics {
+    pub version: String,
+    pub models: ModelDiagnostics,
+    pub database: DatabaseDiagnostics,
+    pub audio: AudioDiagnostics,
+    pub startup: StartupDiagnostics,
+    pub log_file: Option<String>,
+    pub log_tail: Vec<String>,
+}
+
+/// Collect a health report of the running app
+#[tauri::command]
+pub fn get_diagnostics(state: State<'_, AppState>) -> Result<Diagnostics, CommandError> {
+    let log_file = current_log_file(&crate::log_dir());
+
+    Ok(Diagnostics {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        models: model_diagnostics(&state),
+        database: database_diagnostics(state.db_pool.read().as_ref()),
+        audio: AudioDiagnostics {
+            input_device: session::selected_input_device(&state),
+            capture_source: state.config.read().capture_source,
+            loopback_device: AudioCapture::loopback_device_name(),
+        },
+        startup: startup_diagnostics(&state.startup),
+        log_tail: log_file.as_deref().map(|path| tail_lines(path, LOG_TAIL_LINES)).unwrap_or_default(),
+        log_file: log_file.map(|path| path.display().to_string()),
+    })
+}
+
+/// Startup timings, usable model files, errors and late phases, to tell why detection isn't working
+#[tauri::command]
+pub fn get_startup_diagnostics(state: State<'_, AppState>) -> Result<StartupDiagnostics, CommandError> {
+    Ok(startup_diagnostics(&state.startup))
+}
+
+/// Directory holding the log files, for opening it from the UI
+#[tauri::command]
+pub fn log_dir() -> Result<String, CommandError> {
+    let dir = crate::log_dir();
+    std::fs::create_dir_all(&dir).map_err(AppError::from)?;
+    Ok(dir.display().to_string())
+}
+
+/// Last `lines` log lines (at most 500), oldest first
+#[tauri::command]
+pub fn get_recent_logs(lines: usize) -> Result<Vec<String>, CommandError> {
+    Ok(crate::logging::recent_lines(crate::recent_logs(), lines))
+}
+
+fn model_diagnostics(state: &AppState) -> ModelDiagnostics {
+    let paths = ModelPaths::default();
+    let status = |path: Option<String>| path.map(|p| ModelStatus::of(Path::new(&p)));
+
+    ModelDiagnostics {
+        whisper: ModelStatus::of(&whisper::get_model_path()),
+        whisper_enabled: whisper::is_whisper_enabled(),
+        onnx_initialized: get_onnx_env().initialized,
+        vad: status(paths.vad_model),
+        speaker: status(paths.speaker_model),
+        emotion: status(paths.emotion_model),
+        detection_ready: *state.detection_ready.read(),
+    }
+}
+
+fn database_diagnostics(pool: Option<&DbPool>) -> DatabaseDiagnostics {
+    let Some(pool) = pool else {
+        return DatabaseDiagnostics {
+            error: Some("Database not available".to_string()),
+            ..Default::default()
+        };
+    };
+
+    let pool_state = pool.state();
+    let mut diagnostics = DatabaseDiagnostics {
+        available: true,
+        max_connections: Some(pool.max_size()),
+        open_connections: Some(pool_state.connections),
+        idle_connections: Some(pool_state.idle_connections),
+        ..Default::default()
+    };
+
+    let conn = match pool.get_timeout(POOL_TIMEOUT) {
+        Ok(conn) => conn,
+        Err(e) => {
+            diagnostics.error = Some(e.to_string());
+            return diagnostics;
+        }
+    };
+
+    diagnostics.path = conn
+        .query_row("SELECT file FROM pragma_database_list WHERE name = 'main'", [], |row| {
+            row.get::<_, String>(0)
+        })
+        .ok()
+        .filter(|file| !file.is_empty());
+    match conn.query_row("SELECT COALESCE(MAX(version), 0) FROM schema_migrations", [], |row| row.get(0)) {
+        Ok(version) => diagnostics.schema_version = Some(version),
+        Err(e) => diagnostics.error = Some(e.to_string()),
+    }
+
+    diagnostics
+}
+
+fn startup_diagnostics(manager: &StartupManager) -> StartupDiagnostics {
+    let millis = |duration: Option<Duration>| duration.map(|d| d.as_millis() as u64);
+    let startup = manager.state();
+    let models = startup.models_verified();
+    let timeouts = [manager.check_ui_timeout(), manager.check_detection_timeout()]
+        .into_iter()
+        .filter_map(Result::err)
+        .map(|e| e.to_string())
+        .collect();
+
+    StartupDiagnostics {
+        phase: startup.phase().to_string(),
+        ui_ready_ms: millis(startup.ui_ready_time()),
+        detection_ready_ms: millis(startup.detection_ready_time()),
+        vad_verified: models.vad,
+        speaker_model_verified: models.speaker,
+        whisper_verified: models.whisper,
+        missing_models: startup.missing_models().iter().map(|path| path.display().to_string()).collect(),
+        errors: startup.errors(),
+        timeouts,
+    }
+}
+
+/// Most recently written log file in `log_dir`
+///
+/// Daily rotation appends the date to the file name, so the newest one is
+/// today's log.
+fn current_log_file(log_dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(log_dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(crate::LOG_FILE_PREFIX))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .max_by_key(|(modified, _)| *modified)
+        .map(|(_, path)| path)
+}
+
+/// Last `count` lines of the file at `path`, or none if it can't be read
+fn tail_lines(path: &Path, count: usize) -> Vec<String> {
+    let Ok(file) = std::fs::File::open(path) else {
+        return Vec::new();
+    };
+
+    let mut lines = std::collections::VecDeque::with_capacity(count);
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        if lines.len() == count {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+    lines.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_database_diagnostics() {
+        let missing = database_diagnostics(None);
+        assert!(!missing.available);
+        assert!(missing.error.is_some());
+
+        let db = Database::in_memory().unwrap();
+        let diagnostics = database_diagnostics(Some(db.pool()));
+        assert!(diagnostics.available);
+        assert!(diagnostics.schema_version.unwrap() > 0);
+        assert_eq!(diagnostics.max_connections, Some(1));
+        assert!(diagnostics.error.is_none());
+    }
+
+    #[test]
+    fn test_tail_lines_keeps_the_last_lines() {
+        let dir = std::env::temp_dir().join(format!("diagnostics-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.2024-05-01", crate::LOG_FILE_PREFIX));
+        let text: Vec<String> = (0..60).map(|i| format!("line {}", i)).collect();
+        std::fs::write(&path, text.join("\n")).unwrap();
+
+        assert_eq!(current_log_file(&dir), Some(path.clone()));
+        let tail = tail_lines(&path, LOG_TAIL_LINES);
+        assert_eq!(tail.len(), LOG_TAIL_LINES);
+        assert_eq!(tail.first().map(String::as_str), Some("line 10"));
+        assert_eq!(tail.last().map(String::as_str), Some("line 59"));
+
+        assert!(tail_lines(&dir.join("missing"), 5).is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,246 @@
+//! Session report export
+//!
+//! A report gathers a session's row, its detection events and the tracks
+//! played during it, and is written either as a JSON dump or as a Markdown
+//! recap for the GM.
+
+use crate::commands::repository;
+use crate::db::{DetectionEvent, Repository, Session};
+use crate::error::{AppError, CommandError};
+use crate::AppState;
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use tauri::State;
+use tracing::info;
+
+/// Output format of a session report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Json,
+    Markdown,
+}
+
+/// A track started during the session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayedTrack {
+    pub played_at: String,
+    pub track_id: String,
+    /// Library name, or None if the track has since been removed
+    pub name: Option<String>,
+}
+
+/// Everything recorded about one session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionReport {
+    pub session: Session,
+    pub events: Vec<DetectionEvent>,
+    pub tracks_played: Vec<PlayedTrack>,
+}
+
+/// Event types with their own Markdown section, in display order
+const SECTIONS: [(&str, &str); 4] = [
+    ("keyword", "Keywords"),
+    ("emotion", "Emotion timeline"),
+    ("dual_signal", "Dual signals"),
+    ("transcription", "Transcript"),
+];
+
+/// Write a report of a session to `path`, returning the written path
+#[tauri::command]
+pub fn export_session(
+    state: State<'_, AppState>,
+    session_id: String,
+    format: ExportFormat,
+    path: String,
+) -> Result<String, CommandError> {
+    let repo = repository(&state)?;
+    let report = build_report(&repo, &session_id)?;
+
+    let contents = match format {
+        ExportFormat::Json => {
+            serde_json::to_string_pretty(&report).map_err(|e| AppError::Serialization(e.to_string()))?
+        }
+        ExportFormat::Markdown => render_markdown(&report),
+    };
+    std::fs::write(&path, contents).map_err(AppError::from)?;
+
+    info!("Exported session {} to {}", session_id, path);
+    Ok(path)
+}
+
+/// Collect a session's row, events and played tracks from the database
+fn build_report(repo: &Repository, session_id: &str) -> Result<SessionReport, AppError> {
+    let session = repo
+        .get_session(session_id)?
+        .ok_or_else(|| AppError::NotFound(format!("Session not found: {}", session_id)))?;
+    let events = repo.get_session_events(session_id)?;
+
+    let mut tracks_played = Vec::new();
+    for event in events.iter().filter(|e| e.event_type == "track_played") {
+        let track_id = event.details.clone().unwrap_or_default();
+        tracks_played.push(PlayedTrack {
+            played_at: event.timestamp.clone(),
+            name: repo.get_track(&track_id)?.map(|t| t.name),
+            track_id,
+        });
+    }
+
+    Ok(SessionReport {
+        session,
+        events,
+        tracks_played,
+    })
+}
+
+/// Render a human-readable recap, with times relative to the session start
+pub fn render_markdown(report: &SessionReport) -> String {
+    let session = &report.session;
+    let mut out = String::new();
+
+    let _ = writeln!(out, "# Session report\n");
+    let _ = writeln!(out, "- **Session:** {}", session.id);
+    let _ = writeln!(out, "- **Mode:** {}", session.mode);
+    let _ = writeln!(out, "- **Started:** {}", session.started_at);
+    let _ = writeln!(out, "- **Ended:** {}", session.ended_at.as_deref().unwrap_or("not ended"));
+    if let Some(duration_ms) = session.total_duration_ms {
+        let _ = writeln!(out, "- **Duration:** {}", format_elapsed(duration_ms / 1000));
+    }
+
+    let detections: Vec<&DetectionEvent> = report
+        .events
+        .iter()
+        .filter(|e| e.event_type != "track_played")
+        .collect();
+    if detections.is_empty() {
+        let _ = writeln!(out, "\n_No detection events were recorded._");
+    }
+
+    // Known types first, then any other type in order of first appearance
+    let mut groups: Vec<(&str, String)> = SECTIONS
+        .iter()
+        .map(|(event_type, title)| (*event_type, title.to_string()))
+        .collect();
+    for event in &detections {
+        if !groups.iter().any(|(event_type, _)| *event_type == event.event_type) {
+            groups.push((&event.event_type, event.event_type.clone()));
+        }
+    }
+
+    for (event_type, title) in groups {
+        let events: Vec<_> = detections.iter().filter(|e| e.event_type == event_type).collect();
+        if events.is_empty() {
+            continue;
+        }
+
+        let _ = writeln!(out, "\n## {} ({})\n", title, events.len());
+        for event in events {
+            let _ = write!(
+                out,
+                "- `{}` {}",
+                relative_time(&session.started_at, &event.timestamp),
+                event.details.as_deref().unwrap_or("")
+            );
+            let mut notes = Vec::new();
+            if let Some(category) = event.category.as_deref().filter(|c| !c.is_empty()) {
+                notes.push(category.to_string());
+            }
+            if let Some(confidence) = event.confidence {
+                notes.push(format!("{:.0}%", confidence * 100.0));
+            }
+            if !notes.is_empty() {
+                let _ = write!(out, " ({})", notes.join(", "));
+            }
+            out.push('\n');
+        }
+    }
+
+    let _ = writeln!(out, "\n## Tracks played ({})\n", report.tracks_played.len());
+    if report.tracks_played.is_empty() {
+        let _ = writeln!(out, "_No tracks were played._");
+    }
+    for track in &report.tracks_played {
+        let _ = writeln!(
+            out,
+            "- `{}` {}",
+            relative_time(&session.started_at, &track.played_at),
+            track.name.as_deref().unwrap_or(&track.track_id)
+        );
+    }
+
+    out
+}
+
+/// `+HH:MM:SS` since `started_at`, or the raw timestamp if either fails to parse
+fn relative_time(started_at: &str, timestamp: &str) -> String {
+    match (DateTime::parse_from_rfc3339(started_at), DateTime::parse_from_rfc3339(timestamp)) {
+        (Ok(start), Ok(at)) => format!("+{}", format_elapsed((at - start).num_seconds().max(0))),
+        _ => timestamp.to_string(),
+    }
+}
+
+fn format_elapsed(secs: i64) -> String {
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_type: &str, timestamp: &str, details: &str) -> DetectionEvent {
+        let mut event = DetectionEvent::new(uuid::Uuid::new_v4().to_string(), "s1".to_string(), event_type.to_string());
+        event.timestamp = timestamp.to_string();
+        event.details = Some(details.to_string());
+        event
+    }
+
+    fn session() -> Session {
+        let mut session = Session::new("s1".to_string(), "mode_a".to_string());
+        session.started_at = "2024-05-01T19:00:00+00:00".to_string();
+        session
+    }
+
+    #[test]
+    fn test_markdown_groups_events_by_type() {
+        let mut keyword = event("keyword", "2024-05-01T19:01:05+00:00", "dragon");
+        keyword.category = Some("creature".to_string());
+        keyword.confidence = Some(0.92);
+        let report = SessionReport {
+            session: session(),
+            events: vec![
+                event("transcription", "2024-05-01T19:01:04+00:00", "The dragon wakes"),
+                keyword,
+                event("voice_start", "2024-05-01T19:01:00+00:00", ""),
+                event("track_played", "2024-05-01T20:02:03+00:00", "t1"),
+            ],
+            tracks_played: vec![PlayedTrack {
+                played_at: "2024-05-01T20:02:03+00:00".to_string(),
+                track_id: "t1".to_string(),
+                name: Some("Lair".to_string()),
+            }],
+        };
+
+        let markdown = render_markdown(&report);
+        assert!(markdown.contains("## Keywords (1)\n\n- `+00:01:05` dragon (creature, 92%)\n"));
+        assert!(markdown.contains("## Transcript (1)\n\n- `+00:01:04` The dragon wakes\n"));
+        assert!(markdown.contains("## voice_start (1)"));
+        assert!(markdown.contains("## Tracks played (1)\n\n- `+01:02:03` Lair\n"));
+        assert!(!markdown.contains("## track_played"));
+        assert!(markdown.find("## Keywords").unwrap() < markdown.find("## Transcript").unwrap());
+    }
+
+    #[test]
+    fn test_markdown_without_events() {
+        let report = SessionReport {
+            session: session(),
+            events: Vec::new(),
+            tracks_played: Vec::new(),
+        };
+
+        let markdown = render_markdown(&report);
+        assert!(markdown.contains("- **Ended:** not ended"));
+        assert!(markdown.contains("_No detection events were recorded._"));
+        assert!(markdown.contains("## Tracks played (0)\n\n_No tracks were played._"));
+    }
+}
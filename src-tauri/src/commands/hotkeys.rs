@@ -0,0 +1,275 @@
+//! Hotkey binding commands
+
+use crate::commands::{db_pool, session, sfx};
+use crate::db::{DbPool, Repository};
+use crate::error::{AppError, CommandError};
+use crate::hotkeys::{
+    default_hotkeys, HotkeyAction, HotkeyConfig, HotkeyConflict, HotkeyEvent, HotkeyManager, VolumeKeys,
+    DEFAULT_VOLUME_STEP, MAX_SFX_HOTKEYS,
+};
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tracing::{debug, info, warn};
+
+/// Current bindings with the version they were read at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeyBindings {
+    /// Changes whenever a binding does; re-register when it differs from the last one seen
+    pub version: u64,
+    pub hotkeys: Vec<HotkeyConfig>,
+}
+
+fn bindings(manager: &HotkeyManager) -> HotkeyBindings {
+    HotkeyBindings {
+        version: manager.version(),
+        hotkeys: manager.get_all_hotkeys(),
+    }
+}
+
+/// Get every hotkey binding
+#[tauri::command]
+pub fn get_hotkeys(state: State<'_, AppState>) -> Result<HotkeyBindings, CommandError> {
+    Ok(bindings(&state.hotkeys))
+}
+
+/// Bind an action to a key combo, refusing combos another action already uses
+#[tauri::command]
+pub fn set_hotkey(
+    state: State<'_, AppState>,
+    action: HotkeyAction,
+    modifiers: Vec<String>,
+    key: String,
+) -> Result<HotkeyBindings, CommandError> {
+    let pool = db_pool(&state)?;
+    rebind(&pool, &state.hotkeys, HotkeyConfig::new(key, action).with_modifiers(modifiers))?;
+    Ok(bindings(&state.hotkeys))
+}
+
+/// Combos shared by several actions, for settings validation
+#[tauri::command]
+pub fn list_hotkey_conflicts(state: State<'_, AppState>) -> Result<Vec<HotkeyConflict>, CommandError> {
+    Ok(state.hotkeys.conflicts())
+}
+
+/// Bind a key combo to play a stored SFX, replacing the SFX's previous combo
+#[tauri::command]
+pub fn assign_sfx_hotkey(
+    state: State<'_, AppState>,
+    sfx_id: String,
+    key: String,
+    modifiers: Vec<String>,
+) -> Result<HotkeyBindings, CommandError> {
+    let pool = db_pool(&state)?;
+    assign_sfx(&pool, &state.hotkeys, sfx_id, key, modifiers)?;
+    Ok(bindings(&state.hotkeys))
+}
+
+/// Remove the SFX binding on a key combo
+#[tauri::command]
+pub fn unassign_sfx_hotkey(
+    state: State<'_, AppState>,
+    key: String,
+    modifiers: Vec<String>,
+) -> Result<HotkeyBindings, CommandError> {
+    let pool = db_pool(&state)?;
+    unassign_sfx(&pool, &state.hotkeys, key, modifiers)?;
+    Ok(bindings(&state.hotkeys))
+}
+
+/// Restore the default bindings
+#[tauri::command]
+pub fn reset_hotkeys(state: State<'_, AppState>) -> Result<HotkeyBindings, CommandError> {
+    info!("Resetting hotkeys to defaults");
+    state.hotkeys.replace_all(default_hotkeys());
+    state.hotkeys.save_hotkeys(&db_pool(&state)?)?;
+    Ok(bindings(&state.hotkeys))
+}
+
+/// Register a binding and persist the full map
+fn rebind(pool: &DbPool, manager: &HotkeyManager, config: HotkeyConfig) -> Result<(), AppError> {
+    if config.key.trim().is_empty() {
+        return Err(AppError::Hotkey("Hotkey needs a key".to_string()));
+    }
+
+    manager.register(config)?;
+    manager.save_hotkeys(pool)
+}
+
+/// Bind `sfx_id` to a combo, keeping at most `MAX_SFX_HOTKEYS` SFX bindings
+fn assign_sfx(
+    pool: &DbPool,
+    manager: &HotkeyManager,
+    sfx_id: String,
+    key: String,
+    modifiers: Vec<String>,
+) -> Result<(), AppError> {
+    if Repository::new(pool.clone()).get_sfx(&sfx_id)?.is_none() {
+        return Err(AppError::Hotkey(format!("SFX not found: {}", sfx_id)));
+    }
+
+    let action = HotkeyAction::PlaySfxById(sfx_id);
+    let bound = manager.get_all_hotkeys().into_iter().filter(|c| c.action.sfx_id().is_some());
+    if manager.get_hotkey(&action).is_none() && bound.count() >= MAX_SFX_HOTKEYS {
+        return Err(AppError::Hotkey(format!("At most {} SFX can have a hotkey", MAX_SFX_HOTKEYS)));
+    }
+
+    rebind(pool, manager, HotkeyConfig::new(key, action).with_modifiers(modifiers))
+}
+
+/// Unbind the SFX on a combo; other actions on it are left alone
+fn unassign_sfx(pool: &DbPool, manager: &HotkeyManager, key: String, modifiers: Vec<String>) -> Result<(), AppError> {
+    let combo = HotkeyConfig::new(key, HotkeyAction::Stop).with_modifiers(modifiers).combo();
+    match manager.action_for_combo(&combo) {
+        Some(action) if action.sfx_id().is_some() => {
+            manager.unregister(&action);
+            manager.save_hotkeys(pool)
+        }
+        _ => Err(AppError::Hotkey(format!("No SFX bound to {:?} + {}", combo.0, combo.1))),
+    }
+}
+
+/// Act on hotkey presses for the rest of the app's lifetime
+pub fn start_hotkey_dispatcher(app: &AppHandle) {
+    let (tx, rx) = flume::unbounded::<HotkeyEvent>();
+    app.state::<AppState>().hotkeys.set_event_sender(tx);
+
+    let app = app.clone();
+    let spawned = std::thread::Builder::new()
+        .name("hotkey-events".to_string())
+        .spawn(move || {
+            let mut volume_keys = VolumeKeys::default();
+            for event in rx.iter() {
+                let state = app.state::<AppState>();
+                match event.action {
+                    HotkeyAction::PlaySfxById(sfx_id) => {
+                        if let Err(e) = sfx::play_stored_sfx(&state, &sfx_id) {
+                            warn!("SFX hotkey failed: {}", e);
+                        }
+                    }
+                    HotkeyAction::Shift => {
+                        let mode = state.app_mode.read().toggled();
+                        session::apply_app_mode(&app, mode);
+                    }
+                    HotkeyAction::Stop => {
+                        let result = state.audio.run(|engine| {
+                            engine.stop_all();
+                            Ok(())
+                        });
+                        if let Err(e) = result {
+                            warn!("Stop hotkey failed: {}", e);
+                        }
+                    }
+                    HotkeyAction::VolumeUp | HotkeyAction::VolumeDown | HotkeyAction::MuteToggle => {
+                        let step = state
+                            .hotkeys
+                            .get_hotkey(&event.action)
+                            .map(|config| config.volume_step())
+                            .unwrap_or(DEFAULT_VOLUME_STEP);
+                        let result = state.audio.run(|engine| Ok(engine.music_volume())).and_then(|current| {
+                            match volume_keys.target(event.action.clone(), step, current) {
+                                Some(volume) => state.audio.run(move |engine| {
+                                    engine.set_music_volume(volume);
+                                    Ok(())
+                                }),
+                                None => Ok(()),
+                            }
+                        });
+                        if let Err(e) = result {
+                            warn!("Volume hotkey failed: {}", e);
+                        }
+                    }
+                    // Bindable, but nothing to act on yet
+                    HotkeyAction::Next | HotkeyAction::Hold | HotkeyAction::Lock | HotkeyAction::ToggleRecording => {
+                        debug!("No handler for hotkey {:?}", event.action)
+                    }
+                }
+            }
+        });
+
+    if let Err(e) = spawned {
+        warn!("Hotkeys will not be handled: {}", e);
+    }
+}
+
+/// Load the saved bindings at startup
+pub fn restore_hotkeys(state: &AppState) {
+    let Some(pool) = state.db_pool.read().clone() else {
+        return;
+    };
+    if let Err(e) = state.hotkeys.load_hotkeys(&pool) {
+        warn!("Failed to load hotkeys, using defaults: {}", e);
+        state.hotkeys.replace_all(default_hotkeys());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Database, Sfx};
+    use crate::hotkeys::HOTKEYS_SETTING;
+
+    #[test]
+    fn test_rebind_persists_and_rejects_conflicts() {
+        let db = Database::in_memory().unwrap();
+        let pool = db.pool();
+        let loaded = HotkeyManager::new();
+        loaded.load_hotkeys(pool).unwrap();
+        assert_eq!(loaded.get_all_hotkeys().len(), default_hotkeys().len());
+
+        let manager = HotkeyManager::with_defaults();
+
+        let taken = HotkeyConfig::new("n".to_string(), HotkeyAction::Stop).with_modifiers(vec!["ctrl".to_string()]);
+        let err = rebind(pool, &manager, taken).unwrap_err();
+        assert!(err.to_string().contains("Next"));
+
+        let free = HotkeyConfig::new("f12".to_string(), HotkeyAction::Stop);
+        rebind(pool, &manager, free).unwrap();
+
+        loaded.load_hotkeys(pool).unwrap();
+        let stop = loaded.get_hotkey(&HotkeyAction::Stop).unwrap();
+        assert_eq!(stop.key, "f12");
+        assert!(stop.modifiers.is_empty());
+        assert_eq!(loaded.get_all_hotkeys().len(), default_hotkeys().len());
+
+        // Bindings saved before an action existed pick up its default
+        Repository::new(pool.clone())
+            .set_setting(HOTKEYS_SETTING, r#"[{"modifiers":[],"key":"f12","action":"stop"}]"#)
+            .unwrap();
+        loaded.load_hotkeys(pool).unwrap();
+        assert_eq!(loaded.get_all_hotkeys().len(), default_hotkeys().len());
+        assert!(loaded.get_hotkey(&HotkeyAction::VolumeUp).is_some());
+    }
+
+    #[test]
+    fn test_sfx_hotkeys() {
+        let db = Database::in_memory().unwrap();
+        let pool = db.pool();
+        let repo = Repository::new(pool.clone());
+        let manager = HotkeyManager::with_defaults();
+
+        let err = assign_sfx(pool, &manager, "missing".to_string(), "f1".to_string(), vec![]).unwrap_err();
+        assert!(err.to_string().contains("SFX not found"));
+
+        for i in 1..=MAX_SFX_HOTKEYS + 1 {
+            let sfx = Sfx::new(format!("sfx-{}", i), format!("SFX {}", i), format!("/sfx/{}.ogg", i));
+            repo.insert_sfx(&sfx).unwrap();
+        }
+        for i in 1..=MAX_SFX_HOTKEYS {
+            assign_sfx(pool, &manager, format!("sfx-{}", i), format!("f{}", i), vec![]).unwrap();
+        }
+        let extra = "sfx-13".to_string();
+        let err = assign_sfx(pool, &manager, extra, "f1".to_string(), vec!["shift".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("At most"));
+
+        let loaded = HotkeyManager::new();
+        loaded.load_hotkeys(pool).unwrap();
+        let door = HotkeyAction::PlaySfxById("sfx-1".to_string());
+        assert_eq!(loaded.get_hotkey(&door).unwrap().key, "f1");
+
+        unassign_sfx(pool, &manager, "F1".to_string(), vec![]).unwrap();
+        assert!(manager.get_hotkey(&door).is_none());
+        assert!(unassign_sfx(pool, &manager, "n".to_string(), vec!["ctrl".to_string()]).is_err());
+        assign_sfx(pool, &manager, "sfx-13".to_string(), "f1".to_string(), vec![]).unwrap();
+    }
+}
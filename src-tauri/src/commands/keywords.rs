@@ -0,0 +1,442 @@
+//! Keyword vocabulary commands
+
+use crate::commands::repository;
+use crate::db::{self, KeywordGenreMapping, Repository};
+use crate::detection::keyword::{default_ttrpg_vocabulary, Keyword, KeywordVocabulary};
+use crate::error::{AppError, CommandError};
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::State;
+use tracing::{info, warn};
+
+/// How imported keywords combine with the ones already in the database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeStrategy {
+    /// Delete every existing keyword first
+    Replace,
+    /// Leave existing words untouched
+    MergeKeepExisting,
+    /// Overwrite existing words with the imported fields
+    MergeOverwrite,
+}
+
+/// An entry of a keyword pack that was skipped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeywordImportError {
+    /// Position of the entry in the pack
+    pub index: usize,
+    pub message: String,
+}
+
+/// Outcome of a keyword pack import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeywordImport {
+    pub added: usize,
+    pub updated: usize,
+    /// Words already in the database, kept by `MergeKeepExisting`
+    pub unchanged: usize,
+    pub errors: Vec<KeywordImportError>,
+}
+
+/// Import a keyword pack (JSON or YAML) into the database and reload the detector vocabulary
+///
+/// Malformed entries are skipped and listed in the result.
+#[tauri::command]
+pub fn import_keywords(
+    state: State<'_, AppState>,
+    path: String,
+    merge_strategy: MergeStrategy,
+) -> Result<KeywordImport, CommandError> {
+    info!("Importing keywords from {} ({:?})", path, merge_strategy);
+
+    let entries = read_keyword_pack(Path::new(&path))?;
+    let repo = repository(&state)?;
+    let import = import_keyword_pack(&repo, entries, merge_strategy)?;
+    if !import.errors.is_empty() {
+        warn!("Skipped {} malformed keywords from {}", import.errors.len(), path);
+    }
+
+    reload_keywords(&state, &repo)?;
+    Ok(import)
+}
+
+/// Export the active keywords as a JSON keyword pack
+///
+/// Disabled keywords are left out, as packs have no way to say a keyword is
+/// disabled and importing them would turn them back on.
+#[tauri::command]
+pub fn export_keywords(state: State<'_, AppState>, path: String) -> Result<usize, CommandError> {
+    info!("Exporting keywords to: {}", path);
+
+    write_keyword_pack(&repository(&state)?, Path::new(&path)).map_err(CommandError::from)
+}
+
+/// Write the active keywords to `path` as JSON, returning how many were written
+fn write_keyword_pack(repo: &Repository, path: &Path) -> Result<usize, AppError> {
+    let keywords: Vec<Keyword> = repo.get_active_keywords()?.into_iter().map(keyword_from_row).collect();
+    let json = serde_json::to_string_pretty(&keywords).map_err(|e| AppError::Serialization(e.to_string()))?;
+    std::fs::write(path, json)?;
+    Ok(keywords.len())
+}
+
+/// Entries of a keyword pack, parsed one by one later so a bad entry can't sink the rest
+fn read_keyword_pack(path: &Path) -> Result<Vec<serde_json::Value>, AppError> {
+    let extension = path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase());
+    let content = std::fs::read_to_string(path)?;
+
+    match extension.as_deref() {
+        Some("json") => serde_json::from_str(&content).map_err(|e| AppError::Serialization(e.to_string())),
+        Some("yaml") | Some("yml") => {
+            serde_yaml::from_str(&content).map_err(|e| AppError::Serialization(e.to_string()))
+        }
+        _ => Err(AppError::Config(format!("Unsupported keyword file format: {}", path.display()))),
+    }
+}
+
+/// Write the valid entries of a pack to the database following `strategy`
+///
+/// Words are matched case-insensitively; a word repeated in the pack is
+/// reported as an error after its first entry.
+fn import_keyword_pack(
+    repo: &Repository,
+    entries: Vec<serde_json::Value>,
+    strategy: MergeStrategy,
+) -> Result<KeywordImport, AppError> {
+    let mut import = KeywordImport::default();
+    let mut rows: Vec<db::Keyword> = Vec::new();
+    for (index, entry) in entries.into_iter().enumerate() {
+        let message = match keyword_to_row(entry) {
+            Ok(row) if rows.iter().any(|r| r.word.eq_ignore_ascii_case(&row.word)) => {
+                format!("Duplicate keyword: {}", row.word)
+            }
+            Ok(row) => {
+                rows.push(row);
+                continue;
+            }
+            Err(message) => message,
+        };
+        import.errors.push(KeywordImportError { index, message });
+    }
+
+    if strategy == MergeStrategy::Replace {
+        repo.replace_keywords(&rows)?;
+        import.added = rows.len();
+        return Ok(import);
+    }
+
+    let existing = repo.get_all_keywords()?;
+    for mut row in rows {
+        match existing.iter().find(|k| k.word.eq_ignore_ascii_case(&row.word)) {
+            None => {
+                repo.insert_keyword(&row)?;
+                import.added += 1;
+            }
+            Some(_) if strategy == MergeStrategy::MergeKeepExisting => import.unchanged += 1,
+            Some(current) => {
+                row.id = current.id.clone();
+                row.is_active = current.is_active;
+                row.created_at = current.created_at.clone();
+                repo.update_keyword(&row)?;
+                import.updated += 1;
+            }
+        }
+    }
+    Ok(import)
+}
+
+/// Validate a keyword pack entry and convert it to a new keywords table row
+///
+/// Variations are trimmed and deduplicated; the word itself is implied.
+fn keyword_to_row(entry: serde_json::Value) -> Result<db::Keyword, String> {
+    let keyword: Keyword = serde_json::from_value(entry).map_err(|e| e.to_string())?;
+    let word = keyword.word.trim().to_string();
+
+    let mut variations: Vec<String> = Vec::new();
+    for variation in keyword.variations.iter().map(|v| v.trim()) {
+        let duplicate =
+            variation.eq_ignore_ascii_case(&word) || variations.iter().any(|v| v.eq_ignore_ascii_case(variation));
+        if !variation.is_empty() && !duplicate {
+            variations.push(variation.to_string());
+        }
+    }
+
+    let mut row = db::Keyword::new(uuid::Uuid::new_v4().to_string(), word, keyword.category.trim().to_string());
+    row.variations = Some(serde_json::to_string(&variations).map_err(|e| e.to_string())?);
+    row.mood = keyword.mood.map(|m| m.trim().to_string()).filter(|m| !m.is_empty());
+    row.priority = keyword.priority as i32;
+    validate_keyword(&row)?;
+    Ok(row)
+}
+
+/// Build the detector vocabulary from the active rows of the keywords table
+///
+/// Returns `None` while the table is empty so the built-in TTRPG vocabulary
+/// stays in use until the user defines keywords of their own.
+pub fn load_keyword_vocabulary(repo: &Repository) -> Result<Option<KeywordVocabulary>, AppError> {
+    let rows = repo.get_all_keywords()?;
+    if rows.is_empty() {
+        return Ok(None);
+    }
+
+    let mut vocabulary = KeywordVocabulary::new();
+    for row in rows.into_iter().filter(|row| row.is_active) {
+        vocabulary.add_keyword(keyword_from_row(row));
+    }
+    Ok(Some(vocabulary))
+}
+
+/// Convert a keywords table row; `variations` holds a JSON array of strings
+fn keyword_from_row(row: db::Keyword) -> Keyword {
+    let variations: Vec<String> = match row.variations.as_deref() {
+        Some(json) if !json.is_empty() => serde_json::from_str(json).unwrap_or_else(|e| {
+            warn!("Ignoring malformed variations for keyword {}: {}", row.word, e);
+            Vec::new()
+        }),
+        _ => Vec::new(),
+    };
+
+    let mut keyword = Keyword::new(row.word.clone(), row.category);
+    for variation in variations {
+        if !variation.is_empty() && !keyword.variations.contains(&variation) {
+            keyword = keyword.with_variation(variation);
+        }
+    }
+    if let Some(mood) = row.mood.filter(|m| !m.is_empty()) {
+        keyword = keyword.with_mood(mood);
+    }
+    keyword.priority = row.priority.clamp(0, u8::MAX as i32) as u8;
+    keyword
+}
+
+/// Load keywords from the database at startup or after a restore
+pub fn restore_keywords(state: &AppState) {
+    let Ok(repo) = repository(state) else {
+        return;
+    };
+
+    match load_keyword_vocabulary(&repo) {
+        Ok(vocabulary) => *state.keyword_vocabulary.write() = vocabulary,
+        Err(e) => warn!("Failed to load keywords: {}", e),
+    }
+}
+
+/// Rebuild the vocabulary after the keywords table changed
+fn reload_keywords(state: &AppState, repo: &Repository) -> Result<(), CommandError> {
+    let vocabulary = load_keyword_vocabulary(repo)?;
+    apply_vocabulary(state, vocabulary);
+    Ok(())
+}
+
+/// Make `vocabulary` current and hand it to a running session's pipeline
+fn apply_vocabulary(state: &AppState, vocabulary: Option<KeywordVocabulary>) {
+    if let Some(thread) = state.pipeline_thread.lock().as_ref() {
+        thread.set_vocabulary(vocabulary.clone().unwrap_or_else(default_ttrpg_vocabulary));
+    }
+    *state.keyword_vocabulary.write() = vocabulary;
+    *state.keyword_version.write() += 1;
+}
+
+/// Get every keyword in the database
+#[tauri::command]
+pub fn get_keywords(state: State<'_, AppState>) -> Result<Vec<db::Keyword>, CommandError> {
+    repository(&state)?.get_all_keywords().map_err(CommandError::from)
+}
+
+/// Add a keyword and reload the detector vocabulary
+#[tauri::command]
+pub fn add_keyword(
+    state: State<'_, AppState>,
+    word: String,
+    category: String,
+    variations: Vec<String>,
+    mood: Option<String>,
+    priority: Option<i32>,
+) -> Result<db::Keyword, CommandError> {
+    let mut keyword = db::Keyword::new(uuid::Uuid::new_v4().to_string(), word, category);
+    keyword.variations = Some(serde_json::to_string(&variations).map_err(|e| AppError::Serialization(e.to_string()))?);
+    keyword.mood = mood;
+    keyword.priority = priority.unwrap_or(0);
+    validate_keyword(&keyword)?;
+
+    info!("Adding keyword: {} ({})", keyword.word, keyword.category);
+
+    let repo = repository(&state)?;
+    repo.insert_keyword(&keyword)?;
+    reload_keywords(&state, &repo)?;
+    Ok(keyword)
+}
+
+/// Replace a keyword's fields and reload the detector vocabulary
+#[tauri::command]
+pub fn update_keyword(state: State<'_, AppState>, keyword: db::Keyword) -> Result<(), CommandError> {
+    validate_keyword(&keyword)?;
+
+    let repo = repository(&state)?;
+    if !repo.update_keyword(&keyword)? {
+        return Err(CommandError::not_found(format!("Keyword not found: {}", keyword.id)));
+    }
+    reload_keywords(&state, &repo)
+}
+
+/// Delete a keyword and reload the detector vocabulary
+#[tauri::command]
+pub fn delete_keyword(state: State<'_, AppState>, id: String) -> Result<bool, CommandError> {
+    let repo = repository(&state)?;
+    let deleted = repo.delete_keyword(&id)?;
+    if deleted {
+        reload_keywords(&state, &repo)?;
+    }
+    Ok(deleted)
+}
+
+/// Enable or disable a keyword without deleting it
+#[tauri::command]
+pub fn set_keyword_active(state: State<'_, AppState>, id: String, active: bool) -> Result<(), CommandError> {
+    let repo = repository(&state)?;
+    if !repo.set_keyword_active(&id, active)? {
+        return Err(CommandError::not_found(format!("Keyword not found: {}", id)));
+    }
+    reload_keywords(&state, &repo)
+}
+
+fn validate_keyword(keyword: &db::Keyword) -> Result<(), CommandError> {
+    if keyword.word.trim().is_empty() {
+        return Err(CommandError::validation("Keyword word cannot be empty"));
+    }
+    if keyword.category.trim().is_empty() {
+        return Err(CommandError::validation("Keyword category cannot be empty"));
+    }
+    Ok(())
+}
+
+/// Get all keyword category to genre mappings
+#[tauri::command]
+pub fn get_genre_mappings(state: State<'_, AppState>) -> Result<Vec<KeywordGenreMapping>, CommandError> {
+    repository(&state)?.get_genre_mappings().map_err(CommandError::from)
+}
+
+/// Map a keyword category to a genre (updates priority if the mapping exists)
+#[tauri::command]
+pub fn set_genre_mapping(
+    state: State<'_, AppState>,
+    category: String,
+    genre: String,
+    priority: Option<i32>,
+) -> Result<(), CommandError> {
+    info!("Mapping keyword category {} to genre {}", category, genre);
+
+    let mapping = KeywordGenreMapping::new(category, genre, priority.unwrap_or(0));
+    repository(&state)?.upsert_genre_mapping(&mapping).map_err(CommandError::from)
+}
+
+/// Remove a keyword category to genre mapping
+#[tauri::command]
+pub fn delete_genre_mapping(
+    state: State<'_, AppState>,
+    category: String,
+    genre: String,
+) -> Result<bool, CommandError> {
+    repository(&state)?
+        .delete_genre_mapping(&category, &genre)
+        .map_err(CommandError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::keyword::KeywordDetector;
+
+    #[test]
+    fn test_vocabulary_loads_active_rows() {
+        let db = db::Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+        assert!(load_keyword_vocabulary(&repo).unwrap().is_none());
+
+        let mut goblin = db::Keyword::new("k1".to_string(), "goblin".to_string(), "creature".to_string());
+        goblin.variations = Some(r#"["goblins", "hobgoblin"]"#.to_string());
+        goblin.mood = Some("fearful".to_string());
+        repo.insert_keyword(&goblin).unwrap();
+
+        let tavern = db::Keyword::new("k2".to_string(), "tavern".to_string(), "social".to_string());
+        repo.insert_keyword(&tavern).unwrap();
+        repo.set_keyword_active("k2", false).unwrap();
+
+        let vocabulary = load_keyword_vocabulary(&repo).unwrap().unwrap();
+        let mut detector = KeywordDetector::new();
+        detector.set_vocabulary(vocabulary);
+
+        let matches = detector.detect("the hobgoblin runs into the tavern");
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].keyword, "goblin");
+        assert_eq!(matches[0].category, "creature");
+    }
+
+    #[test]
+    fn test_import_keyword_pack_strategies() {
+        let db = db::Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+        let mut dragon = db::Keyword::new("k1".to_string(), "Dragon".to_string(), "creature".to_string());
+        dragon.is_active = false;
+        repo.insert_keyword(&dragon).unwrap();
+
+        let pack: Vec<serde_json::Value> = serde_json::from_str(
+            r#"[
+                {"word": "dragon", "category": "boss", "variations": ["dragon", " wyrm ", "Wyrm", ""], "mood": "angry", "priority": 5},
+                {"word": "tavern", "category": "social", "variations": [], "mood": null, "priority": 1},
+                {"word": "", "category": "social", "variations": [], "mood": null, "priority": 0},
+                {"word": "ghost"},
+                {"word": "TAVERN", "category": "place", "variations": [], "mood": null, "priority": 0}
+            ]"#,
+        )
+        .unwrap();
+
+        let import = import_keyword_pack(&repo, pack.clone(), MergeStrategy::MergeKeepExisting).unwrap();
+        assert_eq!((import.added, import.updated, import.unchanged), (1, 0, 1));
+        let skipped: Vec<usize> = import.errors.iter().map(|e| e.index).collect();
+        assert_eq!(skipped, vec![2, 3, 4]);
+        assert_eq!(repo.get_all_keywords().unwrap().len(), 2);
+
+        let import = import_keyword_pack(&repo, pack.clone(), MergeStrategy::MergeOverwrite).unwrap();
+        assert_eq!((import.added, import.updated, import.unchanged), (0, 2, 0));
+        let rows = repo.get_all_keywords().unwrap();
+        let dragon = rows.iter().find(|k| k.id == "k1").unwrap();
+        assert_eq!(dragon.category, "boss");
+        assert_eq!(dragon.variations.as_deref(), Some(r#"["wyrm"]"#));
+        assert!(!dragon.is_active);
+
+        let import = import_keyword_pack(&repo, pack[1..2].to_vec(), MergeStrategy::Replace).unwrap();
+        assert_eq!(import.added, 1);
+        let words: Vec<String> = repo.get_all_keywords().unwrap().into_iter().map(|k| k.word).collect();
+        assert_eq!(words, vec!["tavern"]);
+    }
+
+    #[test]
+    fn test_exported_pack_reimports_active_keywords() {
+        let db = db::Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+        let mut goblin = db::Keyword::new("k1".to_string(), "goblin".to_string(), "creature".to_string());
+        goblin.variations = Some(r#"["goblins"]"#.to_string());
+        goblin.mood = Some("fearful".to_string());
+        goblin.priority = 3;
+        repo.insert_keyword(&goblin).unwrap();
+        let tavern = db::Keyword::new("k2".to_string(), "tavern".to_string(), "social".to_string());
+        repo.insert_keyword(&tavern).unwrap();
+        repo.set_keyword_active("k2", false).unwrap();
+
+        let path = std::env::temp_dir().join(format!("keywords-{}.json", uuid::Uuid::new_v4()));
+        assert_eq!(write_keyword_pack(&repo, &path).unwrap(), 1);
+        let pack = read_keyword_pack(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let import = import_keyword_pack(&repo, pack, MergeStrategy::Replace).unwrap();
+        assert_eq!((import.added, import.errors.len()), (1, 0));
+        let rows = repo.get_all_keywords().unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].word, "goblin");
+        assert_eq!(rows[0].variations.as_deref(), Some(r#"["goblins"]"#));
+        assert_eq!((rows[0].mood.as_deref(), rows[0].priority), (Some("fearful"), 3));
+        assert!(rows[0].is_active);
+    }
+}
@@ -0,0 +1,252 @@
+//! Music library commands
+
+use crate::audio::metadata;
+use crate::commands::repository;
+use crate::db::{Repository, Track, TrackUpdate};
+use crate::error::CommandError;
+use crate::inference::emotion::EmotionAnalyzer;
+use crate::library::{self, LibraryDiff, LibraryWatcher, LIBRARY_CHANGED_EVENT, LIBRARY_PATH_SETTING};
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tracing::{debug, info, warn};
+
+/// Outcome of importing a single file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ImportStatus {
+    Imported { track_id: String },
+    SkippedDuplicate,
+    Error { reason: String },
+}
+
+/// Per-file import result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackImportResult {
+    pub path: String,
+    #[serde(flatten)]
+    pub status: ImportStatus,
+}
+
+/// Import audio files into the track library
+#[tauri::command]
+pub fn import_tracks(state: State<'_, AppState>, paths: Vec<String>) -> Result<Vec<TrackImportResult>, CommandError> {
+    info!("Importing {} tracks", paths.len());
+
+    let repo = repository(&state)?;
+
+    Ok(paths
+        .into_iter()
+        .map(|path| {
+            let status = import_track(&repo, &path, None);
+            TrackImportResult { path, status }
+        })
+        .collect())
+}
+
+/// Decode, tag and insert a single file
+///
+/// With an `analyzer`, the track's mood is set from a short preview.
+pub(crate) fn import_track(repo: &Repository, path: &str, analyzer: Option<&EmotionAnalyzer>) -> ImportStatus {
+    match repo.get_track_by_path(path) {
+        Ok(Some(_)) => return ImportStatus::SkippedDuplicate,
+        Ok(None) => {}
+        Err(e) => return ImportStatus::Error { reason: e.to_string() },
+    }
+
+    let meta = match metadata::probe_track(Path::new(path)) {
+        Ok(meta) => meta,
+        Err(e) => {
+            warn!("Skipping {}: {}", path, e);
+            return ImportStatus::Error { reason: e.to_string() };
+        }
+    };
+
+    // A track removed earlier comes back with its tags
+    match repo.reinstate_track_by_path(path) {
+        Ok(Some(track_id)) => return ImportStatus::Imported { track_id },
+        Ok(None) => {}
+        Err(e) => return ImportStatus::Error { reason: e.to_string() },
+    }
+
+    let mut track = Track::new(uuid::Uuid::new_v4().to_string(), meta.title, path.to_string());
+    track.duration_ms = meta.duration_ms.map(|ms| ms as i64);
+    if let Some(analyzer) = analyzer {
+        track.mood = detect_mood(analyzer, Path::new(path));
+    }
+
+    if let Err(e) = repo.insert_track(&track) {
+        return ImportStatus::Error { reason: e.to_string() };
+    }
+
+    if !meta.warnings.is_empty() {
+        warn!("Import warnings for {}: {}", path, meta.warnings.join(", "));
+        if let Err(e) = repo.set_track_import_warnings(&track.id, &meta.warnings) {
+            return ImportStatus::Error { reason: e.to_string() };
+        }
+    }
+
+    ImportStatus::Imported { track_id: track.id }
+}
+
+/// Primary emotion of a track's preview, or None if it can't be analyzed
+fn detect_mood(analyzer: &EmotionAnalyzer, path: &Path) -> Option<String> {
+    let result = metadata::mood_preview(path).map_err(CommandError::from).and_then(|(samples, sample_rate)| {
+        analyzer.analyze(&samples, sample_rate).map_err(CommandError::from)
+    });
+
+    match result {
+        Ok(result) => {
+            debug!("Tagged {} as {}", path.display(), result);
+            Some(result.primary.to_string())
+        }
+        Err(e) => {
+            warn!("Failed to tag mood of {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Changes to one track's metadata
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackEdit {
+    pub id: String,
+    #[serde(flatten)]
+    pub changes: TrackUpdate,
+    /// Add an unknown genre instead of rejecting it
+    #[serde(default)]
+    pub create_genre_if_missing: bool,
+}
+
+/// Edit a track's metadata and return the updated track
+#[tauri::command]
+pub fn update_track(state: State<'_, AppState>, track_update: TrackEdit) -> Result<Track, CommandError> {
+    let repo = repository(&state)?;
+    let changes = &track_update.changes;
+
+    if changes.name.as_deref().is_some_and(|name| name.trim().is_empty()) {
+        return Err(CommandError::validation("Track name cannot be empty"));
+    }
+    if changes.volume.is_some_and(|volume| !(0.0..=1.0).contains(&volume)) {
+        return Err(CommandError::validation("Volume must be between 0 and 1"));
+    }
+    if changes.bpm.is_some_and(|bpm| bpm <= 0.0) {
+        return Err(CommandError::validation("BPM must be positive"));
+    }
+    if let Some(genre) = changes.genre.as_deref().filter(|genre| !genre.is_empty()) {
+        if !repo.genre_exists(genre)? {
+            if !track_update.create_genre_if_missing {
+                return Err(CommandError::validation(format!("Unknown genre: {}", genre)));
+            }
+            repo.insert_genre(genre)?;
+        }
+    }
+
+    repo.update_track(&track_update.id, changes)?
+        .ok_or_else(|| CommandError::not_found(format!("Track not found: {}", track_update.id)))
+}
+
+/// Delete a track from the library
+///
+/// Refuses while the track is playing unless `stop_if_playing` is set, in
+/// which case the music is stopped first.
+#[tauri::command]
+pub fn delete_track(
+    state: State<'_, AppState>,
+    track_id: String,
+    stop_if_playing: Option<bool>,
+) -> Result<(), CommandError> {
+    let repo = repository(&state)?;
+
+    let playing_id = state
+        .audio
+        .run(|engine| Ok(engine.current_track().map(|playing| playing.track.id)))?;
+    if playing_id.as_deref() == Some(track_id.as_str()) {
+        if !stop_if_playing.unwrap_or(false) {
+            return Err(CommandError::invalid_state("Track is currently playing"));
+        }
+        state
+            .audio
+            .run(|engine| {
+                engine.stop_music();
+                Ok(())
+            })?;
+    }
+
+    if !repo.delete_track(&track_id)? {
+        return Err(CommandError::not_found(format!("Track not found: {}", track_id)));
+    }
+    info!("Deleted track {}", track_id);
+    Ok(())
+}
+
+/// Set the music library folder, sync it and start watching it
+#[tauri::command]
+pub fn set_library_path(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<LibraryDiff, CommandError> {
+    apply_library_path(&app, &state, &path)
+}
+
+/// Check that `path` is a folder, save it, sync it and watch it
+pub(crate) fn apply_library_path(app: &AppHandle, state: &AppState, path: &str) -> Result<LibraryDiff, CommandError> {
+    info!("Setting library path: {}", path);
+
+    let root = PathBuf::from(path);
+    if !root.is_dir() {
+        return Err(CommandError::validation(format!("Not a directory: {}", path)));
+    }
+
+    let repo = repository(state)?;
+    repo.set_setting(LIBRARY_PATH_SETTING, path)?;
+
+    let diff = library::sync_library(&repo, &root)?;
+    if !diff.is_empty() {
+        let _ = app.emit(LIBRARY_CHANGED_EVENT, diff.clone());
+    }
+
+    start_watcher(app, state, repo, root)?;
+    Ok(diff)
+}
+
+/// Fully diff the library folder against the database
+#[tauri::command]
+pub fn rescan_library(app: AppHandle, state: State<'_, AppState>) -> Result<LibraryDiff, CommandError> {
+    let repo = repository(&state)?;
+    let path = repo
+        .get_setting(LIBRARY_PATH_SETTING)?
+        .ok_or_else(|| CommandError::invalid_state("Library path not set"))?;
+
+    let diff = library::sync_library(&repo, Path::new(&path))?;
+    if !diff.is_empty() {
+        let _ = app.emit(LIBRARY_CHANGED_EVENT, diff.clone());
+    }
+    Ok(diff)
+}
+
+/// Resume watching the saved library folder at startup
+pub fn restore_library_watcher(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    let Ok(repo) = repository(&state) else {
+        return;
+    };
+
+    let Ok(Some(path)) = repo.get_setting(LIBRARY_PATH_SETTING) else {
+        return;
+    };
+
+    let root = PathBuf::from(path);
+    if let Err(e) = start_watcher(app, &state, repo, root) {
+        warn!("Failed to watch music library: {}", e);
+    }
+}
+
+/// Replace the running watcher with one on `root`
+fn start_watcher(app: &AppHandle, state: &AppState, repo: Repository, root: PathBuf) -> Result<(), CommandError> {
+    let handle = app.clone();
+    let watcher = LibraryWatcher::start(root, repo, move |diff| {
+        let _ = handle.emit(LIBRARY_CHANGED_EVENT, diff.clone());
+    })?;
+
+    *state.library_watcher.lock() = Some(watcher);
+    Ok(())
+}
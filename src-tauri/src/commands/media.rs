@@ -0,0 +1,140 @@
+//! Media folder import commands
+
+use crate::commands::library::{import_track, ImportStatus};
+use crate::commands::repository;
+use crate::commands::session::TrackInfo;
+use crate::db::Repository;
+use crate::error::CommandError;
+use crate::inference::emotion::EmotionAnalyzer;
+use crate::library;
+use crate::AppState;
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// Event emitted after each file of a media scan
+pub const SCAN_PROGRESS_EVENT: &str = "scan_progress";
+
+/// Files scanned so far out of the files found
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanProgress {
+    pub scanned: usize,
+    pub total: usize,
+}
+
+/// Import every audio file under a directory that isn't in the library yet
+///
+/// With `auto_tag_mood`, each new track's mood is detected from a 10 second
+/// preview; this slows large imports down noticeably. The scan runs on the
+/// blocking pool so the UI stays responsive.
+#[tauri::command]
+pub async fn scan_media_directory(app: AppHandle, dir: String, auto_tag_mood: bool) -> Result<Vec<TrackInfo>, CommandError> {
+    tokio::task::spawn_blocking(move || scan_media_blocking(&app, &dir, auto_tag_mood))
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?
+}
+
+fn scan_media_blocking(app: &AppHandle, dir: &str, auto_tag_mood: bool) -> Result<Vec<TrackInfo>, CommandError> {
+    let root = Path::new(dir);
+    if !root.is_dir() {
+        return Err(CommandError::validation(format!("Not a directory: {}", dir)));
+    }
+    info!("Scanning media directory: {}", dir);
+
+    let analyzer = if auto_tag_mood {
+        let mut analyzer = EmotionAnalyzer::new();
+        analyzer.init()?;
+        Some(analyzer)
+    } else {
+        None
+    };
+
+    let repo = repository(&app.state::<AppState>())?;
+    let imported = scan_media(&repo, root, analyzer.as_ref(), |progress| {
+        let _ = app.emit(SCAN_PROGRESS_EVENT, progress);
+    });
+
+    info!("Imported {} tracks from {}", imported.len(), dir);
+    Ok(imported)
+}
+
+/// Walk `root`, import new files and return them, reporting progress per file
+fn scan_media(
+    repo: &Repository,
+    root: &Path,
+    analyzer: Option<&EmotionAnalyzer>,
+    mut on_progress: impl FnMut(ScanProgress),
+) -> Vec<TrackInfo> {
+    let files = library::scan_directory(root);
+    let total = files.len();
+    let mut imported = Vec::new();
+
+    for (index, path) in files.iter().enumerate() {
+        let path = path.to_string_lossy();
+        match import_track(repo, &path, analyzer) {
+            ImportStatus::Imported { track_id } => match repo.get_track(&track_id) {
+                Ok(Some(track)) => imported.push(TrackInfo::from(track)),
+                Ok(None) => {}
+                Err(e) => warn!("Failed to load imported track {}: {}", path, e),
+            },
+            ImportStatus::SkippedDuplicate => {}
+            ImportStatus::Error { reason } => warn!("Failed to import {}: {}", path, reason),
+        }
+
+        on_progress(ScanProgress {
+            scanned: index + 1,
+            total,
+        });
+    }
+
+    imported
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    fn write_wav(path: &Path) {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec).unwrap();
+        for i in 0..4000 {
+            writer.write_sample(((i as f32 * 0.05).sin() * 8000.0) as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+    }
+
+    #[test]
+    fn test_scan_imports_new_files_only() {
+        let dir = std::env::temp_dir().join(format!("media-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("battle")).unwrap();
+        write_wav(&dir.join("tavern.wav"));
+        write_wav(&dir.join("battle").join("drums.wav"));
+        std::fs::write(dir.join("notes.txt"), "not audio").unwrap();
+
+        let db = Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+
+        let mut progress = Vec::new();
+        let imported = scan_media(&repo, &dir, None, |p| progress.push((p.scanned, p.total)));
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].duration_ms, Some(500));
+        assert_eq!(progress, vec![(1, 2), (2, 2)]);
+
+        let mut analyzer = EmotionAnalyzer::new();
+        analyzer.init().unwrap();
+        write_wav(&dir.join("forest.wav"));
+        let rescanned = scan_media(&repo, &dir, Some(&analyzer), |_| {});
+        std::fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(rescanned.len(), 1);
+        assert!(rescanned[0].mood.is_some());
+        assert_eq!(repo.get_all_tracks().unwrap().len(), 3);
+    }
+}
@@ -1,4 +1,35 @@
 //! Tauri commands module
 
+pub mod database;
+pub mod diagnostics;
+pub mod export;
+pub mod hotkeys;
+pub mod keywords;
+pub mod library;
+pub mod media;
+pub mod models;
+pub mod playback;
+pub mod playlists;
 pub mod session;
+pub mod settings;
+pub mod sfx;
+pub mod suggestions;
 pub mod training;
+
+use crate::db::{DbPool, Repository};
+use crate::error::{CommandError, ErrorCode};
+use crate::AppState;
+
+/// The managed database pool
+pub(crate) fn db_pool(state: &AppState) -> Result<DbPool, CommandError> {
+    state
+        .db_pool
+        .read()
+        .clone()
+        .ok_or_else(|| CommandError::new(ErrorCode::DatabaseUnavailable, "Database not available"))
+}
+
+/// Build a repository from the managed database pool
+pub(crate) fn repository(state: &AppState) -> Result<Repository, CommandError> {
+    db_pool(state).map(Repository::new)
+}
@@ -0,0 +1,67 @@
+//! Model commands - details of the installed inference models
+
+use crate::commands::repository;
+use crate::error::{AppError, CommandError};
+use crate::inference::whisper::{self, WhisperModelInfo, WhisperModelSize, WHISPER_MODEL_SETTING};
+use crate::AppState;
+use std::path::PathBuf;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+/// Event carrying model download progress (0-1)
+pub const MODEL_DOWNLOAD_PROGRESS_EVENT: &str = "models://download-progress";
+
+/// Identify the installed Whisper model from its file header
+#[tauri::command]
+pub fn get_whisper_model_info() -> Result<WhisperModelInfo, CommandError> {
+    let path = whisper::get_model_path();
+    let info = whisper::read_model_info(&path)?;
+
+    info!("Whisper model {:?}: {} ({} MB, quantized: {})", path, info.name, info.size_mb, info.quantized);
+    Ok(info)
+}
+
+/// Download a Whisper model into the user data directory, emitting progress events
+///
+/// The downloaded size becomes the selected model.
+#[tauri::command]
+pub async fn download_whisper_model(app: AppHandle, model_size: WhisperModelSize) -> Result<PathBuf, CommandError> {
+    let dest_dir = whisper::user_model_dir().ok_or_else(|| CommandError::internal("No user data directory"))?;
+    let (progress_tx, progress_rx) = flume::unbounded::<f64>();
+
+    // Ends when the download drops its sender
+    let emitter = app.clone();
+    let forward = tokio::spawn(async move {
+        while let Ok(progress) = progress_rx.recv_async().await {
+            let _ = emitter.emit(MODEL_DOWNLOAD_PROGRESS_EVENT, progress);
+        }
+    });
+
+    let result = tokio::task::spawn_blocking(move || whisper::download_model(model_size, &dest_dir, progress_tx))
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?;
+    let _ = forward.await;
+
+    let path = result?;
+    whisper::select_model(Some(model_size));
+    let repo = repository(&app.state::<AppState>())?;
+    let value = serde_json::to_string(&model_size).map_err(|e| AppError::Serialization(e.to_string()))?;
+    repo.set_setting(WHISPER_MODEL_SETTING, &value)?;
+    Ok(path)
+}
+
+/// Load the selected Whisper model size at startup
+pub fn restore_whisper_model(state: &AppState) {
+    let Ok(repo) = repository(state) else {
+        return;
+    };
+
+    match repo.get_setting(WHISPER_MODEL_SETTING) {
+        Ok(Some(value)) => match serde_json::from_str::<WhisperModelSize>(&value) {
+            Ok(size) => whisper::select_model(Some(size)),
+            Err(e) => warn!("Ignoring unknown Whisper model {}: {}", value, e),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("Failed to load the Whisper model setting: {}", e),
+    }
+}
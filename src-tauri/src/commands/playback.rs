@@ -0,0 +1,243 @@
+//! Playback commands - resume on startup, track previews, crossfade overrides, mood mappings and play stats
+
+use crate::audio::resume::{self, PlaybackSnapshot, RESUME_LAST_TRACK_SETTING};
+use crate::audio::{CrossfadeType, Track};
+use crate::commands::{repository, sfx};
+use crate::db::{self, CrossfadeOverride, MoodMapping, Repository, TrackStats};
+use crate::detection::keyword::default_ttrpg_vocabulary;
+use crate::error::{AppError, CommandError};
+use crate::orchestrator::autoplay;
+use crate::AppState;
+use std::collections::HashMap;
+use tauri::State;
+use tracing::{info, warn};
+
+/// Default preview length when none is given
+const DEFAULT_PREVIEW_MS: u64 = 15_000;
+
+/// Number of tracks returned by `get_top_tracks` when no limit is given
+const DEFAULT_TOP_TRACKS: i64 = 10;
+
+/// Enable or disable resuming the last track on startup
+#[tauri::command]
+pub fn set_resume_last_track(state: State<'_, AppState>, enabled: bool) -> Result<(), CommandError> {
+    info!("Resume last track on startup: {}", enabled);
+
+    let repo = repository(&state)?;
+    repo.set_setting(RESUME_LAST_TRACK_SETTING, if enabled { "true" } else { "false" })
+        .map_err(CommandError::from)
+}
+
+/// Get the last saved playback position
+#[tauri::command]
+pub fn get_last_playback(state: State<'_, AppState>) -> Result<Option<PlaybackSnapshot>, CommandError> {
+    let repo = repository(&state)?;
+    resume::load_snapshot(&repo).map_err(CommandError::from)
+}
+
+/// Audition the start of a track without touching the session mix
+#[tauri::command]
+pub fn preview_track(state: State<'_, AppState>, track_id: String, duration_ms: Option<u64>) -> Result<(), CommandError> {
+    state.audio.require_output()?;
+    let repo = repository(&state)?;
+    let stored = repo
+        .get_track(&track_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Track not found: {}", track_id)))?;
+
+    let track = Track::from(&stored);
+    let duration_ms = duration_ms.unwrap_or(DEFAULT_PREVIEW_MS);
+
+    state
+        .audio
+        .run(move |engine| engine.preview(&track, duration_ms))
+        .map_err(CommandError::from)
+}
+
+/// Stop the current track preview
+#[tauri::command]
+pub fn stop_preview(state: State<'_, AppState>) -> Result<(), CommandError> {
+    state
+        .audio
+        .run(|engine| {
+            engine.stop_preview();
+            Ok(())
+        })
+        .map_err(CommandError::from)
+}
+
+/// Get the genre-to-genre crossfade matrix
+#[tauri::command]
+pub fn get_crossfade_overrides(state: State<'_, AppState>) -> Result<Vec<CrossfadeOverride>, CommandError> {
+    let repo = repository(&state)?;
+    repo.get_crossfade_overrides().map_err(CommandError::from)
+}
+
+/// Set the crossfade used when switching from one genre to another
+#[tauri::command]
+pub fn set_crossfade_override(
+    state: State<'_, AppState>,
+    from_genre: String,
+    to_genre: String,
+    crossfade_type: String,
+) -> Result<(), CommandError> {
+    info!("Crossfade override: {} -> {} = {}", from_genre, to_genre, crossfade_type);
+
+    let crossfade: CrossfadeType = crossfade_type.parse()?;
+
+    let repo = repository(&state)?;
+    repo.set_crossfade_override(&CrossfadeOverride::new(from_genre, to_genre, crossfade.as_str().to_string()))?;
+
+    load_crossfade_overrides(&state, &repo).map_err(CommandError::from)
+}
+
+/// Get every mood to music mapping
+#[tauri::command]
+pub fn get_mood_mappings(state: State<'_, AppState>) -> Result<Vec<MoodMapping>, CommandError> {
+    repository(&state)?.get_mood_mappings().map_err(CommandError::from)
+}
+
+/// Map a mood to a track or a genre, optionally with an SFX
+///
+/// Pass the `id` of an existing mapping to edit it.
+#[tauri::command]
+pub fn set_mood_mapping(
+    state: State<'_, AppState>,
+    id: Option<String>,
+    mood: String,
+    track_id: Option<String>,
+    genre: Option<String>,
+    priority: Option<i32>,
+    sfx_id: Option<String>,
+) -> Result<MoodMapping, CommandError> {
+    let repo = repository(&state)?;
+
+    let mut mapping = MoodMapping::new(id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string()), mood);
+    mapping.track_id = track_id;
+    mapping.genre = genre;
+    mapping.priority = priority.unwrap_or(0);
+    mapping.sfx_id = sfx_id;
+    validate_mood_mapping(&repo, &mapping)?;
+
+    info!("Mapping mood {} (priority {})", mapping.mood, mapping.priority);
+    repo.upsert_mood_mapping(&mapping)?;
+    Ok(mapping)
+}
+
+/// Remove a mood mapping
+#[tauri::command]
+pub fn delete_mood_mapping(state: State<'_, AppState>, id: String) -> Result<bool, CommandError> {
+    repository(&state)?.delete_mood_mapping(&id).map_err(CommandError::from)
+}
+
+/// Check that a mapping targets exactly one existing track or a genre
+fn validate_mood_mapping(repo: &Repository, mapping: &MoodMapping) -> Result<(), CommandError> {
+    if mapping.mood.trim().is_empty() {
+        return Err(CommandError::validation("Mood cannot be empty"));
+    }
+    match (&mapping.track_id, &mapping.genre) {
+        (Some(_), Some(_)) => return Err(CommandError::validation("Map a mood to a track or a genre, not both")),
+        (None, None) => return Err(CommandError::validation("Mood mapping needs a track or a genre")),
+        (Some(track_id), None) => {
+            if repo.get_track(track_id)?.is_none() {
+                return Err(CommandError::not_found(format!("Track not found: {}", track_id)));
+            }
+        }
+        (None, Some(genre)) => {
+            if genre.trim().is_empty() {
+                return Err(CommandError::validation("Genre cannot be empty"));
+            }
+        }
+    }
+    if let Some(sfx_id) = &mapping.sfx_id {
+        if repo.get_sfx(sfx_id)?.is_none() {
+            return Err(CommandError::not_found(format!("SFX not found: {}", sfx_id)));
+        }
+    }
+    Ok(())
+}
+
+/// Get how often and how long a track has been played
+#[tauri::command]
+pub fn get_track_stats(state: State<'_, AppState>, track_id: String) -> Result<Option<TrackStats>, CommandError> {
+    repository(&state)?.get_track_stats(&track_id).map_err(CommandError::from)
+}
+
+/// Get the most played tracks
+#[tauri::command]
+pub fn get_top_tracks(state: State<'_, AppState>, limit: Option<i64>) -> Result<Vec<TrackStats>, CommandError> {
+    repository(&state)?
+        .get_top_tracks(limit.unwrap_or(DEFAULT_TOP_TRACKS))
+        .map_err(CommandError::from)
+}
+
+/// Push the stored crossfade matrix into the audio engine
+pub fn load_crossfade_overrides(state: &AppState, repo: &Repository) -> Result<(), AppError> {
+    let mut overrides = HashMap::new();
+    for entry in repo.get_crossfade_overrides()? {
+        match entry.crossfade_type.parse::<CrossfadeType>() {
+            Ok(crossfade) => {
+                overrides.insert((entry.from_genre, entry.to_genre), crossfade);
+            }
+            Err(e) => warn!("Ignoring crossfade override {} -> {}: {}", entry.from_genre, entry.to_genre, e),
+        }
+    }
+
+    state.audio.run(move |engine| {
+        engine.set_crossfade_overrides(overrides);
+        Ok(())
+    })
+}
+
+/// Track for a confirmed dual signal: the keyword's genre, the emotion as mood
+pub(crate) fn dual_signal_track(state: &AppState, keyword: &str, emotion: &str) -> Option<db::Track> {
+    let repo = repository(state).ok()?;
+    let genre = {
+        let vocabulary = state.keyword_vocabulary.read();
+        let result = match vocabulary.as_ref() {
+            Some(vocabulary) => autoplay::genre_for_keyword(&repo, vocabulary, keyword),
+            None => autoplay::genre_for_keyword(&repo, &default_ttrpg_vocabulary(), keyword),
+        };
+        result.unwrap_or_else(|e| {
+            warn!("Genre lookup for {} failed: {}", keyword, e);
+            None
+        })
+    };
+
+    autoplay::pick_track(&repo, genre.as_deref(), emotion).unwrap_or_else(|e| {
+        warn!("Track lookup for {} failed: {}", emotion, e);
+        None
+    })
+}
+
+/// Autonomous mode: crossfade to a track matching a confirmed dual signal
+///
+/// Returns the track if the crossfade started; the emotion's other tracks
+/// then rotate after it.
+pub(crate) fn play_for_dual_signal(state: &AppState, keyword: &str, emotion: &str) -> Option<db::Track> {
+    let Some(stored) = dual_signal_track(state, keyword, emotion) else {
+        info!("No track for {} / {}", keyword, emotion);
+        return None;
+    };
+
+    info!("Dual signal {} / {}: playing {}", keyword, emotion, stored.name);
+    let track = Track::from(&stored);
+    let played = match state.audio.play_for_mood(emotion, track) {
+        Ok(()) => Some(stored),
+        Err(e) => {
+            warn!("Failed to play {}: {}", stored.name, e);
+            None
+        }
+    };
+
+    let sfx_id = repository(state).and_then(|repo| autoplay::mapped_sfx(&repo, emotion).map_err(CommandError::from));
+    match sfx_id {
+        Ok(Some(sfx_id)) => {
+            if let Err(e) = sfx::play_stored_sfx(state, &sfx_id) {
+                warn!("Failed to play mapped SFX {}: {}", sfx_id, e);
+            }
+        }
+        Ok(None) => {}
+        Err(e) => warn!("SFX lookup for {} failed: {}", emotion, e),
+    }
+    played
+}
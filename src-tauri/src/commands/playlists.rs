@@ -0,0 +1,78 @@
+//! Saved playlist commands
+
+use crate::commands::repository;
+use crate::commands::session::TrackInfo;
+use crate::db::Playlist;
+use crate::AppState;
+use crate::error::CommandError;
+use tauri::State;
+use tracing::info;
+
+/// Get all saved playlists
+#[tauri::command]
+pub fn get_playlists(state: State<'_, AppState>) -> Result<Vec<Playlist>, CommandError> {
+    repository(&state)?.get_playlists().map_err(CommandError::from)
+}
+
+/// Create an empty playlist
+#[tauri::command]
+pub fn create_playlist(
+    state: State<'_, AppState>,
+    name: String,
+    description: Option<String>,
+) -> Result<Playlist, CommandError> {
+    if name.trim().is_empty() {
+        return Err(CommandError::validation("Playlist name cannot be empty"));
+    }
+    info!("Creating playlist: {}", name);
+
+    let mut playlist = Playlist::new(uuid::Uuid::new_v4().to_string(), name);
+    playlist.description = description;
+    repository(&state)?.create_playlist(&playlist)?;
+    Ok(playlist)
+}
+
+/// Append a track to a playlist, returning its position
+#[tauri::command]
+pub fn add_track_to_playlist(
+    state: State<'_, AppState>,
+    playlist_id: String,
+    track_id: String,
+) -> Result<i64, CommandError> {
+    repository(&state)?
+        .add_track_to_playlist(&playlist_id, &track_id)
+        .map_err(CommandError::from)
+}
+
+/// Remove a track from a playlist
+#[tauri::command]
+pub fn remove_track_from_playlist(
+    state: State<'_, AppState>,
+    playlist_id: String,
+    track_id: String,
+) -> Result<bool, CommandError> {
+    repository(&state)?
+        .remove_track_from_playlist(&playlist_id, &track_id)
+        .map_err(CommandError::from)
+}
+
+/// Reorder a playlist; `track_ids` lists every track in the new order
+#[tauri::command]
+pub fn reorder_playlist(
+    state: State<'_, AppState>,
+    playlist_id: String,
+    track_ids: Vec<String>,
+) -> Result<(), CommandError> {
+    repository(&state)?
+        .reorder_playlist(&playlist_id, &track_ids)
+        .map_err(CommandError::from)
+}
+
+/// Get a playlist's tracks in order
+#[tauri::command]
+pub fn get_playlist_tracks(state: State<'_, AppState>, playlist_id: String) -> Result<Vec<TrackInfo>, CommandError> {
+    let tracks = repository(&state)?
+        .get_playlist_tracks(&playlist_id)?;
+
+    Ok(tracks.into_iter().map(TrackInfo::from).collect())
+}
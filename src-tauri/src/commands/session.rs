@@ -1,17 +1,71 @@
 //! Session control commands
 
-use crate::audio::capture::AudioCapture;
+use crate::audio::capture::{
+    self, AudioCapture, CaptureError, CaptureFormat, CaptureOptions, CaptureSource, CaptureThread, DeviceWatcher,
+    LatencyMode, SessionRecorder, INPUT_DEVICE_SETTING,
+};
+use crate::audio::meter;
+use crate::audio::gain;
+use crate::commands::{playback, repository, settings, suggestions};
+use crate::db::{DetectionEvent, EventFilter, Repository, Session, Transcript};
+use crate::detection::bridge::EventBridge;
+use crate::detection::logger::DetectionLogEntry;
+use crate::detection::pipeline::{
+    DetectionPipeline, PipelineConfig, PipelineEvent, PipelineMetrics, SimulatedDetection, TriggerAction,
+    DETECTION_TIMEOUT_MESSAGE,
+};
+use crate::detection::speaker::SpeakerVerifier;
+use crate::detection::stream::{PipelineStats, PipelineThread};
+use crate::detection::vad::VoiceActivityDetector;
 use crate::dsp::processing;
+use crate::error::{AppError, CommandError, ErrorCode};
 use crate::inference::emotion::EmotionAnalyzer;
-use crate::inference::whisper::WhisperEngine;
-use crate::orchestrator::state::SessionState;
-use crate::state::AppMode;
+use crate::inference::whisper::{Transcription, WhisperEngine};
+use crate::orchestrator::state::{OrchestratorError, SessionConfig, SessionState};
+use crate::state::{AppMode, APP_MODE_SETTING, INPUT_GAIN_SETTING};
 use crate::AppState;
 use cpal::traits::{DeviceTrait, HostTrait};
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use std::collections::HashMap;
+use tauri::{AppHandle, Emitter, Manager, State};
 use tracing::info;
 
+/// Event emitted when the capture device disappears mid-session
+pub const DEVICE_LOST_EVENT: &str = "capture://device_lost";
+
+/// Event emitted with the gap length (ms) once capture is re-attached
+pub const DEVICE_RECOVERED_EVENT: &str = "capture://device_recovered";
+
+/// Event emitted 60 times a second with the input peak level in dBFS
+pub const AUDIO_PEAK_EVENT: &str = "audio_peak_db";
+
+/// Event emitted with the id of a deleted session, so history lists can drop it
+pub const SESSION_DELETED_EVENT: &str = "sessions://deleted";
+
+/// Event emitted with the session's dropped frame count whenever it grows
+pub const PIPELINE_OVERRUN_EVENT: &str = "pipeline_overrun";
+
+/// Event emitted with a readable message when live detection fails
+pub const DETECTION_ERROR_EVENT: &str = "detection://error";
+
+/// Event emitted with a `ProcessingProgress` while a stopped session is processed
+pub const PROCESSING_PROGRESS_EVENT: &str = "session://processing_progress";
+
+/// Event emitted with the final `SessionStatus` once processing is done
+pub const SESSION_COMPLETE_EVENT: &str = "session://complete";
+
+/// Event emitted with the new mode name whenever the app mode changes
+pub const MODE_CHANGED_EVENT: &str = "mode://changed";
+
+/// Wait between attempts to re-attach a lost capture device
+const DEVICE_RECOVERY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Longest recording `test_microphone` will make
+const MIC_TEST_MAX_MS: u64 = 5000;
+
+/// Frame length used for the microphone test's peak and VAD measurements
+const MIC_TEST_FRAME_MS: u32 = 30;
+
 /// Response for session commands
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SessionResponse {
@@ -21,7 +75,7 @@ pub struct SessionResponse {
 }
 
 /// Session status response
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionStatus {
     pub state: String,
     pub is_recording: bool,
@@ -29,6 +83,9 @@ pub struct SessionStatus {
     pub transcription: Option<String>,
     pub emotion: Option<String>,
     pub current_emotion: Option<String>,
+    pub detected_language: Option<String>,
+    /// Some input sample hit full scale within the last second
+    pub clipping_detected: bool,
     pub mode: String,
 }
 
@@ -39,6 +96,48 @@ pub struct AudioDevice {
     pub name: String,
     pub is_input: bool,
     pub is_default: bool,
+    /// The device chosen in a previous session
+    pub is_selected: bool,
+    /// Output device recorded by the loopback capture source
+    pub is_loopback: bool,
+}
+
+/// One page of past sessions
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionHistory {
+    pub sessions: Vec<Session>,
+    /// Number of stored sessions across all pages
+    pub total: i64,
+}
+
+/// A past session with everything detected during it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionDetail {
+    pub session: Session,
+    pub events: Vec<DetectionEvent>,
+}
+
+/// Result of a microphone self-test
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MicrophoneTestReport {
+    /// Device that was tested (None is the system default)
+    pub device_id: Option<String>,
+    pub duration_ms: u64,
+    /// Loudest 30 ms frame in dBFS
+    pub peak_db: f32,
+    /// Level of the whole recording in dBFS
+    pub average_db: f32,
+    /// The energy VAD fired at least once
+    pub speech_detected: bool,
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+/// Audio level meter readings in dBFS
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AudioLevels {
+    pub input_db: f32,
+    pub music_db: f32,
 }
 
 /// Track info
@@ -46,49 +145,221 @@ pub struct AudioDevice {
 pub struct TrackInfo {
     pub id: String,
     pub name: String,
+    pub duration_ms: Option<i64>,
     pub genre: Option<String>,
     pub mood: Option<String>,
     pub is_looping: bool,
+    /// Problems found when the file was imported (empty if clean)
+    pub import_warnings: Vec<String>,
+}
+
+impl From<crate::db::Track> for TrackInfo {
+    fn from(t: crate::db::Track) -> Self {
+        Self {
+            id: t.id,
+            name: t.name,
+            duration_ms: t.duration_ms,
+            genre: t.genre,
+            mood: t.mood,
+            is_looping: t.is_looping,
+            import_warnings: t.import_warnings,
+        }
+    }
+}
+
+/// Library tracks, or an empty list when the database could not be opened
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrackList {
+    pub tracks: Vec<TrackInfo>,
+    /// Set when no database is connected, so the UI can explain the empty list
+    pub db_unavailable: bool,
 }
 
 /// Get available audio devices
 #[tauri::command]
-pub fn get_available_devices() -> Result<Vec<AudioDevice>, String> {
+pub fn get_available_devices(state: State<'_, AppState>) -> Result<Vec<AudioDevice>, CommandError> {
     info!("Getting available audio devices");
 
+    let selected = selected_input_device(&state);
     let mut devices = Vec::new();
 
     // Get input devices using cpal
     let host = cpal::default_host();
-    for device in host.input_devices().map_err(|e| e.to_string())? {
-        let name = device.name().map_err(|e| e.to_string())?;
+    for device in host.input_devices().map_err(device_error)? {
+        let name = device.name().map_err(device_error)?;
         let id = name.clone();
         let is_default = device.default_input_config().is_ok();
+        let is_selected = selected.as_deref() == Some(id.as_str());
 
         devices.push(AudioDevice {
             id,
             name,
             is_input: true,
             is_default,
+            is_selected,
+            is_loopback: false,
+        });
+    }
+
+    // Only listed where the platform supports recording it
+    if let Some(name) = AudioCapture::loopback_device_name() {
+        devices.push(AudioDevice {
+            id: name.clone(),
+            name,
+            is_input: false,
+            is_default: true,
+            is_selected: state.config.read().capture_source.includes_loopback(),
+            is_loopback: true,
         });
     }
 
     Ok(devices)
 }
 
+fn device_error(e: impl std::fmt::Display) -> CommandError {
+    CommandError::new(ErrorCode::DeviceUnavailable, e.to_string())
+}
+
+/// Device chosen in this run, or the one persisted by a previous run
+pub(crate) fn selected_input_device(state: &AppState) -> Option<String> {
+    if let Some(id) = state.active_device_id.read().clone() {
+        return Some(id);
+    }
+    repository(state).ok()?.get_setting(INPUT_DEVICE_SETTING).ok().flatten()
+}
+
+/// Make `id` the capture device for this and future runs
+fn remember_input_device(state: &AppState, id: &str) {
+    *state.active_device_id.write() = Some(id.to_string());
+
+    if let Ok(repo) = repository(state) {
+        if let Err(e) = repo.set_setting(INPUT_DEVICE_SETTING, id) {
+            tracing::warn!("Failed to save input device: {}", e);
+        }
+    }
+}
+
+/// Switch capture to another input device
+///
+/// Not allowed while recording; the next session captures from the new device.
+#[tauri::command]
+pub fn select_input_device(state: State<'_, AppState>, device_id: String) -> Result<(), CommandError> {
+    apply_input_device(&state, &device_id)
+}
+
+/// Check that `device_id` is connected and make it the capture device
+pub(crate) fn apply_input_device(state: &AppState, device_id: &str) -> Result<(), CommandError> {
+    info!("Selecting input device: {}", device_id);
+
+    if *state.session_state.read() == SessionState::Recording {
+        return Err(CommandError::invalid_state("Stop the current session before switching input devices"));
+    }
+
+    let devices = AudioCapture::list_devices()?;
+    if !devices.iter().any(|id| id == device_id) {
+        return Err(CommandError::new(
+            ErrorCode::DeviceUnavailable,
+            format!("Input device not found: {}", device_id),
+        )
+        .with_details(serde_json::json!({ "device_id": device_id })));
+    }
+
+    // Drop any capture left from an interrupted session so it cannot hold the old device
+    if let Some(mut capture) = state.capture.lock().take() {
+        capture.stop();
+    }
+
+    remember_input_device(state, device_id);
+    Ok(())
+}
+
+/// Start polling for input devices and forward changes to the frontend
+pub fn start_device_watcher(app: &AppHandle) {
+    let handle = app.clone();
+    let watcher = DeviceWatcher::start(move |change| {
+        let _ = handle.emit(change.event_name(), change.device_name());
+    });
+
+    match watcher {
+        Ok(watcher) => *app.state::<AppState>().device_watcher.lock() = Some(watcher),
+        Err(e) => tracing::warn!("Failed to watch input devices: {}", e),
+    }
+}
+
 /// Start a recording session - begins audio capture in background thread
 #[tauri::command]
 pub fn start_session(
+    app: AppHandle,
     state: State<'_, AppState>,
-    _device_id: Option<String>,
+    device_id: Option<String>,
     enable_transcription: Option<bool>,
     enable_emotion: Option<bool>,
-) -> Result<SessionResponse, String> {
+    capture_source: Option<CaptureSource>,
+    record_session: Option<bool>,
+) -> Result<SessionResponse, CommandError> {
     info!("Starting session command");
 
+    begin_session(app, &state, device_id, |config| {
+        config.enable_transcription = enable_transcription.unwrap_or(true);
+        config.enable_emotion_analysis = enable_emotion.unwrap_or(true);
+        config.capture_source = capture_source.unwrap_or_default();
+        config.record_session = record_session.unwrap_or(false);
+    })
+}
+
+/// Start a recording session on a specific input device with chosen pipeline stages
+///
+/// Stages left out keep their defaults: everything on except speaker verification.
+#[tauri::command]
+pub fn start_session_with_device(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    device_id: String,
+    enable_transcription: Option<bool>,
+    enable_emotion: Option<bool>,
+    enable_vad: Option<bool>,
+    enable_speaker_verification: Option<bool>,
+) -> Result<SessionResponse, CommandError> {
+    info!("Starting session on device: {}", device_id);
+
+    let devices = AudioCapture::list_devices()?;
+    if !devices.contains(&device_id) {
+        return Ok(SessionResponse {
+            success: false,
+            message: format!("Input device not found: {}", device_id),
+            state: state.session_state.read().to_string(),
+        });
+    }
+
+    begin_session(app, &state, Some(device_id), |config| {
+        config.enable_transcription = enable_transcription.unwrap_or(true);
+        config.enable_emotion_analysis = enable_emotion.unwrap_or(true);
+        config.enable_vad = enable_vad.unwrap_or(true);
+        config.enable_speaker_verification = enable_speaker_verification.unwrap_or(false);
+        config.capture_source = CaptureSource::Microphone;
+    })
+}
+
+/// Apply `configure` to the session config and start capturing
+fn begin_session<C>(
+    app: AppHandle,
+    state: &AppState,
+    device_id: Option<String>,
+    configure: C,
+) -> Result<SessionResponse, CommandError>
+where
+    C: FnOnce(&mut SessionConfig),
+{
     // Check current state
     let current_state = *state.session_state.read();
 
+    if current_state == SessionState::Processing {
+        return Ok(SessionResponse {
+            success: false,
+            message: "The last session is still being processed".to_string(),
+            state: current_state.to_string(),
+        });
+    }
     if current_state != SessionState::Idle {
         return Ok(SessionResponse {
             success: false,
@@ -97,55 +368,525 @@ pub fn start_session(
         });
     }
 
+    // Last session's results are replaced by this one's
+    state.last_transcription.write().take();
+    state.last_emotion.write().take();
+
     // Update config
-    {
-        let mut config = state.config.write();
-        config.enable_transcription = enable_transcription.unwrap_or(true);
-        config.enable_emotion_analysis = enable_emotion.unwrap_or(true);
-    }
+    configure(&mut state.config.write());
+
+    // Clear audio buffers
+    state.audio_buffer.write().clear();
+    state.loopback_buffer.write().clear();
+
+    // New session starts with fresh keyword priorities
+    state.keyword_use_counts.write().clear();
+
+    // Remember an explicitly chosen device, otherwise reuse the last one
+    let device_id = match device_id {
+        Some(id) => {
+            remember_input_device(state, &id);
+            Some(id)
+        }
+        None => selected_input_device(state),
+    };
 
-    // Clear audio buffer
     {
-        let mut buffer = state.audio_buffer.write();
-        buffer.clear();
+        let mut gain = state.input_gain.write();
+        gain.set_gain(state.config.read().input_gain);
+        gain.reset_clipping();
     }
 
-    // Start audio capture in a background thread that runs until stopped
-    let buffer = state.audio_buffer.clone();
-
-    let _handle = std::thread::spawn(move || {
-        let mut capture = AudioCapture::new();
-        let _ = capture.start_recording(move |samples| {
-            let mut buf = buffer.write();
-            buf.extend_from_slice(&samples);
-        });
+    // System audio goes to its own buffer when the microphone is recorded too
+    if state.config.read().capture_source == CaptureSource::Both {
+        match spawn_loopback_capture(state) {
+            Ok(capture) => *state.loopback_capture.lock() = Some(capture),
+            Err(e) => {
+                return Ok(SessionResponse {
+                    success: false,
+                    message: format!("Failed to start system audio capture: {}", e),
+                    state: current_state.to_string(),
+                });
+            }
+        }
+    }
 
-        // Keep recording - the stream stays alive until the thread is dropped
-        // This blocks until the thread is killed
-        loop {
-            std::thread::sleep(std::time::Duration::from_secs(1));
+    // Start audio capture on its own thread; stop_session stops and joins it
+    // Recording goes on without live detection; the response says why it is missing
+    let mut message = "Recording started".to_string();
+    match spawn_capture(&app, state, device_id) {
+        Ok(capture) => {
+            open_session_record(&app, state);
+            if let Err(e) = start_pipeline_stream(&app, state) {
+                tracing::warn!("{}", e);
+                message = format!("Recording started without live detection. {}", e);
+            }
+            supervise_capture(app, capture.errors());
+            *state.capture.lock() = Some(capture);
         }
-    });
+        Err(e) => {
+            state.loopback_capture.lock().take();
+            return Ok(SessionResponse {
+                success: false,
+                message: format!("Failed to start recording: {}", e),
+                state: current_state.to_string(),
+            });
+        }
+    }
 
     // Update state
     *state.session_state.write() = SessionState::Recording;
 
     Ok(SessionResponse {
         success: true,
-        message: "Recording started".to_string(),
+        message,
         state: "recording".to_string(),
     })
 }
 
-/// Stop a recording session and process audio
+/// Start capturing into the session buffer and record the device format
+fn spawn_capture(app: &AppHandle, state: &AppState, device_id: Option<String>) -> Result<CaptureThread, CaptureError> {
+    let buffer = state.audio_buffer.clone();
+    let input_level = state.input_level.clone();
+    let input_gain = state.input_gain.clone();
+    let pipeline_feed = state.pipeline_feed.clone();
+
+    // The meter thread ends when the capture drops the callback holding its sender
+    let (peak_tx, peak_rx) = flume::unbounded();
+    let handle = app.clone();
+    meter::spawn_peak_meter(peak_rx, state.input_peak_db.clone(), move |db| {
+        let _ = handle.emit(AUDIO_PEAK_EVENT, db);
+    });
+
+    let options = CaptureOptions {
+        device_name: device_id,
+        loopback: state.config.read().capture_source == CaptureSource::Loopback,
+        latency_mode: Some(state.config.read().latency_mode),
+        peak_tx: None,
+    };
+    // The recording keeps the raw signal; gain only affects detection
+    let callback = capture::recording(state.session_recorder.clone(), move |mut samples| {
+        // Gain first, so the meter and every consumer of the buffer see the same signal
+        input_gain.write().apply(&mut samples);
+        let _ = peak_tx.send(processing::calculate_db(&samples));
+        *input_level.write() = processing::calculate_rms(&samples);
+        buffer.write().push_slice(&samples);
+        if let Some(feed) = pipeline_feed.lock().as_mut() {
+            feed.push(&samples);
+        }
+    });
+    let capture = CaptureThread::spawn(options, callback)?;
+
+    // The device decides the format; everything downstream reads it from here
+    let capture_info = capture.info();
+    let format = capture_info.format();
+    info!(
+        "Capturing at {} Hz, {} channels, {:?} ms buffers",
+        format.sample_rate,
+        format.channels,
+        capture_info.buffer_ms()
+    );
+    *state.sample_rate.write() = format.sample_rate;
+    *state.channels.write() = format.channels;
+    state
+        .audio_buffer
+        .write()
+        .set_sample_rate(format.sample_rate * format.channels as u32);
+
+    Ok(capture)
+}
+
+/// Run the detection pipeline on live audio for the rest of the session
+fn start_pipeline_stream(app: &AppHandle, state: &AppState) -> Result<(), OrchestratorError> {
+    let config = state.config.read().clone();
+
+    let mut pipeline = DetectionPipeline::new(PipelineConfig::from_session(&config));
+    let (event_tx, event_rx) = flume::unbounded::<PipelineEvent>();
+    let session_id = state.session_id.read().clone().unwrap_or_default();
+    pipeline.set_session_id(session_id.clone());
+    spawn_event_bridge(app, event_rx, session_id);
+    pipeline.set_mode(state.app_mode.read().detection_mode());
+    pipeline.set_keyword_use_counts(state.keyword_use_counts.clone());
+    pipeline.set_logger(state.detection_logger.clone());
+    if let Some(vocabulary) = state.keyword_vocabulary.read().clone() {
+        pipeline.set_vocabulary(vocabulary);
+    }
+    if let Ok(repo) = repository(state) {
+        match SpeakerVerifier::from_repository(&repo) {
+            Ok(verifier) => pipeline.set_speaker_verifier(verifier),
+            Err(e) => tracing::warn!("Failed to load voice profiles: {}", e),
+        }
+        pipeline.set_repository(repo);
+    }
+
+    state.pipeline_stats.reset();
+    let sample_rate = *state.sample_rate.read();
+    let (thread, feed) = PipelineThread::spawn(pipeline, sample_rate, event_tx, state.pipeline_stats.clone())?;
+    *state.pipeline_feed.lock() = Some(feed);
+    *state.pipeline_thread.lock() = Some(thread);
+    Ok(())
+}
+
+/// Forward pipeline events to the frontend until the pipeline is dropped
+fn spawn_event_bridge(app: &AppHandle, events: flume::Receiver<PipelineEvent>, session_id: String) {
+    let app = app.clone();
+    let spawned = std::thread::Builder::new()
+        .name("detection-events".to_string())
+        .spawn(move || {
+            let mut bridge = EventBridge::new(session_id.clone());
+            let started = std::time::Instant::now();
+            let mut emotion_confidence = 0.0;
+            for event in events.iter() {
+                match &event {
+                    PipelineEvent::Emotion(_, confidence) => emotion_confidence = *confidence,
+                    PipelineEvent::Overrun(dropped) => {
+                        tracing::warn!("Detection pipeline is behind: {} frames dropped", dropped);
+                        let _ = app.emit(PIPELINE_OVERRUN_EVENT, *dropped);
+                    }
+                    PipelineEvent::Error(message) if message == DETECTION_TIMEOUT_MESSAGE => {
+                        tracing::debug!("Detection timed out without a dual signal");
+                    }
+                    PipelineEvent::Error(message) => {
+                        let error = OrchestratorError::DetectionError(message.clone());
+                        let _ = app.emit(DETECTION_ERROR_EVENT, error.to_string());
+                    }
+                    PipelineEvent::DualSignal { keyword, emotion, reply } => {
+                        let state = app.state::<AppState>();
+                        let mode = *state.app_mode.read();
+                        let action = match mode {
+                            AppMode::ModeA => playback::play_for_dual_signal(&state, keyword, emotion)
+                                .map(|track| TriggerAction::TrackCrossfaded {
+                                    track_id: track.id,
+                                    track_name: track.name,
+                                }),
+                            AppMode::ModeB => suggestions::on_dual_signal(
+                                &app,
+                                &session_id,
+                                keyword,
+                                emotion,
+                                emotion_confidence,
+                                reply.is_some(),
+                            )
+                            .map(|suggestion| TriggerAction::SuggestionQueued {
+                                suggestion_id: suggestion.id,
+                                track_name: suggestion.track_name,
+                            }),
+                        };
+                        if let Some(reply) = reply {
+                            let _ = reply.send(action.unwrap_or(TriggerAction::Nothing));
+                        }
+                    }
+                    _ => {}
+                }
+
+                let now_ms = started.elapsed().as_millis() as u64;
+                if let Some((name, payload)) = bridge.translate(event, now_ms) {
+                    let _ = app.emit(name, payload);
+                }
+            }
+        });
+
+    if let Err(e) = spawned {
+        tracing::warn!("Detection events will not reach the UI: {}", e);
+    }
+}
+
+/// Stop feeding the live pipeline and wait for it to finish
+fn stop_pipeline_stream(state: &AppState) {
+    state.pipeline_feed.lock().take();
+    if let Some(mut thread) = state.pipeline_thread.lock().take() {
+        thread.stop();
+    }
+}
+
+/// Create the session's database row and, if enabled, its WAV recording
+fn open_session_record(app: &AppHandle, state: &AppState) {
+    let session_id = uuid::Uuid::new_v4().to_string();
+
+    match repository(state) {
+        Ok(repo) => {
+            let session = Session::new(session_id.clone(), state.app_mode.read().to_string());
+            if let Err(e) = repo.start_session(&session) {
+                tracing::warn!("Failed to store session {}: {}", session_id, e);
+            }
+        }
+        Err(e) => tracing::debug!("Session {} not stored: {}", session_id, e),
+    }
+
+    if state.config.read().record_session {
+        let format = CaptureFormat {
+            sample_rate: *state.sample_rate.read(),
+            channels: *state.channels.read(),
+        };
+        let recorder = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| CaptureError::RecordingError(e.to_string()))
+            .and_then(|dir| {
+                let path = dir.join("recordings").join(format!("{}.wav", session_id));
+                SessionRecorder::create(path, format)
+            });
+
+        match recorder {
+            Ok(recorder) => *state.session_recorder.lock() = Some(recorder),
+            Err(e) => tracing::warn!("Session audio will not be recorded: {}", e),
+        }
+    }
+
+    state.detection_logger.lock().reset(session_id.clone());
+    *state.session_id.write() = Some(session_id);
+}
+
+/// Finish the WAV recording and close the session's database row
+fn close_session_record(state: &AppState) {
+    let recording_path =
+        finish_recording(state.session_recorder.lock().take()).map(|path| path.to_string_lossy().to_string());
+
+    let Some(session_id) = state.session_id.write().take() else {
+        return;
+    };
+    let Ok(repo) = repository(state) else {
+        return;
+    };
+
+    let entries = state.detection_logger.lock().take_entries();
+    let keywords_triggered = entries.iter().filter(|e| e.event_type == "keyword").count() as i32;
+    let result = repo
+        .end_session(&session_id)
+        .and_then(|_| store_detections(&repo, &entries))
+        .and_then(|_| repo.get_session_events(&session_id))
+        .and_then(|events| repo.set_session_stats(&session_id, events.len() as i32, keywords_triggered))
+        .and_then(|_| match &recording_path {
+            Some(path) => repo.set_session_recording_path(&session_id, path),
+            None => Ok(()),
+        });
+    if let Err(e) = result {
+        tracing::warn!("Failed to close session {}: {}", session_id, e);
+    }
+}
+
+/// Save logged detections as the session's detection events
+fn store_detections(repo: &Repository, entries: &[DetectionLogEntry]) -> Result<(), AppError> {
+    for entry in entries {
+        let event = DetectionEvent {
+            id: entry.id.clone(),
+            session_id: entry.session_id.clone(),
+            event_type: entry.event_type.clone(),
+            timestamp: entry.timestamp.to_rfc3339(),
+            details: Some(entry.details.clone()).filter(|d| !d.is_empty()),
+            confidence: entry.confidence.map(f64::from),
+            category: entry.category.clone(),
+            triggered_action: entry.triggered_action,
+        };
+        repo.insert_detection_event(&event)?;
+    }
+    Ok(())
+}
+
+/// Start recording system audio into the loopback buffer
+///
+/// Used alongside the microphone, so it skips gain and metering.
+fn spawn_loopback_capture(state: &AppState) -> Result<CaptureThread, CaptureError> {
+    let buffer = state.loopback_buffer.clone();
+    let options = CaptureOptions {
+        loopback: true,
+        ..CaptureOptions::default()
+    };
+    let capture = CaptureThread::spawn(options, move |samples| {
+        buffer.write().push_slice(&samples);
+    })?;
+
+    let format = capture.format();
+    info!("Capturing system audio at {} Hz", format.sample_rate);
+    state
+        .loopback_buffer
+        .write()
+        .set_sample_rate(format.sample_rate * format.channels as u32);
+
+    Ok(capture)
+}
+
+/// Watch a capture for device loss and re-attach to the selected device,
+/// or to the default one while the selected device stays missing
+///
+/// The session goes to `Error` while the device is missing and back to
+/// `Recording` once capture restarts; the thread ends with the capture.
+fn supervise_capture(app: AppHandle, errors: flume::Receiver<CaptureError>) {
+    std::thread::spawn(move || {
+        let Ok(error) = errors.recv() else {
+            return;
+        };
+
+        let state = app.state::<AppState>();
+        if *state.session_state.read() != SessionState::Recording {
+            return;
+        }
+
+        tracing::warn!("Capture device lost: {}", error);
+        *state.session_state.write() = SessionState::Error;
+        *state.input_level.write() = 0.0;
+        let _ = app.emit(DEVICE_LOST_EVENT, error.to_string());
+
+        let lost_at = std::time::Instant::now();
+        if let Some(mut capture) = state.capture.lock().take() {
+            capture.stop();
+        }
+        let format = CaptureFormat {
+            sample_rate: *state.sample_rate.read(),
+            channels: *state.channels.read(),
+        };
+        let selected = selected_input_device(&state);
+
+        let retries = state.config.read().device_recovery_retries;
+        for attempt in 1..=retries {
+            std::thread::sleep(DEVICE_RECOVERY_INTERVAL);
+
+            // Stopped by the user while waiting
+            if *state.session_state.read() != SessionState::Error {
+                return;
+            }
+
+            // Detached while the device opens, so audio in another format never reaches the WAV
+            let recorder = state.session_recorder.lock().take();
+            let buffered = state.audio_buffer.read().len();
+            let mut device = selected.clone();
+            let resumed = match spawn_capture(&app, &state, device.clone()) {
+                Err(e) if device.is_some() => {
+                    tracing::debug!("Selected input device still unavailable: {}", e);
+                    device = None;
+                    spawn_capture(&app, &state, None)
+                }
+                resumed => resumed,
+            };
+
+            match resumed {
+                Ok(mut capture) => {
+                    let mut slot = state.capture.lock();
+                    if *state.session_state.read() != SessionState::Error {
+                        capture.stop();
+                        finish_recording(recorder);
+                        return;
+                    }
+
+                    let resumed_format = capture.format();
+                    if resumed_format == format {
+                        *state.session_recorder.lock() = recorder;
+                    } else {
+                        switch_capture_format(&app, &state, recorder, buffered, format, resumed_format);
+                    }
+                    tracing::warn!(
+                        "Capture resumed on {} after a {:.1}s gap; detection missed that audio",
+                        device.as_deref().unwrap_or("the default device"),
+                        lost_at.elapsed().as_secs_f32()
+                    );
+                    supervise_capture(app.clone(), capture.errors());
+                    *slot = Some(capture);
+                    *state.session_state.write() = SessionState::Recording;
+                    let _ = app.emit(DEVICE_RECOVERED_EVENT, lost_at.elapsed().as_millis() as u64);
+                    return;
+                }
+                Err(e) => {
+                    *state.session_recorder.lock() = recorder;
+                    tracing::warn!("Capture recovery attempt {}/{} failed: {}", attempt, retries, e);
+                }
+            }
+        }
+
+        tracing::error!("Capture device not recovered after {} attempts", retries);
+    });
+}
+
+/// Carry a recovered session over to a device recording in another format
+///
+/// The `buffered` samples captured before the loss are converted to the new
+/// format, the WAV recording, which cannot change format midway, is
+/// finalized and the live pipeline restarts at the new sample rate.
+fn switch_capture_format(
+    app: &AppHandle,
+    state: &AppState,
+    recorder: Option<SessionRecorder>,
+    buffered: usize,
+    from: CaptureFormat,
+    to: CaptureFormat,
+) {
+    tracing::warn!(
+        "Input format changed from {} Hz x{} to {} Hz x{}",
+        from.sample_rate,
+        from.channels,
+        to.sample_rate,
+        to.channels
+    );
+
+    {
+        let mut buffer = state.audio_buffer.write();
+        let mut samples = buffer.drain_all();
+        let resumed = samples.split_off(buffered.min(samples.len()));
+        buffer.push_slice(&convert_capture_format(&samples, from, to));
+        buffer.push_slice(&resumed);
+    }
+
+    if let Some(path) = finish_recording(recorder) {
+        tracing::warn!("Session recording ends at the format change: {:?}", path);
+        let session_id = state.session_id.read().clone();
+        if let (Some(session_id), Ok(repo)) = (session_id, repository(state)) {
+            if let Err(e) = repo.set_session_recording_path(&session_id, &path.to_string_lossy()) {
+                tracing::warn!("Failed to store recording path for session {}: {}", session_id, e);
+            }
+        }
+    }
+
+    if state.pipeline_thread.lock().is_some() {
+        stop_pipeline_stream(state);
+        if let Err(e) = start_pipeline_stream(app, state) {
+            tracing::warn!("Live detection stopped at the format change: {}", e);
+        }
+    }
+}
+
+/// Finalize a WAV recording taken out of the session, returning its path
+fn finish_recording(recorder: Option<SessionRecorder>) -> Option<std::path::PathBuf> {
+    match recorder?.finalize() {
+        Ok(path) => Some(path),
+        Err(e) => {
+            tracing::warn!("Failed to finish session recording: {}", e);
+            None
+        }
+    }
+}
+
+/// Convert interleaved audio between capture formats, downmixing through mono
+fn convert_capture_format(samples: &[f32], from: CaptureFormat, to: CaptureFormat) -> Vec<f32> {
+    let mut mono = if from.channels > 1 {
+        processing::stereo_to_mono(samples, from.channels)
+    } else {
+        samples.to_vec()
+    };
+    if from.sample_rate != to.sample_rate {
+        mono = processing::resample(&mono, from.sample_rate, to.sample_rate);
+    }
+
+    let channels = to.channels as usize;
+    let mut converted = Vec::with_capacity(mono.len() * channels);
+    for sample in mono {
+        converted.resize(converted.len() + channels, sample);
+    }
+    converted
+}
+
+/// Stop a recording session and process its audio in the background
+///
+/// Returns once capture has stopped. Progress is reported through
+/// `PROCESSING_PROGRESS_EVENT` and the result through `SESSION_COMPLETE_EVENT`;
+/// the session stays in `Processing` until then.
 #[tauri::command]
-pub fn stop_session(state: State<'_, AppState>) -> Result<SessionResponse, String> {
+pub fn stop_session(app: AppHandle, state: State<'_, AppState>) -> Result<SessionResponse, CommandError> {
     info!("Stopping session command");
 
     // Check current state
     let current_state = *state.session_state.read();
 
-    if current_state != SessionState::Recording {
+    // A session that lost its device can still be stopped and processed
+    if current_state != SessionState::Recording && current_state != SessionState::Error {
         return Ok(SessionResponse {
             success: false,
             message: format!("Cannot stop session, current state: {}", current_state),
@@ -153,37 +894,144 @@ pub fn stop_session(state: State<'_, AppState>) -> Result<SessionResponse, Strin
         });
     }
 
-    // Update state to processing
+    // Update state to processing first, so device recovery will not re-attach
     *state.session_state.write() = SessionState::Processing;
+    *state.input_level.write() = 0.0;
+
+    // Stop capturing before the buffer is read
+    if let Some(mut capture) = state.capture.lock().take() {
+        capture.stop();
+    }
+    if let Some(mut capture) = state.loopback_capture.lock().take() {
+        capture.stop();
+    }
+    stop_pipeline_stream(&state);
+    suggestions::clear_suggestions(&state);
+    let session_id = state.session_id.read().clone();
+    close_session_record(&state);
 
     // Get audio data
-    let (samples, sample_rate, config) = {
-        let buffer = state.audio_buffer.read();
-        let rate = *state.sample_rate.read();
-        let cfg = state.config.read().clone();
-        (buffer.clone(), rate, cfg)
+    let microphone = {
+        let samples = state.audio_buffer.write().drain_all();
+        let format = CaptureFormat {
+            sample_rate: *state.sample_rate.read(),
+            channels: *state.channels.read(),
+        };
+        (samples, format)
+    };
+    // System audio recorded next to the microphone
+    let loopback = {
+        let mut buffer = state.loopback_buffer.write();
+        let format = CaptureFormat {
+            sample_rate: buffer.sample_rate(),
+            channels: 1,
+        };
+        (buffer.drain_all(), format)
     };
+    let config = state.config.read().clone();
 
-    info!("Processing {} samples at {} Hz", samples.len(), sample_rate);
+    tauri::async_runtime::spawn_blocking(move || process_session_audio(&app, session_id, microphone, loopback, config));
 
-    // Process audio with DSP
-    let mut processed_samples = samples;
+    Ok(SessionResponse {
+        success: true,
+        message: "Processing session audio".to_string(),
+        state: SessionState::Processing.to_string(),
+    })
+}
 
-    // Resample if needed
-    if sample_rate != config.sample_rate {
-        processed_samples = processing::resample(&processed_samples, sample_rate, config.sample_rate);
+/// Store a whole-session transcription if no live segment was stored
+fn store_session_transcript(
+    repo: &Repository,
+    session_id: &str,
+    transcription: &Transcription,
+    duration_ms: u64,
+) -> Result<(), AppError> {
+    if transcription.text.is_empty() {
+        return Ok(());
+    }
+    if !repo.get_session_transcripts(session_id)?.is_empty() {
+        return Ok(());
     }
 
-    // Apply DSP processing
-    processing::remove_dc_offset(&mut processed_samples);
-    processing::normalize(&mut processed_samples, 0.9);
-    processing::noise_gate(&mut processed_samples, config.silence_threshold);
+    let mut transcript = Transcript::new(session_id.to_string(), 0, duration_ms as i64, transcription.text.clone());
+    transcript.confidence = Some(f64::from(transcription.confidence));
+    transcript.language = transcription.language.clone();
+    repo.insert_transcript(&transcript)?;
+    Ok(())
+}
 
-    // Run transcription
-    let mut whisper = WhisperEngine::new();
-    let _ = whisper.init("models/whisper-tiny.bin");
+/// Progress of the audio processing after a session stops
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessingProgress {
+    /// One of "resample", "dsp", "transcription", "emotion"
+    pub stage: String,
+    pub percent: u8,
+}
+
+fn report_progress(app: &AppHandle, stage: &str, percent: u8) {
+    tracing::debug!("Session processing: {} ({}%)", stage, percent);
+    let _ = app.emit(
+        PROCESSING_PROGRESS_EVENT,
+        ProcessingProgress {
+            stage: stage.to_string(),
+            percent,
+        },
+    );
+}
 
+/// Puts the session back to `Idle` and reports `SESSION_COMPLETE_EVENT` when
+/// processing ends, including by a panic
+struct ProcessingGuard<'a> {
+    app: &'a AppHandle,
+}
+
+impl Drop for ProcessingGuard<'_> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            tracing::error!("Session processing panicked");
+        }
+        let state = self.app.state::<AppState>();
+        *state.session_state.write() = SessionState::Idle;
+        let _ = self.app.emit(SESSION_COMPLETE_EVENT, session_status(&state));
+    }
+}
+
+/// Resample, clean up, transcribe and analyze a stopped session's audio, then go idle
+///
+/// The transcription is stored as one segment spanning the session, unless
+/// the live pipeline already stored the session's segments.
+fn process_session_audio(
+    app: &AppHandle,
+    session_id: Option<String>,
+    microphone: (Vec<f32>, CaptureFormat),
+    loopback: (Vec<f32>, CaptureFormat),
+    config: SessionConfig,
+) {
+    let _guard = ProcessingGuard { app };
+    let state = app.state::<AppState>();
+    info!(
+        "Processing {} samples at {} Hz, {} channels",
+        microphone.0.len(),
+        microphone.1.sample_rate,
+        microphone.1.channels
+    );
+
+    report_progress(app, "resample", 0);
+    let mut processed_samples = resample_session_audio(microphone.0, microphone.1, &config);
+    let system_audio = (!loopback.0.is_empty()).then(|| resample_session_audio(loopback.0, loopback.1, &config));
+
+    report_progress(app, "dsp", 20);
+    clean_session_audio(&mut processed_samples, &config);
+    if let Some(mut system_audio) = system_audio {
+        clean_session_audio(&mut system_audio, &config);
+        processed_samples = mix_sources(&processed_samples, &system_audio);
+    }
+
+    // Run transcription
+    report_progress(app, "transcription", 40);
     let transcription = if config.enable_transcription {
+        let mut whisper = WhisperEngine::new();
+        let _ = whisper.init(&crate::inference::whisper::get_model_path().to_string_lossy());
         match whisper.transcribe(&processed_samples, config.sample_rate) {
             Ok(t) => Some(t),
             Err(e) => {
@@ -196,10 +1044,11 @@ pub fn stop_session(state: State<'_, AppState>) -> Result<SessionResponse, Strin
     };
 
     // Run emotion analysis
-    let mut emotion_analyzer = EmotionAnalyzer::new();
-    let _ = emotion_analyzer.init();
-
+    report_progress(app, "emotion", 80);
     let emotion = if config.enable_emotion_analysis {
+        let mut emotion_analyzer = EmotionAnalyzer::new();
+        let _ = emotion_analyzer.init();
+        emotion_analyzer.set_baseline(state.emotion_baseline.read().clone());
         match emotion_analyzer.analyze(&processed_samples, config.sample_rate) {
             Ok(e) => Some(e),
             Err(e) => {
@@ -211,34 +1060,90 @@ pub fn stop_session(state: State<'_, AppState>) -> Result<SessionResponse, Strin
         None
     };
 
+    if let Some(language) = transcription.as_ref().and_then(|t| t.language.clone()) {
+        *state.detected_language.write() = Some(language);
+    }
+    if let (Some(session_id), Some(transcription), Ok(repo)) = (&session_id, &transcription, repository(&state)) {
+        let duration_ms = processed_samples.len() as u64 * 1000 / config.sample_rate.max(1) as u64;
+        if let Err(e) = store_session_transcript(&repo, session_id, transcription, duration_ms) {
+            tracing::warn!("Failed to store transcript of session {}: {}", session_id, e);
+        }
+    }
+
     // Update current emotion
     if let Some(ref e) = emotion {
         *state.current_emotion.write() = e.primary.to_string();
     }
 
-    // Format response
-    let transcription_text = transcription.as_ref().map(|t| t.text.clone()).unwrap_or_default();
-    let emotion_text = emotion.as_ref().map(|e| e.primary.to_string()).unwrap_or_else(|| "unknown".to_string());
+    *state.last_transcription.write() = transcription.map(|t| t.text).filter(|text| !text.is_empty());
+    *state.last_emotion.write() = emotion.map(|e| e.primary.to_string());
+    info!("Session processing complete");
+}
 
-    let message = format!(
-        "Session completed\nTranscription: {}\nEmotion: {}",
-        if transcription_text.is_empty() { "(none)".to_string() } else { transcription_text },
-        emotion_text
-    );
+/// Downmix and resample captured audio to the session format
+fn resample_session_audio(samples: Vec<f32>, format: CaptureFormat, config: &SessionConfig) -> Vec<f32> {
+    let mut processed_samples = samples;
 
-    // Reset state to idle
-    *state.session_state.write() = SessionState::Idle;
+    if format.channels > 1 {
+        processed_samples = processing::stereo_to_mono(&processed_samples, format.channels);
+    }
 
-    Ok(SessionResponse {
-        success: true,
-        message,
-        state: "idle".to_string(),
-    })
+    // Resample if needed
+    if format.sample_rate != config.sample_rate {
+        processed_samples = processing::resample(&processed_samples, format.sample_rate, config.sample_rate);
+    }
+
+    processed_samples
+}
+
+/// Apply DSP clean-up: DC offset removal, normalization and the noise gate
+fn clean_session_audio(samples: &mut [f32], config: &SessionConfig) {
+    processing::remove_dc_offset(samples);
+    processing::normalize(samples, 0.9);
+    processing::noise_gate(samples, config.silence_threshold);
+}
+
+/// Average two mono signals that start together; the longer one's tail is kept as is
+fn mix_sources(a: &[f32], b: &[f32]) -> Vec<f32> {
+    (0..a.len().max(b.len()))
+        .map(|i| match (a.get(i), b.get(i)) {
+            (Some(x), Some(y)) => (x + y) * 0.5,
+            (Some(x), None) | (None, Some(x)) => *x,
+            (None, None) => 0.0,
+        })
+        .collect()
+}
+
+/// Set the microphone gain; applies immediately if a session is recording
+#[tauri::command]
+pub fn set_input_gain(state: State<'_, AppState>, gain: f32) -> Result<f32, CommandError> {
+    let gain = gain::clamp_gain(gain);
+    info!("Input gain: {}", gain);
+
+    settings::apply_input_gain(&state, gain);
+    if let Ok(repo) = repository(&state) {
+        if let Err(e) = repo.set_setting(INPUT_GAIN_SETTING, &gain.to_string()) {
+            tracing::warn!("Failed to save input gain: {}", e);
+        }
+    }
+    Ok(gain)
+}
+
+/// Set the capture buffer size; applies from the next session
+#[tauri::command]
+pub fn set_latency_mode(state: State<'_, AppState>, mode: LatencyMode) -> Result<(), CommandError> {
+    info!("Latency mode: {:?} ({} ms buffers)", mode, mode.buffer_ms());
+    state.config.write().latency_mode = mode;
+    Ok(())
 }
 
 /// Get current session status
 #[tauri::command]
-pub fn get_session_status(state: State<'_, AppState>) -> Result<SessionStatus, String> {
+pub fn get_session_status(state: State<'_, AppState>) -> Result<SessionStatus, CommandError> {
+    Ok(session_status(&state))
+}
+
+fn session_status(state: &AppState) -> SessionStatus {
     let session_state = *state.session_state.read();
     let app_mode = *state.app_mode.read();
     let current_emotion = state.current_emotion.read().clone();
@@ -246,82 +1151,55 @@ pub fn get_session_status(state: State<'_, AppState>) -> Result<SessionStatus, S
     let is_recording = session_state == SessionState::Recording;
     let is_processing = session_state == SessionState::Processing;
 
-    Ok(SessionStatus {
+    SessionStatus {
         state: session_state.to_string(),
         is_recording,
         is_processing,
-        transcription: None,
-        emotion: None,
+        transcription: state.last_transcription.read().clone(),
+        emotion: state.last_emotion.read().clone(),
         current_emotion: Some(current_emotion),
+        detected_language: state.detected_language.read().clone(),
+        clipping_detected: state.input_gain.read().clipping_detected(),
         mode: match app_mode {
             AppMode::ModeA => "autonomous".to_string(),
             AppMode::ModeB => "collaborative".to_string(),
         },
-    })
+    }
 }
 
-/// Get tracks from database
+/// Get tracks from database, optionally limited to one genre
 #[tauri::command]
-pub fn get_tracks(state: State<'_, AppState>, genre: Option<String>) -> Result<Vec<TrackInfo>, String> {
-    let pool = state.db_pool.read();
-
-    if let Some(ref pool) = *pool {
-        // Use repository to fetch tracks
-        // For now, return sample tracks
-        Ok(vec![
-            TrackInfo {
-                id: "1".to_string(),
-                name: "Battle Theme".to_string(),
-                genre: Some("combat".to_string()),
-                mood: Some("angry".to_string()),
-                is_looping: true,
-            },
-            TrackInfo {
-                id: "2".to_string(),
-                name: "Mystery Theme".to_string(),
-                genre: Some("exploration".to_string()),
-                mood: Some("neutral".to_string()),
-                is_looping: true,
-            },
-            TrackInfo {
-                id: "3".to_string(),
-                name: "Victory Fanfare".to_string(),
-                genre: Some("social".to_string()),
-                mood: Some("happy".to_string()),
-                is_looping: false,
-            },
-        ])
-    } else {
-        // Database not available, return sample tracks
-        Ok(vec![
-            TrackInfo {
-                id: "sample-1".to_string(),
-                name: "Ambient Dungeon".to_string(),
-                genre: Some("exploration".to_string()),
-                mood: Some("neutral".to_string()),
-                is_looping: true,
-            },
-            TrackInfo {
-                id: "sample-2".to_string(),
-                name: "Boss Battle".to_string(),
-                genre: Some("combat".to_string()),
-                mood: Some("angry".to_string()),
-                is_looping: true,
-            },
-        ])
-    }
+pub fn get_tracks(state: State<'_, AppState>, genre: Option<String>) -> Result<TrackList, CommandError> {
+    let pool = state.db_pool.read().clone();
+    list_tracks(pool.map(Repository::new), genre.as_deref())
+}
+
+fn list_tracks(repo: Option<Repository>, genre: Option<&str>) -> Result<TrackList, CommandError> {
+    let Some(repo) = repo else {
+        return Ok(TrackList {
+            tracks: Vec::new(),
+            db_unavailable: true,
+        });
+    };
+
+    let tracks = match genre {
+        Some(genre) => repo.get_tracks_by_genre(genre),
+        None => repo.get_all_tracks(),
+    }?;
+
+    Ok(TrackList {
+        tracks: tracks.into_iter().map(TrackInfo::from).collect(),
+        db_unavailable: false,
+    })
 }
 
 /// Set application mode (A: autonomous, B: collaborative)
 #[tauri::command]
-pub fn set_app_mode(state: State<'_, AppState>, mode: String) -> Result<SessionResponse, String> {
-    let new_mode = match mode.as_str() {
-        "autonomous" => AppMode::ModeA,
-        "collaborative" => AppMode::ModeB,
-        _ => return Err("Invalid mode. Use 'autonomous' or 'collaborative'".to_string()),
-    };
+pub fn set_app_mode(app: AppHandle, mode: String) -> Result<SessionResponse, CommandError> {
+    let new_mode = AppMode::parse(&mode)
+        .ok_or_else(|| CommandError::validation("Invalid mode. Use 'autonomous' or 'collaborative'"))?;
 
-    *state.app_mode.write() = new_mode;
+    apply_app_mode(&app, new_mode);
 
     Ok(SessionResponse {
         success: true,
@@ -330,19 +1208,54 @@ pub fn set_app_mode(state: State<'_, AppState>, mode: String) -> Result<SessionR
     })
 }
 
+/// Switch the app mode everywhere: the live pipeline, the settings table,
+/// the tray and the frontend
+pub(crate) fn apply_app_mode(app: &AppHandle, mode: AppMode) {
+    let state = app.state::<AppState>();
+    *state.app_mode.write() = mode;
+    info!("App mode set to {}", mode);
+
+    if let Some(thread) = state.pipeline_thread.lock().as_ref() {
+        thread.set_mode(mode.detection_mode());
+    }
+
+    if let Ok(repo) = repository(&state) {
+        if let Err(e) = repo.set_setting(APP_MODE_SETTING, &mode.to_string()) {
+            tracing::warn!("Failed to save app mode: {}", e);
+        }
+    }
+
+    if let Some(tray) = app.tray_by_id(crate::TRAY_ID) {
+        let _ = tray.set_tooltip(Some(crate::tray_tooltip(mode)));
+    }
+    let _ = app.emit(MODE_CHANGED_EVENT, mode.to_string());
+}
+
+/// Load the saved app mode at startup
+pub fn restore_app_mode(state: &AppState) {
+    let Ok(repo) = repository(state) else {
+        return;
+    };
+
+    match repo.get_setting(APP_MODE_SETTING) {
+        Ok(Some(name)) => match AppMode::parse(&name) {
+            Some(mode) => *state.app_mode.write() = mode,
+            None => tracing::warn!("Ignoring unknown saved app mode: {}", name),
+        },
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Failed to load app mode: {}", e),
+    }
+}
+
 /// Get current application mode
 #[tauri::command]
-pub fn get_app_mode(state: State<'_, AppState>) -> Result<String, String> {
-    let mode = *state.app_mode.read();
-    Ok(match mode {
-        AppMode::ModeA => "autonomous".to_string(),
-        AppMode::ModeB => "collaborative".to_string(),
-    })
+pub fn get_app_mode(state: State<'_, AppState>) -> Result<String, CommandError> {
+    Ok(state.app_mode.read().to_string())
 }
 
 /// Enable/disable detection
 #[tauri::command]
-pub fn set_detection_enabled(state: State<'_, AppState>, enabled: bool) -> Result<SessionResponse, String> {
+pub fn set_detection_enabled(state: State<'_, AppState>, enabled: bool) -> Result<SessionResponse, CommandError> {
     *state.detection_ready.write() = enabled;
 
     Ok(SessionResponse {
@@ -351,3 +1264,365 @@ pub fn set_detection_enabled(state: State<'_, AppState>, enabled: bool) -> Resul
         state: if enabled { "enabled" } else { "disabled" }.to_string(),
     })
 }
+
+/// How long `simulate_detection` waits for the trigger chain to act
+const SIMULATION_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Inject a keyword and/or emotion into the running session's pipeline
+///
+/// Walks the same path as a real detection, through the FSM to playback or a
+/// suggestion, and returns what was done. Logged events are marked simulated.
+#[tauri::command]
+pub async fn simulate_detection(
+    app: AppHandle,
+    keyword: Option<String>,
+    emotion: Option<String>,
+    confidence: f32,
+) -> Result<TriggerAction, CommandError> {
+    let keyword = keyword.filter(|k| !k.trim().is_empty());
+    let emotion = emotion.filter(|e| !e.trim().is_empty());
+    if keyword.is_none() && emotion.is_none() {
+        return Err(CommandError::validation("Give a keyword, an emotion or both"));
+    }
+    if !(0.0..=1.0).contains(&confidence) {
+        return Err(CommandError::validation("Confidence must be between 0 and 1"));
+    }
+
+    let detection = SimulatedDetection {
+        keyword,
+        emotion,
+        confidence,
+    };
+    let reply = match app.state::<AppState>().pipeline_thread.lock().as_ref() {
+        Some(thread) => thread.simulate(detection)?,
+        None => return Err(CommandError::invalid_state("Start a session to simulate detections")),
+    };
+
+    // Wait without blocking the thread the command was called on
+    match tokio::time::timeout(SIMULATION_TIMEOUT, reply.recv_async()).await {
+        Ok(Ok(action)) => Ok(action),
+        Ok(Err(flume::RecvError::Disconnected)) => Ok(TriggerAction::Nothing),
+        Err(_) => Err(AppError::Timeout("Simulated detection got no reply".to_string()).into()),
+    }
+}
+
+/// Get per-session keyword use counts (for the keyword heat-map)
+#[tauri::command]
+pub fn keyword_use_counts(state: State<'_, AppState>) -> Result<HashMap<String, u32>, CommandError> {
+    Ok(state.keyword_use_counts.read().clone())
+}
+
+/// List past sessions, newest first
+#[tauri::command]
+pub fn get_session_history(
+    state: State<'_, AppState>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<SessionHistory, CommandError> {
+    let repo = repository(&state)?;
+    let sessions = repo
+        .list_sessions(limit.unwrap_or(50), offset.unwrap_or(0))?;
+    let total = repo.count_sessions()?;
+
+    Ok(SessionHistory { sessions, total })
+}
+
+/// Get a past session and its detection events
+#[tauri::command]
+pub fn get_session_detail(state: State<'_, AppState>, session_id: String) -> Result<SessionDetail, CommandError> {
+    let repo = repository(&state)?;
+    let session = repo
+        .get_session(&session_id)?
+        .ok_or_else(|| CommandError::not_found(format!("Session not found: {}", session_id)))?;
+    let events = repo.get_session_events(&session_id)?;
+
+    Ok(SessionDetail { session, events })
+}
+
+/// Search detection event details across sessions, or within one
+#[tauri::command]
+pub fn search_session_events(
+    state: State<'_, AppState>,
+    query: String,
+    session_id: Option<String>,
+) -> Result<Vec<DetectionEvent>, CommandError> {
+    Ok(repository(&state)?.search_events(&query, session_id.as_deref())?)
+}
+
+/// Search stored transcripts across sessions, or within one
+///
+/// Matching segments come with their start and end time in the session.
+#[tauri::command]
+pub fn search_transcripts(
+    state: State<'_, AppState>,
+    query: String,
+    session_id: Option<String>,
+) -> Result<Vec<Transcript>, CommandError> {
+    Ok(repository(&state)?.search_transcripts(&query, session_id.as_deref())?)
+}
+
+/// Query detection events for the live log view
+#[tauri::command]
+pub fn get_detection_events(
+    state: State<'_, AppState>,
+    filter: EventFilter,
+) -> Result<Vec<DetectionEvent>, CommandError> {
+    Ok(repository(&state)?.query_detection_events(&filter)?)
+}
+
+/// Count detection events matching a filter, for pagination
+#[tauri::command]
+pub fn count_detection_events(state: State<'_, AppState>, filter: EventFilter) -> Result<i64, CommandError> {
+    Ok(repository(&state)?.count_detection_events(&filter)?)
+}
+
+/// Delete a past session, its detection events and recording
+///
+/// `confirm` must be true; the running session cannot be deleted.
+#[tauri::command]
+pub fn delete_session(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+    confirm: bool,
+) -> Result<(), CommandError> {
+    if !confirm {
+        return Err(CommandError::validation("Deleting a session must be confirmed"));
+    }
+    if state.session_id.read().as_deref() == Some(session_id.as_str()) {
+        return Err(CommandError::invalid_state("Cannot delete the running session"));
+    }
+
+    info!("Deleting session: {}", session_id);
+    repository(&state)?
+        .delete_session(&session_id)?;
+
+    let _ = app.emit(SESSION_DELETED_EVENT, &session_id);
+    Ok(())
+}
+
+/// Record from the session's input device for a moment and report its levels
+///
+/// Refused while a session is running, so the test never competes with it.
+#[tauri::command]
+pub async fn test_microphone(app: AppHandle, duration_ms: u64) -> Result<MicrophoneTestReport, CommandError> {
+    let (device_id, input_gain) = {
+        let state = app.state::<AppState>();
+        let current_state = *state.session_state.read();
+        if current_state != SessionState::Idle {
+            return Err(CommandError::invalid_state(format!(
+                "Cannot test the microphone, current state: {}",
+                current_state
+            )));
+        }
+        let input_gain = state.config.read().input_gain;
+        (selected_input_device(&state), input_gain)
+    };
+    let duration_ms = duration_ms.min(MIC_TEST_MAX_MS);
+    info!("Testing microphone {:?} for {}ms", device_id, duration_ms);
+
+    tokio::task::spawn_blocking(move || run_microphone_test(device_id, input_gain, duration_ms))
+        .await
+        .map_err(|e| CommandError::internal(e.to_string()))?
+        .map_err(CommandError::from)
+}
+
+/// Capture for `duration_ms` on a temporary capture thread, then analyze it
+fn run_microphone_test(
+    device_id: Option<String>,
+    input_gain: f32,
+    duration_ms: u64,
+) -> Result<MicrophoneTestReport, CaptureError> {
+    let (tx, rx) = flume::unbounded::<Vec<f32>>();
+    let options = CaptureOptions {
+        device_name: device_id.clone(),
+        ..CaptureOptions::default()
+    };
+
+    let mut gain = gain::InputGain::default();
+    gain.set_gain(input_gain);
+    let mut capture = CaptureThread::spawn(options, move |mut samples| {
+        gain.apply(&mut samples);
+        let _ = tx.send(samples);
+    })?;
+    std::thread::sleep(std::time::Duration::from_millis(duration_ms));
+    capture.stop();
+
+    let samples: Vec<f32> = rx.drain().flatten().collect();
+    let mut report = analyze_microphone_test(&samples, capture.format());
+    report.device_id = device_id;
+    report.duration_ms = duration_ms;
+    Ok(report)
+}
+
+/// Measure levels and run the energy VAD over a mono recording
+fn analyze_microphone_test(samples: &[f32], format: CaptureFormat) -> MicrophoneTestReport {
+    let frame = ((format.sample_rate * MIC_TEST_FRAME_MS / 1000) as usize).max(1);
+
+    let mut vad = VoiceActivityDetector::new();
+    vad.set_sample_rate(format.sample_rate);
+
+    let mut peak_db = meter::SILENCE_DB;
+    let mut speech_detected = false;
+    for (i, chunk) in samples.chunks(frame).enumerate() {
+        peak_db = peak_db.max(processing::calculate_db(chunk));
+        speech_detected |= vad.process_frame(chunk, i as u64 * MIC_TEST_FRAME_MS as u64).is_speech;
+    }
+
+    MicrophoneTestReport {
+        device_id: None,
+        duration_ms: samples.len() as u64 * 1000 / format.sample_rate.max(1) as u64,
+        peak_db,
+        average_db: processing::calculate_db(samples),
+        speech_detected,
+        sample_rate: format.sample_rate,
+        channels: format.channels,
+    }
+}
+
+/// Get frame counters and the FSM state of the live detection pipeline
+#[tauri::command]
+pub fn get_pipeline_stats(state: State<'_, AppState>) -> Result<PipelineStats, CommandError> {
+    Ok(state.pipeline_stats.snapshot())
+}
+
+/// Get processing latency and drop counts of the live detection pipeline
+#[tauri::command]
+pub fn get_pipeline_metrics(state: State<'_, AppState>) -> Result<PipelineMetrics, CommandError> {
+    Ok(state.pipeline_stats.metrics())
+}
+
+/// Get the latest input peak level in dBFS (for frontends without event support)
+#[tauri::command]
+pub fn get_audio_peak(state: State<'_, AppState>) -> Result<f32, CommandError> {
+    Ok(*state.input_peak_db.read())
+}
+
+/// Get current input and music levels for the VU meters
+#[tauri::command]
+pub fn get_audio_levels(state: State<'_, AppState>) -> Result<AudioLevels, CommandError> {
+    let input_rms = *state.input_level.read();
+    let music_rms = *state.music_level.read();
+
+    Ok(AudioLevels {
+        input_db: processing::calculate_db(&[input_rms]),
+        music_db: processing::calculate_db(&[music_rms]),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_microphone_report_levels() {
+        let format = CaptureFormat {
+            sample_rate: 16000,
+            channels: 1,
+        };
+
+        let silence = analyze_microphone_test(&vec![0.0; 16000], format);
+        assert!(!silence.speech_detected);
+        assert_eq!(silence.peak_db, meter::SILENCE_DB);
+
+        // Quiet half second, then a loud burst
+        let mut samples = vec![0.01f32; 8000];
+        samples.extend(vec![0.8f32; 8000]);
+        let report = analyze_microphone_test(&samples, format);
+        assert!(report.speech_detected);
+        assert!(report.peak_db > report.average_db);
+        assert_eq!(report.duration_ms, 1000);
+    }
+
+    #[test]
+    fn test_list_tracks_reads_database() {
+        let db = crate::db::Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+        for (id, name, genre) in [("t1", "Tavern", "town"), ("t2", "Skirmish", "combat")] {
+            let mut track = crate::db::Track::new(id.to_string(), name.to_string(), format!("/music/{}.ogg", id));
+            track.duration_ms = Some(90_000);
+            track.genre = Some(genre.to_string());
+            track.mood = Some("neutral".to_string());
+            repo.insert_track(&track).unwrap();
+        }
+
+        let all = list_tracks(Some(repo.clone()), None).unwrap();
+        assert!(!all.db_unavailable);
+        let names: Vec<_> = all.tracks.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, vec!["Skirmish", "Tavern"]);
+        assert_eq!(all.tracks[0].duration_ms, Some(90_000));
+        assert_eq!(all.tracks[0].mood.as_deref(), Some("neutral"));
+
+        let combat = list_tracks(Some(repo), Some("combat")).unwrap();
+        assert_eq!(combat.tracks.len(), 1);
+        assert_eq!(combat.tracks[0].id, "t2");
+
+        assert!(list_tracks(None, None).unwrap().db_unavailable);
+    }
+
+    #[test]
+    fn test_store_detections_persists_logged_events() {
+        let db = crate::db::Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+        repo.start_session(&Session::new("s1".to_string(), "autonomous".to_string())).unwrap();
+
+        let mut logger = crate::detection::DetectionLogger::new("s1".to_string());
+        logger.log_keyword("dragon", "creature", 0.9);
+        logger.log_emotion("fearful", 0.7);
+        store_detections(&repo, &logger.take_entries()).unwrap();
+
+        let events = repo.get_session_events("s1").unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().any(|e| e.event_type == "keyword" && e.category.as_deref() == Some("creature")));
+        assert!(logger.entries().is_empty());
+    }
+
+    #[test]
+    fn test_mix_sources_keeps_longer_tail() {
+        let mixed = mix_sources(&[0.4, 0.2], &[0.0, 0.2, 0.6]);
+        assert_eq!(mixed, vec![0.2, 0.2, 0.6]);
+    }
+
+    #[test]
+    fn test_48khz_stereo_capture_is_resampled_to_16khz_mono() {
+        let tone: Vec<f32> = (0..48000).map(|i| (i as f32 * 0.05).sin() * 0.5).collect();
+        let stereo: Vec<f32> = tone.iter().flat_map(|&s| [s, s]).collect();
+
+        let format = CaptureFormat {
+            sample_rate: 48000,
+            channels: 2,
+        };
+        let config = SessionConfig::default();
+        assert_eq!(config.sample_rate, 16000);
+
+        // One second of 48 kHz stereo becomes one second of 16 kHz mono
+        let mut processed = resample_session_audio(stereo, format, &config);
+        clean_session_audio(&mut processed, &config);
+        assert_eq!(processed.len(), 16000);
+    }
+
+    #[test]
+    fn test_capture_format_conversion_for_a_recovered_device() {
+        let mono_44k = CaptureFormat {
+            sample_rate: 44100,
+            channels: 1,
+        };
+        let stereo_48k = CaptureFormat {
+            sample_rate: 48000,
+            channels: 2,
+        };
+
+        // Half a second of 48 kHz stereo becomes half a second of 44.1 kHz mono
+        let stereo = vec![0.25; 48000];
+        let mono = convert_capture_format(&stereo, stereo_48k, mono_44k);
+        assert_eq!(mono.len(), 22050);
+
+        // Mono is duplicated into every channel
+        let stereo_44k = CaptureFormat {
+            channels: 2,
+            ..mono_44k
+        };
+        let upmixed = convert_capture_format(&[0.1, 0.2], mono_44k, stereo_44k);
+        assert_eq!(upmixed, vec![0.1, 0.1, 0.2, 0.2]);
+    }
+}
@@ -0,0 +1,187 @@
+//! Typed settings commands
+
+use crate::audio::gain;
+use crate::commands::{library, repository, session};
+use crate::db::Repository;
+use crate::error::{AppError, CommandError};
+use crate::state::{SessionConfig, SettingKey, INPUT_GAIN_SETTING, SESSION_CONFIG_SETTING};
+use crate::AppState;
+use serde_json::{Map, Value};
+use tauri::{AppHandle, State};
+use tracing::{info, warn};
+
+/// Look up a known setting key
+fn setting_key(key: &str) -> Result<SettingKey, CommandError> {
+    SettingKey::parse(key).ok_or_else(|| CommandError::validation(format!("Unknown setting: {}", key)))
+}
+
+/// Get a setting, or its default if it was never set
+#[tauri::command]
+pub fn get_setting(state: State<'_, AppState>, key: String) -> Result<Value, CommandError> {
+    let key = setting_key(&key)?;
+    let stored = repository(&state)?.get_setting(key.as_str())?;
+
+    Ok(stored.map(|value| key.decode(&value)).unwrap_or_else(|| key.default_value()))
+}
+
+/// Validate and store a setting, applying it right away where it has live state
+///
+/// The library folder and input device go through the same checks as
+/// `set_library_path` and `select_input_device`, which also store them.
+#[tauri::command]
+pub fn set_setting(app: AppHandle, state: State<'_, AppState>, key: String, value: Value) -> Result<(), CommandError> {
+    let key = setting_key(&key)?;
+    let stored = key.encode(&value).map_err(CommandError::validation)?;
+    info!("Setting {} = {}", key.as_str(), stored);
+
+    match key {
+        SettingKey::LibraryPath => return library::apply_library_path(&app, &state, &stored).map(|_| ()),
+        SettingKey::InputDevice => return session::apply_input_device(&state, &stored),
+        _ => {}
+    }
+
+    repository(&state)?.set_setting(key.as_str(), &stored)?;
+
+    match key {
+        SettingKey::AppMode => {
+            if let Some(mode) = crate::state::AppMode::parse(&stored) {
+                session::apply_app_mode(&app, mode);
+            }
+        }
+        SettingKey::InputGain => apply_input_gain(&state, stored.parse().unwrap_or(1.0)),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Get every known setting, stored values merged over the defaults
+#[tauri::command]
+pub fn get_all_settings(state: State<'_, AppState>) -> Result<Map<String, Value>, CommandError> {
+    let stored = repository(&state)?.get_all_settings()?;
+
+    Ok(SettingKey::ALL
+        .into_iter()
+        .map(|key| {
+            let value = stored
+                .get(key.as_str())
+                .map(|value| key.decode(value))
+                .unwrap_or_else(|| key.default_value());
+            (key.as_str().to_string(), value)
+        })
+        .collect())
+}
+
+/// Validate and store the session configuration, and use it from the next session on
+#[tauri::command]
+pub fn save_session_config(state: State<'_, AppState>, config: SessionConfig) -> Result<(), CommandError> {
+    config.validate().map_err(CommandError::validation)?;
+    info!("Saving session configuration");
+
+    store_session_config(&repository(&state)?, &config)?;
+    apply_session_config(&state, config);
+    Ok(())
+}
+
+/// Apply the saved session configuration, returning the configuration in use
+///
+/// Without a saved configuration the current one is kept.
+#[tauri::command]
+pub fn load_session_config(state: State<'_, AppState>) -> Result<SessionConfig, CommandError> {
+    if let Some(config) = stored_session_config(&repository(&state)?)? {
+        apply_session_config(&state, config);
+    }
+    Ok(state.config.read().clone())
+}
+
+/// Store `config`, with its gain also under the gain's own key
+///
+/// The gain is restored from that key at startup, after the configuration,
+/// so both must agree.
+fn store_session_config(repo: &Repository, config: &SessionConfig) -> Result<(), CommandError> {
+    let json = serde_json::to_string(config).map_err(|e| AppError::Serialization(e.to_string()))?;
+    repo.set_setting(SESSION_CONFIG_SETTING, &json)?;
+    repo.set_setting(INPUT_GAIN_SETTING, &config.input_gain.to_string())
+        .map_err(CommandError::from)
+}
+
+/// The saved session configuration, if one was saved and is still valid
+fn stored_session_config(repo: &Repository) -> Result<Option<SessionConfig>, CommandError> {
+    let Some(json) = repo.get_setting(SESSION_CONFIG_SETTING)? else {
+        return Ok(None);
+    };
+
+    let invalid = |e: String| CommandError::validation(format!("Invalid saved session configuration: {}", e));
+    let config: SessionConfig = serde_json::from_str(&json).map_err(|e| invalid(e.to_string()))?;
+    config.validate().map_err(invalid)?;
+    Ok(Some(config))
+}
+
+/// Replace the session configuration, keeping the live input gain in step
+fn apply_session_config(state: &AppState, config: SessionConfig) {
+    let gain = config.input_gain;
+    *state.config.write() = config;
+    apply_input_gain(state, gain);
+}
+
+/// Use a gain for the running capture and the next session
+pub(crate) fn apply_input_gain(state: &AppState, gain: f32) {
+    let gain = gain::clamp_gain(gain);
+    state.config.write().input_gain = gain;
+    state.input_gain.write().set_gain(gain);
+}
+
+/// Load the saved input gain at startup
+pub fn restore_input_gain(state: &AppState) {
+    let Ok(repo) = repository(state) else {
+        return;
+    };
+
+    match repo.get_setting(INPUT_GAIN_SETTING) {
+        Ok(Some(value)) => match value.parse::<f32>() {
+            Ok(gain) => apply_input_gain(state, gain),
+            Err(_) => warn!("Ignoring invalid saved input gain: {}", value),
+        },
+        Ok(None) => {}
+        Err(e) => warn!("Failed to load input gain: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+    use crate::detection::fsm::DetectionMode;
+
+    #[test]
+    fn test_session_config_survives_restart() {
+        let path = std::env::temp_dir().join(format!("settings-{}.db", uuid::Uuid::new_v4()));
+        let path = path.to_string_lossy().to_string();
+        let config = SessionConfig {
+            detection_mode: DetectionMode::Collaborative,
+            input_gain: 2.5,
+            ..SessionConfig::default()
+        };
+
+        {
+            let db = Database::new(&path).unwrap();
+            let repo = Repository::new(db.pool().clone());
+            assert!(stored_session_config(&repo).unwrap().is_none());
+            store_session_config(&repo, &config).unwrap();
+        }
+
+        let db = Database::new(&path).unwrap();
+        let repo = Repository::new(db.pool().clone());
+        let loaded = stored_session_config(&repo).unwrap().unwrap();
+        assert_eq!(loaded.detection_mode, DetectionMode::Collaborative);
+        assert_eq!(loaded.input_gain, 2.5);
+        assert_eq!(repo.get_setting(INPUT_GAIN_SETTING).unwrap().as_deref(), Some("2.5"));
+
+        // A configuration edited out of range is not applied
+        repo.set_setting(SESSION_CONFIG_SETTING, r#"{"sample_rate": 44100}"#).unwrap();
+        assert!(stored_session_config(&repo).unwrap_err().message.contains("sample_rate"));
+
+        drop(repo);
+        drop(db);
+        std::fs::remove_file(&path).ok();
+    }
+}
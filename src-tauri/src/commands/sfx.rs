@@ -0,0 +1,67 @@
+//! SFX board commands
+
+use crate::audio::metadata;
+use crate::commands::repository;
+use crate::db::Sfx;
+use crate::error::CommandError;
+use crate::AppState;
+use std::path::Path;
+use tauri::State;
+use tracing::info;
+
+/// Get SFX, optionally filtered by category
+#[tauri::command]
+pub fn get_sfx(state: State<'_, AppState>, category: Option<String>) -> Result<Vec<Sfx>, CommandError> {
+    let repo = repository(&state)?;
+
+    match category {
+        Some(category) => repo.get_sfx_by_category(&category),
+        None => repo.get_all_sfx(),
+    }
+    .map_err(CommandError::from)
+}
+
+/// Import a sound effect file
+#[tauri::command]
+pub fn import_sfx(
+    state: State<'_, AppState>,
+    path: String,
+    name: Option<String>,
+    category: Option<String>,
+    volume: Option<f64>,
+) -> Result<Sfx, CommandError> {
+    info!("Importing SFX: {}", path);
+
+    let meta = metadata::probe_track(Path::new(&path))?;
+
+    let mut sfx = Sfx::new(uuid::Uuid::new_v4().to_string(), name.unwrap_or(meta.title), path);
+    sfx.duration_ms = meta.duration_ms.map(|ms| ms as i64);
+    sfx.category = category;
+    sfx.volume = volume.unwrap_or(1.0).clamp(0.0, 1.0);
+
+    repository(&state)?.insert_sfx(&sfx)?;
+    Ok(sfx)
+}
+
+/// Play a stored SFX at its saved volume
+#[tauri::command]
+pub fn play_sfx_by_id(state: State<'_, AppState>, sfx_id: String) -> Result<(), CommandError> {
+    play_stored_sfx(&state, &sfx_id)
+}
+
+/// Look up an SFX and play it, shared with the SFX hotkeys
+pub(crate) fn play_stored_sfx(state: &AppState, sfx_id: &str) -> Result<(), CommandError> {
+    state.audio.require_output()?;
+    let sfx = repository(state)?
+        .get_sfx(sfx_id)?
+        .ok_or_else(|| CommandError::not_found(format!("SFX not found: {}", sfx_id)))?;
+
+    state.audio.play_sfx(&sfx).map_err(CommandError::from)
+}
+
+/// Delete a stored SFX
+#[tauri::command]
+pub fn delete_sfx(state: State<'_, AppState>, sfx_id: String) -> Result<bool, CommandError> {
+    info!("Deleting SFX: {}", sfx_id);
+    repository(&state)?.delete_sfx(&sfx_id).map_err(CommandError::from)
+}
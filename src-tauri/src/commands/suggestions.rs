@@ -0,0 +1,161 @@
+//! Collaborative mode suggestion commands
+
+use crate::audio::engine::now_ms;
+use crate::audio::Track;
+use crate::commands::{playback, repository};
+use crate::db::DetectionEvent;
+use crate::detection::logger::SIMULATED_DETAIL;
+use crate::error::CommandError;
+use crate::orchestrator::autoplay;
+use crate::orchestrator::suggestions::{Suggestion, CROSSFADE_ACTION, SUGGESTION_TTL_MS};
+use crate::state::AppMode;
+use crate::AppState;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tracing::{info, warn};
+
+/// Event emitted when a new suggestion is queued
+pub const SUGGESTION_EVENT: &str = "suggestion://new";
+
+/// Queue a music change for a confirmed dual signal in collaborative mode
+///
+/// Called from the detection event thread; does nothing in autonomous mode.
+/// Returns the suggestion if one was queued.
+pub(crate) fn on_dual_signal(
+    app: &AppHandle,
+    session_id: &str,
+    keyword: &str,
+    emotion: &str,
+    confidence: f32,
+    simulated: bool,
+) -> Option<Suggestion> {
+    let state = app.state::<AppState>();
+    if *state.app_mode.read() != AppMode::ModeB {
+        return None;
+    }
+    expire_suggestions(&state);
+
+    let track = playback::dual_signal_track(&state, keyword, emotion);
+    let suggested_at_ms = now_ms();
+    let suggestion = Suggestion {
+        id: uuid::Uuid::new_v4().to_string(),
+        session_id: session_id.to_string(),
+        action_type: CROSSFADE_ACTION.to_string(),
+        mood: emotion.to_string(),
+        track_id: track.as_ref().map(|t| t.id.clone()),
+        track_name: track.map(|t| t.name),
+        keyword: keyword.to_string(),
+        emotion: emotion.to_string(),
+        confidence,
+        suggested_at_ms,
+        expires_at_ms: suggested_at_ms + SUGGESTION_TTL_MS,
+        simulated,
+    };
+
+    if !state.suggestions.lock().push(suggestion.clone()) {
+        return None;
+    }
+    info!("Suggesting {} music for keyword {}", suggestion.mood, suggestion.keyword);
+    let _ = app.emit(SUGGESTION_EVENT, &suggestion);
+    Some(suggestion)
+}
+
+/// Drop suggestions past their TTL, logging each as an unanswered detection
+fn expire_suggestions(state: &AppState) {
+    let expired = state.suggestions.lock().expire(now_ms());
+    for suggestion in expired {
+        log_suggestion(state, &suggestion, "suggestion_expired", false);
+    }
+}
+
+/// Expire every pending suggestion when the session stops
+pub(crate) fn clear_suggestions(state: &AppState) {
+    let pending = state.suggestions.lock().clear();
+    for suggestion in pending {
+        log_suggestion(state, &suggestion, "suggestion_expired", false);
+    }
+}
+
+/// Store the outcome of a suggestion as a detection event of its session
+fn log_suggestion(state: &AppState, suggestion: &Suggestion, event_type: &str, triggered_action: bool) {
+    let Ok(repo) = repository(state) else {
+        return;
+    };
+
+    let mut event = DetectionEvent::new(
+        uuid::Uuid::new_v4().to_string(),
+        suggestion.session_id.clone(),
+        event_type.to_string(),
+    );
+    let mut details = format!(
+        "keyword: {}, emotion: {}, mood: {}",
+        suggestion.keyword, suggestion.emotion, suggestion.mood
+    );
+    if suggestion.simulated {
+        details = format!("{}, {}", details, SIMULATED_DETAIL);
+    }
+    event.details = Some(details);
+    event.confidence = Some(suggestion.confidence as f64);
+    event.category = Some(suggestion.mood.clone());
+    event.triggered_action = triggered_action;
+
+    if let Err(e) = repo.insert_detection_event(&event) {
+        warn!("Failed to log {} for {}: {}", event_type, suggestion.id, e);
+    }
+}
+
+/// Get suggestions still waiting for the GM
+#[tauri::command]
+pub fn get_pending_suggestions(state: State<'_, AppState>) -> Result<Vec<Suggestion>, CommandError> {
+    expire_suggestions(&state);
+    Ok(state.suggestions.lock().pending().to_vec())
+}
+
+/// Accept a suggestion: crossfade to its track as autonomous mode would
+#[tauri::command]
+pub fn accept_suggestion(state: State<'_, AppState>, id: String) -> Result<Suggestion, CommandError> {
+    state.audio.require_output()?;
+    expire_suggestions(&state);
+    // Only taken once its track plays, so a failure leaves it pending
+    let suggestion = state
+        .suggestions
+        .lock()
+        .pending()
+        .iter()
+        .find(|suggestion| suggestion.id == id)
+        .cloned()
+        .ok_or_else(|| CommandError::not_found(format!("No pending suggestion: {}", id)))?;
+    if suggestion.action_type != CROSSFADE_ACTION {
+        return Err(CommandError::validation(format!("Unsupported suggestion action: {}", suggestion.action_type)));
+    }
+
+    let repo = repository(&state)?;
+    let stored = match &suggestion.track_id {
+        Some(track_id) => repo.get_track(track_id)?,
+        None => autoplay::pick_track(&repo, None, &suggestion.mood)?,
+    };
+    let Some(stored) = stored else {
+        return Err(CommandError::not_found(format!("No track available for mood {}", suggestion.mood)));
+    };
+
+    let track = Track::from(&stored);
+    state.audio.play_for_mood(&suggestion.mood, track)?;
+    state.suggestions.lock().take(&id);
+
+    info!("Accepted suggestion {} ({})", suggestion.id, stored.name);
+    log_suggestion(&state, &suggestion, "suggestion_accepted", true);
+    Ok(suggestion)
+}
+
+/// Reject a suggestion, keeping the current music
+#[tauri::command]
+pub fn reject_suggestion(state: State<'_, AppState>, id: String) -> Result<(), CommandError> {
+    let suggestion = state
+        .suggestions
+        .lock()
+        .take(&id)
+        .ok_or_else(|| CommandError::not_found(format!("No pending suggestion: {}", id)))?;
+
+    info!("Rejected suggestion {}", suggestion.id);
+    log_suggestion(&state, &suggestion, "suggestion_rejected", false);
+    Ok(())
+}
@@ -1,9 +1,20 @@
 //! Voice training commands
 
+use crate::audio::capture::{CaptureOptions, CaptureThread};
+use crate::audio::gain;
+use crate::commands::{repository, session};
+use crate::db::{self, Repository};
+use crate::detection::speaker;
+use crate::dsp::processing;
+use crate::error::{AppError, CommandError, ErrorCode};
+use crate::inference::emotion::EmotionAnalyzer;
+use crate::ml::{ModelPaths, SpeakerModel};
+use crate::profile::{self, ConsentGuard, EmotionBaseline, EncryptedStorage, ProfileStorage, RecordingQuality};
+use crate::state::SessionState;
 use crate::AppState;
 use serde::{Deserialize, Serialize};
 use tauri::State;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Training passage
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,6 +34,18 @@ pub struct VoiceProfile {
     pub created_at: String,
 }
 
+impl From<&db::VoiceProfile> for VoiceProfile {
+    fn from(row: &db::VoiceProfile) -> Self {
+        Self {
+            id: row.id.clone(),
+            name: row.name.clone(),
+            is_default: row.is_default,
+            consent_given: row.consent_given,
+            created_at: row.created_at.clone(),
+        }
+    }
+}
+
 /// Training status
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TrainingStatus {
@@ -76,43 +99,518 @@ pub fn get_training_passages() -> Vec<TrainingPassage> {
 
 /// Get training status
 #[tauri::command]
-pub fn get_training_status(state: State<'_, AppState>) -> Result<TrainingStatus, String> {
-    // For now, return mock status
+pub fn get_training_status(state: State<'_, AppState>) -> Result<TrainingStatus, CommandError> {
+    let repo = repository(&state)?;
+    Ok(training_status(&repo, state.voice_training.read().as_ref())?)
+}
+
+/// Enrollment of the default profile plus progress of any training in flight
+///
+/// A default profile whose consent was revoked no longer counts as enrolled.
+fn training_status(
+    repo: &Repository,
+    training: Option<&profile::VoiceTraining>,
+) -> Result<TrainingStatus, AppError> {
+    let (completed, total) = training
+        .map(|t| t.progress())
+        .unwrap_or((0, profile::default_training_passages().len()));
+    let profile = repo
+        .get_default_voice_profile()?
+        .filter(|row| row.consent_given)
+        .map(|row| VoiceProfile::from(&row));
+
     Ok(TrainingStatus {
-        is_enrolled: false,
-        profile: None,
-        passages_completed: 0,
-        total_passages: 7,
+        is_enrolled: profile.is_some(),
+        profile,
+        passages_completed: completed as u32,
+        total_passages: total as u32,
+    })
+}
+
+/// List all voice profiles
+#[tauri::command]
+pub fn get_voice_profiles(state: State<'_, AppState>) -> Result<Vec<VoiceProfile>, CommandError> {
+    let repo = repository(&state)?;
+    Ok(repo.get_voice_profiles()?.iter().map(VoiceProfile::from).collect())
+}
+
+/// Make a voice profile the default used for speaker verification
+#[tauri::command]
+pub fn set_default_voice_profile(state: State<'_, AppState>, profile_id: String) -> Result<(), CommandError> {
+    info!("Setting default voice profile: {}", profile_id);
+
+    let repo = repository(&state)?;
+    if !repo.set_default_voice_profile(&profile_id)? {
+        return Err(CommandError::not_found(format!("Voice profile not found: {}", profile_id)));
+    }
+
+    // The baseline belongs to the previous default
+    *state.emotion_baseline.write() = None;
+    restore_emotion_baseline(&state);
+    Ok(())
+}
+
+/// Start recording a training passage from the selected microphone
+///
+/// Recording an already accepted passage again replaces it once the new take
+/// passes the quality check.
+#[tauri::command]
+pub fn start_training_recording(state: State<'_, AppState>, passage_index: usize) -> Result<(), CommandError> {
+    if *state.session_state.read() != SessionState::Idle {
+        return Err(CommandError::invalid_state("Stop the session before recording training passages"));
+    }
+
+    let mut capture_slot = state.training_capture.lock();
+    if capture_slot.is_some() {
+        return Err(CommandError::invalid_state("A training passage is already being recorded"));
+    }
+
+    {
+        let mut training = state.voice_training.write();
+        let training = training.get_or_insert_with(profile::VoiceTraining::new);
+        if !training.select_passage(passage_index) {
+            return Err(CommandError::not_found(format!("No training passage {}", passage_index)));
+        }
+    }
+    info!("Recording training passage {}", passage_index);
+
+    let samples = state.training_samples.clone();
+    samples.lock().clear();
+    let mut gain = gain::InputGain::default();
+    gain.set_gain(state.config.read().input_gain);
+
+    let options = CaptureOptions {
+        device_name: session::selected_input_device(&state),
+        ..CaptureOptions::default()
+    };
+    let capture = CaptureThread::spawn(options, move |mut chunk| {
+        gain.apply(&mut chunk);
+        samples.lock().extend_from_slice(&chunk);
+    })
+    .map_err(|e| CommandError::new(ErrorCode::DeviceUnavailable, format!("Failed to start recording: {}", e)))?;
+
+    *capture_slot = Some(capture);
+    Ok(())
+}
+
+/// Stop the current take and check it; accepted takes are added to the training
+#[tauri::command]
+pub fn stop_training_recording(state: State<'_, AppState>) -> Result<RecordingQuality, CommandError> {
+    let mut capture = state
+        .training_capture
+        .lock()
+        .take()
+        .ok_or_else(|| CommandError::invalid_state("No training passage is being recorded"))?;
+    capture.stop();
+
+    let sample_rate = capture.format().sample_rate;
+    let samples = std::mem::take(&mut *state.training_samples.lock());
+
+    let mut training = state.voice_training.write();
+    let training = training
+        .as_mut()
+        .ok_or_else(|| CommandError::invalid_state("No training session in progress"))?;
+    let passage = training
+        .current_passage()
+        .ok_or_else(|| CommandError::invalid_state("No training passage selected"))?;
+
+    let quality = profile::check_recording(&samples, sample_rate, passage);
+    if quality.passed {
+        training.add_recording(processing::resample(&samples, sample_rate, profile::VoiceTraining::SAMPLE_RATE));
+        info!("Training passage accepted ({} ms)", quality.duration_ms);
+    } else {
+        info!("Training passage rejected: {}", quality.reasons.join("; "));
+    }
+
+    Ok(quality)
+}
+
+/// Get training progress as (recorded passages, total passages)
+#[tauri::command]
+pub fn get_training_progress(state: State<'_, AppState>) -> Result<(usize, usize), CommandError> {
+    Ok(match state.voice_training.read().as_ref() {
+        Some(training) => training.progress(),
+        None => (0, profile::default_training_passages().len()),
     })
 }
 
-/// Save voice profile
+/// Enroll the GM from the recorded training passages
+///
+/// Extracts a speaker embedding per passage, averages them into the profile
+/// embedding and derives the emotion baseline from the same recordings. The
+/// profile is written to the `voice_profiles` table and becomes the default;
+/// the profile store keeps the encrypted embedding and the emotion baseline.
+/// Nothing is kept in the database if writing those files fails.
+///
+/// Pass the `profile_id` of an existing profile to re-enroll it: its
+/// embeddings are replaced and its name and threshold kept.
 #[tauri::command]
 pub fn save_voice_profile(
     state: State<'_, AppState>,
     name: String,
     consent_given: bool,
-) -> Result<VoiceProfile, String> {
+    profile_id: Option<String>,
+) -> Result<VoiceProfile, CommandError> {
     info!("Saving voice profile: {}", name);
 
-    // Create profile
-    let profile = VoiceProfile {
-        id: uuid::Uuid::new_v4().to_string(),
-        name,
-        is_default: true,
-        consent_given,
-        created_at: chrono::Utc::now().to_rfc3339(),
+    if !consent_given {
+        return Err(CommandError::validation("Consent is required to store a voice profile"));
+    }
+
+    let mut analyzer = EmotionAnalyzer::new();
+    analyzer.init()?;
+    let mut model = speaker_model();
+
+    let enrollment = {
+        let training = state.voice_training.read();
+        let training = training
+            .as_ref()
+            .ok_or_else(|| CommandError::invalid_state("No training recordings; record the training passages first"))?;
+        training
+            .enroll(&mut model, &analyzer)
+            .map_err(|e| CommandError::validation(format!("Cannot enroll voice profile: {}", e)))?
+    };
+
+    let embedding = enrollment.mean_embedding().to_bytes();
+
+    let repo = repository(&state)?;
+    let existing = match &profile_id {
+        Some(id) => Some(
+            repo.get_voice_profiles()?
+                .into_iter()
+                .find(|p| &p.id == id)
+                .ok_or_else(|| CommandError::not_found(format!("Voice profile not found: {}", id)))?,
+        ),
+        None => None,
     };
+    let mut row = existing
+        .clone()
+        .unwrap_or_else(|| db::VoiceProfile::new(uuid::Uuid::new_v4().to_string(), name));
+    row.is_default = true;
+    row.consent_given = consent_given;
+    row.embedding = Some(embedding.clone());
+    let passages: Vec<Vec<u8>> = enrollment.embeddings.iter().map(|e| e.to_bytes()).collect();
+
+    let storage = ProfileStorage::new(ProfileStorage::default_path());
+    let saved = repo.save_voice_enrollment(&row, &passages, existing.is_some(), || {
+        let mut stored = storage
+            .load_profile(&row.id)?
+            .unwrap_or_else(|| profile::VoiceProfile::new(row.id.clone(), row.name.clone()));
+        stored.consent_given = consent_given;
+        stored.set_emotion_baseline(enrollment.emotion_baseline.clone());
+        ConsentGuard::new(ProfileStorage::new(ProfileStorage::default_path())).save_profile(&stored)?;
+        EncryptedStorage::new(ProfileStorage::default_path()).store_embedding(&row.id, &embedding)
+    });
+    match saved {
+        Ok(true) => {}
+        Ok(false) => {
+            return Err(CommandError::validation(format!(
+                "Voice profile {} has no consent to re-enroll",
+                row.id
+            )))
+        }
+        Err(e) => {
+            if existing.is_none() {
+                if let Err(cleanup) = storage.delete_profile(&row.id) {
+                    warn!("Failed to remove files of unsaved profile {}: {}", row.id, cleanup);
+                }
+            }
+            return Err(e.into());
+        }
+    }
+
+    let profile = VoiceProfile::from(&row);
+
+    info!("Enrolled {} from {} passages", profile.name, enrollment.embeddings.len());
+    *state.emotion_baseline.write() = Some(enrollment.emotion_baseline);
+    state.voice_training.write().take();
 
     Ok(profile)
 }
 
-/// Delete voice profile
+/// Resemblyzer model if its file is present, else the placeholder extractor
+fn speaker_model() -> SpeakerModel {
+    let mut model = SpeakerModel::new();
+    if let Some(path) = ModelPaths::default().speaker_model {
+        if std::path::Path::new(&path).exists() {
+            if let Err(e) = model.load(&path) {
+                warn!("Failed to load speaker model: {}", e);
+            }
+        }
+    }
+    model
+}
+
+/// Number of noise clips used as the impostor baseline
+const CALIBRATION_NOISE_SAMPLES: usize = 8;
+
+/// Calibrate the speaker threshold for a profile against a noise baseline
+#[tauri::command]
+pub fn calibrate_speaker_threshold(state: State<'_, AppState>, profile_id: String) -> Result<f32, CommandError> {
+    info!("Calibrating speaker threshold for profile: {}", profile_id);
+
+    let repo = repository(&state)?;
+    let positive: Vec<speaker::SpeakerEmbedding> = repo
+        .get_voice_profile_embeddings(&profile_id)?
+        .iter()
+        .map(|bytes| speaker::SpeakerEmbedding::from_bytes(bytes))
+        .collect();
+
+    if positive.is_empty() {
+        return Err(CommandError::not_found("Profile has no enrollment recordings"));
+    }
+
+    // Two seconds of white noise per impostor sample, embedded by the
+    // same model that produced the enrollment embeddings
+    let mut model = speaker_model();
+    let negative = (0..CALIBRATION_NOISE_SAMPLES)
+        .map(|_| {
+            let noise: Vec<f32> = (0..32000).map(|_| rand::random::<f32>() * 0.2 - 0.1).collect();
+            model
+                .extract_embedding(&noise, 16000)
+                .map(|embedding| speaker::SpeakerEmbedding::new(embedding.data))
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+
+    let threshold = speaker::SpeakerVerifier::new().calibrate(&positive, &negative);
+    repo.set_voice_profile_threshold(&profile_id, threshold)?;
+
+    Ok(threshold)
+}
+
+/// Seconds of session audio used for emotion calibration
+const BASELINE_WINDOW_SECS: u32 = 60;
+
+/// Length of each analysis window during emotion calibration
+const BASELINE_CHUNK_SECS: u32 = 3;
+
+/// Calibrate the default profile's emotion baseline from the current session
+///
+/// Analyzes the last 60 seconds of session audio in non-overlapping 3-second
+/// windows and stores the per-emotion mean confidence as the GM's baseline.
+#[tauri::command]
+pub fn calibrate_emotion_baseline(state: State<'_, AppState>) -> Result<EmotionBaseline, CommandError> {
+    let repo = repository(&state)?;
+    let row = repo
+        .get_default_voice_profile()?
+        .ok_or_else(|| CommandError::not_found("No voice profile enrolled"))?;
+
+    info!("Calibrating emotion baseline for profile: {}", row.id);
+
+    let sample_rate = *state.sample_rate.read();
+    let channels = *state.channels.read();
+    let samples = processing::stereo_to_mono(
+        &state.audio_buffer.read().snapshot_last(BASELINE_WINDOW_SECS as f32),
+        channels,
+    );
+
+    // Raw scores: calibration must not be skewed by an older baseline
+    let mut analyzer = EmotionAnalyzer::new();
+    analyzer.init()?;
+
+    let chunk = (sample_rate * BASELINE_CHUNK_SECS) as usize;
+    let results: Vec<_> = samples
+        .chunks_exact(chunk)
+        .filter_map(|window| analyzer.analyze(window, sample_rate).ok())
+        .collect();
+
+    if results.is_empty() {
+        return Err(CommandError::validation(format!(
+            "Need at least {} seconds of session audio to calibrate",
+            BASELINE_CHUNK_SECS
+        )));
+    }
+
+    let baseline = EmotionBaseline::from_results(&results);
+
+    let guard = ConsentGuard::new(ProfileStorage::new(ProfileStorage::default_path()));
+    let mut stored = guard
+        .storage()
+        .load_profile(&row.id)?
+        .unwrap_or_else(|| profile::VoiceProfile::new(row.id.clone(), row.name.clone()));
+    stored.consent_given = row.consent_given;
+    stored.set_emotion_baseline(baseline.clone());
+    guard.save_profile(&stored)?;
+
+    info!("Emotion baseline calibrated from {} windows", results.len());
+    *state.emotion_baseline.write() = Some(baseline.clone());
+
+    Ok(baseline)
+}
+
+/// Load the default profile's emotion baseline at startup
+pub fn restore_emotion_baseline(state: &AppState) {
+    let Ok(repo) = repository(state) else {
+        return;
+    };
+    let Ok(Some(row)) = repo.get_default_voice_profile() else {
+        return;
+    };
+
+    match ProfileStorage::new(ProfileStorage::default_path()).load_profile(&row.id) {
+        Ok(Some(stored)) => *state.emotion_baseline.write() = Some(stored.emotion_baseline),
+        Ok(None) => {}
+        Err(e) => warn!("Failed to load emotion baseline: {}", e),
+    }
+}
+
+/// Revoke biometric consent: deletes embeddings but keeps the profile
+///
+/// The embeddings are removed from the database and the profile's files from
+/// disk, and the running session stops verifying against the profile. The
+/// deletion is recorded in the audit log.
+#[tauri::command]
+pub fn revoke_consent(state: State<'_, AppState>, profile_id: String) -> Result<(), CommandError> {
+    info!("Consent revoked for voice profile: {}", profile_id);
+
+    let repo = repository(&state)?;
+    let storage = ProfileStorage::new(ProfileStorage::default_path());
+    let revoked = revoke_voice_profile(&repo, &storage, &profile_id)?;
+    if !revoked {
+        return Err(CommandError::not_found(format!("Voice profile not found: {}", profile_id)));
+    }
+
+    if let Some(thread) = state.pipeline_thread.lock().as_ref() {
+        thread.revoke_speaker_profile(&profile_id);
+    }
+    // The baseline may have belonged to the revoked profile
+    *state.emotion_baseline.write() = None;
+    restore_emotion_baseline(&state);
+    Ok(())
+}
+
+/// Delete a profile's biometric data from the database and its files from disk
+fn revoke_voice_profile(repo: &Repository, storage: &ProfileStorage, profile_id: &str) -> Result<bool, AppError> {
+    // Files go first so the audit entry records what actually happened on disk
+    let deleted = storage.delete_profile(profile_id);
+    let file_error = deleted.as_ref().err().map(|e| e.to_string());
+    let revoked = repo.revoke_voice_profile_consent(profile_id, file_error.as_deref())?;
+    deleted?;
+    Ok(revoked)
+}
+
+/// Delete a voice profile: stored files, database row and embeddings
+///
+/// Deleting the default profile promotes another one, if any is left.
 #[tauri::command]
 pub fn delete_voice_profile(
     state: State<'_, AppState>,
     profile_id: String,
-) -> Result<(), String> {
+) -> Result<(), CommandError> {
     info!("Deleting voice profile: {}", profile_id);
+
+    let repo = repository(&state)?;
+    let storage = ProfileStorage::new(ProfileStorage::default_path());
+    let deleted = remove_voice_profile(&repo, &storage, &profile_id)?;
+    if !deleted {
+        return Err(CommandError::not_found(format!("Voice profile not found: {}", profile_id)));
+    }
+
+    // The baseline may have belonged to the deleted profile
+    *state.emotion_baseline.write() = None;
+    restore_emotion_baseline(&state);
     Ok(())
 }
+
+/// Delete a profile from the database and its files from disk
+fn remove_voice_profile(repo: &Repository, storage: &ProfileStorage, profile_id: &str) -> Result<bool, AppError> {
+    let deleted = repo.delete_voice_profile(profile_id)?;
+    storage.delete_profile(profile_id)?;
+    Ok(deleted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    fn temp_storage() -> ProfileStorage {
+        ProfileStorage::new(std::env::temp_dir().join(format!("profiles-{}", uuid::Uuid::new_v4())))
+    }
+
+    fn enroll(repo: &Repository, storage: &ProfileStorage, id: &str, is_default: bool) {
+        let mut stored = profile::VoiceProfile::new(id.to_string(), id.to_uppercase());
+        stored.consent_given = true;
+        storage.save_profile(&stored).unwrap();
+        std::fs::write(storage.path().join(format!("{}.emb", id)), [1, 2, 3]).unwrap();
+
+        let mut row = db::VoiceProfile::new(id.to_string(), id.to_uppercase());
+        row.is_default = is_default;
+        row.consent_given = true;
+        repo.insert_voice_profile(&row).unwrap();
+        repo.add_voice_profile_embedding(id, 0, &[1, 2, 3]).unwrap();
+    }
+
+    #[test]
+    fn test_status_without_profiles() {
+        let db = Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+        let status = training_status(&repo, None).unwrap();
+        assert!(!status.is_enrolled);
+        assert!(status.profile.is_none());
+        assert_eq!(status.passages_completed, 0);
+        assert_eq!(status.total_passages, 7);
+
+        let mut training = profile::VoiceTraining::new();
+        training.add_recording(vec![0.1; 16000]);
+        let status = training_status(&repo, Some(&training)).unwrap();
+        assert_eq!(status.passages_completed, 1);
+    }
+
+    #[test]
+    fn test_deleting_default_promotes_another_profile() {
+        let db = Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+        let storage = temp_storage();
+        enroll(&repo, &storage, "first", false);
+        enroll(&repo, &storage, "second", true);
+
+        let status = training_status(&repo, None).unwrap();
+        assert!(status.is_enrolled);
+        assert_eq!(status.profile.unwrap().id, "second");
+
+        assert!(remove_voice_profile(&repo, &storage, "second").unwrap());
+        assert!(!storage.path().join("second.json").exists());
+        assert!(!storage.path().join("second.emb").exists());
+        assert!(repo.get_voice_profile_embeddings("second").unwrap().is_empty());
+        assert_eq!(storage.list_profiles().unwrap(), vec!["first"]);
+        assert_eq!(repo.get_default_voice_profile().unwrap().unwrap().id, "first");
+        assert_eq!(training_status(&repo, None).unwrap().profile.unwrap().id, "first");
+
+        assert!(remove_voice_profile(&repo, &storage, "first").unwrap());
+        assert!(!remove_voice_profile(&repo, &storage, "first").unwrap());
+        assert!(repo.get_default_voice_profile().unwrap().is_none());
+        assert!(!training_status(&repo, None).unwrap().is_enrolled);
+
+        std::fs::remove_dir_all(storage.path()).unwrap();
+    }
+
+    #[test]
+    fn test_revoking_consent_deletes_biometric_data() {
+        let db = Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+        let storage = temp_storage();
+        enroll(&repo, &storage, "gm", true);
+        assert!(training_status(&repo, None).unwrap().is_enrolled);
+
+        assert!(revoke_voice_profile(&repo, &storage, "gm").unwrap());
+        assert!(!storage.path().join("gm.json").exists());
+        assert!(!storage.path().join("gm.emb").exists());
+        assert!(repo.get_voice_profile_embeddings("gm").unwrap().is_empty());
+        let row = repo.get_default_voice_profile().unwrap().unwrap();
+        assert!(row.embedding.is_none());
+        assert!(!row.consent_given);
+        assert!(!training_status(&repo, None).unwrap().is_enrolled);
+
+        let audit = repo.get_audit_log(10).unwrap();
+        assert_eq!(audit.len(), 1);
+        assert_eq!(audit[0].action, "consent_revoked");
+        assert_eq!(audit[0].subject_id.as_deref(), Some("gm"));
+        assert!(audit[0].details.as_deref().unwrap().ends_with("files deleted"));
+
+        assert!(!revoke_voice_profile(&repo, &storage, "missing").unwrap());
+        assert_eq!(repo.get_audit_log(10).unwrap().len(), 1);
+
+        std::fs::remove_dir_all(storage.path()).unwrap();
+    }
+}
@@ -1,5 +1,582 @@
 //! Database migrations
 //!
-//! This module re-exports migrations from the main db module.
+//! Every schema change in order, with the SQL that reverts it, and the
+//! rollback that runs those reverts.
 
-pub use super::get_migrations;
+use super::Database;
+use crate::error::AppError;
+
+/// Migration definition
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub sql: &'static str,
+    /// Reverts `sql`; `None` marks the migration as irreversible
+    pub undo_sql: Option<&'static str>,
+}
+
+/// Get all migrations
+pub fn get_migrations() -> Vec<Migration> {
+    vec![
+        // Migration 1: Initial schema
+        Migration {
+            version: 1,
+            name: "initial_schema",
+            sql: r#"
+                -- Tracks table
+                CREATE TABLE IF NOT EXISTS tracks (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    file_path TEXT NOT NULL,
+                    duration_ms INTEGER,
+                    genre TEXT,
+                    mood TEXT,
+                    is_looping INTEGER DEFAULT 0,
+                    volume REAL DEFAULT 1.0,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );
+
+                -- Track genres table
+                CREATE TABLE IF NOT EXISTS track_genres (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL UNIQUE,
+                    color TEXT,
+                    created_at TEXT NOT NULL
+                );
+
+                -- SFX table
+                CREATE TABLE IF NOT EXISTS sfx (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    file_path TEXT NOT NULL,
+                    duration_ms INTEGER,
+                    category TEXT,
+                    volume REAL DEFAULT 1.0,
+                    created_at TEXT NOT NULL
+                );
+
+                -- Sessions table
+                CREATE TABLE IF NOT EXISTS sessions (
+                    id TEXT PRIMARY KEY,
+                    started_at TEXT NOT NULL,
+                    ended_at TEXT,
+                    mode TEXT NOT NULL,
+                    total_duration_ms INTEGER,
+                    created_at TEXT NOT NULL
+                );
+
+                -- Keywords table
+                CREATE TABLE IF NOT EXISTS keywords (
+                    id TEXT PRIMARY KEY,
+                    word TEXT NOT NULL,
+                    category TEXT NOT NULL,
+                    variations TEXT,
+                    mood TEXT,
+                    priority INTEGER DEFAULT 0,
+                    is_active INTEGER DEFAULT 1,
+                    created_at TEXT NOT NULL
+                );
+
+                -- Detection events table
+                CREATE TABLE IF NOT EXISTS detection_events (
+                    id TEXT PRIMARY KEY,
+                    session_id TEXT NOT NULL,
+                    event_type TEXT NOT NULL,
+                    timestamp TEXT NOT NULL,
+                    details TEXT,
+                    confidence REAL,
+                    category TEXT,
+                    triggered_action INTEGER DEFAULT 0,
+                    FOREIGN KEY (session_id) REFERENCES sessions(id)
+                );
+
+                -- Voice profiles table
+                CREATE TABLE IF NOT EXISTS voice_profiles (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    embedding BLOB,
+                    is_default INTEGER DEFAULT 0,
+                    consent_given INTEGER DEFAULT 0,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );
+
+                -- Settings table
+                CREATE TABLE IF NOT EXISTS settings (
+                    key TEXT PRIMARY KEY,
+                    value TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );
+
+                -- Create indexes
+                CREATE INDEX IF NOT EXISTS idx_tracks_genre ON tracks(genre);
+                CREATE INDEX IF NOT EXISTS idx_tracks_mood ON tracks(mood);
+                CREATE INDEX IF NOT EXISTS idx_detection_events_session ON detection_events(session_id);
+                CREATE INDEX IF NOT EXISTS idx_detection_events_type ON detection_events(event_type);
+            "#,
+            undo_sql: None,
+        },
+        // Migration 2: Add more session details
+        Migration {
+            version: 2,
+            name: "session_details",
+            sql: r#"
+                -- Add columns to sessions
+                ALTER TABLE sessions ADD COLUMN detected_events_count INTEGER DEFAULT 0;
+                ALTER TABLE sessions ADD COLUMN keywords_triggered INTEGER DEFAULT 0;
+                ALTER TABLE sessions ADD COLUMN emotions_detected TEXT;
+                ALTER TABLE sessions ADD COLUMN tracks_played TEXT;
+            "#,
+            undo_sql: Some(r#"
+                ALTER TABLE sessions DROP COLUMN detected_events_count;
+                ALTER TABLE sessions DROP COLUMN keywords_triggered;
+                ALTER TABLE sessions DROP COLUMN emotions_detected;
+                ALTER TABLE sessions DROP COLUMN tracks_played;
+            "#),
+        },
+        // Migration 3: Keyword category to track genre mapping
+        Migration {
+            version: 3,
+            name: "keyword_genre_mappings",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS keyword_genre_mappings (
+                    keyword_category TEXT NOT NULL,
+                    genre_name TEXT NOT NULL,
+                    priority INTEGER DEFAULT 0,
+                    created_at TEXT NOT NULL,
+                    PRIMARY KEY (keyword_category, genre_name)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_keyword_genre_mappings_category ON keyword_genre_mappings(keyword_category);
+            "#,
+            undo_sql: Some(r#"
+                DROP TABLE IF EXISTS keyword_genre_mappings;
+            "#),
+        },
+        // Migration 4: One track row per file
+        //
+        // The oldest row of each file is kept and takes the genre and mood of
+        // a duplicate where it has none; duplicates are moved to
+        // duplicate_tracks rather than dropped.
+        Migration {
+            version: 4,
+            name: "unique_track_paths",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS duplicate_tracks AS
+                    SELECT * FROM tracks WHERE rowid NOT IN (SELECT MIN(rowid) FROM tracks GROUP BY file_path);
+                UPDATE tracks SET
+                    genre = COALESCE(genre, (SELECT d.genre FROM duplicate_tracks d
+                        WHERE d.file_path = tracks.file_path AND d.genre IS NOT NULL LIMIT 1)),
+                    mood = COALESCE(mood, (SELECT d.mood FROM duplicate_tracks d
+                        WHERE d.file_path = tracks.file_path AND d.mood IS NOT NULL LIMIT 1))
+                WHERE rowid IN (SELECT MIN(rowid) FROM tracks GROUP BY file_path);
+                DELETE FROM tracks WHERE rowid NOT IN (SELECT MIN(rowid) FROM tracks GROUP BY file_path);
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_tracks_file_path ON tracks(file_path);
+            "#,
+            undo_sql: Some(r#"
+                DROP INDEX IF EXISTS idx_tracks_file_path;
+                INSERT INTO tracks SELECT * FROM duplicate_tracks;
+                DROP TABLE duplicate_tracks;
+            "#),
+        },
+        // Migration 5: Library sync flags
+        Migration {
+            version: 5,
+            name: "track_library_flags",
+            sql: r#"
+                ALTER TABLE tracks ADD COLUMN deleted_at TEXT;
+                ALTER TABLE tracks ADD COLUMN decode_error TEXT;
+            "#,
+            undo_sql: Some(r#"
+                ALTER TABLE tracks DROP COLUMN deleted_at;
+                ALTER TABLE tracks DROP COLUMN decode_error;
+            "#),
+        },
+        // Migration 6: Multiple enrollment embeddings per voice profile
+        Migration {
+            version: 6,
+            name: "voice_profile_embeddings",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS voice_profile_embeddings (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    profile_id TEXT NOT NULL,
+                    passage_index INTEGER NOT NULL,
+                    embedding BLOB NOT NULL,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY (profile_id) REFERENCES voice_profiles(id) ON DELETE CASCADE
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_voice_profile_embeddings_profile ON voice_profile_embeddings(profile_id);
+
+                -- Move existing single embeddings into the new table
+                INSERT INTO voice_profile_embeddings (profile_id, passage_index, embedding, created_at)
+                    SELECT id, 0, embedding, updated_at FROM voice_profiles WHERE embedding IS NOT NULL;
+            "#,
+            undo_sql: Some(r#"
+                DROP TABLE IF EXISTS voice_profile_embeddings;
+            "#),
+        },
+        // Migration 7: Per-profile calibrated speaker threshold
+        Migration {
+            version: 7,
+            name: "voice_profile_threshold",
+            sql: r#"
+                ALTER TABLE voice_profiles ADD COLUMN speaker_threshold REAL;
+            "#,
+            undo_sql: Some(r#"
+                ALTER TABLE voice_profiles DROP COLUMN speaker_threshold;
+            "#),
+        },
+        // Migration 8: Import analysis warnings (JSON array) per track
+        Migration {
+            version: 8,
+            name: "track_import_warnings",
+            sql: r#"
+                ALTER TABLE tracks ADD COLUMN import_warnings TEXT;
+            "#,
+            undo_sql: Some(r#"
+                ALTER TABLE tracks DROP COLUMN import_warnings;
+            "#),
+        },
+        // Migration 9: Per-genre-pair crossfade overrides
+        Migration {
+            version: 9,
+            name: "crossfade_overrides",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS crossfade_overrides (
+                    from_genre TEXT NOT NULL,
+                    to_genre TEXT NOT NULL,
+                    crossfade_type TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    PRIMARY KEY (from_genre, to_genre)
+                );
+            "#,
+            undo_sql: Some(r#"
+                DROP TABLE IF EXISTS crossfade_overrides;
+            "#),
+        },
+        // Migration 10: WAV recording of the session audio
+        Migration {
+            version: 10,
+            name: "session_recording_path",
+            sql: r#"
+                ALTER TABLE sessions ADD COLUMN recording_path TEXT;
+            "#,
+            undo_sql: Some(r#"
+                ALTER TABLE sessions DROP COLUMN recording_path;
+            "#),
+        },
+        // Migration 11: Full-text search over detection event details; the
+        // index is created by `create_fts_indexes` where FTS5 is available
+        Migration {
+            version: 11,
+            name: "detection_events_fts",
+            sql: r#"
+                -- See `DETECTION_EVENTS_FTS_SQL`
+            "#,
+            undo_sql: Some(r#"
+                DROP TABLE IF EXISTS detection_events_fts;
+            "#),
+        },
+        // Migration 12: Saved playlists with ordered tracks
+        Migration {
+            version: 12,
+            name: "playlists",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS playlists (
+                    id TEXT PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    description TEXT,
+                    created_at TEXT NOT NULL
+                );
+
+                CREATE TABLE IF NOT EXISTS playlist_tracks (
+                    playlist_id TEXT NOT NULL,
+                    track_id TEXT NOT NULL,
+                    position INTEGER NOT NULL,
+                    created_at TEXT NOT NULL,
+                    PRIMARY KEY (playlist_id, track_id),
+                    FOREIGN KEY (playlist_id) REFERENCES playlists(id) ON DELETE CASCADE,
+                    FOREIGN KEY (track_id) REFERENCES tracks(id) ON DELETE CASCADE
+                );
+
+                CREATE UNIQUE INDEX IF NOT EXISTS idx_playlist_tracks_position ON playlist_tracks(playlist_id, position);
+            "#,
+            undo_sql: Some(r#"
+                DROP TABLE IF EXISTS playlist_tracks;
+                DROP TABLE IF EXISTS playlists;
+            "#),
+        },
+        // Migration 13: Per-track play statistics
+        Migration {
+            version: 13,
+            name: "track_stats",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS track_stats (
+                    track_id TEXT PRIMARY KEY,
+                    play_count INTEGER NOT NULL DEFAULT 0,
+                    total_played_ms INTEGER NOT NULL DEFAULT 0,
+                    last_played_at TEXT,
+                    FOREIGN KEY (track_id) REFERENCES tracks(id) ON DELETE CASCADE
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_track_stats_play_count ON track_stats(play_count);
+            "#,
+            undo_sql: Some(r#"
+                DROP TABLE IF EXISTS track_stats;
+            "#),
+        },
+        // Migration 14: Manually tagged track tempo
+        Migration {
+            version: 14,
+            name: "track_bpm",
+            sql: r#"
+                ALTER TABLE tracks ADD COLUMN bpm REAL;
+            "#,
+            undo_sql: Some(r#"
+                ALTER TABLE tracks DROP COLUMN bpm;
+            "#),
+        },
+        // Migration 15: Explicit mood to music mapping
+        Migration {
+            version: 15,
+            name: "mood_mappings",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS mood_mappings (
+                    id TEXT PRIMARY KEY,
+                    mood TEXT NOT NULL,
+                    track_id TEXT REFERENCES tracks(id) ON DELETE CASCADE,
+                    genre TEXT,
+                    priority INTEGER NOT NULL DEFAULT 0,
+                    sfx_id TEXT REFERENCES sfx(id) ON DELETE SET NULL,
+                    created_at TEXT NOT NULL,
+                    CHECK ((track_id IS NULL) <> (genre IS NULL))
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_mood_mappings_mood ON mood_mappings(mood);
+            "#,
+            undo_sql: Some(r#"
+                DROP TABLE IF EXISTS mood_mappings;
+            "#),
+        },
+        // Migration 16: Record of privacy-relevant actions such as consent revocation
+        Migration {
+            version: 16,
+            name: "audit_log",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS audit_log (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    action TEXT NOT NULL,
+                    subject_id TEXT,
+                    details TEXT,
+                    created_at TEXT NOT NULL
+                );
+            "#,
+            undo_sql: Some(r#"
+                DROP TABLE IF EXISTS audit_log;
+            "#),
+        },
+        // Migration 17: Real NULLs where untyped inserts stored empty strings
+        Migration {
+            version: 17,
+            name: "null_empty_values",
+            sql: r#"
+                UPDATE tracks SET duration_ms = NULL WHERE duration_ms = '';
+                UPDATE tracks SET genre = NULL WHERE genre = '';
+                UPDATE tracks SET mood = NULL WHERE mood = '';
+                UPDATE detection_events SET details = NULL WHERE details = '';
+                UPDATE detection_events SET confidence = NULL WHERE confidence = '';
+                UPDATE detection_events SET category = NULL WHERE category = '';
+                UPDATE keywords SET variations = NULL WHERE variations = '';
+                UPDATE keywords SET mood = NULL WHERE mood = '';
+            "#,
+            // Older versions wrote absent values as empty strings
+            undo_sql: Some(r#"
+                UPDATE tracks SET duration_ms = '' WHERE duration_ms IS NULL;
+                UPDATE tracks SET genre = '' WHERE genre IS NULL;
+                UPDATE tracks SET mood = '' WHERE mood IS NULL;
+                UPDATE detection_events SET details = '' WHERE details IS NULL;
+                UPDATE detection_events SET confidence = '' WHERE confidence IS NULL;
+                UPDATE detection_events SET category = '' WHERE category IS NULL;
+                UPDATE keywords SET variations = '' WHERE variations IS NULL;
+                UPDATE keywords SET mood = '' WHERE mood IS NULL;
+            "#),
+        },
+        // Migration 18: Transcribed speech segments; the FTS index is created
+        // by `create_fts_indexes` where FTS5 is available
+        Migration {
+            version: 18,
+            name: "transcripts",
+            sql: r#"
+                CREATE TABLE IF NOT EXISTS transcripts (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    session_id TEXT NOT NULL,
+                    start_ms INTEGER NOT NULL,
+                    end_ms INTEGER NOT NULL,
+                    text TEXT NOT NULL,
+                    confidence REAL,
+                    language TEXT,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_transcripts_session ON transcripts(session_id, start_ms);
+            "#,
+            undo_sql: Some(r#"
+                DROP TRIGGER IF EXISTS transcripts_fts_insert;
+                DROP TRIGGER IF EXISTS transcripts_fts_delete;
+                DROP TABLE IF EXISTS transcripts_fts;
+                DROP TABLE IF EXISTS transcripts;
+            "#),
+        },
+        // Migration 19: Tracks removed by the user stay out of library syncs
+        Migration {
+            version: 19,
+            name: "track_excluded",
+            sql: r#"
+                ALTER TABLE tracks ADD COLUMN excluded INTEGER NOT NULL DEFAULT 0;
+            "#,
+            undo_sql: Some(r#"
+                ALTER TABLE tracks DROP COLUMN excluded;
+            "#),
+        },
+    ]
+}
+
+impl Database {
+    /// Undo applied migrations newer than `version`, newest first
+    ///
+    /// All undo steps run in one transaction. Rolling back past a migration
+    /// without undo SQL fails before anything is changed.
+    pub fn rollback_to(&self, version: i64) -> Result<(), AppError> {
+        let mut conn = self.pool.get()?;
+
+        let current_version: i64 = conn.query_row(
+            "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+            [],
+            |row| row.get(0),
+        )?;
+
+        let steps: Vec<Migration> = get_migrations()
+            .into_iter()
+            .rev()
+            .filter(|m| m.version > version && m.version <= current_version)
+            .collect();
+
+        if let Some(migration) = steps.iter().find(|m| m.undo_sql.is_none()) {
+            tracing::warn!("Cannot roll back past migration v{} ({})", migration.version, migration.name);
+            return Err(AppError::Database("migration is irreversible".to_string()));
+        }
+
+        let tx = conn.transaction()?;
+        for migration in &steps {
+            tracing::info!("Reverting migration v{}", migration.version);
+            if let Some(undo_sql) = migration.undo_sql {
+                tx.execute_batch(undo_sql)?;
+            }
+            tx.execute("DELETE FROM schema_migrations WHERE version = ?1", [migration.version])?;
+        }
+        tx.commit()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrations_defined() {
+        let migrations = get_migrations();
+        assert!(!migrations.is_empty());
+        assert_eq!(migrations[0].version, 1);
+    }
+
+    fn schema_version(db: &Database) -> i64 {
+        db.pool()
+            .get()
+            .unwrap()
+            .query_row("SELECT MAX(version) FROM schema_migrations", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_rollback_and_reapply() {
+        let db = Database::in_memory().unwrap();
+        let latest = get_migrations().last().unwrap().version;
+        assert_eq!(schema_version(&db), latest);
+
+        db.rollback_to(8).unwrap();
+        assert_eq!(schema_version(&db), 8);
+        let conn = db.pool().get().unwrap();
+        assert!(conn.prepare("SELECT * FROM crossfade_overrides").is_err());
+        assert!(conn.prepare("SELECT recording_path FROM sessions").is_err());
+        drop(conn);
+
+        db.run_migrations().unwrap();
+        assert_eq!(schema_version(&db), latest);
+    }
+
+    #[test]
+    fn test_duplicate_tracks_are_kept_aside() {
+        let db = Database::in_memory().unwrap();
+        db.rollback_to(3).unwrap();
+        let conn = db.pool().get().unwrap();
+        conn.execute_batch(
+            "INSERT INTO tracks (id, name, file_path, created_at, updated_at) VALUES ('a', 'A', '/m/a.ogg', '', '');
+             INSERT INTO tracks (id, name, file_path, genre, created_at, updated_at) VALUES ('b', 'B', '/m/a.ogg', 'combat', '', '');",
+        )
+        .unwrap();
+        drop(conn);
+
+        db.run_migrations().unwrap();
+        let conn = db.pool().get().unwrap();
+        let genre: String = conn
+            .query_row("SELECT genre FROM tracks WHERE file_path = '/m/a.ogg'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(genre, "combat");
+        let kept_aside: String = conn.query_row("SELECT id FROM duplicate_tracks", [], |row| row.get(0)).unwrap();
+        assert_eq!(kept_aside, "b");
+        drop(conn);
+
+        db.rollback_to(3).unwrap();
+        let conn = db.pool().get().unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM tracks", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn test_rollback_refuses_irreversible_migration() {
+        let db = Database::in_memory().unwrap();
+        let latest = schema_version(&db);
+
+        let err = db.rollback_to(0).unwrap_err();
+        assert!(err.to_string().contains("irreversible"));
+        assert_eq!(schema_version(&db), latest);
+    }
+
+    #[test]
+    fn test_rollback_restores_empty_strings() {
+        let db = Database::in_memory().unwrap();
+        let conn = db.pool().get().unwrap();
+        conn.execute_batch(
+            "INSERT INTO tracks (id, name, file_path, created_at, updated_at) VALUES ('a', 'A', '/m/a.ogg', '', '');",
+        )
+        .unwrap();
+        drop(conn);
+
+        let genre = |db: &Database| -> Option<String> {
+            db.pool()
+                .get()
+                .unwrap()
+                .query_row("SELECT genre FROM tracks WHERE id = 'a'", [], |row| row.get(0))
+                .unwrap()
+        };
+        db.rollback_to(16).unwrap();
+        assert_eq!(genre(&db).as_deref(), Some(""));
+        db.run_migrations().unwrap();
+        assert_eq!(genre(&db), None);
+    }
+}
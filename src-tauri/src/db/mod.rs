@@ -13,7 +13,10 @@ pub use repository::*;
 use crate::error::AppError;
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, OpenFlags};
 use std::path::Path;
+use std::time::Duration;
 
 /// Database pool type alias
 pub type DbPool = Pool<SqliteConnectionManager>;
@@ -52,6 +55,22 @@ impl Database {
         Ok(db)
     }
 
+    /// Create an in-memory database (single connection, used for tests)
+    pub fn in_memory() -> Result<Self, AppError> {
+        let manager = SqliteConnectionManager::memory();
+        let pool = Pool::builder()
+            .max_size(1)
+            .build(manager)
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let db = Self {
+            pool,
+            db_path: ":memory:".to_string(),
+        };
+        db.run_migrations()?;
+        Ok(db)
+    }
+
     /// Get the connection pool
     pub fn pool(&self) -> &DbPool {
         &self.pool
@@ -101,141 +120,155 @@ impl Database {
             }
         }
 
+        create_fts_indexes(&conn);
+
+        Ok(())
+    }
+
+    /// Copy the live database to `dest_path` with SQLite's online backup API
+    pub fn backup(&self, dest_path: &Path) -> Result<(), AppError> {
+        if let Some(parent) = dest_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let conn = self.pool.get()?;
+        let mut dest = Connection::open(dest_path)?;
+        let backup = Backup::new(&conn, &mut dest)?;
+        backup.run_to_completion(BACKUP_PAGES_PER_STEP, Duration::from_millis(10), None)?;
+
+        tracing::info!("Backed up database to {:?}", dest_path);
+        Ok(())
+    }
+
+    /// Replace the database file with the backup at `src_path` and bring it
+    /// up to the current schema
+    ///
+    /// Pooled connections opened before the restore should be discarded.
+    pub fn restore(&self, src_path: &Path) -> Result<(), AppError> {
+        if self.db_path == ":memory:" {
+            return Err(AppError::Database("Cannot restore an in-memory database".to_string()));
+        }
+
+        // Refuse anything SQLite can't read before overwriting the live file
+        let src = Connection::open_with_flags(src_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        src.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0))?;
+        drop(src);
+
+        std::fs::copy(src_path, &self.db_path)?;
+        self.run_migrations()?;
+
+        tracing::info!("Restored database from {:?}", src_path);
         Ok(())
     }
 }
 
-/// Migration definition
-pub struct Migration {
-    pub version: i64,
-    pub name: &'static str,
-    pub sql: &'static str,
+/// External-content FTS5 index over transcript text, kept in sync by triggers
+const TRANSCRIPTS_FTS_SQL: &str = r#"
+    CREATE VIRTUAL TABLE transcripts_fts USING fts5(text, content='transcripts', content_rowid='id');
+
+    CREATE TRIGGER transcripts_fts_insert AFTER INSERT ON transcripts BEGIN
+        INSERT INTO transcripts_fts (rowid, text) VALUES (new.id, new.text);
+    END;
+
+    CREATE TRIGGER transcripts_fts_delete AFTER DELETE ON transcripts BEGIN
+        INSERT INTO transcripts_fts (transcripts_fts, rowid, text) VALUES ('delete', old.id, old.text);
+    END;
+
+    INSERT INTO transcripts_fts (transcripts_fts) VALUES ('rebuild');
+"#;
+
+/// FTS5 index over detection event details and types, written alongside
+/// each event by the repository
+const DETECTION_EVENTS_FTS_SQL: &str = r#"
+    CREATE VIRTUAL TABLE detection_events_fts USING fts5(
+        details,
+        event_type,
+        session_id UNINDEXED,
+        event_id UNINDEXED
+    );
+
+    INSERT INTO detection_events_fts (details, event_type, session_id, event_id)
+        SELECT COALESCE(details, ''), event_type, session_id, id FROM detection_events;
+"#;
+
+/// Create the transcript and detection event search indexes that are missing
+///
+/// Kept out of the migrations because SQLite may be built without FTS5;
+/// search then falls back to LIKE queries.
+fn create_fts_indexes(conn: &Connection) {
+    for (table, sql) in [
+        ("transcripts_fts", TRANSCRIPTS_FTS_SQL),
+        ("detection_events_fts", DETECTION_EVENTS_FTS_SQL),
+    ] {
+        if has_table(conn, table) {
+            continue;
+        }
+
+        let created = conn.unchecked_transaction().and_then(|tx| {
+            tx.execute_batch(sql)?;
+            tx.commit()
+        });
+        if let Err(e) = created {
+            tracing::warn!("Full-text search index {} is unavailable: {}", table, e);
+        }
+    }
 }
 
-/// Get all migrations
-pub fn get_migrations() -> Vec<Migration> {
-    vec![
-        // Migration 1: Initial schema
-        Migration {
-            version: 1,
-            name: "initial_schema",
-            sql: r#"
-                -- Tracks table
-                CREATE TABLE IF NOT EXISTS tracks (
-                    id TEXT PRIMARY KEY,
-                    name TEXT NOT NULL,
-                    file_path TEXT NOT NULL,
-                    duration_ms INTEGER,
-                    genre TEXT,
-                    mood TEXT,
-                    is_looping INTEGER DEFAULT 0,
-                    volume REAL DEFAULT 1.0,
-                    created_at TEXT NOT NULL,
-                    updated_at TEXT NOT NULL
-                );
-
-                -- Track genres table
-                CREATE TABLE IF NOT EXISTS track_genres (
-                    id TEXT PRIMARY KEY,
-                    name TEXT NOT NULL UNIQUE,
-                    color TEXT,
-                    created_at TEXT NOT NULL
-                );
-
-                -- SFX table
-                CREATE TABLE IF NOT EXISTS sfx (
-                    id TEXT PRIMARY KEY,
-                    name TEXT NOT NULL,
-                    file_path TEXT NOT NULL,
-                    duration_ms INTEGER,
-                    category TEXT,
-                    volume REAL DEFAULT 1.0,
-                    created_at TEXT NOT NULL
-                );
-
-                -- Sessions table
-                CREATE TABLE IF NOT EXISTS sessions (
-                    id TEXT PRIMARY KEY,
-                    started_at TEXT NOT NULL,
-                    ended_at TEXT,
-                    mode TEXT NOT NULL,
-                    total_duration_ms INTEGER,
-                    created_at TEXT NOT NULL
-                );
-
-                -- Keywords table
-                CREATE TABLE IF NOT EXISTS keywords (
-                    id TEXT PRIMARY KEY,
-                    word TEXT NOT NULL,
-                    category TEXT NOT NULL,
-                    variations TEXT,
-                    mood TEXT,
-                    priority INTEGER DEFAULT 0,
-                    is_active INTEGER DEFAULT 1,
-                    created_at TEXT NOT NULL
-                );
-
-                -- Detection events table
-                CREATE TABLE IF NOT EXISTS detection_events (
-                    id TEXT PRIMARY KEY,
-                    session_id TEXT NOT NULL,
-                    event_type TEXT NOT NULL,
-                    timestamp TEXT NOT NULL,
-                    details TEXT,
-                    confidence REAL,
-                    category TEXT,
-                    triggered_action INTEGER DEFAULT 0,
-                    FOREIGN KEY (session_id) REFERENCES sessions(id)
-                );
-
-                -- Voice profiles table
-                CREATE TABLE IF NOT EXISTS voice_profiles (
-                    id TEXT PRIMARY KEY,
-                    name TEXT NOT NULL,
-                    embedding BLOB,
-                    is_default INTEGER DEFAULT 0,
-                    consent_given INTEGER DEFAULT 0,
-                    created_at TEXT NOT NULL,
-                    updated_at TEXT NOT NULL
-                );
-
-                -- Settings table
-                CREATE TABLE IF NOT EXISTS settings (
-                    key TEXT PRIMARY KEY,
-                    value TEXT NOT NULL,
-                    updated_at TEXT NOT NULL
-                );
-
-                -- Create indexes
-                CREATE INDEX IF NOT EXISTS idx_tracks_genre ON tracks(genre);
-                CREATE INDEX IF NOT EXISTS idx_tracks_mood ON tracks(mood);
-                CREATE INDEX IF NOT EXISTS idx_detection_events_session ON detection_events(session_id);
-                CREATE INDEX IF NOT EXISTS idx_detection_events_type ON detection_events(event_type);
-            "#,
-        },
-        // Migration 2: Add more session details
-        Migration {
-            version: 2,
-            name: "session_details",
-            sql: r#"
-                -- Add columns to sessions
-                ALTER TABLE sessions ADD COLUMN detected_events_count INTEGER DEFAULT 0;
-                ALTER TABLE sessions ADD COLUMN keywords_triggered INTEGER DEFAULT 0;
-                ALTER TABLE sessions ADD COLUMN emotions_detected TEXT;
-                ALTER TABLE sessions ADD COLUMN tracks_played TEXT;
-            "#,
-        },
-    ]
+/// Whether a table (including virtual tables) exists
+pub(crate) fn has_table(conn: &Connection, name: &str) -> bool {
+    conn.query_row(
+        "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [name],
+        |_| Ok(()),
+    )
+    .is_ok()
 }
 
+/// Pages copied per step of an online backup
+const BACKUP_PAGES_PER_STEP: std::os::raw::c_int = 256;
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_migrations_defined() {
-        let migrations = get_migrations();
-        assert!(!migrations.is_empty());
-        assert_eq!(migrations[0].version, 1);
+    fn test_backup_and_restore_round_trip() {
+        let dir = std::env::temp_dir().join(format!("db-backup-{}", uuid::Uuid::new_v4()));
+        let live = Database::new(dir.join("live.db").to_str().unwrap()).unwrap();
+        let conn = live.pool().get().unwrap();
+        conn.execute(
+            "INSERT INTO settings (key, value, updated_at) VALUES ('marker', 'before', '')",
+            [],
+        )
+        .unwrap();
+
+        let backup_path = dir.join("backup.db");
+        live.backup(&backup_path).unwrap();
+
+        conn.execute("UPDATE settings SET value = 'after' WHERE key = 'marker'", []).unwrap();
+        drop(conn);
+        live.restore(&backup_path).unwrap();
+
+        let restored = Database::new(live.path()).unwrap();
+        let value: String = restored
+            .pool()
+            .get()
+            .unwrap()
+            .query_row("SELECT value FROM settings WHERE key = 'marker'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(value, "before");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_restore_rejects_non_database() {
+        let dir = std::env::temp_dir().join(format!("db-restore-{}", uuid::Uuid::new_v4()));
+        let live = Database::new(dir.join("live.db").to_str().unwrap()).unwrap();
+        let bogus = dir.join("bogus.db");
+        std::fs::write(&bogus, b"not a database at all, just some text").unwrap();
+
+        assert!(live.restore(&bogus).is_err());
+        std::fs::remove_dir_all(&dir).ok();
     }
 }
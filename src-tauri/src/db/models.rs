@@ -14,8 +14,12 @@ pub struct Track {
     pub mood: Option<String>,
     pub is_looping: bool,
     pub volume: f64,
+    /// Tempo tagged by the user
+    pub bpm: Option<f64>,
     pub created_at: String,
     pub updated_at: String,
+    /// Problems found by the import analysis pass (clicks, silence, NaNs)
+    pub import_warnings: Vec<String>,
 }
 
 impl Track {
@@ -30,12 +34,50 @@ impl Track {
             mood: None,
             is_looping: false,
             volume: 1.0,
+            bpm: None,
             created_at: now.clone(),
             updated_at: now,
+            import_warnings: Vec::new(),
         }
     }
 }
 
+/// Track fields to change; None leaves a field as it is
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackUpdate {
+    pub name: Option<String>,
+    /// An empty string clears the genre
+    pub genre: Option<String>,
+    /// An empty string clears the mood
+    pub mood: Option<String>,
+    pub is_looping: Option<bool>,
+    pub volume: Option<f64>,
+    pub bpm: Option<f64>,
+}
+
+impl TrackUpdate {
+    /// True if no field would change
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.genre.is_none()
+            && self.mood.is_none()
+            && self.is_looping.is_none()
+            && self.volume.is_none()
+            && self.bpm.is_none()
+    }
+}
+
+/// Library sync state of a track file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    pub file_path: String,
+    pub is_deleted: bool,
+    pub decode_error: Option<String>,
+    /// Removed by the user; syncs leave it alone even while the file exists
+    pub is_excluded: bool,
+}
+
 /// Genre model
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Genre {
@@ -56,6 +98,35 @@ impl Genre {
     }
 }
 
+/// Play statistics of a track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackStats {
+    pub track_id: String,
+    pub play_count: i64,
+    pub total_played_ms: i64,
+    pub last_played_at: Option<String>,
+}
+
+/// Playlist model (tracks are kept in playlist_tracks)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Playlist {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub created_at: String,
+}
+
+impl Playlist {
+    pub fn new(id: String, name: String) -> Self {
+        Self {
+            id,
+            name,
+            description: None,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
 /// SFX model
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Sfx {
@@ -96,6 +167,8 @@ pub struct Session {
     pub keywords_triggered: Option<i32>,
     pub emotions_detected: Option<String>,
     pub tracks_played: Option<String>,
+    /// WAV file of the session audio (migration 10)
+    pub recording_path: Option<String>,
 }
 
 impl Session {
@@ -112,6 +185,7 @@ impl Session {
             keywords_triggered: None,
             emotions_detected: None,
             tracks_played: None,
+            recording_path: None,
         }
     }
 }
@@ -172,6 +246,51 @@ impl DetectionEvent {
     }
 }
 
+/// Transcribed speech segment, timed from the session start
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub id: i64,
+    pub session_id: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+    pub confidence: Option<f64>,
+    pub language: Option<String>,
+    pub created_at: String,
+}
+
+impl Transcript {
+    /// New segment; the id is assigned on insert
+    pub fn new(session_id: String, start_ms: i64, end_ms: i64, text: String) -> Self {
+        Self {
+            id: 0,
+            session_id,
+            start_ms,
+            end_ms,
+            text,
+            confidence: None,
+            language: None,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Filter for querying detection events; unset fields match everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventFilter {
+    pub session_id: Option<String>,
+    pub event_type: Option<String>,
+    pub category: Option<String>,
+    pub min_confidence: Option<f64>,
+    pub max_confidence: Option<f64>,
+    /// Inclusive RFC 3339 bounds on the event timestamp
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
 /// Voice profile model
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VoiceProfile {
@@ -199,6 +318,76 @@ impl VoiceProfile {
     }
 }
 
+/// Keyword category to track genre mapping
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeywordGenreMapping {
+    pub keyword_category: String,
+    pub genre_name: String,
+    pub priority: i32,
+    pub created_at: String,
+}
+
+impl KeywordGenreMapping {
+    pub fn new(keyword_category: String, genre_name: String, priority: i32) -> Self {
+        Self {
+            keyword_category,
+            genre_name,
+            priority,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Music, and optionally an SFX, to play for a detected mood
+///
+/// Targets either one track or a whole genre.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoodMapping {
+    pub id: String,
+    pub mood: String,
+    pub track_id: Option<String>,
+    pub genre: Option<String>,
+    /// Higher priorities are tried first
+    pub priority: i32,
+    pub sfx_id: Option<String>,
+    pub created_at: String,
+}
+
+impl MoodMapping {
+    pub fn new(id: String, mood: String) -> Self {
+        Self {
+            id,
+            mood,
+            track_id: None,
+            genre: None,
+            priority: 0,
+            sfx_id: None,
+            created_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
+/// Crossfade type to use when switching between two genres
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossfadeOverride {
+    pub from_genre: String,
+    pub to_genre: String,
+    /// One of "instant", "quick", "musical", "long"
+    pub crossfade_type: String,
+    pub updated_at: String,
+}
+
+impl CrossfadeOverride {
+    pub fn new(from_genre: String, to_genre: String, crossfade_type: String) -> Self {
+        Self {
+            from_genre,
+            to_genre,
+            crossfade_type,
+            updated_at: Utc::now().to_rfc3339(),
+        }
+    }
+}
+
 /// Setting model
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Setting {
@@ -216,3 +405,13 @@ impl Setting {
         }
     }
 }
+
+/// Audit log entry for a privacy-relevant action
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub action: String,
+    pub subject_id: Option<String>,
+    pub details: Option<String>,
+    pub created_at: String,
+}
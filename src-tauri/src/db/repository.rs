@@ -5,9 +5,13 @@ use crate::db::DbPool;
 use crate::error::AppError;
 use r2d2::PooledConnection;
 use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, OptionalExtension};
+use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Database repository
+#[derive(Clone)]
 pub struct Repository {
     pool: DbPool,
 }
@@ -31,77 +35,373 @@ impl Repository {
     pub fn get_all_tracks(&self) -> Result<Vec<Track>, AppError> {
         let conn = self.get_conn()?;
         let mut stmt = conn.prepare(
-            "SELECT id, name, file_path, duration_ms, genre, mood, is_looping, volume, created_at, updated_at FROM tracks ORDER BY name"
+            "SELECT id, name, file_path, duration_ms, genre, mood, is_looping, volume, created_at, updated_at, import_warnings, bpm FROM tracks WHERE deleted_at IS NULL AND decode_error IS NULL ORDER BY name"
         )?;
 
-        let tracks = stmt
+        let tracks = stmt.query_map([], track_from_row)?.collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tracks)
+    }
+
+    /// Get tracks by genre
+    pub fn get_tracks_by_genre(&self, genre: &str) -> Result<Vec<Track>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, file_path, duration_ms, genre, mood, is_looping, volume, created_at, updated_at, import_warnings, bpm FROM tracks WHERE genre = ?1 AND deleted_at IS NULL AND decode_error IS NULL ORDER BY name"
+        )?;
+
+        let tracks = stmt.query_map([genre], track_from_row)?.collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tracks)
+    }
+
+    /// Get tracks by mood
+    pub fn get_tracks_by_mood(&self, mood: &str) -> Result<Vec<Track>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, file_path, duration_ms, genre, mood, is_looping, volume, created_at, updated_at, import_warnings, bpm FROM tracks WHERE mood = ?1 AND deleted_at IS NULL AND decode_error IS NULL ORDER BY name"
+        )?;
+
+        let tracks = stmt.query_map([mood], track_from_row)?.collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tracks)
+    }
+
+    /// Get tracks matching both a genre and a mood
+    pub fn get_tracks_by_genre_and_mood(&self, genre: &str, mood: &str) -> Result<Vec<Track>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, file_path, duration_ms, genre, mood, is_looping, volume, created_at, updated_at, import_warnings, bpm FROM tracks WHERE genre = ?1 AND mood = ?2 AND deleted_at IS NULL AND decode_error IS NULL ORDER BY name"
+        )?;
+
+        let tracks = stmt.query_map([genre, mood], track_from_row)?.collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tracks)
+    }
+
+    /// Get an active track by ID
+    pub fn get_track(&self, id: &str) -> Result<Option<Track>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, file_path, duration_ms, genre, mood, is_looping, volume, created_at, updated_at, import_warnings, bpm FROM tracks WHERE id = ?1 AND deleted_at IS NULL AND decode_error IS NULL"
+        )?;
+
+        let track = stmt.query_row([id], track_from_row).ok();
+
+        Ok(track)
+    }
+
+    /// Get a track by file path, unless it was removed from the library
+    pub fn get_track_by_path(&self, file_path: &str) -> Result<Option<Track>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, file_path, duration_ms, genre, mood, is_looping, volume, created_at, updated_at, import_warnings, bpm FROM tracks WHERE file_path = ?1 AND deleted_at IS NULL"
+        )?;
+
+        let track = stmt.query_row([file_path], track_from_row).ok();
+
+        Ok(track)
+    }
+
+    /// Insert a track
+    pub fn insert_track(&self, track: &Track) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "INSERT INTO tracks (id, name, file_path, duration_ms, genre, mood, is_looping, volume, created_at, updated_at, bpm) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                track.id,
+                track.name,
+                track.file_path,
+                track.duration_ms,
+                track.genre,
+                track.mood,
+                track.is_looping,
+                track.volume,
+                track.created_at,
+                track.updated_at,
+                track.bpm,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Change the given fields of a track and return the updated row
+    ///
+    /// Returns None if no active track has `id`.
+    pub fn update_track(&self, id: &str, update: &TrackUpdate) -> Result<Option<Track>, AppError> {
+        let mut assignments = Vec::new();
+        let mut values = Vec::new();
+        let mut assign = |column: &str, value: Value| {
+            values.push(value);
+            assignments.push(format!("{} = ?{}", column, values.len()));
+        };
+
+        let text_or_null = |text: &str| {
+            if text.is_empty() {
+                Value::Null
+            } else {
+                Value::Text(text.to_string())
+            }
+        };
+        if let Some(name) = &update.name {
+            assign("name", Value::Text(name.clone()));
+        }
+        if let Some(genre) = &update.genre {
+            assign("genre", text_or_null(genre));
+        }
+        if let Some(mood) = &update.mood {
+            assign("mood", text_or_null(mood));
+        }
+        if let Some(is_looping) = update.is_looping {
+            assign("is_looping", Value::Integer(is_looping as i64));
+        }
+        if let Some(volume) = update.volume {
+            assign("volume", Value::Real(volume));
+        }
+        if let Some(bpm) = update.bpm {
+            assign("bpm", Value::Real(bpm));
+        }
+        assign("updated_at", Value::Text(chrono::Utc::now().to_rfc3339()));
+        values.push(Value::Text(id.to_string()));
+
+        let sql = format!(
+            "UPDATE tracks SET {} WHERE id = ?{} AND deleted_at IS NULL",
+            assignments.join(", "),
+            values.len()
+        );
+        let updated = self.get_conn()?.execute(&sql, params_from_iter(values))?;
+        if updated == 0 {
+            return Ok(None);
+        }
+        self.get_track(id)
+    }
+
+    /// Remove a track from the library, with its stats, playlist entries and mood mappings
+    ///
+    /// The row is kept, soft-deleted and excluded, so a later library sync
+    /// does not import the file again.
+    pub fn delete_track(&self, id: &str) -> Result<bool, AppError> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        let deleted = tx.execute(
+            "UPDATE tracks SET deleted_at = ?2, updated_at = ?2, excluded = 1 WHERE id = ?1 AND deleted_at IS NULL",
+            params![id, now],
+        )?;
+        if deleted == 0 {
+            return Ok(false);
+        }
+        tx.execute("DELETE FROM playlist_tracks WHERE track_id = ?1", [id])?;
+        tx.execute("DELETE FROM track_stats WHERE track_id = ?1", [id])?;
+        tx.execute("DELETE FROM mood_mappings WHERE track_id = ?1", [id])?;
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Check whether `name` is a known genre
+    pub fn genre_exists(&self, name: &str) -> Result<bool, AppError> {
+        let conn = self.get_conn()?;
+        let exists = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM track_genres WHERE name = ?1)",
+            [name],
+            |row| row.get(0),
+        )?;
+        Ok(exists)
+    }
+
+    /// Add a genre; adding an existing one is a no-op
+    pub fn insert_genre(&self, name: &str) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "INSERT OR IGNORE INTO track_genres (id, name, created_at) VALUES (?1, ?2, ?3)",
+            params![uuid::Uuid::new_v4().to_string(), name, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Get sync state for every track file, including deleted and broken ones
+    pub fn get_library_entries(&self) -> Result<Vec<LibraryEntry>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare("SELECT file_path, deleted_at, decode_error, excluded FROM tracks")?;
+
+        let entries = stmt
+            .query_map([], |row| {
+                Ok(LibraryEntry {
+                    file_path: row.get(0)?,
+                    is_deleted: row.get::<_, Option<String>>(1)?.is_some(),
+                    decode_error: row.get(2)?,
+                    is_excluded: row.get::<_, i32>(3)? != 0,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(entries)
+    }
+
+    /// Mark a track as removed from disk
+    pub fn soft_delete_track_by_path(&self, file_path: &str) -> Result<bool, AppError> {
+        let conn = self.get_conn()?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let updated = conn.execute(
+            "UPDATE tracks SET deleted_at = ?2, updated_at = ?2 WHERE file_path = ?1 AND deleted_at IS NULL",
+            [file_path, &now],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Restore a soft-deleted track whose file reappeared, unless the user removed it
+    pub fn restore_track_by_path(&self, file_path: &str) -> Result<bool, AppError> {
+        let conn = self.get_conn()?;
+        let now = chrono::Utc::now().to_rfc3339();
+        let updated = conn.execute(
+            "UPDATE tracks SET deleted_at = NULL, updated_at = ?2 WHERE file_path = ?1 AND deleted_at IS NOT NULL AND excluded = 0",
+            [file_path, &now],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Bring back a removed track the user imports again, returning its id
+    ///
+    /// Unlike `restore_track_by_path`, this also undoes a user delete.
+    pub fn reinstate_track_by_path(&self, file_path: &str) -> Result<Option<String>, AppError> {
+        let conn = self.get_conn()?;
+        let id: Option<String> = conn
+            .query_row(
+                "SELECT id FROM tracks WHERE file_path = ?1 AND deleted_at IS NOT NULL",
+                [file_path],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(id) = &id {
+            conn.execute(
+                "UPDATE tracks SET deleted_at = NULL, excluded = 0, updated_at = ?2 WHERE id = ?1",
+                [id, &chrono::Utc::now().to_rfc3339()],
+            )?;
+        }
+        Ok(id)
+    }
+
+    /// Flag a track whose file could not be decoded
+    pub fn set_track_decode_error(&self, track_id: &str, error: &str) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "UPDATE tracks SET decode_error = ?2 WHERE id = ?1",
+            [track_id, error],
+        )?;
+        Ok(())
+    }
+
+    /// Store analysis warnings found while importing a track
+    pub fn set_track_import_warnings(&self, track_id: &str, warnings: &[String]) -> Result<(), AppError> {
+        let json = serde_json::to_string(warnings).map_err(|e| AppError::Serialization(e.to_string()))?;
+        let conn = self.get_conn()?;
+        conn.execute(
+            "UPDATE tracks SET import_warnings = ?2 WHERE id = ?1",
+            [track_id, json.as_str()],
+        )?;
+        Ok(())
+    }
+
+    // ========== SFX ==========
+
+    /// Get all SFX
+    pub fn get_all_sfx(&self) -> Result<Vec<Sfx>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, file_path, duration_ms, category, volume, created_at FROM sfx ORDER BY category, name"
+        )?;
+
+        let sfx = stmt
             .query_map([], |row| {
-                Ok(Track {
+                Ok(Sfx {
                     id: row.get(0)?,
                     name: row.get(1)?,
                     file_path: row.get(2)?,
                     duration_ms: row.get(3)?,
-                    genre: row.get(4)?,
-                    mood: row.get(5)?,
-                    is_looping: row.get::<_, i32>(6)? != 0,
-                    volume: row.get(7)?,
-                    created_at: row.get(8)?,
-                    updated_at: row.get(9)?,
+                    category: row.get(4)?,
+                    volume: row.get(5)?,
+                    created_at: row.get(6)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(tracks)
+        Ok(sfx)
     }
 
-    /// Get tracks by genre
-    pub fn get_tracks_by_genre(&self, genre: &str) -> Result<Vec<Track>, AppError> {
+    /// Get SFX by category
+    pub fn get_sfx_by_category(&self, category: &str) -> Result<Vec<Sfx>, AppError> {
         let conn = self.get_conn()?;
         let mut stmt = conn.prepare(
-            "SELECT id, name, file_path, duration_ms, genre, mood, is_looping, volume, created_at, updated_at FROM tracks WHERE genre = ?1 ORDER BY name"
+            "SELECT id, name, file_path, duration_ms, category, volume, created_at FROM sfx WHERE category = ?1 ORDER BY name"
         )?;
 
-        let tracks = stmt
-            .query_map([genre], |row| {
-                Ok(Track {
+        let sfx = stmt
+            .query_map([category], |row| {
+                Ok(Sfx {
                     id: row.get(0)?,
                     name: row.get(1)?,
                     file_path: row.get(2)?,
                     duration_ms: row.get(3)?,
-                    genre: row.get(4)?,
-                    mood: row.get(5)?,
-                    is_looping: row.get::<_, i32>(6)? != 0,
-                    volume: row.get(7)?,
-                    created_at: row.get(8)?,
-                    updated_at: row.get(9)?,
+                    category: row.get(4)?,
+                    volume: row.get(5)?,
+                    created_at: row.get(6)?,
                 })
             })?
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(tracks)
+        Ok(sfx)
     }
 
-    /// Insert a track
-    pub fn insert_track(&self, track: &Track) -> Result<(), AppError> {
+    /// Get SFX by ID
+    pub fn get_sfx(&self, sfx_id: &str) -> Result<Option<Sfx>, AppError> {
+        let conn = self.get_conn()?;
+        let sfx = conn
+            .query_row(
+                "SELECT id, name, file_path, duration_ms, category, volume, created_at FROM sfx WHERE id = ?1",
+                [sfx_id],
+                |row| {
+                    Ok(Sfx {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        file_path: row.get(2)?,
+                        duration_ms: row.get(3)?,
+                        category: row.get(4)?,
+                        volume: row.get(5)?,
+                        created_at: row.get(6)?,
+                    })
+                },
+            )
+            .ok();
+        Ok(sfx)
+    }
+
+    /// Insert an SFX
+    pub fn insert_sfx(&self, sfx: &Sfx) -> Result<(), AppError> {
         let conn = self.get_conn()?;
         conn.execute(
-            "INSERT INTO tracks (id, name, file_path, duration_ms, genre, mood, is_looping, volume, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            [
-                &track.id,
-                &track.name,
-                &track.file_path,
-                &track.duration_ms.map(|v| v.to_string()).unwrap_or_default(),
-                &track.genre.clone().unwrap_or_default(),
-                &track.mood.clone().unwrap_or_default(),
-                &(if track.is_looping { 1 } else { 0 }).to_string(),
-                &track.volume.to_string(),
-                &track.created_at,
-                &track.updated_at,
+            "INSERT INTO sfx (id, name, file_path, duration_ms, category, volume, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                sfx.id,
+                sfx.name,
+                sfx.file_path,
+                sfx.duration_ms,
+                sfx.category,
+                sfx.volume,
+                sfx.created_at,
             ],
         )?;
         Ok(())
     }
 
+    /// Delete an SFX
+    pub fn delete_sfx(&self, sfx_id: &str) -> Result<bool, AppError> {
+        let conn = self.get_conn()?;
+        let deleted = conn.execute("DELETE FROM sfx WHERE id = ?1", [sfx_id])?;
+        Ok(deleted > 0)
+    }
+
     // ========== Sessions ==========
 
     /// Start a new session
@@ -114,22 +414,117 @@ impl Repository {
         Ok(())
     }
 
-    /// End a session
+    /// End a session, storing its duration
     pub fn end_session(&self, session_id: &str) -> Result<(), AppError> {
         let conn = self.get_conn()?;
         let ended_at = chrono::Utc::now().to_rfc3339();
         conn.execute(
-            "UPDATE sessions SET ended_at = ?1 WHERE id = ?2",
+            "UPDATE sessions SET ended_at = ?1, total_duration_ms = CAST((julianday(?1) - julianday(started_at)) * 86400000 AS INTEGER) WHERE id = ?2",
             [&ended_at, session_id],
         )?;
         Ok(())
     }
 
+    /// Store the detection counts of a finished session
+    pub fn set_session_stats(
+        &self,
+        session_id: &str,
+        detected_events_count: i32,
+        keywords_triggered: i32,
+    ) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "UPDATE sessions SET detected_events_count = ?1, keywords_triggered = ?2 WHERE id = ?3",
+            params![detected_events_count, keywords_triggered, session_id],
+        )?;
+        Ok(())
+    }
+
+    /// List sessions, newest first
+    pub fn list_sessions(&self, limit: usize, offset: usize) -> Result<Vec<Session>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, started_at, ended_at, mode, total_duration_ms, created_at, detected_events_count, keywords_triggered, emotions_detected, tracks_played, recording_path FROM sessions ORDER BY started_at DESC LIMIT ?1 OFFSET ?2"
+        )?;
+
+        let sessions = stmt
+            .query_map(params![limit as i64, offset as i64], |row| {
+                Ok(Session {
+                    id: row.get(0)?,
+                    started_at: row.get(1)?,
+                    ended_at: row.get(2)?,
+                    mode: row.get(3)?,
+                    total_duration_ms: row.get(4)?,
+                    created_at: row.get(5)?,
+                    detected_events_count: row.get(6)?,
+                    keywords_triggered: row.get(7)?,
+                    emotions_detected: row.get(8)?,
+                    tracks_played: row.get(9)?,
+                    recording_path: row.get(10)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(sessions)
+    }
+
+    /// Delete a session with its detection events and WAV recording
+    pub fn delete_session(&self, session_id: &str) -> Result<(), AppError> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        let recording_path: Option<String> = tx
+            .query_row(
+                "SELECT recording_path FROM sessions WHERE id = ?1",
+                [session_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => AppError::NotFound(format!("Session not found: {}", session_id)),
+                e => e.into(),
+            })?;
+
+        // Events reference the session, so they go first
+        if super::has_table(&tx, "detection_events_fts") {
+            tx.execute("DELETE FROM detection_events_fts WHERE session_id = ?1", [session_id])?;
+        }
+        tx.execute("DELETE FROM detection_events WHERE session_id = ?1", [session_id])?;
+        tx.execute("DELETE FROM transcripts WHERE session_id = ?1", [session_id])?;
+        tx.execute("DELETE FROM sessions WHERE id = ?1", [session_id])?;
+        tx.commit()?;
+
+        if let Some(path) = recording_path {
+            if let Err(e) = std::fs::remove_file(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    return Err(AppError::Io(format!("Failed to delete recording {}: {}", path, e)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Count all stored sessions
+    pub fn count_sessions(&self) -> Result<i64, AppError> {
+        let conn = self.get_conn()?;
+        let count = conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))?;
+        Ok(count)
+    }
+
+    /// Store where the session audio was recorded
+    pub fn set_session_recording_path(&self, session_id: &str, path: &str) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "UPDATE sessions SET recording_path = ?1 WHERE id = ?2",
+            [path, session_id],
+        )?;
+        Ok(())
+    }
+
     /// Get session by ID
     pub fn get_session(&self, session_id: &str) -> Result<Option<Session>, AppError> {
         let conn = self.get_conn()?;
         let mut stmt = conn.prepare(
-            "SELECT id, started_at, ended_at, mode, total_duration_ms, created_at, detected_events_count, keywords_triggered, emotions_detected, tracks_played FROM sessions WHERE id = ?1"
+            "SELECT id, started_at, ended_at, mode, total_duration_ms, created_at, detected_events_count, keywords_triggered, emotions_detected, tracks_played, recording_path FROM sessions WHERE id = ?1"
         )?;
 
         let session = stmt
@@ -145,6 +540,7 @@ impl Repository {
                     keywords_triggered: row.get(7)?,
                     emotions_detected: row.get(8)?,
                     tracks_played: row.get(9)?,
+                    recording_path: row.get(10)?,
                 })
             })
             .ok();
@@ -156,23 +552,146 @@ impl Repository {
 
     /// Insert detection event
     pub fn insert_detection_event(&self, event: &DetectionEvent) -> Result<(), AppError> {
-        let conn = self.get_conn()?;
-        conn.execute(
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+        tx.execute(
             "INSERT INTO detection_events (id, session_id, event_type, timestamp, details, confidence, category, triggered_action) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            [
-                &event.id,
-                &event.session_id,
-                &event.event_type,
-                &event.timestamp,
-                &event.details.clone().unwrap_or_default(),
-                &event.confidence.map(|v| v.to_string()).unwrap_or_default(),
-                &event.category.clone().unwrap_or_default(),
-                &(if event.triggered_action { 1 } else { 0 }).to_string(),
+            params![
+                event.id,
+                event.session_id,
+                event.event_type,
+                event.timestamp,
+                event.details,
+                event.confidence,
+                event.category,
+                event.triggered_action,
             ],
         )?;
+        if super::has_table(&tx, "detection_events_fts") {
+            tx.execute(
+                "INSERT INTO detection_events_fts (details, event_type, session_id, event_id) VALUES (?1, ?2, ?3, ?4)",
+                params![event.details, event.event_type, event.session_id, event.id],
+            )?;
+        }
+        tx.commit()?;
         Ok(())
     }
 
+    /// Search event details and types, optionally within one session
+    ///
+    /// Every word of `query` must match; FTS5 operators are treated as plain
+    /// text. Without FTS5 the words are matched as substrings with LIKE instead.
+    pub fn search_events(&self, query: &str, session_id: Option<&str>) -> Result<Vec<DetectionEvent>, AppError> {
+        let words: Vec<&str> = query.split_whitespace().collect();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.get_conn()?;
+        if super::has_table(&conn, "detection_events_fts") {
+            let mut stmt = conn.prepare(
+                "SELECT e.id, e.session_id, e.event_type, e.timestamp, e.details, e.confidence, e.category, e.triggered_action FROM detection_events_fts f JOIN detection_events e ON e.id = f.event_id WHERE detection_events_fts MATCH ?1 AND (?2 IS NULL OR f.session_id = ?2) ORDER BY e.timestamp"
+            )?;
+            let events = stmt
+                .query_map(params![fts_query(query), session_id], event_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(events);
+        }
+
+        tracing::warn!("Detection event search index unavailable, falling back to LIKE");
+        let mut values = vec![session_id.map_or(Value::Null, |id| Value::Text(id.to_string()))];
+        let mut conditions = Vec::new();
+        for word in words {
+            values.push(Value::Text(format!("%{}%", escape_like(word))));
+            conditions.push(format!(
+                "(COALESCE(details, '') || ' ' || event_type) LIKE ?{} ESCAPE '\\'",
+                values.len()
+            ));
+        }
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, session_id, event_type, timestamp, details, confidence, category, triggered_action FROM detection_events WHERE (?1 IS NULL OR session_id = ?1) AND {} ORDER BY timestamp",
+            conditions.join(" AND ")
+        ))?;
+        let events = stmt
+            .query_map(params_from_iter(values), event_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(events)
+    }
+
+    // ========== Transcripts ==========
+
+    /// Store a transcribed segment, returning its id
+    pub fn insert_transcript(&self, transcript: &Transcript) -> Result<i64, AppError> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "INSERT INTO transcripts (session_id, start_ms, end_ms, text, confidence, language, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                transcript.session_id,
+                transcript.start_ms,
+                transcript.end_ms,
+                transcript.text,
+                transcript.confidence,
+                transcript.language,
+                transcript.created_at,
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// Get the transcribed segments of a session in order
+    pub fn get_session_transcripts(&self, session_id: &str) -> Result<Vec<Transcript>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, session_id, start_ms, end_ms, text, confidence, language, created_at FROM transcripts WHERE session_id = ?1 ORDER BY start_ms, id"
+        )?;
+
+        let transcripts = stmt
+            .query_map([session_id], transcript_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(transcripts)
+    }
+
+    /// Search transcript text, optionally within one session
+    ///
+    /// Every word of `query` must match. Without FTS5 the words are matched
+    /// as substrings with LIKE instead.
+    pub fn search_transcripts(&self, query: &str, session_id: Option<&str>) -> Result<Vec<Transcript>, AppError> {
+        let words: Vec<&str> = query.split_whitespace().collect();
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.get_conn()?;
+        if super::has_table(&conn, "transcripts_fts") {
+            let mut stmt = conn.prepare(
+                "SELECT t.id, t.session_id, t.start_ms, t.end_ms, t.text, t.confidence, t.language, t.created_at FROM transcripts_fts f JOIN transcripts t ON t.id = f.rowid WHERE transcripts_fts MATCH ?1 AND (?2 IS NULL OR t.session_id = ?2) ORDER BY t.session_id, t.start_ms"
+            )?;
+            let transcripts = stmt
+                .query_map(params![fts_query(query), session_id], transcript_from_row)?
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(transcripts);
+        }
+
+        tracing::warn!("Transcript search index unavailable, falling back to LIKE");
+        let mut values = vec![session_id.map_or(Value::Null, |id| Value::Text(id.to_string()))];
+        let mut conditions = Vec::new();
+        for word in words {
+            values.push(Value::Text(format!("%{}%", escape_like(word))));
+            conditions.push(format!("text LIKE ?{} ESCAPE '\\'", values.len()));
+        }
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, session_id, start_ms, end_ms, text, confidence, language, created_at FROM transcripts WHERE (?1 IS NULL OR session_id = ?1) AND {} ORDER BY session_id, start_ms",
+            conditions.join(" AND ")
+        ))?;
+        let transcripts = stmt
+            .query_map(params_from_iter(values), transcript_from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(transcripts)
+    }
+
     /// Get detection events for session
     pub fn get_session_events(&self, session_id: &str) -> Result<Vec<DetectionEvent>, AppError> {
         let conn = self.get_conn()?;
@@ -198,6 +717,51 @@ impl Repository {
         Ok(events)
     }
 
+    /// Query detection events matching `filter`, oldest first (ties by id)
+    pub fn query_detection_events(&self, filter: &EventFilter) -> Result<Vec<DetectionEvent>, AppError> {
+        let (where_clause, mut values) = event_filter_clause(filter);
+        values.push(Value::Integer(filter.limit.unwrap_or(-1)));
+        values.push(Value::Integer(filter.offset.unwrap_or(0)));
+
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT id, session_id, event_type, timestamp, details, confidence, category, triggered_action FROM detection_events WHERE {} ORDER BY timestamp, id LIMIT ?{} OFFSET ?{}",
+            where_clause,
+            values.len() - 1,
+            values.len()
+        ))?;
+
+        let events = stmt
+            .query_map(params_from_iter(values), |row| {
+                Ok(DetectionEvent {
+                    id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    event_type: row.get(2)?,
+                    timestamp: row.get(3)?,
+                    details: row.get(4)?,
+                    confidence: row.get(5)?,
+                    category: row.get(6)?,
+                    triggered_action: row.get::<_, i32>(7)? != 0,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(events)
+    }
+
+    /// Count detection events matching `filter`, ignoring its limit and offset
+    pub fn count_detection_events(&self, filter: &EventFilter) -> Result<i64, AppError> {
+        let (where_clause, values) = event_filter_clause(filter);
+
+        let conn = self.get_conn()?;
+        let count = conn.query_row(
+            &format!("SELECT COUNT(*) FROM detection_events WHERE {}", where_clause),
+            params_from_iter(values),
+            |row| row.get(0),
+        )?;
+        Ok(count)
+    }
+
     // ========== Keywords ==========
 
     /// Get all active keywords
@@ -230,43 +794,1440 @@ impl Repository {
         let conn = self.get_conn()?;
         conn.execute(
             "INSERT INTO keywords (id, word, category, variations, mood, priority, is_active, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-            [
-                &keyword.id,
-                &keyword.word,
-                &keyword.category,
-                &keyword.variations.clone().unwrap_or_default(),
-                &keyword.mood.clone().unwrap_or_default(),
-                &keyword.priority.to_string(),
-                &(if keyword.is_active { 1 } else { 0 }).to_string(),
-                &keyword.created_at,
+            params![
+                keyword.id,
+                keyword.word,
+                keyword.category,
+                keyword.variations,
+                keyword.mood,
+                keyword.priority,
+                keyword.is_active,
+                keyword.created_at,
             ],
         )?;
         Ok(())
     }
 
-    // ========== Settings ==========
-
-    /// Get a setting
-    pub fn get_setting(&self, key: &str) -> Result<Option<String>, AppError> {
-        let conn = self.get_conn()?;
-        let value = conn
-            .query_row(
-                "SELECT value FROM settings WHERE key = ?1",
-                [key],
-                |row| row.get(0),
-            )
-            .ok();
-        Ok(value)
-    }
-
-    /// Set a setting
-    pub fn set_setting(&self, key: &str, value: &str) -> Result<(), AppError> {
+    /// Get every keyword, active or not
+    pub fn get_all_keywords(&self) -> Result<Vec<Keyword>, AppError> {
         let conn = self.get_conn()?;
-        let updated_at = chrono::Utc::now().to_rfc3339();
-        conn.execute(
-            "INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?1, ?2, ?3)",
-            [key, value, &updated_at],
+        let mut stmt = conn.prepare(
+            "SELECT id, word, category, variations, mood, priority, is_active, created_at FROM keywords ORDER BY category, word"
+        )?;
+
+        let keywords = stmt
+            .query_map([], |row| {
+                Ok(Keyword {
+                    id: row.get(0)?,
+                    word: row.get(1)?,
+                    category: row.get(2)?,
+                    variations: row.get(3)?,
+                    mood: row.get(4)?,
+                    priority: row.get(5)?,
+                    is_active: row.get::<_, i32>(6)? != 0,
+                    created_at: row.get(7)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(keywords)
+    }
+
+    /// Update a keyword's fields; returns false if the id is unknown
+    pub fn update_keyword(&self, keyword: &Keyword) -> Result<bool, AppError> {
+        let conn = self.get_conn()?;
+        let updated = conn.execute(
+            "UPDATE keywords SET word = ?1, category = ?2, variations = ?3, mood = ?4, priority = ?5, is_active = ?6 WHERE id = ?7",
+            params![
+                keyword.word,
+                keyword.category,
+                keyword.variations,
+                keyword.mood,
+                keyword.priority,
+                keyword.is_active,
+                keyword.id,
+            ],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Enable or disable a keyword; returns false if the id is unknown
+    pub fn set_keyword_active(&self, id: &str, active: bool) -> Result<bool, AppError> {
+        let conn = self.get_conn()?;
+        let updated = conn.execute(
+            "UPDATE keywords SET is_active = ?1 WHERE id = ?2",
+            params![active, id],
+        )?;
+        Ok(updated > 0)
+    }
+
+    /// Delete a keyword; returns false if the id is unknown
+    pub fn delete_keyword(&self, id: &str) -> Result<bool, AppError> {
+        let conn = self.get_conn()?;
+        let deleted = conn.execute("DELETE FROM keywords WHERE id = ?1", [id])?;
+        Ok(deleted > 0)
+    }
+
+    /// Replace every keyword with `keywords` in one transaction
+    pub fn replace_keywords(&self, keywords: &[Keyword]) -> Result<(), AppError> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        tx.execute("DELETE FROM keywords", [])?;
+        for keyword in keywords {
+            tx.execute(
+                "INSERT INTO keywords (id, word, category, variations, mood, priority, is_active, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    keyword.id,
+                    keyword.word,
+                    keyword.category,
+                    keyword.variations,
+                    keyword.mood,
+                    keyword.priority,
+                    keyword.is_active,
+                    keyword.created_at,
+                ],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    // ========== Keyword Genre Mappings ==========
+
+    /// Get the highest-priority genre mapped to a keyword category
+    pub fn get_genre_for_category(&self, category: &str) -> Result<Option<String>, AppError> {
+        let conn = self.get_conn()?;
+        let genre = conn
+            .query_row(
+                "SELECT genre_name FROM keyword_genre_mappings WHERE keyword_category = ?1 ORDER BY priority DESC, genre_name LIMIT 1",
+                [category],
+                |row| row.get(0),
+            )
+            .ok();
+        Ok(genre)
+    }
+
+    /// Get all keyword genre mappings
+    pub fn get_genre_mappings(&self) -> Result<Vec<KeywordGenreMapping>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT keyword_category, genre_name, priority, created_at FROM keyword_genre_mappings ORDER BY keyword_category, priority DESC"
+        )?;
+
+        let mappings = stmt
+            .query_map([], |row| {
+                Ok(KeywordGenreMapping {
+                    keyword_category: row.get(0)?,
+                    genre_name: row.get(1)?,
+                    priority: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(mappings)
+    }
+
+    /// Insert or update a keyword genre mapping
+    pub fn upsert_genre_mapping(&self, mapping: &KeywordGenreMapping) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO keyword_genre_mappings (keyword_category, genre_name, priority, created_at) VALUES (?1, ?2, ?3, ?4)",
+            [
+                &mapping.keyword_category,
+                &mapping.genre_name,
+                &mapping.priority.to_string(),
+                &mapping.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a keyword genre mapping
+    pub fn delete_genre_mapping(&self, category: &str, genre: &str) -> Result<bool, AppError> {
+        let conn = self.get_conn()?;
+        let deleted = conn.execute(
+            "DELETE FROM keyword_genre_mappings WHERE keyword_category = ?1 AND genre_name = ?2",
+            [category, genre],
+        )?;
+        Ok(deleted > 0)
+    }
+
+    // ========== Mood Mappings ==========
+
+    /// Get all mood mappings
+    pub fn get_mood_mappings(&self) -> Result<Vec<MoodMapping>, AppError> {
+        self.query_mood_mappings(
+            "SELECT id, mood, track_id, genre, priority, sfx_id, created_at FROM mood_mappings ORDER BY mood, priority DESC, created_at",
+            [],
+        )
+    }
+
+    /// Get the mappings for one mood, highest priority first
+    pub fn get_mood_mappings_for(&self, mood: &str) -> Result<Vec<MoodMapping>, AppError> {
+        self.query_mood_mappings(
+            "SELECT id, mood, track_id, genre, priority, sfx_id, created_at FROM mood_mappings WHERE mood = ?1 ORDER BY priority DESC, created_at",
+            [mood],
+        )
+    }
+
+    fn query_mood_mappings<P: rusqlite::Params>(&self, sql: &str, params: P) -> Result<Vec<MoodMapping>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(sql)?;
+
+        let mappings = stmt
+            .query_map(params, |row| {
+                Ok(MoodMapping {
+                    id: row.get(0)?,
+                    mood: row.get(1)?,
+                    track_id: row.get(2)?,
+                    genre: row.get(3)?,
+                    priority: row.get(4)?,
+                    sfx_id: row.get(5)?,
+                    created_at: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(mappings)
+    }
+
+    /// Insert a mood mapping, or replace the one with the same id
+    pub fn upsert_mood_mapping(&self, mapping: &MoodMapping) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO mood_mappings (id, mood, track_id, genre, priority, sfx_id, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                mapping.id,
+                mapping.mood,
+                mapping.track_id,
+                mapping.genre,
+                mapping.priority,
+                mapping.sfx_id,
+                mapping.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Delete a mood mapping
+    pub fn delete_mood_mapping(&self, id: &str) -> Result<bool, AppError> {
+        let conn = self.get_conn()?;
+        let deleted = conn.execute("DELETE FROM mood_mappings WHERE id = ?1", [id])?;
+        Ok(deleted > 0)
+    }
+
+    // ========== Crossfade Overrides ==========
+
+    /// Get the whole genre-to-genre crossfade matrix
+    pub fn get_crossfade_overrides(&self) -> Result<Vec<CrossfadeOverride>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT from_genre, to_genre, crossfade_type, updated_at FROM crossfade_overrides ORDER BY from_genre, to_genre"
+        )?;
+
+        let overrides = stmt
+            .query_map([], |row| {
+                Ok(CrossfadeOverride {
+                    from_genre: row.get(0)?,
+                    to_genre: row.get(1)?,
+                    crossfade_type: row.get(2)?,
+                    updated_at: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(overrides)
+    }
+
+    /// Insert or update the crossfade used between two genres
+    pub fn set_crossfade_override(&self, entry: &CrossfadeOverride) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO crossfade_overrides (from_genre, to_genre, crossfade_type, updated_at) VALUES (?1, ?2, ?3, ?4)",
+            [
+                &entry.from_genre,
+                &entry.to_genre,
+                &entry.crossfade_type,
+                &entry.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    // ========== Track Stats ==========
+
+    /// Count a play of a track starting at `started_at`
+    pub fn record_track_play(&self, track_id: &str, started_at: &str) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "INSERT INTO track_stats (track_id, play_count, total_played_ms, last_played_at) VALUES (?1, 1, 0, ?2)
+             ON CONFLICT(track_id) DO UPDATE SET play_count = play_count + 1, last_played_at = excluded.last_played_at",
+            [track_id, started_at],
+        )?;
+        Ok(())
+    }
+
+    /// Add listening time to a track
+    pub fn add_track_played_time(&self, track_id: &str, played_ms: i64) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "UPDATE track_stats SET total_played_ms = total_played_ms + ?1 WHERE track_id = ?2",
+            params![played_ms, track_id],
+        )?;
+        Ok(())
+    }
+
+    /// Get a track's play statistics (None if it was never played)
+    pub fn get_track_stats(&self, track_id: &str) -> Result<Option<TrackStats>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT track_id, play_count, total_played_ms, last_played_at FROM track_stats WHERE track_id = ?1"
+        )?;
+
+        let stats = stmt
+            .query_row([track_id], |row| {
+                Ok(TrackStats {
+                    track_id: row.get(0)?,
+                    play_count: row.get(1)?,
+                    total_played_ms: row.get(2)?,
+                    last_played_at: row.get(3)?,
+                })
+            })
+            .ok();
+
+        Ok(stats)
+    }
+
+    /// Get the most played tracks, most recently played first on ties
+    pub fn get_top_tracks(&self, limit: i64) -> Result<Vec<TrackStats>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT track_id, play_count, total_played_ms, last_played_at FROM track_stats ORDER BY play_count DESC, last_played_at DESC LIMIT ?1"
+        )?;
+
+        let stats = stmt
+            .query_map([limit], |row| {
+                Ok(TrackStats {
+                    track_id: row.get(0)?,
+                    play_count: row.get(1)?,
+                    total_played_ms: row.get(2)?,
+                    last_played_at: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(stats)
+    }
+
+    // ========== Playlists ==========
+
+    /// Get all playlists
+    pub fn get_playlists(&self) -> Result<Vec<Playlist>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare("SELECT id, name, description, created_at FROM playlists ORDER BY name")?;
+
+        let playlists = stmt
+            .query_map([], |row| {
+                Ok(Playlist {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    description: row.get(2)?,
+                    created_at: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(playlists)
+    }
+
+    /// Create an empty playlist
+    pub fn create_playlist(&self, playlist: &Playlist) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "INSERT INTO playlists (id, name, description, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![playlist.id, playlist.name, playlist.description, playlist.created_at],
         )?;
         Ok(())
     }
+
+    /// Append a track to the end of a playlist, returning its position
+    pub fn add_track_to_playlist(&self, playlist_id: &str, track_id: &str) -> Result<i64, AppError> {
+        let conn = self.get_conn()?;
+        let position: i64 = conn.query_row(
+            "SELECT COALESCE(MAX(position) + 1, 0) FROM playlist_tracks WHERE playlist_id = ?1",
+            [playlist_id],
+            |row| row.get(0),
+        )?;
+
+        conn.execute(
+            "INSERT INTO playlist_tracks (playlist_id, track_id, position, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![playlist_id, track_id, position, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(position)
+    }
+
+    /// Remove a track and close the gap it leaves; returns false if it wasn't in the playlist
+    pub fn remove_track_from_playlist(&self, playlist_id: &str, track_id: &str) -> Result<bool, AppError> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        let position: Option<i64> = tx
+            .query_row(
+                "SELECT position FROM playlist_tracks WHERE playlist_id = ?1 AND track_id = ?2",
+                [playlist_id, track_id],
+                |row| row.get(0),
+            )
+            .ok();
+        let Some(position) = position else {
+            return Ok(false);
+        };
+
+        tx.execute(
+            "DELETE FROM playlist_tracks WHERE playlist_id = ?1 AND track_id = ?2",
+            [playlist_id, track_id],
+        )?;
+        // Shift through negative positions so the unique index never sees a duplicate
+        tx.execute(
+            "UPDATE playlist_tracks SET position = -position WHERE playlist_id = ?1 AND position > ?2",
+            params![playlist_id, position],
+        )?;
+        tx.execute(
+            "UPDATE playlist_tracks SET position = -position - 1 WHERE playlist_id = ?1 AND position < 0",
+            [playlist_id],
+        )?;
+
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Put a playlist's tracks in the given order
+    ///
+    /// `track_ids` must contain exactly the tracks already in the playlist.
+    pub fn reorder_playlist(&self, playlist_id: &str, track_ids: &[String]) -> Result<(), AppError> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        let count: i64 = tx.query_row(
+            "SELECT COUNT(*) FROM playlist_tracks WHERE playlist_id = ?1",
+            [playlist_id],
+            |row| row.get(0),
+        )?;
+        if count != track_ids.len() as i64 {
+            return Err(AppError::Database(format!(
+                "Playlist has {} tracks but {} were given",
+                count,
+                track_ids.len()
+            )));
+        }
+
+        for (index, track_id) in track_ids.iter().enumerate() {
+            let updated = tx.execute(
+                "UPDATE playlist_tracks SET position = ?1 WHERE playlist_id = ?2 AND track_id = ?3",
+                params![-(index as i64) - 1, playlist_id, track_id],
+            )?;
+            if updated == 0 {
+                return Err(AppError::NotFound(format!("Track {} is not in the playlist", track_id)));
+            }
+        }
+        tx.execute(
+            "UPDATE playlist_tracks SET position = -position - 1 WHERE playlist_id = ?1",
+            [playlist_id],
+        )?;
+
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Get a playlist's tracks in order, skipping tracks removed from the library
+    pub fn get_playlist_tracks(&self, playlist_id: &str) -> Result<Vec<Track>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT t.id, t.name, t.file_path, t.duration_ms, t.genre, t.mood, t.is_looping, t.volume, t.created_at, t.updated_at, t.import_warnings, t.bpm FROM playlist_tracks p JOIN tracks t ON t.id = p.track_id WHERE p.playlist_id = ?1 AND t.deleted_at IS NULL ORDER BY p.position"
+        )?;
+
+        let tracks = stmt.query_map([playlist_id], track_from_row)?.collect::<Result<Vec<_>, _>>()?;
+
+        Ok(tracks)
+    }
+
+    // ========== Voice Profiles ==========
+
+    /// Insert a voice profile (refused unless consent was given)
+    pub fn insert_voice_profile(&self, profile: &VoiceProfile) -> Result<(), AppError> {
+        crate::profile::require_consent(&profile.id, profile.consent_given)?;
+
+        let conn = self.get_conn()?;
+        insert_voice_profile_in(&conn, profile)
+    }
+
+    /// Store an enrollment and make its profile the default, in one transaction
+    ///
+    /// A new profile's row is inserted; re-enrolling an existing profile
+    /// replaces its averaged embedding and passage embeddings. `write_files`
+    /// runs before the commit, so the database is rolled back if it fails.
+    /// Returns false if the profile to re-enroll is missing or has no consent.
+    pub fn save_voice_enrollment(
+        &self,
+        profile: &VoiceProfile,
+        embeddings: &[Vec<u8>],
+        existing: bool,
+        write_files: impl FnOnce() -> Result<(), AppError>,
+    ) -> Result<bool, AppError> {
+        crate::profile::require_consent(&profile.id, profile.consent_given)?;
+
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+        if existing {
+            let embedding = profile.embedding.as_deref().unwrap_or_default();
+            if !update_voice_profile_embedding_in(&tx, &profile.id, embedding)? {
+                return Ok(false);
+            }
+            tx.execute(
+                "DELETE FROM voice_profile_embeddings WHERE profile_id = ?1",
+                [&profile.id],
+            )?;
+        } else {
+            insert_voice_profile_in(&tx, profile)?;
+        }
+        for (index, embedding) in embeddings.iter().enumerate() {
+            add_voice_profile_embedding_in(&tx, &profile.id, index, embedding)?;
+        }
+        set_default_voice_profile_in(&tx, &profile.id)?;
+
+        write_files()?;
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Get the default voice profile
+    pub fn get_default_voice_profile(&self) -> Result<Option<VoiceProfile>, AppError> {
+        let conn = self.get_conn()?;
+        let profile = conn
+            .query_row(
+                "SELECT id, name, embedding, is_default, consent_given, created_at, updated_at FROM voice_profiles WHERE is_default = 1 ORDER BY updated_at DESC LIMIT 1",
+                [],
+                |row| {
+                    Ok(VoiceProfile {
+                        id: row.get(0)?,
+                        name: row.get(1)?,
+                        embedding: row.get(2)?,
+                        is_default: row.get::<_, i32>(3)? != 0,
+                        consent_given: row.get::<_, i32>(4)? != 0,
+                        created_at: row.get(5)?,
+                        updated_at: row.get(6)?,
+                    })
+                },
+            )
+            .ok();
+        Ok(profile)
+    }
+
+    /// Get all voice profiles, oldest first
+    pub fn get_voice_profiles(&self) -> Result<Vec<VoiceProfile>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, name, embedding, is_default, consent_given, created_at, updated_at FROM voice_profiles ORDER BY created_at, id"
+        )?;
+
+        let profiles = stmt
+            .query_map([], |row| {
+                Ok(VoiceProfile {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    embedding: row.get(2)?,
+                    is_default: row.get::<_, i32>(3)? != 0,
+                    consent_given: row.get::<_, i32>(4)? != 0,
+                    created_at: row.get(5)?,
+                    updated_at: row.get(6)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(profiles)
+    }
+
+    /// Make a profile the default, clearing the flag on all others
+    ///
+    /// Returns false, leaving the current default untouched, if the profile
+    /// does not exist.
+    pub fn set_default_voice_profile(&self, profile_id: &str) -> Result<bool, AppError> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+        if !set_default_voice_profile_in(&tx, profile_id)? {
+            return Ok(false);
+        }
+        tx.commit()?;
+        Ok(true)
+    }
+
+    /// Replace the averaged embedding of a profile that has consent
+    pub fn update_voice_profile_embedding(&self, profile_id: &str, embedding: &[u8]) -> Result<bool, AppError> {
+        let conn = self.get_conn()?;
+        update_voice_profile_embedding_in(&conn, profile_id, embedding)
+    }
+
+    /// Store one enrollment embedding for a profile
+    pub fn add_voice_profile_embedding(
+        &self,
+        profile_id: &str,
+        passage_index: usize,
+        embedding: &[u8],
+    ) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        add_voice_profile_embedding_in(&conn, profile_id, passage_index, embedding)
+    }
+
+    /// Get all enrollment embeddings for a profile, in passage order
+    pub fn get_voice_profile_embeddings(&self, profile_id: &str) -> Result<Vec<Vec<u8>>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT embedding FROM voice_profile_embeddings WHERE profile_id = ?1 ORDER BY passage_index, id"
+        )?;
+
+        let embeddings = stmt
+            .query_map([profile_id], |row| row.get(0))?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(embeddings)
+    }
+
+    /// Revoke consent: drop all embedding data but keep the profile row
+    ///
+    /// The revocation is recorded in the audit log, along with the outcome of
+    /// deleting the profile's files, which the caller does beforehand.
+    pub fn revoke_voice_profile_consent(&self, profile_id: &str, file_error: Option<&str>) -> Result<bool, AppError> {
+        let mut conn = self.get_conn()?;
+        // Overwrite the freed pages so the embeddings don't linger in the file
+        conn.pragma_update(None, "secure_delete", true)?;
+        let revoked = Self::revoke_consent_in(&mut conn, profile_id, file_error);
+        conn.pragma_update(None, "secure_delete", false)?;
+        let revoked = revoked?;
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(revoked)
+    }
+
+    fn revoke_consent_in(
+        conn: &mut rusqlite::Connection,
+        profile_id: &str,
+        file_error: Option<&str>,
+    ) -> Result<bool, AppError> {
+        let tx = conn.transaction()?;
+        let now = chrono::Utc::now().to_rfc3339();
+
+        let updated = tx.execute(
+            "UPDATE voice_profiles SET embedding = NULL, consent_given = 0, updated_at = ?2 WHERE id = ?1",
+            params![profile_id, now],
+        )?;
+        let embeddings = tx.execute(
+            "DELETE FROM voice_profile_embeddings WHERE profile_id = ?1",
+            [profile_id],
+        )?;
+        if updated > 0 {
+            let details = match file_error {
+                None => format!("embeddings deleted: {}, files deleted", embeddings),
+                Some(e) => format!("embeddings deleted: {}, file deletion failed: {}", embeddings, e),
+            };
+            tx.execute(
+                "INSERT INTO audit_log (action, subject_id, details, created_at) VALUES ('consent_revoked', ?1, ?2, ?3)",
+                params![profile_id, details, now],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(updated > 0)
+    }
+
+    /// Get audit log entries, newest first
+    pub fn get_audit_log(&self, limit: usize) -> Result<Vec<AuditEntry>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, action, subject_id, details, created_at FROM audit_log ORDER BY id DESC LIMIT ?1",
+        )?;
+
+        let entries = stmt
+            .query_map([limit as i64], |row| {
+                Ok(AuditEntry {
+                    id: row.get(0)?,
+                    action: row.get(1)?,
+                    subject_id: row.get(2)?,
+                    details: row.get(3)?,
+                    created_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(entries)
+    }
+
+    /// Delete a voice profile with its embeddings
+    ///
+    /// Deleting the default profile promotes the most recently updated one
+    /// left, if any.
+    pub fn delete_voice_profile(&self, profile_id: &str) -> Result<bool, AppError> {
+        let mut conn = self.get_conn()?;
+        let tx = conn.transaction()?;
+
+        let was_default = tx
+            .query_row(
+                "SELECT is_default FROM voice_profiles WHERE id = ?1",
+                [profile_id],
+                |row| row.get::<_, i32>(0),
+            )
+            .map(|d| d != 0)
+            .unwrap_or(false);
+
+        let deleted = tx.execute("DELETE FROM voice_profiles WHERE id = ?1", [profile_id])?;
+        if was_default {
+            tx.execute(
+                "UPDATE voice_profiles SET is_default = 1 WHERE id = (SELECT id FROM voice_profiles ORDER BY updated_at DESC LIMIT 1)",
+                [],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(deleted > 0)
+    }
+
+    /// Store the calibrated speaker threshold for a profile
+    pub fn set_voice_profile_threshold(&self, profile_id: &str, threshold: f32) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        conn.execute(
+            "UPDATE voice_profiles SET speaker_threshold = ?2, updated_at = ?3 WHERE id = ?1",
+            params![profile_id, threshold as f64, chrono::Utc::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    /// Get the calibrated speaker threshold for a profile
+    pub fn get_voice_profile_threshold(&self, profile_id: &str) -> Result<Option<f32>, AppError> {
+        let conn = self.get_conn()?;
+        let threshold = conn
+            .query_row(
+                "SELECT speaker_threshold FROM voice_profiles WHERE id = ?1",
+                [profile_id],
+                |row| row.get::<_, Option<f64>>(0),
+            )
+            .ok()
+            .flatten();
+        Ok(threshold.map(|t| t as f32))
+    }
+
+    // ========== Settings ==========
+
+    /// Get a setting
+    pub fn get_setting(&self, key: &str) -> Result<Option<String>, AppError> {
+        let conn = self.get_conn()?;
+        let value = conn
+            .query_row(
+                "SELECT value FROM settings WHERE key = ?1",
+                [key],
+                |row| row.get(0),
+            )
+            .ok();
+        Ok(value)
+    }
+
+    /// Set a setting
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        let updated_at = chrono::Utc::now().to_rfc3339();
+        conn.execute(
+            "INSERT OR REPLACE INTO settings (key, value, updated_at) VALUES (?1, ?2, ?3)",
+            [key, value, &updated_at],
+        )?;
+        Ok(())
+    }
+
+    /// Get every stored setting
+    pub fn get_all_settings(&self) -> Result<HashMap<String, String>, AppError> {
+        let conn = self.get_conn()?;
+        let mut stmt = conn.prepare("SELECT key, value FROM settings")?;
+
+        let settings = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+        Ok(settings)
+    }
+
+    // ========== Maintenance ==========
+
+    /// Size of the database in bytes
+    pub fn database_size(&self) -> Result<u64, AppError> {
+        let conn = self.get_conn()?;
+        let size: i64 = conn.query_row(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(size as u64)
+    }
+
+    /// Fold the WAL back into the database and rebuild it without free pages
+    pub fn vacuum(&self) -> Result<(), AppError> {
+        let conn = self.get_conn()?;
+        conn.execute_batch("PRAGMA wal_checkpoint(FULL); VACUUM;")?;
+        Ok(())
+    }
+
+    /// Run SQLite's integrity check; true if it found no problems
+    pub fn integrity_check(&self) -> Result<bool, AppError> {
+        let conn = self.get_conn()?;
+        let result: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+        if result != "ok" {
+            tracing::warn!("Database integrity check failed: {}", result);
+        }
+        Ok(result == "ok")
+    }
+}
+
+/// Map a `transcripts` row selected in column order
+fn event_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<DetectionEvent> {
+    Ok(DetectionEvent {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        event_type: row.get(2)?,
+        timestamp: row.get(3)?,
+        details: row.get(4)?,
+        confidence: row.get(5)?,
+        category: row.get(6)?,
+        triggered_action: row.get::<_, i32>(7)? != 0,
+    })
+}
+
+fn transcript_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Transcript> {
+    Ok(Transcript {
+        id: row.get(0)?,
+        session_id: row.get(1)?,
+        start_ms: row.get(2)?,
+        end_ms: row.get(3)?,
+        text: row.get(4)?,
+        confidence: row.get(5)?,
+        language: row.get(6)?,
+        created_at: row.get(7)?,
+    })
+}
+
+/// Escape LIKE wildcards so user input is matched literally (escape char `\`)
+fn escape_like(input: &str) -> String {
+    input.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+/// Quote each word so user input is matched literally by FTS5
+fn fts_query(input: &str) -> String {
+    input
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// WHERE clause and its bound values for an event filter
+///
+/// Only placeholders are interpolated; every value is bound.
+fn event_filter_clause(filter: &EventFilter) -> (String, Vec<Value>) {
+    let mut conditions = vec!["1 = 1".to_string()];
+    let mut values = Vec::new();
+    let mut condition = |sql: &str, value: Value| {
+        values.push(value);
+        conditions.push(sql.replace('?', &format!("?{}", values.len())));
+    };
+
+    if let Some(session_id) = &filter.session_id {
+        condition("session_id = ?", Value::Text(session_id.clone()));
+    }
+    if let Some(event_type) = &filter.event_type {
+        condition("event_type = ?", Value::Text(event_type.clone()));
+    }
+    if let Some(category) = &filter.category {
+        condition("category = ?", Value::Text(category.clone()));
+    }
+    // Events without a confidence never match a confidence bound
+    if let Some(min) = filter.min_confidence {
+        condition("typeof(confidence) = 'real' AND confidence >= ?", Value::Real(min));
+    }
+    if let Some(max) = filter.max_confidence {
+        condition("typeof(confidence) = 'real' AND confidence <= ?", Value::Real(max));
+    }
+    if let Some(since) = &filter.since {
+        condition("timestamp >= ?", Value::Text(since.clone()));
+    }
+    if let Some(until) = &filter.until {
+        condition("timestamp <= ?", Value::Text(until.clone()));
+    }
+
+    (conditions.join(" AND "), values)
+}
+
+fn insert_voice_profile_in(conn: &rusqlite::Connection, profile: &VoiceProfile) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO voice_profiles (id, name, embedding, is_default, consent_given, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            profile.id,
+            profile.name,
+            profile.embedding,
+            profile.is_default as i32,
+            profile.consent_given as i32,
+            profile.created_at,
+            profile.updated_at,
+        ],
+    )?;
+    Ok(())
+}
+
+fn set_default_voice_profile_in(conn: &rusqlite::Connection, profile_id: &str) -> Result<bool, AppError> {
+    let now = chrono::Utc::now().to_rfc3339();
+    let updated = conn.execute(
+        "UPDATE voice_profiles SET is_default = 1, updated_at = ?2 WHERE id = ?1",
+        params![profile_id, now],
+    )?;
+    if updated == 0 {
+        return Ok(false);
+    }
+    conn.execute(
+        "UPDATE voice_profiles SET is_default = 0, updated_at = ?2 WHERE id != ?1 AND is_default = 1",
+        params![profile_id, now],
+    )?;
+    Ok(true)
+}
+
+fn update_voice_profile_embedding_in(
+    conn: &rusqlite::Connection,
+    profile_id: &str,
+    embedding: &[u8],
+) -> Result<bool, AppError> {
+    let updated = conn.execute(
+        "UPDATE voice_profiles SET embedding = ?2, updated_at = ?3 WHERE id = ?1 AND consent_given = 1",
+        params![profile_id, embedding, chrono::Utc::now().to_rfc3339()],
+    )?;
+    Ok(updated > 0)
+}
+
+fn add_voice_profile_embedding_in(
+    conn: &rusqlite::Connection,
+    profile_id: &str,
+    passage_index: usize,
+    embedding: &[u8],
+) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO voice_profile_embeddings (profile_id, passage_index, embedding, created_at) VALUES (?1, ?2, ?3, ?4)",
+        params![
+            profile_id,
+            passage_index as i64,
+            embedding,
+            chrono::Utc::now().to_rfc3339(),
+        ],
+    )?;
+    Ok(())
+}
+
+/// Map a row selecting `id, name, file_path, duration_ms, genre, mood,
+/// is_looping, volume, created_at, updated_at, import_warnings, bpm`
+fn track_from_row(row: &rusqlite::Row) -> rusqlite::Result<Track> {
+    Ok(Track {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        file_path: row.get(2)?,
+        duration_ms: row.get(3)?,
+        genre: row.get(4)?,
+        mood: row.get(5)?,
+        is_looping: row.get::<_, i32>(6)? != 0,
+        volume: row.get(7)?,
+        bpm: row.get(11)?,
+        created_at: row.get(8)?,
+        updated_at: row.get(9)?,
+        import_warnings: parse_import_warnings(row.get(10)?),
+    })
+}
+
+/// Decode the JSON `import_warnings` column (NULL means no warnings)
+fn parse_import_warnings(json: Option<String>) -> Vec<String> {
+    json.and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    fn test_repo() -> Repository {
+        let db = Database::in_memory().unwrap();
+        Repository::new(db.pool().clone())
+    }
+
+    #[test]
+    fn test_track_stats_accumulate() {
+        let repo = test_repo();
+        for id in ["a", "b"] {
+            let mut track = Track::new(id.to_string(), id.to_uppercase(), format!("/music/{}.ogg", id));
+            track.duration_ms = Some(60_000);
+            repo.insert_track(&track).unwrap();
+        }
+        assert!(repo.get_track_stats("a").unwrap().is_none());
+
+        repo.record_track_play("a", "2024-01-01T10:00:00+00:00").unwrap();
+        repo.add_track_played_time("a", 30_000).unwrap();
+        repo.record_track_play("a", "2024-01-02T10:00:00+00:00").unwrap();
+        repo.add_track_played_time("a", 15_000).unwrap();
+        repo.record_track_play("b", "2024-01-03T10:00:00+00:00").unwrap();
+
+        let stats = repo.get_track_stats("a").unwrap().unwrap();
+        assert_eq!(stats.play_count, 2);
+        assert_eq!(stats.total_played_ms, 45_000);
+        assert_eq!(stats.last_played_at.as_deref(), Some("2024-01-02T10:00:00+00:00"));
+
+        let top: Vec<_> = repo.get_top_tracks(10).unwrap().into_iter().map(|s| s.track_id).collect();
+        assert_eq!(top, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_update_and_delete_track() {
+        let repo = test_repo();
+        let mut track = Track::new("a".to_string(), "A".to_string(), "/music/a.ogg".to_string());
+        track.duration_ms = Some(1000);
+        track.genre = Some("ambient".to_string());
+        track.mood = Some("calm".to_string());
+        repo.insert_track(&track).unwrap();
+
+        let update = TrackUpdate {
+            name: Some("Tavern".to_string()),
+            mood: Some(String::new()),
+            bpm: Some(96.0),
+            ..Default::default()
+        };
+        let updated = repo.update_track("a", &update).unwrap().unwrap();
+        assert_eq!(updated.name, "Tavern");
+        assert_eq!(updated.genre.as_deref(), Some("ambient"));
+        assert_eq!(updated.mood, None);
+        assert_eq!(updated.bpm, Some(96.0));
+        assert!(repo.update_track("missing", &update).unwrap().is_none());
+
+        assert!(!repo.genre_exists("folk").unwrap());
+        repo.insert_genre("folk").unwrap();
+        repo.insert_genre("folk").unwrap();
+        assert!(repo.genre_exists("folk").unwrap());
+
+        assert!(repo.delete_track("a").unwrap());
+        assert!(repo.get_track("a").unwrap().is_none());
+        assert!(!repo.delete_track("a").unwrap());
+
+        // Importing the file again brings the removed row back
+        assert!(repo.get_track_by_path("/music/a.ogg").unwrap().is_none());
+        assert_eq!(repo.reinstate_track_by_path("/music/a.ogg").unwrap().as_deref(), Some("a"));
+        assert_eq!(repo.get_track_by_path("/music/a.ogg").unwrap().unwrap().name, "Tavern");
+        assert!(repo.reinstate_track_by_path("/music/a.ogg").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_playlist_order() {
+        let repo = test_repo();
+        for id in ["a", "b", "c"] {
+            let mut track = Track::new(id.to_string(), id.to_uppercase(), format!("/music/{}.ogg", id));
+            track.duration_ms = Some(1000);
+            repo.insert_track(&track).unwrap();
+        }
+        repo.create_playlist(&Playlist::new("boss".to_string(), "Boss fight".to_string())).unwrap();
+
+        for id in ["a", "b", "c"] {
+            repo.add_track_to_playlist("boss", id).unwrap();
+        }
+        let ids = |repo: &Repository| -> Vec<String> {
+            repo.get_playlist_tracks("boss").unwrap().into_iter().map(|t| t.id).collect()
+        };
+        assert_eq!(ids(&repo), vec!["a", "b", "c"]);
+
+        repo.reorder_playlist("boss", &["c".to_string(), "a".to_string(), "b".to_string()]).unwrap();
+        assert_eq!(ids(&repo), vec!["c", "a", "b"]);
+        assert!(repo.reorder_playlist("boss", &["c".to_string()]).is_err());
+
+        assert!(repo.remove_track_from_playlist("boss", "c").unwrap());
+        assert!(!repo.remove_track_from_playlist("boss", "c").unwrap());
+        assert_eq!(ids(&repo), vec!["a", "b"]);
+        // Positions were compacted, so appending lands at the end
+        assert_eq!(repo.add_track_to_playlist("boss", "c").unwrap(), 2);
+    }
+
+    #[test]
+    fn test_genre_for_category() {
+        let repo = test_repo();
+        assert_eq!(repo.get_genre_for_category("combat").unwrap(), None);
+
+        repo.upsert_genre_mapping(&KeywordGenreMapping::new("combat".to_string(), "battle".to_string(), 1)).unwrap();
+        repo.upsert_genre_mapping(&KeywordGenreMapping::new("combat".to_string(), "boss".to_string(), 5)).unwrap();
+        assert_eq!(repo.get_genre_for_category("combat").unwrap().as_deref(), Some("boss"));
+
+        assert!(repo.delete_genre_mapping("combat", "boss").unwrap());
+        assert_eq!(repo.get_genre_for_category("combat").unwrap().as_deref(), Some("battle"));
+        assert_eq!(repo.get_genre_mappings().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_list_sessions_paginates_newest_first() {
+        let repo = test_repo();
+        for (id, started_at) in [("a", "2024-01-01T10:00:00+00:00"), ("b", "2024-01-02T10:00:00+00:00"), ("c", "2024-01-03T10:00:00+00:00")] {
+            let mut session = Session::new(id.to_string(), "autonomous".to_string());
+            session.started_at = started_at.to_string();
+            repo.start_session(&session).unwrap();
+        }
+        repo.end_session("a").unwrap();
+        repo.set_session_stats("a", 4, 2).unwrap();
+
+        assert_eq!(repo.count_sessions().unwrap(), 3);
+        let page: Vec<String> = repo.list_sessions(2, 1).unwrap().into_iter().map(|s| s.id).collect();
+        assert_eq!(page, vec!["b", "a"]);
+
+        let ended = repo.get_session("a").unwrap().unwrap();
+        assert_eq!(ended.keywords_triggered, Some(2));
+        assert!(ended.total_duration_ms.unwrap() > 0);
+    }
+
+    #[test]
+    fn test_delete_session_cascades() {
+        let repo = test_repo();
+        let recording = std::env::temp_dir().join(format!("session-{}.wav", uuid::Uuid::new_v4()));
+        std::fs::write(&recording, b"RIFF").unwrap();
+
+        repo.start_session(&Session::new("s1".to_string(), "autonomous".to_string())).unwrap();
+        repo.set_session_recording_path("s1", &recording.to_string_lossy()).unwrap();
+        repo.insert_detection_event(&DetectionEvent::new("e1".to_string(), "s1".to_string(), "keyword".to_string())).unwrap();
+
+        repo.delete_session("s1").unwrap();
+        assert!(repo.get_session("s1").unwrap().is_none());
+        assert!(repo.get_session_events("s1").unwrap().is_empty());
+        assert!(!recording.exists());
+        assert!(repo.delete_session("s1").is_err());
+    }
+
+    #[test]
+    fn test_search_events() {
+        let repo = test_repo();
+        for (id, session, details) in [("e1", "s1", "a red dragon attacks"), ("e2", "s2", "the dragon sleeps"), ("e3", "s2", "roll initiative")] {
+            repo.start_session(&Session::new(session.to_string(), "autonomous".to_string())).ok();
+            let mut event = DetectionEvent::new(id.to_string(), session.to_string(), "keyword".to_string());
+            event.details = Some(details.to_string());
+            event.confidence = Some(0.9);
+            event.category = Some("combat".to_string());
+            repo.insert_detection_event(&event).unwrap();
+        }
+
+        assert_eq!(repo.search_events("dragon", None).unwrap().len(), 2);
+        let in_s2 = repo.search_events("dragon", Some("s2")).unwrap();
+        assert_eq!(in_s2.len(), 1);
+        assert_eq!(in_s2[0].id, "e2");
+
+        // Operators and quotes are plain text, not syntax errors
+        assert!(repo.search_events("dragon\" OR", None).unwrap().is_empty());
+        assert!(repo.search_events("   ", None).unwrap().is_empty());
+
+        // Without the FTS5 index, events are still stored and matched as substrings
+        repo.get_conn()
+            .unwrap()
+            .execute_batch("DROP TABLE detection_events_fts;")
+            .unwrap();
+        let mut event = DetectionEvent::new("e4".to_string(), "s1".to_string(), "emotion".to_string());
+        event.details = Some("dragonfire".to_string());
+        repo.insert_detection_event(&event).unwrap();
+        assert_eq!(repo.search_events("dragon", Some("s1")).unwrap().len(), 2);
+        assert_eq!(repo.search_events("keyword sleeps", None).unwrap()[0].id, "e2");
+        repo.delete_session("s2").unwrap();
+        assert!(repo.search_events("sleeps", None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_query_detection_events() {
+        let repo = test_repo();
+        repo.start_session(&Session::new("s1".to_string(), "autonomous".to_string())).unwrap();
+        for (id, event_type, minute, confidence, category) in [
+            ("e3", "keyword", 2, 0.9, "creature"),
+            ("e1", "keyword", 1, 0.4, "creature"),
+            ("e2", "keyword", 1, 0.8, "creature"),
+            ("e4", "emotion", 3, 0.7, "fearful"),
+            ("e5", "keyword", 4, 0.95, "combat"),
+        ] {
+            let mut event = DetectionEvent::new(id.to_string(), "s1".to_string(), event_type.to_string());
+            event.timestamp = format!("2024-05-01T19:0{}:00+00:00", minute);
+            event.confidence = Some(confidence);
+            event.category = Some(category.to_string());
+            repo.insert_detection_event(&event).unwrap();
+        }
+
+        let ids = |filter: &EventFilter| -> Vec<String> {
+            repo.query_detection_events(filter).unwrap().into_iter().map(|e| e.id).collect()
+        };
+
+        // Same timestamp falls back to id order
+        assert_eq!(ids(&EventFilter::default()), vec!["e1", "e2", "e3", "e4", "e5"]);
+
+        let filter = EventFilter {
+            session_id: Some("s1".to_string()),
+            event_type: Some("keyword".to_string()),
+            category: Some("creature".to_string()),
+            min_confidence: Some(0.5),
+            until: Some("2024-05-01T19:03:00+00:00".to_string()),
+            ..EventFilter::default()
+        };
+        assert_eq!(ids(&filter), vec!["e2", "e3"]);
+        assert_eq!(repo.count_detection_events(&filter).unwrap(), 2);
+
+        let page = EventFilter {
+            limit: Some(2),
+            offset: Some(2),
+            ..EventFilter::default()
+        };
+        assert_eq!(ids(&page), vec!["e3", "e4"]);
+        assert_eq!(repo.count_detection_events(&page).unwrap(), 5);
+
+        // Values are bound, so quotes are just text
+        let hostile = EventFilter {
+            event_type: Some("keyword' OR '1'='1".to_string()),
+            ..EventFilter::default()
+        };
+        assert!(ids(&hostile).is_empty());
+    }
+
+    #[test]
+    fn test_vacuum_and_integrity_check() {
+        let repo = test_repo();
+        for i in 0..50 {
+            repo.set_setting(&format!("key{}", i), &"x".repeat(1000)).unwrap();
+        }
+        assert!(repo.database_size().unwrap() > 0);
+
+        repo.vacuum().unwrap();
+        assert!(repo.integrity_check().unwrap());
+    }
+
+    #[test]
+    fn test_session_recording_path() {
+        let repo = test_repo();
+        repo.start_session(&Session::new("s1".to_string(), "autonomous".to_string())).unwrap();
+        repo.set_session_recording_path("s1", "/tmp/s1.wav").unwrap();
+
+        let session = repo.get_session("s1").unwrap().unwrap();
+        assert_eq!(session.recording_path.as_deref(), Some("/tmp/s1.wav"));
+    }
+
+    #[test]
+    fn test_voice_profile_embeddings() {
+        let repo = test_repo();
+        let mut profile = VoiceProfile::new("gm".to_string(), "GM".to_string());
+        profile.consent_given = true;
+        repo.insert_voice_profile(&profile).unwrap();
+
+        repo.add_voice_profile_embedding("gm", 1, &[5, 6]).unwrap();
+        repo.add_voice_profile_embedding("gm", 0, &[1, 2, 3, 4]).unwrap();
+
+        let embeddings = repo.get_voice_profile_embeddings("gm").unwrap();
+        assert_eq!(embeddings, vec![vec![1, 2, 3, 4], vec![5, 6]]);
+    }
+
+    #[test]
+    fn test_voice_profile_consent() {
+        let repo = test_repo();
+        let mut profile = VoiceProfile::new("gm".to_string(), "GM".to_string());
+        profile.embedding = Some(vec![1, 2, 3, 4]);
+        assert!(matches!(repo.insert_voice_profile(&profile), Err(AppError::Profile(_))));
+
+        profile.consent_given = true;
+        repo.insert_voice_profile(&profile).unwrap();
+        repo.add_voice_profile_embedding("gm", 0, &[1, 2, 3, 4]).unwrap();
+
+        assert!(repo.revoke_voice_profile_consent("gm", None).unwrap());
+        assert!(repo.get_voice_profile_embeddings("gm").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_voice_enrollment_rolls_back_when_files_fail() {
+        let repo = test_repo();
+        let mut profile = VoiceProfile::new("gm".to_string(), "GM".to_string());
+        profile.consent_given = true;
+        profile.embedding = Some(vec![1, 2]);
+        let passages = vec![vec![1, 1], vec![3, 3]];
+
+        let failed = repo.save_voice_enrollment(&profile, &passages, false, || {
+            Err(AppError::Profile("disk full".to_string()))
+        });
+        assert!(failed.is_err());
+        assert!(repo.get_voice_profiles().unwrap().is_empty());
+        assert!(repo.get_voice_profile_embeddings("gm").unwrap().is_empty());
+
+        assert!(repo.save_voice_enrollment(&profile, &passages, false, || Ok(())).unwrap());
+        assert_eq!(repo.get_default_voice_profile().unwrap().unwrap().id, "gm");
+
+        // Re-enrolling replaces the embeddings of the same profile
+        profile.embedding = Some(vec![9, 9]);
+        assert!(repo.save_voice_enrollment(&profile, &[vec![7, 7]], true, || Ok(())).unwrap());
+        assert_eq!(repo.get_voice_profile_embeddings("gm").unwrap(), vec![vec![7, 7]]);
+        assert_eq!(repo.get_default_voice_profile().unwrap().unwrap().embedding, Some(vec![9, 9]));
+        profile.id = "missing".to_string();
+        assert!(!repo.save_voice_enrollment(&profile, &[], true, || Ok(())).unwrap());
+    }
+
+    #[test]
+    fn test_revoked_embeddings_leave_no_bytes_in_the_file() {
+        let path = std::env::temp_dir().join(format!("consent-{}.db", uuid::Uuid::new_v4()));
+        let marker: Vec<u8> = b"EMBEDDING-MARKER-".iter().copied().cycle().take(512).collect();
+        {
+            let db = Database::new(&path.to_string_lossy()).unwrap();
+            let repo = Repository::new(db.pool().clone());
+            let mut profile = VoiceProfile::new("gm".to_string(), "GM".to_string());
+            profile.embedding = Some(marker.clone());
+            profile.consent_given = true;
+            repo.insert_voice_profile(&profile).unwrap();
+            repo.add_voice_profile_embedding("gm", 0, &marker).unwrap();
+            assert!(repo.revoke_voice_profile_consent("gm", None).unwrap());
+        }
+
+        for file in [path.clone(), path.with_extension("db-wal")] {
+            if let Ok(bytes) = std::fs::read(&file) {
+                assert!(!bytes.windows(17).any(|w| w == b"EMBEDDING-MARKER-"), "{:?}", file);
+            }
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_default_voice_profile_is_exclusive() {
+        let repo = test_repo();
+        for (id, is_default) in [("first", true), ("second", true), ("third", false)] {
+            let mut profile = VoiceProfile::new(id.to_string(), id.to_uppercase());
+            profile.is_default = is_default;
+            profile.consent_given = true;
+            repo.insert_voice_profile(&profile).unwrap();
+        }
+
+        assert!(repo.set_default_voice_profile("third").unwrap());
+        let defaults: Vec<String> = repo
+            .get_voice_profiles()
+            .unwrap()
+            .into_iter()
+            .filter(|p| p.is_default)
+            .map(|p| p.id)
+            .collect();
+        assert_eq!(defaults, vec!["third"]);
+        assert!(!repo.set_default_voice_profile("missing").unwrap());
+        assert_eq!(repo.get_default_voice_profile().unwrap().unwrap().id, "third");
+
+        // Stored as a BLOB, and refused once consent is revoked
+        assert!(repo.update_voice_profile_embedding("third", &[9, 8, 7]).unwrap());
+        let kind: String = repo
+            .get_conn()
+            .unwrap()
+            .query_row("SELECT typeof(embedding) FROM voice_profiles WHERE id = 'third'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(kind, "blob");
+        assert_eq!(repo.get_default_voice_profile().unwrap().unwrap().embedding, Some(vec![9, 8, 7]));
+        repo.revoke_voice_profile_consent("third", None).unwrap();
+        assert!(!repo.update_voice_profile_embedding("third", &[1]).unwrap());
+    }
+
+    #[test]
+    fn test_search_transcripts() {
+        let repo = test_repo();
+        for session_id in ["s1", "s2"] {
+            repo.start_session(&Session::new(session_id.to_string(), "autonomous".to_string())).unwrap();
+        }
+        repo.insert_transcript(&Transcript::new("s1".to_string(), 0, 4000, "The lich rises".to_string()))
+            .unwrap();
+        repo.insert_transcript(&Transcript::new("s1".to_string(), 4000, 9000, "Roll for 100% damage".to_string()))
+            .unwrap();
+        repo.insert_transcript(&Transcript::new("s2".to_string(), 500, 3000, "A lich lair".to_string()))
+            .unwrap();
+
+        let hits = repo.search_transcripts("lich", None).unwrap();
+        assert_eq!(hits.iter().map(|t| t.start_ms).collect::<Vec<_>>(), vec![0, 500]);
+        assert_eq!(repo.search_transcripts("LICH", Some("s2")).unwrap()[0].text, "A lich lair");
+        assert!(repo.search_transcripts("lich dragon", None).unwrap().is_empty());
+
+        // Without the FTS5 index, the same words match as substrings
+        repo.get_conn()
+            .unwrap()
+            .execute_batch(
+                "DROP TRIGGER transcripts_fts_insert; DROP TRIGGER transcripts_fts_delete; DROP TABLE transcripts_fts;",
+            )
+            .unwrap();
+        assert_eq!(repo.search_transcripts("lich", Some("s1")).unwrap()[0].end_ms, 4000);
+        assert_eq!(repo.search_transcripts("100%", None).unwrap().len(), 1);
+        assert!(repo.search_transcripts("10_%", None).unwrap().is_empty());
+
+        repo.delete_session("s1").unwrap();
+        assert_eq!(repo.search_transcripts("lich", None).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_inserts_store_typed_values() {
+        let repo = test_repo();
+        let mut track = Track::new("a".to_string(), "A".to_string(), "/music/a.ogg".to_string());
+        track.volume = 0.75;
+        track.is_looping = true;
+        track.bpm = Some(120.0);
+        repo.insert_track(&track).unwrap();
+
+        let stored = repo.get_track("a").unwrap().unwrap();
+        assert_eq!(stored.duration_ms, None);
+        assert_eq!(stored.genre, None);
+        assert_eq!(stored.volume, 0.75);
+        assert!(stored.is_looping);
+        assert_eq!(stored.bpm, Some(120.0));
+
+        repo.start_session(&Session::new("s1".to_string(), "autonomous".to_string())).unwrap();
+        let mut event = DetectionEvent::new("e1".to_string(), "s1".to_string(), "emotion".to_string());
+        event.confidence = Some(0.5);
+        event.triggered_action = true;
+        repo.insert_detection_event(&event).unwrap();
+
+        let stored = repo.get_session_events("s1").unwrap().remove(0);
+        assert_eq!(stored.confidence, Some(0.5));
+        assert_eq!(stored.details, None);
+        assert_eq!(stored.category, None);
+        assert!(stored.triggered_action);
+
+        repo.insert_keyword(&Keyword::new("k1".to_string(), "dragon".to_string(), "creature".to_string()))
+            .unwrap();
+        let stored = repo.get_all_keywords().unwrap().remove(0);
+        assert_eq!(stored.variations, None);
+        assert_eq!(stored.mood, None);
+
+        let conn = repo.get_conn().unwrap();
+        let types: (String, String, String, String) = conn
+            .query_row(
+                "SELECT typeof(t.duration_ms), typeof(t.volume), typeof(e.confidence), typeof(e.triggered_action) FROM tracks t, detection_events e",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .unwrap();
+        assert_eq!(types, ("null".into(), "real".into(), "real".into(), "integer".into()));
+    }
+
+    #[test]
+    fn test_legacy_text_values_read_back() {
+        let db = Database::in_memory().unwrap();
+        db.rollback_to(16).unwrap();
+        let conn = db.pool().get().unwrap();
+        conn.execute(
+            "INSERT INTO tracks (id, name, file_path, duration_ms, genre, mood, is_looping, volume, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            ["a", "A", "/music/a.ogg", "", "", "calm", "1", "0.5", "", ""],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO tracks (id, name, file_path, duration_ms, genre, mood, is_looping, volume, created_at, updated_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            ["b", "B", "/music/b.ogg", "90000", "battle", "", "0", "1", "", ""],
+        )
+        .unwrap();
+        drop(conn);
+        db.run_migrations().unwrap();
+
+        let repo = Repository::new(db.pool().clone());
+        let a = repo.get_track("a").unwrap().unwrap();
+        assert_eq!((a.duration_ms, a.genre, a.mood.as_deref()), (None, None, Some("calm")));
+        assert!(a.is_looping);
+        assert_eq!(a.volume, 0.5);
+        let b = repo.get_track("b").unwrap().unwrap();
+        assert_eq!((b.duration_ms, b.mood), (Some(90_000), None));
+        assert!(!b.is_looping);
+    }
 }
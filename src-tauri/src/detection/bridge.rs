@@ -0,0 +1,139 @@
+//! Translation of pipeline events into frontend notifications
+//!
+//! `PipelineEvent` stays an internal type; the bridge turns the events the UI
+//! cares about into serializable payloads tagged with the session id and a
+//! monotonic timestamp, and picks the event name each one is emitted under.
+//! Emotion results arrive for every speech segment, so they are throttled.
+
+use crate::detection::pipeline::PipelineEvent;
+use serde::Serialize;
+
+/// Keyword matched in a transcription
+pub const KEYWORD_EVENT: &str = "detection://keyword";
+
+/// Emotion detected in a speech segment (throttled)
+pub const EMOTION_EVENT: &str = "detection://emotion";
+
+/// Keyword and emotion confirmed together
+pub const DUAL_SIGNAL_EVENT: &str = "detection://dual_signal";
+
+/// Speech started or ended
+pub const VOICE_ACTIVITY_EVENT: &str = "detection://voice_activity";
+
+/// Minimum gap between two emitted emotion events (at most 2 per second)
+pub const EMOTION_EVENT_INTERVAL_MS: u64 = 500;
+
+/// Detection event as sent to the frontend
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DetectionEventDto {
+    Keyword { keyword: String },
+    Emotion { emotion: String, confidence: f32 },
+    DualSignal { keyword: String, emotion: String },
+    VoiceActivity { active: bool, duration_ms: Option<u64> },
+}
+
+/// Payload of every `detection://` event
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectionPayload {
+    pub session_id: String,
+    /// Milliseconds since the bridge started, never going backwards
+    pub timestamp_ms: u64,
+    #[serde(flatten)]
+    pub event: DetectionEventDto,
+}
+
+/// Stateful translator from pipeline events to frontend payloads
+pub struct EventBridge {
+    session_id: String,
+    last_emotion_ms: Option<u64>,
+}
+
+impl EventBridge {
+    /// Create a bridge for one session
+    pub fn new(session_id: String) -> Self {
+        Self {
+            session_id,
+            last_emotion_ms: None,
+        }
+    }
+
+    /// Event name and payload for `event` at `now_ms`, or None if the UI
+    /// doesn't need it or it was throttled
+    pub fn translate(&mut self, event: PipelineEvent, now_ms: u64) -> Option<(&'static str, DetectionPayload)> {
+        let (name, event) = match event {
+            PipelineEvent::Keyword(keyword) => (KEYWORD_EVENT, DetectionEventDto::Keyword { keyword }),
+            PipelineEvent::Emotion(emotion, confidence) => {
+                if let Some(last) = self.last_emotion_ms {
+                    if now_ms.saturating_sub(last) < EMOTION_EVENT_INTERVAL_MS {
+                        return None;
+                    }
+                }
+                self.last_emotion_ms = Some(now_ms);
+                (EMOTION_EVENT, DetectionEventDto::Emotion { emotion, confidence })
+            }
+            PipelineEvent::DualSignal { keyword, emotion, .. } => {
+                (DUAL_SIGNAL_EVENT, DetectionEventDto::DualSignal { keyword, emotion })
+            }
+            PipelineEvent::VoiceStart(_) => (
+                VOICE_ACTIVITY_EVENT,
+                DetectionEventDto::VoiceActivity {
+                    active: true,
+                    duration_ms: None,
+                },
+            ),
+            PipelineEvent::VoiceEnd { start_ms, end_ms } => (
+                VOICE_ACTIVITY_EVENT,
+                DetectionEventDto::VoiceActivity {
+                    active: false,
+                    duration_ms: Some(end_ms.saturating_sub(start_ms)),
+                },
+            ),
+            _ => return None,
+        };
+
+        Some((
+            name,
+            DetectionPayload {
+                session_id: self.session_id.clone(),
+                timestamp_ms: now_ms,
+                event,
+            },
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_emotion_events_are_throttled() {
+        let mut bridge = EventBridge::new("s1".to_string());
+        let emotion = || PipelineEvent::Emotion("happy".to_string(), 0.8);
+
+        assert!(bridge.translate(emotion(), 0).is_some());
+        assert!(bridge.translate(emotion(), 200).is_none());
+        assert!(bridge.translate(emotion(), 499).is_none());
+        assert!(bridge.translate(emotion(), 500).is_some());
+
+        // Other events are never throttled
+        assert!(bridge.translate(PipelineEvent::Keyword("dragon".to_string()), 510).is_some());
+        assert!(bridge.translate(PipelineEvent::Keyword("dragon".to_string()), 511).is_some());
+    }
+
+    #[test]
+    fn test_payload_carries_session_and_timestamp() {
+        let mut bridge = EventBridge::new("s1".to_string());
+        let (name, payload) = bridge.translate(PipelineEvent::Keyword("dragon".to_string()), 42).unwrap();
+        assert_eq!(name, KEYWORD_EVENT);
+
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["session_id"], "s1");
+        assert_eq!(json["timestamp_ms"], 42);
+        assert_eq!(json["kind"], "keyword");
+        assert_eq!(json["keyword"], "dragon");
+
+        assert!(bridge.translate(PipelineEvent::Transcription("hi".to_string()), 50).is_none());
+    }
+}
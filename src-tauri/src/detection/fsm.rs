@@ -147,6 +147,11 @@ impl DetectionFsm {
         self.mode = mode;
     }
 
+    /// Get the detection mode
+    pub fn mode(&self) -> DetectionMode {
+        self.mode
+    }
+
     /// Get current state
     pub fn state(&self) -> DetectionState {
         self.state
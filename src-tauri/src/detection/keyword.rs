@@ -1,8 +1,10 @@
 //! Keyword detection module
 
 use crate::error::AppError;
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Keyword match result
 #[derive(Debug, Clone)]
@@ -22,6 +24,9 @@ pub struct Keyword {
     pub variations: Vec<String>,
     pub mood: Option<String>,
     pub priority: u8,
+    /// Times this keyword has fired in the current session
+    #[serde(default, skip_serializing)]
+    pub use_count: u32,
 }
 
 impl Keyword {
@@ -33,6 +38,7 @@ impl Keyword {
             variations: vec![word],
             mood: None,
             priority: 0,
+            use_count: 0,
         }
     }
 
@@ -47,14 +53,23 @@ impl Keyword {
         self.mood = Some(mood);
         self
     }
+
+    /// Priority after applying per-session usage decay
+    pub fn effective_priority(&self, decay_rate: f32) -> f32 {
+        self.priority as f32 - decay_rate * self.use_count as f32
+    }
 }
 
+/// Default priority decay applied per use within a session
+pub const DEFAULT_KEYWORD_DECAY_RATE: f32 = 0.5;
+
 /// Keyword vocabulary
 #[derive(Debug, Clone)]
 pub struct KeywordVocabulary {
     keywords: HashMap<String, Keyword>,
     categories: HashMap<String, Vec<String>>,
     version: u64,
+    decay_rate: f32,
 }
 
 impl KeywordVocabulary {
@@ -64,9 +79,39 @@ impl KeywordVocabulary {
             keywords: HashMap::new(),
             categories: HashMap::new(),
             version: 0,
+            decay_rate: DEFAULT_KEYWORD_DECAY_RATE,
+        }
+    }
+
+    /// Set the priority decay rate applied per use
+    pub fn set_decay_rate(&mut self, decay_rate: f32) {
+        self.decay_rate = decay_rate.max(0.0);
+    }
+
+    /// Record that a keyword fired (applies to all of its variations)
+    pub fn record_use(&mut self, word: &str) {
+        for keyword in self.keywords.values_mut() {
+            if keyword.word == word {
+                keyword.use_count += 1;
+            }
         }
     }
 
+    /// Reset all per-session use counts
+    pub fn reset_use_counts(&mut self) {
+        for keyword in self.keywords.values_mut() {
+            keyword.use_count = 0;
+        }
+    }
+
+    /// Get use counts keyed by keyword
+    pub fn use_counts(&self) -> HashMap<String, u32> {
+        self.keywords
+            .values()
+            .map(|k| (k.word.clone(), k.use_count))
+            .collect()
+    }
+
     /// Add a keyword
     pub fn add_keyword(&mut self, keyword: Keyword) {
         for variation in &keyword.variations {
@@ -132,16 +177,17 @@ impl KeywordVocabulary {
             }
         }
 
-        // Sort by priority and confidence
+        // Sort by decayed priority and confidence
         matches.sort_by(|a, b| {
             let keyword_a = self.keywords.get(&a.keyword.to_lowercase());
             let keyword_b = self.keywords.get(&b.keyword.to_lowercase());
 
-            let priority_a = keyword_a.map(|k| k.priority).unwrap_or(0);
-            let priority_b = keyword_b.map(|k| k.priority).unwrap_or(0);
+            let priority_a = keyword_a.map(|k| k.effective_priority(self.decay_rate)).unwrap_or(0.0);
+            let priority_b = keyword_b.map(|k| k.effective_priority(self.decay_rate)).unwrap_or(0.0);
 
             priority_b
-                .cmp(&priority_a)
+                .partial_cmp(&priority_a)
+                .unwrap_or(std::cmp::Ordering::Equal)
                 .then(b.confidence.partial_cmp(&a.confidence).unwrap_or(std::cmp::Ordering::Equal))
         });
 
@@ -161,23 +207,62 @@ impl KeywordVocabulary {
         self.version
     }
 
-    /// Load from JSON
-    pub fn from_json(json: &str) -> Result<Self, AppError> {
-        let keywords: Vec<Keyword> = serde_json::from_str(json)
-            .map_err(|e| AppError::Serialization(e.to_string()))?;
+    /// Number of distinct keywords (variations are not counted separately)
+    pub fn len(&self) -> usize {
+        self.unique_keywords().len()
+    }
+
+    /// Check if the vocabulary is empty
+    pub fn is_empty(&self) -> bool {
+        self.keywords.is_empty()
+    }
+
+    /// One entry per keyword, sorted by word for stable output
+    fn unique_keywords(&self) -> Vec<&Keyword> {
+        let mut keywords: Vec<&Keyword> = Vec::new();
+        for keyword in self.keywords.values() {
+            if !keywords.iter().any(|k| k.word == keyword.word) {
+                keywords.push(keyword);
+            }
+        }
+        keywords.sort_by(|a, b| a.word.cmp(&b.word));
+        keywords
+    }
 
+    /// Build a vocabulary from a list of keywords
+    fn from_keywords(keywords: Vec<Keyword>) -> Self {
         let mut vocab = Self::new();
         for keyword in keywords {
             vocab.add_keyword(keyword);
         }
+        vocab
+    }
 
-        Ok(vocab)
+    /// Load from JSON
+    pub fn from_json(json: &str) -> Result<Self, AppError> {
+        let keywords: Vec<Keyword> = serde_json::from_str(json)
+            .map_err(|e| AppError::Serialization(e.to_string()))?;
+
+        Ok(Self::from_keywords(keywords))
     }
 
     /// Serialize to JSON
     pub fn to_json(&self) -> Result<String, AppError> {
-        let keywords: Vec<&Keyword> = self.keywords.values().collect();
-        serde_json::to_string_pretty(&keywords)
+        serde_json::to_string_pretty(&self.unique_keywords())
+            .map_err(|e| AppError::Serialization(e.to_string()))
+    }
+
+    /// Load from YAML
+    pub fn from_yaml(yaml: &str) -> Result<Self, AppError> {
+        let keywords: Vec<Keyword> = serde_yaml::from_str(yaml)
+            .map_err(|e| AppError::Serialization(e.to_string()))?;
+
+        Ok(Self::from_keywords(keywords))
+    }
+
+    /// Serialize to YAML
+    pub fn to_yaml(&self) -> Result<String, AppError> {
+        serde_yaml::to_string(&self.unique_keywords())
             .map_err(|e| AppError::Serialization(e.to_string()))
     }
 }
@@ -192,6 +277,7 @@ impl Default for KeywordVocabulary {
 pub struct KeywordDetector {
     vocabulary: KeywordVocabulary,
     fuzzy_threshold: f32,
+    use_counts: Arc<RwLock<HashMap<String, u32>>>,
 }
 
 impl KeywordDetector {
@@ -200,14 +286,42 @@ impl KeywordDetector {
         Self {
             vocabulary: KeywordVocabulary::new(),
             fuzzy_threshold: 0.7,
+            use_counts: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// Share the per-session use counts with an external observer
+    pub fn set_use_counts(&mut self, use_counts: Arc<RwLock<HashMap<String, u32>>>) {
+        self.use_counts = use_counts;
+    }
+
+    /// Record that a keyword fired, decaying its priority for the rest of the session
+    pub fn record_use(&mut self, word: &str) {
+        self.vocabulary.record_use(word);
+        *self.use_counts.write().entry(word.to_string()).or_insert(0) += 1;
+    }
+
+    /// Reset use counts at the start of a new session
+    pub fn reset_use_counts(&mut self) {
+        self.vocabulary.reset_use_counts();
+        self.use_counts.write().clear();
+    }
+
+    /// Get per-session use counts
+    pub fn keyword_use_counts(&self) -> HashMap<String, u32> {
+        self.use_counts.read().clone()
+    }
+
     /// Set vocabulary
     pub fn set_vocabulary(&mut self, vocabulary: KeywordVocabulary) {
         self.vocabulary = vocabulary;
     }
 
+    /// Get the current vocabulary
+    pub fn vocabulary(&self) -> &KeywordVocabulary {
+        &self.vocabulary
+    }
+
     /// Load vocabulary from file
     pub fn load_vocabulary(&mut self, path: &str) -> Result<(), AppError> {
         let content = std::fs::read_to_string(path)?;
@@ -217,6 +331,30 @@ impl KeywordDetector {
         Ok(())
     }
 
+    /// Load vocabulary from a JSON or YAML file, chosen by extension
+    pub fn load_vocabulary_auto(&mut self, path: &str) -> Result<(), AppError> {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+
+        let content = std::fs::read_to_string(path)?;
+        let vocab = match extension.as_deref() {
+            Some("json") => KeywordVocabulary::from_json(&content)?,
+            Some("yaml") | Some("yml") => KeywordVocabulary::from_yaml(&content)?,
+            _ => {
+                return Err(AppError::Config(format!(
+                    "Unsupported keyword file format: {}",
+                    path
+                )))
+            }
+        };
+
+        self.vocabulary = vocab;
+        tracing::info!("Loaded {} keywords from {}", self.vocabulary.len(), path);
+        Ok(())
+    }
+
     /// Detect keywords in text
     pub fn detect(&self, text: &str) -> Vec<KeywordMatch> {
         self.vocabulary.search(text)
@@ -334,4 +472,36 @@ mod tests {
         assert!(categories.contains(&"exploration".to_string()));
         assert!(categories.contains(&"creature".to_string()));
     }
+
+    #[test]
+    fn test_keyword_priority_decay() {
+        let mut detector = KeywordDetector::new();
+        detector.set_vocabulary(default_ttrpg_vocabulary());
+
+        let matches = detector.detect("battle dragon");
+        assert_eq!(matches[0].keyword, "battle");
+
+        detector.record_use("battle");
+        detector.record_use("battle");
+        let matches = detector.detect("battle dragon");
+        assert_eq!(matches[0].keyword, "dragon");
+        assert_eq!(detector.keyword_use_counts().get("battle"), Some(&2));
+
+        detector.reset_use_counts();
+        assert!(detector.keyword_use_counts().is_empty());
+        assert_eq!(detector.detect("battle dragon")[0].keyword, "battle");
+    }
+
+    #[test]
+    fn test_yaml_round_trip() {
+        let vocab = default_ttrpg_vocabulary();
+        let yaml = vocab.to_yaml().unwrap();
+
+        let loaded = KeywordVocabulary::from_yaml(&yaml).unwrap();
+        assert_eq!(loaded.len(), vocab.len());
+        assert_eq!(loaded.get("fight").map(|k| k.word.as_str()), Some("battle"));
+
+        let json_loaded = KeywordVocabulary::from_json(&vocab.to_json().unwrap()).unwrap();
+        assert_eq!(json_loaded.len(), vocab.len());
+    }
 }
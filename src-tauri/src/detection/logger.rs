@@ -64,11 +64,16 @@ impl DetectionLogEntry {
     }
 }
 
+/// Appended to the details of simulated entries, so analytics can exclude them
+pub const SIMULATED_DETAIL: &str = "simulated: true";
+
 /// Detection event logger
 pub struct DetectionLogger {
     session_id: String,
     entries: Vec<DetectionLogEntry>,
     max_entries: usize,
+    /// Mark new entries as simulated
+    simulated: bool,
 }
 
 impl DetectionLogger {
@@ -78,6 +83,7 @@ impl DetectionLogger {
             session_id,
             entries: Vec::new(),
             max_entries: 10000,
+            simulated: false,
         }
     }
 
@@ -86,11 +92,15 @@ impl DetectionLogger {
         self.session_id = session_id;
     }
 
+    /// Mark entries logged from now on as simulated (or not)
+    pub fn set_simulated(&mut self, simulated: bool) {
+        self.simulated = simulated;
+    }
+
     /// Log an event
     pub fn log(&mut self, event_type: &str) -> &DetectionLogEntry {
         let entry = DetectionLogEntry::new(self.session_id.clone(), event_type);
-        self.entries.push(entry);
-        self.trim();
+        self.push(entry);
         self.entries.last().unwrap()
     }
 
@@ -100,8 +110,7 @@ impl DetectionLogger {
             .with_details(keyword)
             .with_category(category)
             .with_confidence(confidence);
-        self.entries.push(entry);
-        self.trim();
+        self.push(entry);
     }
 
     /// Log emotion detection
@@ -109,8 +118,7 @@ impl DetectionLogger {
         let entry = DetectionLogEntry::new(self.session_id.clone(), "emotion")
             .with_details(emotion)
             .with_confidence(confidence);
-        self.entries.push(entry);
-        self.trim();
+        self.push(entry);
     }
 
     /// Log dual signal detection
@@ -119,8 +127,7 @@ impl DetectionLogger {
         let entry = DetectionLogEntry::new(self.session_id.clone(), "dual_signal")
             .with_details(&details)
             .with_triggered_action();
-        self.entries.push(entry);
-        self.trim();
+        self.push(entry);
     }
 
     /// Log voice activity
@@ -131,8 +138,19 @@ impl DetectionLogger {
             .unwrap_or_default();
         let entry = DetectionLogEntry::new(self.session_id.clone(), event_type)
             .with_details(&details);
-        self.entries.push(entry);
-        self.trim();
+        self.push(entry);
+    }
+
+    /// Log a transcribed speech segment
+    pub fn log_transcription(&mut self, text: &str) {
+        let entry = DetectionLogEntry::new(self.session_id.clone(), "transcription").with_details(text);
+        self.push(entry);
+    }
+
+    /// Log a track starting to play
+    pub fn log_track_played(&mut self, track_id: &str) {
+        let entry = DetectionLogEntry::new(self.session_id.clone(), "track_played").with_details(track_id);
+        self.push(entry);
     }
 
     /// Log speaker verification
@@ -141,8 +159,18 @@ impl DetectionLogger {
         let entry = DetectionLogEntry::new(self.session_id.clone(), "speaker_verification")
             .with_details(details)
             .with_confidence(similarity);
-        self.entries.push(entry);
-        self.trim();
+        self.push(entry);
+    }
+
+    /// Start logging a new session, discarding any previous entries
+    pub fn reset(&mut self, session_id: String) {
+        self.session_id = session_id;
+        self.entries.clear();
+    }
+
+    /// Remove and return all entries
+    pub fn take_entries(&mut self) -> Vec<DetectionLogEntry> {
+        std::mem::take(&mut self.entries)
     }
 
     /// Get all entries
@@ -171,6 +199,18 @@ impl DetectionLogger {
         serde_json::to_string_pretty(&self.entries).unwrap_or_default()
     }
 
+    fn push(&mut self, mut entry: DetectionLogEntry) {
+        if self.simulated {
+            entry.details = if entry.details.is_empty() {
+                SIMULATED_DETAIL.to_string()
+            } else {
+                format!("{}, {}", entry.details, SIMULATED_DETAIL)
+            };
+        }
+        self.entries.push(entry);
+        self.trim();
+    }
+
     /// Trim entries if over limit
     fn trim(&mut self) {
         if self.entries.len() > self.max_entries {
@@ -6,17 +6,22 @@
 //! - Speech-to-text transcription
 //! - Keyword matching
 //! - Detection state machine
+//! - Forwarding detections to the frontend
 
+pub mod bridge;
 pub mod fsm;
 pub mod keyword;
 pub mod logger;
 pub mod pipeline;
 pub mod speaker;
+pub mod stream;
 pub mod vad;
 
+pub use bridge::*;
 pub use fsm::*;
 pub use keyword::*;
 pub use logger::*;
 pub use pipeline::*;
 pub use speaker::*;
+pub use stream::*;
 pub use vad::*;
@@ -1,16 +1,33 @@
 //! Detection pipeline - orchestrates all detection components
 
+use crate::db::{Repository, Transcript};
 use crate::detection::fsm::{DetectionEvent, DetectionFsm, DetectionMode, DetectionState};
-use crate::detection::keyword::{default_ttrpg_vocabulary, KeywordDetector};
+use crate::detection::keyword::{default_ttrpg_vocabulary, KeywordDetector, KeywordMatch, KeywordVocabulary};
+use crate::detection::logger::DetectionLogger;
 use crate::detection::speaker::{SpeakerVerifier, SpeakerEmbedding};
+use crate::audio::AudioRingBuffer;
 use crate::detection::vad::VoiceActivityDetector;
 use crate::error::AppError;
-use crate::inference::emotion::EmotionAnalyzer;
-use crate::inference::whisper::WhisperEngine;
+use crate::inference::emotion::{EmotionAnalyzer, EmotionError};
+use crate::inference::whisper::{self, WhisperEngine, WhisperError};
+use crate::state::channels::AUDIO_BUFFER_CAPACITY;
+use crate::state::SessionConfig;
 use flume::{Receiver, Sender};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use tracing::Level;
+
+/// Length of the frames `process_buffered` feeds to the VAD
+const FRAME_MS: u32 = 30;
+
+/// How often `run_async_with` checks for tasks while no audio arrives
+const TASK_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Work run on the pipeline between audio frames by `DetectionPipeline::run_async_with`
+pub type PipelineTask = Box<dyn FnOnce(&mut DetectionPipeline) + Send>;
 
 /// Detection pipeline configuration
 #[derive(Debug, Clone)]
@@ -18,8 +35,12 @@ pub struct PipelineConfig {
     pub enable_vad: bool,
     pub enable_speaker_verification: bool,
     pub enable_transcription: bool,
+    /// Transcribe in short overlapping windows and emit partial results
+    pub enable_streaming_transcription: bool,
     pub enable_emotion: bool,
     pub vad_threshold: f32,
+    /// Audio kept from before the VAD fires and prepended to each speech segment
+    pub pre_roll_ms: u32,
     pub transcription_segment_ms: u32,
     pub detection_timeout_ms: u64,
     pub cooldown_ms: u64,
@@ -31,8 +52,10 @@ impl Default for PipelineConfig {
             enable_vad: true,
             enable_speaker_verification: false,
             enable_transcription: true,
+            enable_streaming_transcription: false,
             enable_emotion: true,
             vad_threshold: 0.5,
+            pre_roll_ms: 500,
             transcription_segment_ms: 8000,
             detection_timeout_ms: 10000,
             cooldown_ms: 3000,
@@ -40,6 +63,24 @@ impl Default for PipelineConfig {
     }
 }
 
+impl PipelineConfig {
+    /// Pipeline stages enabled by a session config
+    pub fn from_session(config: &SessionConfig) -> Self {
+        Self {
+            enable_vad: config.enable_vad,
+            enable_speaker_verification: config.enable_speaker_verification,
+            enable_transcription: config.enable_transcription,
+            enable_emotion: config.enable_emotion_analysis,
+            ..Self::default()
+        }
+    }
+
+    /// Number of pre-roll samples at `sample_rate`
+    pub fn pre_roll_samples(&self, sample_rate: u32) -> usize {
+        (sample_rate as u64 * self.pre_roll_ms as u64 / 1000) as usize
+    }
+}
+
 /// Detection pipeline event
 #[derive(Debug, Clone)]
 pub enum PipelineEvent {
@@ -49,18 +90,76 @@ pub enum PipelineEvent {
     VoiceEnd { start_ms: u64, end_ms: u64 },
     /// Transcription ready
     Transcription(String),
+    /// Partial transcription from a streaming window
+    PartialTranscription(String),
     /// Keyword detected
     Keyword(String),
     /// Emotion detected
     Emotion(String, f32),
     /// Dual signal confirmed
-    DualSignal { keyword: String, emotion: String },
+    ///
+    /// `reply` is set when a simulated detection confirmed it and receives
+    /// what was done about the signal.
+    DualSignal {
+        keyword: String,
+        emotion: String,
+        reply: Option<Sender<TriggerAction>>,
+    },
+    /// Dual signal resolved to a track genre via the keyword category mapping
+    GenreResolved { category: String, genre: String },
     /// Speaker verified
     SpeakerVerified(bool),
+    /// Frames were dropped because the pipeline fell behind (session total)
+    Overrun(u64),
     /// Pipeline error
     Error(String),
 }
 
+/// Message of the `PipelineEvent::Error` sent when a detection times out
+pub const DETECTION_TIMEOUT_MESSAGE: &str = "timeout";
+
+/// Detections injected as if they had been heard, see `DetectionPipeline::simulate`
+#[derive(Debug, Clone)]
+pub struct SimulatedDetection {
+    pub keyword: Option<String>,
+    pub emotion: Option<String>,
+    /// Confidence of both the keyword and the emotion
+    pub confidence: f32,
+}
+
+/// What was done about a confirmed dual signal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum TriggerAction {
+    /// Autonomous mode crossfaded to a track
+    TrackCrossfaded { track_id: String, track_name: String },
+    /// Collaborative mode queued a suggestion for the GM
+    SuggestionQueued { suggestion_id: String, track_name: Option<String> },
+    /// No dual signal, or no track to play for it
+    Nothing,
+}
+
+/// Processing cost of the pipeline, to tell whether it keeps up with realtime
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct PipelineMetrics {
+    pub frames_processed: u64,
+    /// Frames discarded before reaching the pipeline
+    pub frames_dropped: u64,
+    pub avg_process_time_us: u64,
+    pub peak_process_time_us: u64,
+}
+
+impl PipelineMetrics {
+    /// Account for one processed frame
+    fn record_frame(&mut self, elapsed: Duration) {
+        let elapsed_us = elapsed.as_micros() as u64;
+        self.frames_processed += 1;
+        self.avg_process_time_us =
+            (self.avg_process_time_us * (self.frames_processed - 1) + elapsed_us) / self.frames_processed;
+        self.peak_process_time_us = self.peak_process_time_us.max(elapsed_us);
+    }
+}
+
 /// Detection pipeline
 pub struct DetectionPipeline {
     config: PipelineConfig,
@@ -70,11 +169,23 @@ pub struct DetectionPipeline {
     whisper: WhisperEngine,
     emotion_analyzer: EmotionAnalyzer,
     fsm: DetectionFsm,
-    audio_buffer: Arc<RwLock<Vec<f32>>>,
+    audio_buffer: Arc<RwLock<AudioRingBuffer>>,
+    /// Most recent non-speech audio, so segments don't start mid-word
+    pre_roll: AudioRingBuffer,
     segment_buffer: Vec<f32>,
     event_tx: Option<Sender<PipelineEvent>>,
+    repository: Option<Repository>,
+    /// Session transcribed segments are stored under
+    session_id: Option<String>,
+    logger: Option<Arc<Mutex<DetectionLogger>>>,
+    last_keyword_category: Option<String>,
     sample_rate: u32,
     last_voice_time: Option<Instant>,
+    /// Audio timestamp at which the FSM started detecting
+    detecting_since_ms: Option<u64>,
+    /// Audio timestamp at the end of the latest frame
+    audio_end_ms: u64,
+    metrics: PipelineMetrics,
     is_running: bool,
 }
 
@@ -87,6 +198,8 @@ impl DetectionPipeline {
         let mut keyword_detector = KeywordDetector::new();
         keyword_detector.set_vocabulary(default_ttrpg_vocabulary());
 
+        let pre_roll = AudioRingBuffer::new(config.pre_roll_samples(16000), 16000);
+
         Self {
             config,
             vad,
@@ -95,31 +208,64 @@ impl DetectionPipeline {
             whisper: WhisperEngine::new(),
             emotion_analyzer: EmotionAnalyzer::new(),
             fsm: DetectionFsm::new(),
-            audio_buffer: Arc::new(RwLock::new(Vec::new())),
+            audio_buffer: Arc::new(RwLock::new(AudioRingBuffer::new(AUDIO_BUFFER_CAPACITY, 16000))),
+            pre_roll,
             segment_buffer: Vec::new(),
             event_tx: None,
+            repository: None,
+            session_id: None,
+            logger: None,
+            last_keyword_category: None,
             sample_rate: 16000,
             last_voice_time: None,
+            detecting_since_ms: None,
+            audio_end_ms: 0,
+            metrics: PipelineMetrics::default(),
             is_running: false,
         }
     }
 
     /// Initialize the pipeline
+    ///
+    /// Both models are loaded even if one fails; the first failure of an
+    /// enabled stage is returned. The pipeline still runs without that stage.
     pub fn init(&mut self) -> Result<(), AppError> {
         tracing::info!("Initializing detection pipeline");
+        let mut result = Ok(());
 
         // Initialize whisper
-        if let Err(e) = self.whisper.init("models/whisper-tiny.bin") {
+        if let Err(e) = self.whisper.init(&whisper::get_model_path().to_string_lossy()) {
             tracing::warn!("Whisper init warning: {}", e);
+            if self.config.enable_transcription {
+                result = Err(AppError::Detection(format!("Speech recognition unavailable: {}", e)));
+            }
         }
 
         // Initialize emotion analyzer
         if let Err(e) = self.emotion_analyzer.init() {
             tracing::warn!("Emotion analyzer init warning: {}", e);
+            if self.config.enable_emotion && result.is_ok() {
+                result = Err(AppError::Detection(format!("Emotion analysis unavailable: {}", e)));
+            }
         }
 
         tracing::info!("Detection pipeline initialized");
-        Ok(())
+        result
+    }
+
+    /// Report a failure to the event listener as `PipelineEvent::Error`
+    pub fn report_error(&self, error: AppError) {
+        let message = match error {
+            AppError::Detection(message) => message,
+            AppError::Timeout(reason) => {
+                tracing::debug!("Detection timed out: {}", reason);
+                self.emit(PipelineEvent::Error(DETECTION_TIMEOUT_MESSAGE.to_string()));
+                return;
+            }
+            other => other.to_string(),
+        };
+        tracing::warn!("Detection pipeline error: {}", message);
+        self.emit(PipelineEvent::Error(message));
     }
 
     /// Set the event sender
@@ -127,15 +273,61 @@ impl DetectionPipeline {
         self.event_tx = Some(tx);
     }
 
-    /// Set the audio buffer
-    pub fn set_audio_buffer(&mut self, buffer: Arc<RwLock<Vec<f32>>>) {
+    /// Set the repository used to resolve keyword categories to genres
+    pub fn set_repository(&mut self, repository: Repository) {
+        self.repository = Some(repository);
+    }
+
+    /// Store transcribed segments under `session_id` (needs a repository)
+    pub fn set_session_id(&mut self, session_id: String) {
+        self.session_id = Some(session_id);
+    }
+
+    /// Record transcriptions, keyword, emotion and dual-signal detections in `logger`
+    pub fn set_logger(&mut self, logger: Arc<Mutex<DetectionLogger>>) {
+        self.logger = Some(logger);
+    }
+
+    /// Set the audio buffer shared with capture, consumed by `process_buffered`
+    pub fn set_audio_buffer(&mut self, buffer: Arc<RwLock<AudioRingBuffer>>) {
         self.audio_buffer = buffer;
     }
 
+    /// Replace the keyword vocabulary
+    pub fn set_vocabulary(&mut self, vocabulary: KeywordVocabulary) {
+        self.keyword_detector.set_vocabulary(vocabulary);
+    }
+
+    /// Share keyword use counts so they can be observed outside the pipeline
+    pub fn set_keyword_use_counts(&mut self, use_counts: Arc<RwLock<HashMap<String, u32>>>) {
+        self.keyword_detector.set_use_counts(use_counts);
+    }
+
     /// Set sample rate
     pub fn set_sample_rate(&mut self, sample_rate: u32) {
         self.sample_rate = sample_rate;
         self.vad.set_sample_rate(sample_rate);
+        self.pre_roll = AudioRingBuffer::new(self.config.pre_roll_samples(sample_rate), sample_rate);
+    }
+
+    /// Replace the speaker verifier, e.g. with one loaded from the repository
+    pub fn set_speaker_verifier(&mut self, verifier: SpeakerVerifier) {
+        self.speaker_verifier = verifier;
+    }
+
+    /// Stop verifying against a profile whose consent was revoked
+    ///
+    /// Speaker verification is turned off when no enrolled profile is left.
+    pub fn revoke_speaker_profile(&mut self, profile_id: &str) {
+        if !self.speaker_verifier.get_profiles().iter().any(|p| p.id == profile_id) {
+            return;
+        }
+
+        self.speaker_verifier.remove_profile(profile_id);
+        if self.speaker_verifier.get_profiles().is_empty() && self.config.enable_speaker_verification {
+            tracing::info!("Speaker verification disabled: no enrolled profile left");
+            self.config.enable_speaker_verification = false;
+        }
     }
 
     /// Set detection mode
@@ -143,22 +335,111 @@ impl DetectionPipeline {
         self.fsm.set_mode(mode);
     }
 
+    /// Consume audio added to the shared buffer since the last call, one VAD frame at a time
+    pub fn process_buffered(&mut self, timestamp_ms: u64) {
+        if !self.is_running {
+            return;
+        }
+
+        let frame = (self.sample_rate * FRAME_MS / 1000) as usize;
+        let chunks = self.audio_buffer.write().drain_chunks(frame);
+        for (i, chunk) in chunks.iter().enumerate() {
+            self.process_audio(chunk, timestamp_ms + (i as u32 * FRAME_MS) as u64);
+        }
+    }
+
+    /// Process frames from `audio_rx` on a tokio task until every sender is dropped
+    ///
+    /// The capture side should bound the channel to `channels::AUDIO_FRAME_QUEUE_CAPACITY`
+    /// frames so a slow pipeline can catch up on bursts without stalling capture.
+    /// Inference blocks, so the task runs on the blocking pool. Timestamps count
+    /// the samples received, which keeps them exact while a backlog is drained.
+    pub fn run_async(
+        self,
+        audio_rx: Receiver<Vec<f32>>,
+        event_tx: Sender<PipelineEvent>,
+    ) -> tokio::task::JoinHandle<()> {
+        let (_task_tx, task_rx) = flume::unbounded();
+        self.run_async_with(audio_rx, event_tx, task_rx, |_| {})
+    }
+
+    /// `run_async`, also running the tasks from `task_rx` between frames
+    ///
+    /// `on_frame` is called after each frame is processed. A task that stops
+    /// the pipeline ends the task without waiting for the audio senders.
+    pub fn run_async_with(
+        mut self,
+        audio_rx: Receiver<Vec<f32>>,
+        event_tx: Sender<PipelineEvent>,
+        task_rx: Receiver<PipelineTask>,
+        mut on_frame: impl FnMut(&mut DetectionPipeline) + Send + 'static,
+    ) -> tokio::task::JoinHandle<()> {
+        self.set_event_sender(event_tx);
+
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = self.init() {
+                self.report_error(e);
+            }
+            self.start();
+
+            let mut samples_received: u64 = 0;
+            loop {
+                for task in task_rx.try_iter() {
+                    task(&mut self);
+                }
+                if !self.is_running {
+                    return;
+                }
+                match audio_rx.recv_timeout(TASK_POLL_INTERVAL) {
+                    Ok(frame) => {
+                        let timestamp_ms = samples_received * 1000 / self.sample_rate.max(1) as u64;
+                        self.process_audio(&frame, timestamp_ms);
+                        samples_received += frame.len() as u64;
+                        on_frame(&mut self);
+                    }
+                    Err(flume::RecvTimeoutError::Timeout) => {}
+                    Err(flume::RecvTimeoutError::Disconnected) => break,
+                }
+            }
+
+            self.stop();
+        })
+    }
+
     /// Process incoming audio data
     pub fn process_audio(&mut self, samples: &[f32], timestamp_ms: u64) {
         if !self.is_running {
             return;
         }
 
-        // Add to buffers
-        {
-            let mut buffer = self.audio_buffer.write();
-            buffer.extend_from_slice(samples);
-        }
-        self.segment_buffer.extend_from_slice(samples);
+        let started = Instant::now();
+        self.process_frame(samples, timestamp_ms);
+        self.metrics.record_frame(started.elapsed());
+    }
+
+    /// Run one frame through VAD, segmentation and, when a segment is ready, analysis
+    fn process_frame(&mut self, samples: &[f32], timestamp_ms: u64) {
+        self.audio_end_ms = timestamp_ms + self.duration_ms(samples.len());
 
         // Run VAD
         if self.config.enable_vad {
-            let vad_result = self.vad.process_frame(samples, timestamp_ms);
+            let was_speaking = self.vad.is_speaking();
+            let vad_result = {
+                let _span = tracing::span!(Level::DEBUG, "vad", samples_count = samples.len()).entered();
+                self.vad.process_frame(samples, timestamp_ms)
+            };
+
+            // Only speech is segmented; the pre-roll covers the word onset
+            // that was still under the threshold when the VAD fired
+            if self.vad.is_speaking() {
+                if !was_speaking {
+                    let pre_roll = self.pre_roll.drain_all();
+                    self.segment_buffer.extend_from_slice(&pre_roll);
+                }
+                self.segment_buffer.extend_from_slice(samples);
+            } else {
+                self.pre_roll.push_slice(samples);
+            }
 
             if vad_result.is_speech {
                 self.last_voice_time = Some(Instant::now());
@@ -176,90 +457,319 @@ impl DetectionPipeline {
                         end_ms: timestamp_ms,
                     });
                 }
+                if let Err(e) = self.process_segment() {
+                    self.report_error(e);
+                }
             }
+        } else {
+            // Without VAD every sample is part of a segment
+            self.segment_buffer.extend_from_slice(samples);
         }
 
         // Check if we should process a segment
         let segment_samples = (self.sample_rate as u32 * self.config.transcription_segment_ms) / 1000;
         if self.segment_buffer.len() >= segment_samples as usize {
-            self.process_segment();
+            if let Err(e) = self.process_segment() {
+                self.report_error(e);
+            }
+        }
+
+        if let Err(e) = self.check_detection_timeout(timestamp_ms) {
+            self.report_error(e);
+        }
+    }
+
+    /// Return the FSM to listening if it detected for `detection_timeout_ms`
+    /// without confirming a dual signal
+    fn check_detection_timeout(&mut self, timestamp_ms: u64) -> Result<(), AppError> {
+        if self.fsm.state() != DetectionState::Detecting {
+            self.detecting_since_ms = None;
+            return Ok(());
         }
+
+        let since = *self.detecting_since_ms.get_or_insert(timestamp_ms);
+        if timestamp_ms.saturating_sub(since) < self.config.detection_timeout_ms {
+            return Ok(());
+        }
+
+        self.detecting_since_ms = None;
+        self.fsm.process_event(&DetectionEvent::Timeout);
+        Err(AppError::Timeout(format!(
+            "No dual signal within {}ms",
+            self.config.detection_timeout_ms
+        )))
+    }
+
+    /// Analyze whatever speech is buffered, even if shorter than a full segment
+    pub fn flush(&mut self) -> Result<(), AppError> {
+        let result = self.process_segment();
+        self.segment_buffer.clear();
+        result
     }
 
     /// Process accumulated audio segment
-    fn process_segment(&mut self) {
+    ///
+    /// A failing stage does not stop the other; the first failure is returned.
+    /// Each stage runs in its own span, so per-stage latency shows in the
+    /// span timings of the structured log. The spans are at debug level, so
+    /// they are only recorded when running with `RUST_LOG=debug`.
+    #[tracing::instrument(level = "debug", skip(self), fields(samples_count))]
+    fn process_segment(&mut self) -> Result<(), AppError> {
         if self.segment_buffer.is_empty() {
-            return;
+            return Ok(());
         }
+        let mut result = Ok(());
 
         let segment = std::mem::take(&mut self.segment_buffer);
         self.segment_buffer = Vec::new();
+        tracing::Span::current().record("samples_count", segment.len());
+        let (start_ms, end_ms) = self.segment_bounds(segment.len());
 
+        // Stages whose model failed to load were reported by init and are skipped
         // Run transcription
-        if self.config.enable_transcription {
-            match self.whisper.transcribe(&segment, self.sample_rate) {
-                Ok(result) => {
-                    if !result.text.is_empty() {
-                        tracing::debug!("Transcription: {}", result.text);
-                        self.emit(PipelineEvent::Transcription(result.text.clone()));
+        if self.config.enable_transcription && self.whisper.is_initialized() {
+            let transcription = {
+                let _span = tracing::span!(Level::DEBUG, "transcription", samples_count = segment.len()).entered();
+                self.transcribe_segment(&segment)
+            };
+            match transcription {
+                Ok((text, confidence)) => {
+                    if !text.is_empty() {
+                        tracing::debug!("Transcription: {}", text);
+                        self.emit(PipelineEvent::Transcription(text.clone()));
+                        if let Some(logger) = &self.logger {
+                            logger.lock().log_transcription(&text);
+                        }
+                        self.store_transcript(&text, confidence, start_ms, end_ms);
 
                         // Check keywords
-                        let matches = self.keyword_detector.detect(&result.text);
+                        let _span = tracing::span!(Level::DEBUG, "keyword_search", samples_count = segment.len()).entered();
+                        let matches = self.keyword_detector.detect(&text);
                         for m in matches {
-                            tracing::info!("Keyword detected: {} ({})", m.keyword, m.category);
-                            self.fsm.process_event(&DetectionEvent::KeywordMatched(m.keyword.clone()));
-                            self.emit(PipelineEvent::Keyword(m.keyword));
+                            self.on_keyword(m);
                         }
                     }
                 }
                 Err(e) => {
-                    tracing::warn!("Transcription error: {}", e);
+                    result = Err(AppError::Detection(format!("Transcription failed: {}", e)));
                 }
             }
         }
 
         // Run emotion analysis
-        if self.config.enable_emotion {
-            match self.emotion_analyzer.analyze(&segment, self.sample_rate) {
-                Ok(result) => {
-                    let emotion_str = result.primary.to_string();
-                    tracing::debug!("Emotion: {} ({:.2})", emotion_str, result.confidence);
-                    self.fsm.process_event(&DetectionEvent::EmotionDetected(
-                        emotion_str.clone(),
-                        result.confidence,
-                    ));
-                    self.emit(PipelineEvent::Emotion(emotion_str, result.confidence));
+        if self.config.enable_emotion && self.emotion_analyzer.is_initialized() {
+            let analysis = {
+                let _span = tracing::span!(Level::DEBUG, "emotion", samples_count = segment.len()).entered();
+                self.emotion_analyzer.analyze(&segment, self.sample_rate)
+            };
+            match analysis {
+                Ok(result) => self.on_emotion(result.primary.to_string(), result.confidence),
+                // Too short to judge, e.g. the tail flushed on stop
+                Err(EmotionError::InsufficientData(reason)) => {
+                    tracing::debug!("Skipping emotion analysis: {}", reason);
                 }
                 Err(e) => {
-                    tracing::warn!("Emotion analysis error: {}", e);
+                    if result.is_ok() {
+                        result = Err(AppError::Detection(format!("Emotion analysis failed: {}", e)));
+                    }
                 }
             }
         }
 
-        // Check FSM state
-        let state = self.fsm.state();
-        if self.fsm.is_dual_signal_confirmed() {
-            if let (Some(keyword), Some(emotion)) = (
-                self.fsm.get_last_keyword().cloned(),
-                self.fsm.get_last_emotion().cloned(),
-            ) {
-                self.emit(PipelineEvent::DualSignal {
-                    keyword,
-                    emotion,
-                });
+        self.check_dual_signal(None);
+
+        result
+    }
+
+    /// Inject detections as if they came from a speech segment
+    ///
+    /// Logged entries are marked simulated. A dual signal confirmed by the
+    /// injection carries `reply`; otherwise `reply` is dropped.
+    ///
+    /// The injection runs on an FSM of its own, so it never pairs with a real
+    /// detection or an earlier simulation, and leaves the live FSM untouched.
+    pub fn simulate(&mut self, detection: SimulatedDetection, reply: Sender<TriggerAction>) {
+        tracing::info!("Simulating detection: {:?}", detection);
+        if let Some(logger) = &self.logger {
+            logger.lock().set_simulated(true);
+        }
+
+        let mut fsm = DetectionFsm::new();
+        fsm.set_mode(self.fsm.mode());
+        let live_fsm = std::mem::replace(&mut self.fsm, fsm);
+        let live_category = self.last_keyword_category.take();
+
+        self.fsm.process_event(&DetectionEvent::VoiceDetected);
+        if let Some(word) = detection.keyword {
+            let category = self
+                .keyword_detector
+                .vocabulary()
+                .get(&word)
+                .map(|k| k.category.clone())
+                .unwrap_or_default();
+            self.on_keyword(KeywordMatch {
+                start_index: 0,
+                end_index: word.len(),
+                keyword: word,
+                category,
+                confidence: detection.confidence,
+            });
+        }
+        if let Some(emotion) = detection.emotion {
+            self.on_emotion(emotion, detection.confidence);
+        }
+        self.check_dual_signal(Some(reply));
+
+        self.fsm = live_fsm;
+        self.last_keyword_category = live_category;
+        if let Some(logger) = &self.logger {
+            logger.lock().set_simulated(false);
+        }
+    }
+
+    fn on_keyword(&mut self, m: KeywordMatch) {
+        tracing::info!("Keyword detected: {} ({})", m.keyword, m.category);
+        self.fsm.process_event(&DetectionEvent::KeywordMatched(m.keyword.clone()));
+        self.keyword_detector.record_use(&m.keyword);
+        self.last_keyword_category = Some(m.category.clone());
+        if let Some(logger) = &self.logger {
+            logger.lock().log_keyword(&m.keyword, &m.category, m.confidence);
+        }
+        self.emit(PipelineEvent::Keyword(m.keyword));
+    }
+
+    fn on_emotion(&mut self, emotion: String, confidence: f32) {
+        tracing::debug!("Emotion: {} ({:.2})", emotion, confidence);
+        self.fsm.process_event(&DetectionEvent::EmotionDetected(emotion.clone(), confidence));
+        if let Some(logger) = &self.logger {
+            logger.lock().log_emotion(&emotion, confidence);
+        }
+        self.emit(PipelineEvent::Emotion(emotion, confidence));
+    }
+
+    /// Emit the dual signal if the FSM confirmed one
+    fn check_dual_signal(&mut self, reply: Option<Sender<TriggerAction>>) {
+        if !self.fsm.is_dual_signal_confirmed() {
+            return;
+        }
+        let (Some(keyword), Some(emotion)) = (
+            self.fsm.get_last_keyword().cloned(),
+            self.fsm.get_last_emotion().cloned(),
+        ) else {
+            return;
+        };
+
+        if let Some(logger) = &self.logger {
+            logger.lock().log_dual_signal(&keyword, &emotion);
+        }
+        self.emit(PipelineEvent::DualSignal { keyword, emotion, reply });
+
+        if let Some(genre) = self.resolve_genre() {
+            self.emit(genre);
+        }
+    }
+
+    /// Transcribe a segment, streaming partial results if enabled
+    ///
+    /// Streamed transcriptions come without a confidence.
+    fn transcribe_segment(&self, segment: &[f32]) -> Result<(String, Option<f32>), WhisperError> {
+        if !self.config.enable_streaming_transcription {
+            return self
+                .whisper
+                .transcribe(segment, self.sample_rate)
+                .map(|r| (r.text, Some(r.confidence)));
+        }
+
+        // Forward partials as they arrive; the thread ends when `tx` is dropped
+        let (tx, rx) = flume::unbounded::<String>();
+        if let Some(event_tx) = self.event_tx.clone() {
+            std::thread::spawn(move || {
+                for partial in rx.iter() {
+                    let _ = event_tx.send(PipelineEvent::PartialTranscription(partial));
+                }
+            });
+        }
+
+        self.whisper
+            .transcribe_streaming(segment, self.sample_rate, tx)
+            .map(|text| (text, None))
+    }
+
+    /// Length of `samples` mono samples in milliseconds
+    fn duration_ms(&self, samples: usize) -> u64 {
+        samples as u64 * 1000 / self.sample_rate.max(1) as u64
+    }
+
+    /// Start and end, in audio time, of a segment ending with the latest frame
+    fn segment_bounds(&self, samples: usize) -> (u64, u64) {
+        (self.audio_end_ms.saturating_sub(self.duration_ms(samples)), self.audio_end_ms)
+    }
+
+    /// Persist a transcribed segment under the current session
+    fn store_transcript(&self, text: &str, confidence: Option<f32>, start_ms: u64, end_ms: u64) {
+        let (Some(repository), Some(session_id)) = (&self.repository, &self.session_id) else {
+            return;
+        };
+
+        let mut transcript = Transcript::new(session_id.clone(), start_ms as i64, end_ms as i64, text.to_string());
+        transcript.confidence = confidence.map(f64::from);
+        transcript.language = self.whisper.detected_language();
+        if let Err(e) = repository.insert_transcript(&transcript) {
+            tracing::warn!("Failed to store transcript: {}", e);
+        }
+    }
+
+    /// Look up the genre mapped to the last matched keyword category
+    fn resolve_genre(&self) -> Option<PipelineEvent> {
+        let repository = self.repository.as_ref()?;
+        let category = self.last_keyword_category.clone()?;
+
+        match repository.get_genre_for_category(&category) {
+            Ok(Some(genre)) => {
+                tracing::info!("Resolved category {} to genre {}", category, genre);
+                Some(PipelineEvent::GenreResolved { category, genre })
+            }
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!("Genre lookup failed: {}", e);
+                None
             }
         }
     }
 
+    /// Latency and drop counters since the pipeline started
+    pub fn metrics(&self) -> PipelineMetrics {
+        self.metrics
+    }
+
+    /// Update the count of frames dropped upstream, emitting an overrun if it grew
+    pub fn set_frames_dropped(&mut self, frames_dropped: u64) {
+        if frames_dropped > self.metrics.frames_dropped {
+            self.metrics.frames_dropped = frames_dropped;
+            self.emit(PipelineEvent::Overrun(frames_dropped));
+        }
+    }
+
     /// Start the pipeline
     pub fn start(&mut self) {
         self.is_running = true;
         self.fsm.process_event(&DetectionEvent::Reset);
+        self.keyword_detector.reset_use_counts();
+        self.last_keyword_category = None;
+        self.metrics = PipelineMetrics::default();
+        self.vad.reset();
+        self.pre_roll.clear();
+        self.segment_buffer.clear();
         tracing::info!("Detection pipeline started");
     }
 
     /// Stop the pipeline
+    ///
+    /// Buffered speech is flushed first so the end of the session is analyzed.
     pub fn stop(&mut self) {
+        if let Err(e) = self.flush() {
+            self.report_error(e);
+        }
         self.is_running = false;
         tracing::info!("Detection pipeline stopped");
     }
@@ -291,10 +801,279 @@ impl Default for DetectionPipeline {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::detection::logger::SIMULATED_DETAIL;
+    use crate::state::channels::AUDIO_FRAME_QUEUE_CAPACITY;
 
     #[test]
     fn test_pipeline_creation() {
         let pipeline = DetectionPipeline::new(PipelineConfig::default());
         assert!(!pipeline.is_running());
     }
+
+    #[test]
+    fn test_config_from_session() {
+        let session = SessionConfig {
+            enable_vad: false,
+            enable_emotion_analysis: false,
+            ..SessionConfig::default()
+        };
+
+        let config = PipelineConfig::from_session(&session);
+        assert!(!config.enable_vad);
+        assert!(!config.enable_emotion);
+        assert!(config.enable_transcription);
+    }
+
+    #[test]
+    fn test_speech_segment_starts_with_pre_roll() {
+        let config = PipelineConfig {
+            enable_transcription: false,
+            enable_emotion: false,
+            ..PipelineConfig::default()
+        };
+        let mut pipeline = DetectionPipeline::new(config);
+        pipeline.start();
+
+        // One second of quiet lead-in, then a burst right at the threshold crossing
+        let lead_in: Vec<f32> = (0..16000).map(|i| (i % 100) as f32 * 0.001).collect();
+        for (i, frame) in lead_in.chunks(480).enumerate() {
+            pipeline.process_audio(frame, i as u64 * 30);
+        }
+        assert!(pipeline.segment_buffer.is_empty());
+
+        let burst = vec![0.9f32; 480];
+        pipeline.process_audio(&burst, 1020);
+
+        let pre_roll = pipeline.config.pre_roll_samples(16000);
+        assert_eq!(pipeline.segment_buffer.len(), pre_roll + burst.len());
+        assert_eq!(&pipeline.segment_buffer[..pre_roll], &lead_in[lead_in.len() - pre_roll..]);
+        assert_eq!(&pipeline.segment_buffer[pre_roll..], &burst[..]);
+
+        // Voice end hands the segment off and starts collecting pre-roll again
+        pipeline.process_audio(&[0.0; 480], 1050);
+        assert!(pipeline.segment_buffer.is_empty());
+        assert_eq!(pipeline.pre_roll.len(), 480);
+    }
+
+    #[test]
+    fn test_simulate_reaches_dual_signal() {
+        let mut pipeline = DetectionPipeline::new(PipelineConfig::default());
+        let logger = Arc::new(Mutex::new(DetectionLogger::new("session".to_string())));
+        pipeline.set_logger(logger.clone());
+        let (event_tx, event_rx) = flume::unbounded();
+        pipeline.set_event_sender(event_tx);
+
+        // A keyword alone confirms nothing: the reply is dropped
+        let (reply_tx, reply_rx) = flume::bounded(1);
+        let keyword = SimulatedDetection {
+            keyword: Some("dragon".to_string()),
+            emotion: None,
+            confidence: 0.9,
+        };
+        pipeline.simulate(keyword, reply_tx);
+        assert!(matches!(reply_rx.try_recv(), Err(flume::TryRecvError::Disconnected)));
+        assert_eq!(pipeline.state(), DetectionState::Listening);
+
+        // Nor does an emotion paired with the earlier simulated keyword
+        let (reply_tx, reply_rx) = flume::bounded(1);
+        let emotion = SimulatedDetection {
+            keyword: None,
+            emotion: Some("angry".to_string()),
+            confidence: 0.8,
+        };
+        pipeline.simulate(emotion, reply_tx);
+        assert!(matches!(reply_rx.try_recv(), Err(flume::TryRecvError::Disconnected)));
+
+        let (reply_tx, _reply_rx) = flume::bounded(1);
+        let both = SimulatedDetection {
+            keyword: Some("dragon".to_string()),
+            emotion: Some("angry".to_string()),
+            confidence: 0.8,
+        };
+        pipeline.simulate(both, reply_tx);
+        assert_eq!(pipeline.state(), DetectionState::Listening);
+        let dual = event_rx.drain().find_map(|e| match e {
+            PipelineEvent::DualSignal { keyword, emotion, reply } => Some((keyword, emotion, reply.is_some())),
+            _ => None,
+        });
+        assert_eq!(dual, Some(("dragon".to_string(), "angry".to_string(), true)));
+
+        let mut logger = logger.lock();
+        assert_eq!(logger.entries().len(), 5);
+        assert!(logger.entries().iter().all(|e| e.details.contains(SIMULATED_DETAIL)));
+        logger.log_transcription("back to normal");
+        assert!(!logger.entries()[5].details.contains(SIMULATED_DETAIL));
+    }
+
+    #[test]
+    fn test_stop_flushes_partial_segment() {
+        let config = PipelineConfig {
+            enable_vad: false,
+            enable_transcription: false,
+            enable_emotion: false,
+            ..PipelineConfig::default()
+        };
+        let mut pipeline = DetectionPipeline::new(config);
+        pipeline.start();
+
+        pipeline.process_audio(&[0.2; 480], 0);
+        assert_eq!(pipeline.segment_buffer.len(), 480);
+
+        pipeline.stop();
+        assert!(pipeline.segment_buffer.is_empty());
+        assert!(!pipeline.is_running());
+    }
+
+    #[test]
+    fn test_transcripts_are_stored_with_segment_bounds() {
+        let config = PipelineConfig {
+            enable_vad: false,
+            enable_transcription: false,
+            enable_emotion: false,
+            ..PipelineConfig::default()
+        };
+        let db = crate::db::Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+        repo.start_session(&crate::db::Session::new("s1".to_string(), "autonomous".to_string()))
+            .unwrap();
+
+        let mut pipeline = DetectionPipeline::new(config);
+        pipeline.set_repository(repo.clone());
+        pipeline.set_session_id("s1".to_string());
+        pipeline.start();
+
+        pipeline.process_audio(&[0.2; 480], 1000);
+        pipeline.process_audio(&[0.2; 480], 1030);
+        let (start_ms, end_ms) = pipeline.segment_bounds(pipeline.segment_buffer.len());
+        assert_eq!((start_ms, end_ms), (1000, 1060));
+
+        pipeline.store_transcript("the lich rises", Some(0.8), start_ms, end_ms);
+        let stored = repo.get_session_transcripts("s1").unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!((stored[0].start_ms, stored[0].end_ms), (1000, 1060));
+        assert_eq!(stored[0].text, "the lich rises");
+        assert!((stored[0].confidence.unwrap() - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_detection_times_out_without_dual_signal() {
+        let config = PipelineConfig {
+            enable_vad: false,
+            enable_transcription: false,
+            enable_emotion: false,
+            detection_timeout_ms: 1000,
+            ..PipelineConfig::default()
+        };
+        let mut pipeline = DetectionPipeline::new(config);
+        let (event_tx, event_rx) = flume::unbounded();
+        pipeline.set_event_sender(event_tx);
+        pipeline.start();
+
+        pipeline.fsm.process_event(&DetectionEvent::VoiceDetected);
+        pipeline.on_keyword(KeywordMatch {
+            keyword: "dragon".to_string(),
+            category: String::new(),
+            confidence: 0.9,
+            start_index: 0,
+            end_index: 6,
+        });
+        assert_eq!(pipeline.state(), DetectionState::Detecting);
+
+        pipeline.process_audio(&[0.0; 480], 0);
+        pipeline.process_audio(&[0.0; 480], 990);
+        assert_eq!(pipeline.state(), DetectionState::Detecting);
+
+        pipeline.process_audio(&[0.0; 480], 1000);
+        assert_eq!(pipeline.state(), DetectionState::Listening);
+        let errors: Vec<String> = event_rx
+            .drain()
+            .filter_map(|e| match e {
+                PipelineEvent::Error(message) => Some(message),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(errors, vec![DETECTION_TIMEOUT_MESSAGE]);
+    }
+
+    #[tokio::test]
+    async fn test_run_async_processes_queued_frames() {
+        let config = PipelineConfig {
+            enable_transcription: false,
+            enable_emotion: false,
+            ..PipelineConfig::default()
+        };
+        let pipeline = DetectionPipeline::new(config);
+        let (audio_tx, audio_rx) = flume::bounded(AUDIO_FRAME_QUEUE_CAPACITY);
+        let (event_tx, event_rx) = flume::unbounded();
+        let handle = pipeline.run_async(audio_rx, event_tx);
+
+        // A burst larger than the queue is absorbed while the task drains it
+        for i in 0..AUDIO_FRAME_QUEUE_CAPACITY * 2 {
+            let level = if (20..40).contains(&i) { 0.9 } else { 0.0 };
+            audio_tx.send_async(vec![level; 480]).await.unwrap();
+        }
+        drop(audio_tx);
+        handle.await.unwrap();
+
+        let events: Vec<_> = event_rx.drain().collect();
+        assert!(events.iter().any(|e| matches!(e, PipelineEvent::VoiceStart(600))));
+    }
+
+    #[test]
+    fn test_metrics_track_frames_and_overruns() {
+        let config = PipelineConfig {
+            enable_transcription: false,
+            enable_emotion: false,
+            ..PipelineConfig::default()
+        };
+        let mut pipeline = DetectionPipeline::new(config);
+        let (event_tx, event_rx) = flume::unbounded();
+        pipeline.set_event_sender(event_tx);
+        pipeline.start();
+
+        for i in 0..3 {
+            pipeline.process_audio(&[0.0; 480], i * 30);
+        }
+        let metrics = pipeline.metrics();
+        assert_eq!(metrics.frames_processed, 3);
+        assert!(metrics.peak_process_time_us >= metrics.avg_process_time_us);
+
+        pipeline.set_frames_dropped(2);
+        pipeline.set_frames_dropped(2);
+        pipeline.set_frames_dropped(5);
+        let overruns: Vec<_> = event_rx
+            .drain()
+            .filter_map(|e| match e {
+                PipelineEvent::Overrun(dropped) => Some(dropped),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(overruns, vec![2, 5]);
+        assert_eq!(pipeline.metrics().frames_dropped, 5);
+    }
+
+    #[test]
+    fn test_process_buffered_drains_new_audio_only() {
+        let buffer = Arc::new(RwLock::new(AudioRingBuffer::new(16000, 16000)));
+        // Without VAD gating every drained frame reaches the segment buffer
+        let config = PipelineConfig {
+            enable_vad: false,
+            ..PipelineConfig::default()
+        };
+        let mut pipeline = DetectionPipeline::new(config);
+        pipeline.set_audio_buffer(buffer.clone());
+        pipeline.start();
+
+        // 2.5 frames: two are consumed, the partial one waits
+        buffer.write().push_slice(&vec![0.0; 1200]);
+        pipeline.process_buffered(0);
+        assert_eq!(pipeline.segment_buffer.len(), 960);
+
+        pipeline.process_buffered(60);
+        assert_eq!(pipeline.segment_buffer.len(), 960);
+
+        buffer.write().push_slice(&vec![0.0; 240]);
+        pipeline.process_buffered(60);
+        assert_eq!(pipeline.segment_buffer.len(), 1440);
+    }
 }
@@ -1,5 +1,6 @@
 //! Speaker verification module
 
+use crate::db::Repository;
 use crate::error::AppError;
 use crate::state::constants::SPEAKER_SIMILARITY_THRESHOLD;
 
@@ -39,6 +40,40 @@ impl SpeakerEmbedding {
 
         dot_product / (norm_a * norm_b)
     }
+
+    /// Average several embeddings element-wise
+    pub fn mean(embeddings: &[SpeakerEmbedding]) -> SpeakerEmbedding {
+        let dimension = embeddings.iter().map(|e| e.dimension).max().unwrap_or(0);
+        let mut data = vec![0.0f32; dimension];
+
+        for embedding in embeddings {
+            for (d, v) in data.iter_mut().zip(embedding.data.iter()) {
+                *d += v;
+            }
+        }
+
+        if !embeddings.is_empty() {
+            for d in data.iter_mut() {
+                *d /= embeddings.len() as f32;
+            }
+        }
+
+        SpeakerEmbedding::new(data)
+    }
+
+    /// Serialize as little-endian f32 bytes (for BLOB storage)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.data.iter().flat_map(|v| v.to_le_bytes()).collect()
+    }
+
+    /// Deserialize from little-endian f32 bytes
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let data = bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        Self::new(data)
+    }
 }
 
 /// Speaker verification result
@@ -54,22 +89,36 @@ pub struct SpeakerVerificationResult {
 pub struct VoiceProfile {
     pub id: String,
     pub name: String,
-    pub embedding: SpeakerEmbedding,
+    /// One embedding per enrollment recording
+    pub embeddings: Vec<SpeakerEmbedding>,
     pub created_at: i64,
     pub is_default: bool,
+    /// Calibrated threshold, the verifier's threshold applies when unset
+    pub threshold: Option<f32>,
 }
 
 impl VoiceProfile {
     /// Create a new voice profile
-    pub fn new(id: String, name: String, embedding: SpeakerEmbedding) -> Self {
+    pub fn new(id: String, name: String, embeddings: Vec<SpeakerEmbedding>) -> Self {
         Self {
             id,
             name,
-            embedding,
+            embeddings,
             created_at: chrono::Utc::now().timestamp(),
             is_default: false,
+            threshold: None,
         }
     }
+
+    /// Add an enrollment embedding
+    pub fn add_embedding(&mut self, embedding: SpeakerEmbedding) {
+        self.embeddings.push(embedding);
+    }
+
+    /// Average of all enrolled embeddings
+    pub fn mean_embedding(&self) -> SpeakerEmbedding {
+        SpeakerEmbedding::mean(&self.embeddings)
+    }
 }
 
 /// Speaker verification system
@@ -88,11 +137,77 @@ impl SpeakerVerifier {
         }
     }
 
+    /// Verifier enrolled with every consented profile stored in `repo`
+    ///
+    /// Profiles keep their calibrated threshold; those without enrollment
+    /// recordings fall back to their single stored embedding.
+    pub fn from_repository(repo: &Repository) -> Result<Self, AppError> {
+        let mut verifier = Self::new();
+        for row in repo.get_voice_profiles()?.into_iter().filter(|p| p.consent_given) {
+            let mut embeddings: Vec<SpeakerEmbedding> = repo
+                .get_voice_profile_embeddings(&row.id)?
+                .iter()
+                .map(|bytes| SpeakerEmbedding::from_bytes(bytes))
+                .collect();
+            if embeddings.is_empty() {
+                embeddings.extend(row.embedding.as_deref().map(SpeakerEmbedding::from_bytes));
+            }
+            if embeddings.is_empty() {
+                continue;
+            }
+
+            let mut profile = VoiceProfile::new(row.id.clone(), row.name, embeddings);
+            profile.is_default = row.is_default;
+            profile.threshold = repo.get_voice_profile_threshold(&row.id)?;
+            verifier.enroll(profile);
+        }
+        Ok(verifier)
+    }
+
     /// Set the verification threshold
     pub fn set_threshold(&mut self, threshold: f32) {
         self.threshold = threshold.clamp(0.0, 1.0);
     }
 
+    /// Get the verification threshold
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// Calibrate the threshold at the equal error rate between genuine and impostor samples
+    ///
+    /// Scores are cosine similarities against the mean of the positive embeddings.
+    /// Sweeps 0.5..=1.0, applies the best threshold and returns it.
+    pub fn calibrate(
+        &mut self,
+        positive_embeddings: &[SpeakerEmbedding],
+        negative_embeddings: &[SpeakerEmbedding],
+    ) -> f32 {
+        if positive_embeddings.is_empty() || negative_embeddings.is_empty() {
+            return self.threshold;
+        }
+
+        let reference = SpeakerEmbedding::mean(positive_embeddings);
+        let positive: Vec<f32> = positive_embeddings.iter().map(|e| e.cosine_similarity(&reference)).collect();
+        let negative: Vec<f32> = negative_embeddings.iter().map(|e| e.cosine_similarity(&reference)).collect();
+
+        let mut best = (f32::MAX, self.threshold);
+        for step in 0..=100 {
+            let threshold = 0.5 + step as f32 * 0.005;
+            let false_reject = positive.iter().filter(|&&s| s < threshold).count() as f32 / positive.len() as f32;
+            let false_accept = negative.iter().filter(|&&s| s >= threshold).count() as f32 / negative.len() as f32;
+
+            let gap = (false_reject - false_accept).abs();
+            if gap < best.0 {
+                best = (gap, threshold);
+            }
+        }
+
+        tracing::info!("Calibrated speaker threshold: {:.3}", best.1);
+        self.set_threshold(best.1);
+        best.1
+    }
+
     /// Enroll a new voice profile
     pub fn enroll(&mut self, profile: VoiceProfile) {
         tracing::info!("Enrolling voice profile: {}", profile.name);
@@ -120,18 +235,19 @@ impl SpeakerVerifier {
             };
         }
 
-        let mut best_match: Option<(String, f32)> = None;
+        let mut best_match: Option<(&VoiceProfile, f32)> = None;
 
         for profile in &self.enrolled_profiles {
-            let similarity = embedding.cosine_similarity(&profile.embedding);
+            let similarity = embedding.cosine_similarity(&profile.mean_embedding());
 
             if best_match.is_none() || similarity > best_match.as_ref().unwrap().1 {
-                best_match = Some((profile.id.clone(), similarity));
+                best_match = Some((profile, similarity));
             }
         }
 
-        if let Some((id, similarity)) = best_match {
-            let is_verified = similarity >= self.threshold;
+        if let Some((profile, similarity)) = best_match {
+            let is_verified = similarity >= profile.threshold.unwrap_or(self.threshold);
+            let id = profile.id.clone();
             SpeakerVerificationResult {
                 is_verified,
                 similarity,
@@ -235,7 +351,7 @@ mod tests {
 
         // Create test profile
         let embedding = SpeakerEmbedding::new(vec![1.0, 0.0, 0.0]);
-        let profile = VoiceProfile::new("test".to_string(), "Test GM".to_string(), embedding);
+        let profile = VoiceProfile::new("test".to_string(), "Test GM".to_string(), vec![embedding]);
         verifier.enroll(profile);
 
         // Verify with same embedding
@@ -243,4 +359,67 @@ mod tests {
         let result = verifier.verify(&test_embedding);
         assert!(result.is_verified);
     }
+
+    #[test]
+    fn test_calibrate_separates_speakers() {
+        let mut verifier = SpeakerVerifier::new();
+        let positive = vec![
+            SpeakerEmbedding::new(vec![1.0, 0.1, 0.0]),
+            SpeakerEmbedding::new(vec![1.0, 0.0, 0.1]),
+        ];
+        let negative = vec![
+            SpeakerEmbedding::new(vec![0.0, 1.0, 0.0]),
+            SpeakerEmbedding::new(vec![0.3, 0.0, 1.0]),
+        ];
+
+        let threshold = verifier.calibrate(&positive, &negative);
+        assert!((0.5..=1.0).contains(&threshold));
+        assert_eq!(verifier.threshold(), threshold);
+
+        let reference = SpeakerEmbedding::mean(&positive);
+        assert!(positive.iter().all(|e| e.cosine_similarity(&reference) >= threshold));
+        assert!(negative.iter().all(|e| e.cosine_similarity(&reference) < threshold));
+    }
+
+    #[test]
+    fn test_calibrated_threshold_is_loaded_from_repository() {
+        let db = crate::db::Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+
+        let mut row = crate::db::VoiceProfile::new("gm".to_string(), "GM".to_string());
+        row.consent_given = true;
+        repo.insert_voice_profile(&row).unwrap();
+        repo.add_voice_profile_embedding("gm", 0, &SpeakerEmbedding::new(vec![1.0, 0.0, 0.0]).to_bytes())
+            .unwrap();
+
+        // Similarity 0.8 passes the default threshold
+        let probe = SpeakerEmbedding::new(vec![0.8, 0.6, 0.0]);
+        let verifier = SpeakerVerifier::from_repository(&repo).unwrap();
+        assert_eq!(verifier.get_profiles().len(), 1);
+        assert!(verifier.verify(&probe).is_verified);
+
+        repo.set_voice_profile_threshold("gm", 0.9).unwrap();
+        let verifier = SpeakerVerifier::from_repository(&repo).unwrap();
+        let result = verifier.verify(&probe);
+        assert!(!result.is_verified);
+        assert_eq!(result.speaker_id.as_deref(), Some("gm"));
+    }
+
+    #[test]
+    fn test_mean_embedding() {
+        let profile = VoiceProfile::new(
+            "gm".to_string(),
+            "GM".to_string(),
+            vec![
+                SpeakerEmbedding::new(vec![1.0, 0.0]),
+                SpeakerEmbedding::new(vec![0.0, 1.0]),
+            ],
+        );
+
+        let mean = profile.mean_embedding();
+        assert_eq!(mean.data, vec![0.5, 0.5]);
+
+        let restored = SpeakerEmbedding::from_bytes(&mean.to_bytes());
+        assert_eq!(restored.data, mean.data);
+    }
 }
@@ -0,0 +1,291 @@
+//! Live streaming of captured audio into the detection pipeline
+//!
+//! The capture callback must never block, so it only cuts audio into fixed
+//! frames and pushes them into a bounded channel. The `DetectionPipeline`
+//! drains it through `DetectionPipeline::run_async_with` on the blocking
+//! pool; vocabulary, mode and other changes are queued to it as tasks run
+//! between frames. When the pipeline falls behind, the oldest queued frames
+//! are dropped and counted instead of stalling capture.
+
+use crate::detection::fsm::{DetectionMode, DetectionState};
+use crate::detection::keyword::KeywordVocabulary;
+use crate::detection::pipeline::{
+    DetectionPipeline, PipelineEvent, PipelineMetrics, PipelineTask, SimulatedDetection, TriggerAction,
+};
+use crate::error::AppError;
+use crate::state::channels::AUDIO_FRAME_QUEUE_CAPACITY;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tracing::{debug, info, warn};
+
+/// Length of the frames sent to the pipeline thread
+pub const STREAM_FRAME_MS: u32 = 30;
+
+/// Counters shared between the capture callback, the pipeline thread and commands
+#[derive(Debug, Default)]
+pub struct StreamStats {
+    frames_processed: AtomicU64,
+    frames_dropped: AtomicU64,
+    state: RwLock<DetectionState>,
+    metrics: RwLock<PipelineMetrics>,
+}
+
+impl StreamStats {
+    /// Zero the counters for a new session
+    pub fn reset(&self) {
+        self.frames_processed.store(0, Ordering::Relaxed);
+        self.frames_dropped.store(0, Ordering::Relaxed);
+        *self.state.write() = DetectionState::default();
+        *self.metrics.write() = PipelineMetrics::default();
+    }
+
+    /// Latest latency and drop counters published by the pipeline thread
+    pub fn metrics(&self) -> PipelineMetrics {
+        *self.metrics.read()
+    }
+
+    /// Copy the current values
+    pub fn snapshot(&self) -> PipelineStats {
+        PipelineStats {
+            frames_processed: self.frames_processed.load(Ordering::Relaxed),
+            frames_dropped: self.frames_dropped.load(Ordering::Relaxed),
+            fsm_state: *self.state.read(),
+        }
+    }
+}
+
+/// Realtime health of the streaming pipeline
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct PipelineStats {
+    pub frames_processed: u64,
+    pub frames_dropped: u64,
+    pub fsm_state: DetectionState,
+}
+
+/// Capture-side end of the stream: cuts audio into frames and queues them
+pub struct FrameSender {
+    tx: flume::Sender<Vec<f32>>,
+    /// Used to pop the oldest frame when the queue is full
+    rx: flume::Receiver<Vec<f32>>,
+    frame_len: usize,
+    pending: Vec<f32>,
+    stats: Arc<StreamStats>,
+}
+
+impl FrameSender {
+    /// Queue `samples`, keeping any partial frame for the next call
+    pub fn push(&mut self, samples: &[f32]) {
+        self.pending.extend_from_slice(samples);
+
+        let complete = self.pending.len() / self.frame_len * self.frame_len;
+        if complete == 0 {
+            return;
+        }
+
+        let rest = self.pending.split_off(complete);
+        let ready = std::mem::replace(&mut self.pending, rest);
+        for frame in ready.chunks(self.frame_len) {
+            self.send(frame.to_vec());
+        }
+    }
+
+    fn send(&self, mut frame: Vec<f32>) {
+        loop {
+            match self.tx.try_send(frame) {
+                Ok(()) => return,
+                Err(flume::TrySendError::Full(rejected)) => {
+                    if self.rx.try_recv().is_ok() {
+                        self.stats.frames_dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                    frame = rejected;
+                }
+                Err(flume::TrySendError::Disconnected(_)) => return,
+            }
+        }
+    }
+}
+
+/// Detection pipeline running on streamed frames until stopped
+pub struct PipelineThread {
+    task_tx: flume::Sender<PipelineTask>,
+    /// Disconnects once the pipeline task has finished
+    done_rx: flume::Receiver<()>,
+    stats: Arc<StreamStats>,
+}
+
+impl PipelineThread {
+    /// Initialize and start `pipeline` on the blocking pool; audio at
+    /// `sample_rate` goes in through the returned sender
+    ///
+    /// Models load on the pipeline task, so frames sent meanwhile may be dropped.
+    pub fn spawn(
+        mut pipeline: DetectionPipeline,
+        sample_rate: u32,
+        event_tx: flume::Sender<PipelineEvent>,
+        stats: Arc<StreamStats>,
+    ) -> Result<(Self, FrameSender), AppError> {
+        let (frame_tx, frame_rx) = flume::bounded::<Vec<f32>>(AUDIO_FRAME_QUEUE_CAPACITY);
+        let (task_tx, task_rx) = flume::unbounded::<PipelineTask>();
+        let (done_tx, done_rx) = flume::bounded::<()>(1);
+
+        let sender = FrameSender {
+            tx: frame_tx,
+            rx: frame_rx.clone(),
+            frame_len: ((sample_rate * STREAM_FRAME_MS / 1000) as usize).max(1),
+            pending: Vec::new(),
+            stats: stats.clone(),
+        };
+
+        pipeline.set_sample_rate(sample_rate);
+        let frame_stats = stats.clone();
+        let on_frame = move |pipeline: &mut DetectionPipeline| {
+            pipeline.set_frames_dropped(frame_stats.frames_dropped.load(Ordering::Relaxed));
+            frame_stats.frames_processed.fetch_add(1, Ordering::Relaxed);
+            *frame_stats.state.write() = pipeline.state();
+            *frame_stats.metrics.write() = pipeline.metrics();
+        };
+
+        let runtime = tauri::async_runtime::handle();
+        let runtime = runtime.inner();
+        let handle = {
+            let _entered = runtime.enter();
+            pipeline.run_async_with(frame_rx, event_tx, task_rx, on_frame)
+        };
+        runtime.spawn(async move {
+            if handle.await.is_err() {
+                warn!("Pipeline task panicked");
+            }
+            debug!("Pipeline task stopped");
+            drop(done_tx);
+        });
+
+        info!("Streaming {} ms frames to the detection pipeline", STREAM_FRAME_MS);
+        Ok((
+            Self {
+                task_tx,
+                done_rx,
+                stats,
+            },
+            sender,
+        ))
+    }
+
+    /// Run `task` on the pipeline before the next frame is processed
+    fn queue(&self, task: impl FnOnce(&mut DetectionPipeline) + Send + 'static) -> Result<(), AppError> {
+        self.task_tx
+            .send(Box::new(task))
+            .map_err(|_| AppError::Detection("Detection pipeline is not running".to_string()))
+    }
+
+    /// Swap the keyword vocabulary before the next frame is processed
+    pub fn set_vocabulary(&self, vocabulary: KeywordVocabulary) {
+        let _ = self.queue(move |pipeline| {
+            debug!("Pipeline vocabulary updated ({} keywords)", vocabulary.len());
+            pipeline.set_vocabulary(vocabulary);
+        });
+    }
+
+    /// Switch the detection mode before the next frame is processed
+    pub fn set_mode(&self, mode: DetectionMode) {
+        let _ = self.queue(move |pipeline| {
+            debug!("Pipeline mode set to {}", mode);
+            pipeline.set_mode(mode);
+        });
+    }
+
+    /// Drop a voice profile from speaker verification before the next frame is processed
+    pub fn revoke_speaker_profile(&self, profile_id: &str) {
+        let profile_id = profile_id.to_string();
+        let _ = self.queue(move |pipeline| pipeline.revoke_speaker_profile(&profile_id));
+    }
+
+    /// Inject detections before the next frame is processed
+    ///
+    /// The returned receiver gets what was done about a dual signal the
+    /// injection confirmed; it disconnects without a message otherwise.
+    pub fn simulate(&self, detection: SimulatedDetection) -> Result<flume::Receiver<TriggerAction>, AppError> {
+        let (reply_tx, reply_rx) = flume::bounded(1);
+        let stats = self.stats.clone();
+        self.queue(move |pipeline| {
+            pipeline.simulate(detection, reply_tx);
+            *stats.state.write() = pipeline.state();
+        })?;
+        Ok(reply_rx)
+    }
+
+    /// Stop the pipeline and wait for its task to finish
+    pub fn stop(&mut self) {
+        let _ = self.queue(DetectionPipeline::stop);
+        let _ = self.done_rx.recv();
+    }
+}
+
+impl Drop for PipelineThread {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sender(capacity: usize, frame_len: usize) -> (FrameSender, flume::Receiver<Vec<f32>>, Arc<StreamStats>) {
+        let (tx, rx) = flume::bounded(capacity);
+        let stats = Arc::new(StreamStats::default());
+        let sender = FrameSender {
+            tx,
+            rx: rx.clone(),
+            frame_len,
+            pending: Vec::new(),
+            stats: stats.clone(),
+        };
+        (sender, rx, stats)
+    }
+
+    #[test]
+    fn test_push_cuts_fixed_frames() {
+        let (mut sender, rx, _) = sender(10, 4);
+        sender.push(&[1.0, 2.0, 3.0]);
+        assert!(rx.is_empty());
+
+        sender.push(&[4.0, 5.0, 6.0, 7.0, 8.0, 9.0]);
+        assert_eq!(rx.drain().collect::<Vec<_>>(), vec![vec![1.0, 2.0, 3.0, 4.0], vec![5.0, 6.0, 7.0, 8.0]]);
+        assert_eq!(sender.pending, vec![9.0]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_pipeline_thread_runs_until_stopped() {
+        let config = crate::detection::pipeline::PipelineConfig {
+            enable_transcription: false,
+            enable_emotion: false,
+            ..Default::default()
+        };
+        let stats = Arc::new(StreamStats::default());
+        let (event_tx, event_rx) = flume::unbounded();
+        let (mut thread, mut feed) =
+            PipelineThread::spawn(DetectionPipeline::new(config), 16000, event_tx, stats.clone()).unwrap();
+
+        feed.push(&[0.0; 9600]);
+        feed.push(&[0.9; 9600]);
+        while stats.snapshot().frames_processed < 40 {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+
+        // The capture side is still connected; stop must not wait for it
+        tokio::task::block_in_place(|| thread.stop());
+        assert!(event_rx.drain().any(|e| matches!(e, PipelineEvent::VoiceStart(600))));
+        assert!(thread.queue(|_| {}).is_err());
+    }
+
+    #[test]
+    fn test_full_queue_drops_oldest() {
+        let (mut sender, rx, stats) = sender(2, 1);
+        sender.push(&[1.0, 2.0, 3.0, 4.0]);
+
+        assert_eq!(rx.drain().collect::<Vec<_>>(), vec![vec![3.0], vec![4.0]]);
+        assert_eq!(stats.snapshot().frames_dropped, 2);
+    }
+}
@@ -0,0 +1,153 @@
+//! Mel spectrogram features
+
+use realfft::RealFftPlanner;
+
+/// Convert frequency in Hz to the Slaney mel scale (librosa default)
+fn hz_to_mel(hz: f32) -> f32 {
+    let f_sp = 200.0 / 3.0;
+    let min_log_hz = 1000.0;
+    let min_log_mel = min_log_hz / f_sp;
+    let log_step = (6.4f32).ln() / 27.0;
+
+    if hz >= min_log_hz {
+        min_log_mel + (hz / min_log_hz).ln() / log_step
+    } else {
+        hz / f_sp
+    }
+}
+
+/// Convert a Slaney mel value back to Hz
+fn mel_to_hz(mel: f32) -> f32 {
+    let f_sp = 200.0 / 3.0;
+    let min_log_hz = 1000.0;
+    let min_log_mel = min_log_hz / f_sp;
+    let log_step = (6.4f32).ln() / 27.0;
+
+    if mel >= min_log_mel {
+        min_log_hz * (log_step * (mel - min_log_mel)).exp()
+    } else {
+        mel * f_sp
+    }
+}
+
+/// Build a Slaney-normalized triangular mel filterbank (`n_mels` x `n_fft / 2 + 1`)
+pub fn mel_filterbank(sample_rate: u32, n_fft: usize, n_mels: usize) -> Vec<Vec<f32>> {
+    let n_bins = n_fft / 2 + 1;
+    let max_mel = hz_to_mel(sample_rate as f32 / 2.0);
+
+    let mel_points: Vec<f32> = (0..n_mels + 2)
+        .map(|i| mel_to_hz(max_mel * i as f32 / (n_mels + 1) as f32))
+        .collect();
+    let bin_hz: Vec<f32> = (0..n_bins)
+        .map(|i| i as f32 * sample_rate as f32 / n_fft as f32)
+        .collect();
+
+    (0..n_mels)
+        .map(|m| {
+            let (lower, center, upper) = (mel_points[m], mel_points[m + 1], mel_points[m + 2]);
+            let norm = 2.0 / (upper - lower);
+            bin_hz
+                .iter()
+                .map(|&f| {
+                    let rising = (f - lower) / (center - lower);
+                    let falling = (upper - f) / (upper - center);
+                    rising.min(falling).max(0.0) * norm
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Compute a power mel spectrogram (one `n_mels` vector per frame)
+///
+/// Frames are Hann-windowed and centered like librosa, with reflect padding.
+pub fn mel_spectrogram(
+    samples: &[f32],
+    sample_rate: u32,
+    n_fft: usize,
+    hop_length: usize,
+    n_mels: usize,
+) -> Vec<Vec<f32>> {
+    if samples.is_empty() || n_fft == 0 || hop_length == 0 {
+        return Vec::new();
+    }
+
+    let pad = n_fft / 2;
+    let padded: Vec<f32> = (0..samples.len() + 2 * pad)
+        .map(|i| {
+            let idx = i as isize - pad as isize;
+            let last = samples.len() as isize - 1;
+            let reflected = if idx < 0 {
+                -idx
+            } else if idx > last {
+                2 * last - idx
+            } else {
+                idx
+            };
+            samples[reflected.clamp(0, last) as usize]
+        })
+        .collect();
+
+    let window: Vec<f32> = (0..n_fft)
+        .map(|i| 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / n_fft as f32).cos())
+        .collect();
+    let filterbank = mel_filterbank(sample_rate, n_fft, n_mels);
+
+    let mut planner = RealFftPlanner::<f32>::new();
+    let fft = planner.plan_fft_forward(n_fft);
+    let mut input = fft.make_input_vec();
+    let mut spectrum = fft.make_output_vec();
+    let mut power = vec![0.0f32; spectrum.len()];
+
+    let n_frames = 1 + (padded.len() - n_fft) / hop_length;
+    let mut frames = Vec::with_capacity(n_frames);
+
+    for frame in 0..n_frames {
+        let start = frame * hop_length;
+        for (i, value) in input.iter_mut().enumerate() {
+            *value = padded[start + i] * window[i];
+        }
+
+        if fft.process(&mut input, &mut spectrum).is_err() {
+            continue;
+        }
+
+        for (p, c) in power.iter_mut().zip(spectrum.iter()) {
+            *p = c.norm_sqr();
+        }
+
+        frames.push(
+            filterbank
+                .iter()
+                .map(|filter| filter.iter().zip(power.iter()).map(|(w, p)| w * p).sum())
+                .collect(),
+        );
+    }
+
+    frames
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mel_spectrogram_shape() {
+        let samples: Vec<f32> = (0..16000)
+            .map(|i| (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 16000.0).sin())
+            .collect();
+
+        let frames = mel_spectrogram(&samples, 16000, 400, 160, 40);
+        assert_eq!(frames.len(), 101);
+        assert!(frames.iter().all(|f| f.len() == 40));
+
+        // A 440 Hz tone puts its energy in the low bands
+        let loudest = frames[50]
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(i, _)| i)
+            .unwrap();
+        assert!(loudest < 10);
+    }
+}
@@ -1,3 +1,4 @@
 //! Digital Signal Processing module
 
+pub mod mel;
 pub mod processing;
@@ -157,6 +157,84 @@ pub fn stereo_to_mono(samples: &[f32], channels: u16) -> Vec<f32> {
     mono
 }
 
+/// Hop between onset envelope frames in `detect_bpm`
+const BPM_HOP_SIZE: usize = 512;
+
+/// Tempo range considered by `detect_bpm`
+const MIN_BPM: f32 = 60.0;
+const MAX_BPM: f32 = 200.0;
+
+/// Estimate the tempo of mono audio in beats per minute
+///
+/// Builds an onset envelope from the positive energy flux between 512-sample
+/// hops, then picks the strongest autocorrelation lag in the 60–200 BPM range.
+/// A peak at half that lag wins when it is nearly as strong, since a beat
+/// train also correlates at twice its period. Returns None for audio that is
+/// too short or has no rhythmic content.
+pub fn detect_bpm(samples: &[f32], sample_rate: u32) -> Option<f32> {
+    if sample_rate == 0 {
+        return None;
+    }
+
+    let energies: Vec<f32> = samples
+        .chunks_exact(BPM_HOP_SIZE)
+        .map(|hop| hop.iter().map(|s| s * s).sum())
+        .collect();
+    let flux: Vec<f32> = energies.windows(2).map(|w| (w[1] - w[0]).max(0.0)).collect();
+
+    // Light smoothing so beats falling between hops still line up
+    let mut envelope: Vec<f32> = (0..flux.len())
+        .map(|i| {
+            let start = i.saturating_sub(1);
+            let end = (i + 2).min(flux.len());
+            flux[start..end].iter().sum::<f32>() / (end - start) as f32
+        })
+        .collect();
+    let mean = envelope.iter().sum::<f32>() / envelope.len().max(1) as f32;
+    envelope.iter_mut().for_each(|v| *v -= mean);
+
+    let frame_rate = sample_rate as f32 / BPM_HOP_SIZE as f32;
+    let min_lag = (60.0 * frame_rate / MAX_BPM).floor().max(1.0) as usize;
+    let max_lag = (60.0 * frame_rate / MIN_BPM).ceil() as usize;
+    if envelope.len() < max_lag * 2 {
+        return None;
+    }
+
+    let autocorrelation = |lag: usize| -> f32 {
+        let n = envelope.len() - lag;
+        envelope[..n].iter().zip(&envelope[lag..]).map(|(a, b)| a * b).sum::<f32>() / n as f32
+    };
+    let scores: Vec<f32> = (min_lag..=max_lag).map(autocorrelation).collect();
+    let score = |lag: usize| scores[lag - min_lag];
+
+    let mut best = (min_lag..=max_lag).max_by(|a, b| score(*a).total_cmp(&score(*b)))?;
+    if score(best) <= 0.0 {
+        return None;
+    }
+
+    let half = best / 2;
+    if half >= min_lag {
+        let candidate = (half..=(half + 1).min(max_lag)).max_by(|a, b| score(*a).total_cmp(&score(*b)))?;
+        if score(candidate) >= 0.5 * score(best) {
+            best = candidate;
+        }
+    }
+
+    // Parabolic interpolation for a fractional lag
+    let mut lag = best as f32;
+    if best > min_lag && best < max_lag {
+        let (prev, peak, next) = (score(best - 1), score(best), score(best + 1));
+        let denominator = prev - 2.0 * peak + next;
+        if denominator.abs() > f32::EPSILON {
+            lag += (0.5 * (prev - next) / denominator).clamp(-0.5, 0.5);
+        }
+    }
+
+    let bpm = 60.0 * frame_rate / lag;
+    debug!("Detected {:.1} BPM", bpm);
+    Some(bpm)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -175,6 +253,29 @@ mod tests {
         assert!((rms - 1.0).abs() < 0.001);
     }
 
+    fn click_track(bpm: f32, sample_rate: u32, seconds: f32) -> Vec<f32> {
+        let mut samples = vec![0.0f32; (sample_rate as f32 * seconds) as usize];
+        let period = (60.0 / bpm * sample_rate as f32) as usize;
+        let click_len = sample_rate as usize / 100;
+        for start in (0..samples.len()).step_by(period) {
+            for (i, sample) in samples[start..].iter_mut().take(click_len).enumerate() {
+                *sample = (i as f32 * 0.3).sin() * (1.0 - i as f32 / click_len as f32);
+            }
+        }
+        samples
+    }
+
+    #[test]
+    fn test_detect_bpm_finds_click_tempo() {
+        for (bpm, rate) in [(120.0, 22050), (90.0, 44100), (174.0, 44100)] {
+            let detected = detect_bpm(&click_track(bpm, rate, 20.0), rate).unwrap();
+            assert!((detected - bpm).abs() < 2.0, "expected {} BPM, got {}", bpm, detected);
+        }
+
+        assert_eq!(detect_bpm(&vec![0.0; 44100 * 10], 44100), None);
+        assert_eq!(detect_bpm(&click_track(120.0, 44100, 1.0), 44100), None);
+    }
+
     #[test]
     fn test_resample() {
         let samples = vec![1.0, 2.0, 3.0, 4.0];
@@ -1,5 +1,9 @@
 //! Application error types
 
+use crate::audio::capture::CaptureError;
+use crate::inference::emotion::EmotionError;
+use crate::inference::whisper::WhisperError;
+use std::path::PathBuf;
 use thiserror::Error;
 
 /// Main application error type
@@ -37,11 +41,48 @@ pub enum AppError {
 
     #[error("Serialization error: {0}")]
     Serialization(String),
+
+    #[error("Timeout: {0}")]
+    Timeout(String),
+
+    #[error("Model not found: {}", .0.display())]
+    ModelNotFound(PathBuf),
+
+    /// A requested row does not exist, e.g. "Session not found: <id>"
+    #[error("{0}")]
+    NotFound(String),
+}
+
+impl AppError {
+    /// What to tell the user, without the underlying technical detail
+    ///
+    /// `Display` keeps the detail for logs.
+    pub fn user_message(&self) -> &str {
+        match self {
+            AppError::Audio(_) => "Microphone error — check your audio settings",
+            AppError::Playback(_) => "Playback error — check your audio output device",
+            AppError::Database(_) => "The app database could not be accessed",
+            AppError::Inference(_) => "A detection model failed — check that the model files are installed",
+            AppError::Config(_) => "Invalid settings — check your configuration",
+            AppError::State(_) => "That action isn't available right now",
+            AppError::Detection(_) => "Detection failed — try restarting the session",
+            AppError::Profile(_) => "Voice profile error — check your voice training",
+            AppError::Hotkey(_) => "The hotkey could not be registered — it may be used by another app",
+            AppError::Io(_) => "A file could not be read or written",
+            AppError::Serialization(_) => "Saved data could not be read — it may be corrupted",
+            AppError::Timeout(_) => "The operation took too long — try again",
+            AppError::ModelNotFound(_) => "A model file is missing — download the detection models",
+            AppError::NotFound(_) => "The requested item no longer exists",
+        }
+    }
 }
 
 impl From<rusqlite::Error> for AppError {
     fn from(e: rusqlite::Error) -> Self {
-        AppError::Database(e.to_string())
+        match e {
+            rusqlite::Error::QueryReturnedNoRows => AppError::NotFound(e.to_string()),
+            e => AppError::Database(e.to_string()),
+        }
     }
 }
 
@@ -51,6 +92,13 @@ impl From<r2d2::Error> for AppError {
     }
 }
 
+#[cfg(feature = "ort")]
+impl<R> From<ort::Error<R>> for AppError {
+    fn from(e: ort::Error<R>) -> Self {
+        AppError::Inference(e.to_string())
+    }
+}
+
 impl From<std::io::Error> for AppError {
     fn from(e: std::io::Error) -> Self {
         AppError::Io(e.to_string())
@@ -62,6 +110,177 @@ impl serde::Serialize for AppError {
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        serializer.serialize_str(self.user_message())
+    }
+}
+
+/// Stable error category the UI can key behavior off
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// The app is not in a state that allows the request, e.g. a session is running
+    InvalidState,
+    /// No usable audio device
+    DeviceUnavailable,
+    /// The database is missing or failing
+    DatabaseUnavailable,
+    /// A model file is missing or failed to load
+    ModelMissing,
+    /// The requested item does not exist
+    NotFound,
+    /// The request itself is invalid
+    Validation,
+    /// Anything else
+    Internal,
+}
+
+/// Error returned by Tauri commands
+#[derive(Error, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[error("{message}")]
+pub struct CommandError {
+    pub code: ErrorCode,
+    /// Human-readable description
+    pub message: String,
+    /// Extra data for the UI, e.g. the offending id
+    pub details: Option<serde_json::Value>,
+}
+
+impl CommandError {
+    /// Create an error without details
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    /// With details
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
+    pub fn invalid_state(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidState, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Validation, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+}
+
+/// Shows the user message; the technical `Display` text goes in `details`
+///
+/// Validation, state and not-found errors keep their specific message, which
+/// says what to change.
+impl From<AppError> for CommandError {
+    fn from(e: AppError) -> Self {
+        let code = match &e {
+            AppError::Audio(_) => ErrorCode::DeviceUnavailable,
+            AppError::Database(_) => ErrorCode::DatabaseUnavailable,
+            AppError::State(_) => ErrorCode::InvalidState,
+            AppError::ModelNotFound(_) => ErrorCode::ModelMissing,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::Profile(_) | AppError::Hotkey(_) | AppError::Config(_) => ErrorCode::Validation,
+            AppError::Playback(_)
+            | AppError::Inference(_)
+            | AppError::Detection(_)
+            | AppError::Io(_)
+            | AppError::Serialization(_)
+            | AppError::Timeout(_) => ErrorCode::Internal,
+        };
+        let message = match code {
+            ErrorCode::InvalidState | ErrorCode::Validation | ErrorCode::NotFound => e.to_string(),
+            _ => e.user_message().to_string(),
+        };
+        Self::new(code, message).with_details(serde_json::Value::String(e.to_string()))
+    }
+}
+
+impl From<CaptureError> for CommandError {
+    fn from(e: CaptureError) -> Self {
+        let code = match &e {
+            CaptureError::ThreadError(_) | CaptureError::RecordingError(_) => ErrorCode::Internal,
+            _ => ErrorCode::DeviceUnavailable,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<WhisperError> for CommandError {
+    fn from(e: WhisperError) -> Self {
+        let code = match &e {
+            WhisperError::NotInitialized
+            | WhisperError::ModelLoadError(_)
+            | WhisperError::ModelNotFound(_)
+            | WhisperError::FeatureNotEnabled => ErrorCode::ModelMissing,
+            WhisperError::InferenceError(_) | WhisperError::AudioError(_) => ErrorCode::Internal,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<EmotionError> for CommandError {
+    fn from(e: EmotionError) -> Self {
+        let code = match &e {
+            EmotionError::NotInitialized | EmotionError::ModelLoadError(_) => ErrorCode::ModelMissing,
+            EmotionError::InsufficientData(_) => ErrorCode::Validation,
+            EmotionError::AnalysisError(_) => ErrorCode::Internal,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+/// Commands not migrated to `CommandError` yet still return plain strings
+impl From<CommandError> for String {
+    fn from(e: CommandError) -> Self {
+        e.message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_error_codes_are_stable() {
+        let error = CommandError::from(CaptureError::NoInputDevice);
+        assert_eq!(error.code, ErrorCode::DeviceUnavailable);
+        assert_eq!(error.message, "No input device available");
+
+        assert_eq!(CommandError::from(WhisperError::FeatureNotEnabled).code, ErrorCode::ModelMissing);
+        assert_eq!(CommandError::from(AppError::Database("locked".to_string())).code, ErrorCode::DatabaseUnavailable);
+        let missing = AppError::from(rusqlite::Error::QueryReturnedNoRows);
+        assert_eq!(CommandError::from(missing).code, ErrorCode::NotFound);
+
+        let error = CommandError::from(AppError::Database("disk I/O error".to_string()));
+        assert_eq!(error.message, "The app database could not be accessed");
+        assert_eq!(error.details, Some(serde_json::json!("Database error: disk I/O error")));
+        let error = CommandError::from(AppError::State("Session already running".to_string()));
+        assert_eq!(error.message, "State error: Session already running");
+
+        let json = serde_json::to_value(CommandError::not_found("Session not found: s1")).unwrap();
+        assert_eq!(json["code"], "not_found");
+        assert_eq!(json["message"], "Session not found: s1");
+        assert!(json["details"].is_null());
+    }
+
+    #[test]
+    fn test_app_error_serializes_user_message() {
+        let error = AppError::Audio("The requested device is no longer available".to_string());
+        assert_eq!(error.to_string(), "Audio error: The requested device is no longer available");
+        assert_eq!(
+            serde_json::to_value(&error).unwrap(),
+            "Microphone error — check your audio settings"
+        );
     }
 }
@@ -4,16 +4,35 @@
 //! - Next: Skip to next track/mood
 //! - Shift: Switch between autonomous/collaborative mode
 //! - Hold/Lock: Hold current music or lock to current mood
+//! - Stop: Stop all playback
+//! - Volume: Raise, lower or mute the music
+//! - SFX: Play a stored sound effect
+//!
+//! Next, Hold, Lock and ToggleRecording can be bound but do nothing yet.
 
+use crate::db::{DbPool, Repository};
 use crate::error::AppError;
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use parking_lot::RwLock;
 
+/// Settings key holding the JSON list of customized bindings
+pub const HOTKEYS_SETTING: &str = "hotkeys";
+
+/// Music volume change per volume key press, unless the binding sets a `step`
+pub const DEFAULT_VOLUME_STEP: f32 = 0.05;
+
+/// Most SFX that can have a hotkey at once, one per function key
+pub const MAX_SFX_HOTKEYS: usize = 12;
+
 /// Hotkey action
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum HotkeyAction {
     /// Play next track
@@ -28,6 +47,24 @@ pub enum HotkeyAction {
     ToggleRecording,
     /// Emergency stop
     Stop,
+    /// Raise music volume
+    VolumeUp,
+    /// Lower music volume
+    VolumeDown,
+    /// Mute music, or restore the volume it had before muting
+    MuteToggle,
+    /// Play the SFX with this id from the `sfx` table
+    PlaySfxById(String),
+}
+
+impl HotkeyAction {
+    /// Id of the SFX this action plays, if any
+    pub fn sfx_id(&self) -> Option<&str> {
+        match self {
+            HotkeyAction::PlaySfxById(sfx_id) => Some(sfx_id),
+            _ => None,
+        }
+    }
 }
 
 /// Hotkey configuration
@@ -39,6 +76,12 @@ pub struct HotkeyConfig {
     pub key: String,
     /// Action to perform
     pub action: HotkeyAction,
+    /// Action-specific options, e.g. `{"step": 0.1}` for the volume keys
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+    /// Id of the OS registration (None until registered)
+    #[serde(skip)]
+    pub id: Option<u32>,
 }
 
 impl HotkeyConfig {
@@ -48,6 +91,8 @@ impl HotkeyConfig {
             modifiers: vec![],
             key,
             action,
+            metadata: None,
+            id: None,
         }
     }
 
@@ -56,6 +101,77 @@ impl HotkeyConfig {
         self.modifiers = modifiers;
         self
     }
+
+    /// Volume change per press for the volume keys
+    pub fn volume_step(&self) -> f32 {
+        self.metadata
+            .as_ref()
+            .and_then(|m| m.get("step"))
+            .and_then(|step| step.as_f64())
+            .map(|step| (step as f32).clamp(0.0, 1.0))
+            .unwrap_or(DEFAULT_VOLUME_STEP)
+    }
+
+    /// Key combination in canonical form: lowercase, modifiers sorted
+    pub fn combo(&self) -> (Vec<String>, String) {
+        let mut modifiers: Vec<String> = self.modifiers.iter().map(|m| m.to_lowercase()).collect();
+        modifiers.sort();
+        modifiers.dedup();
+        (modifiers, self.key.to_lowercase())
+    }
+
+    /// OS hotkey for this combo, e.g. "ctrl+shift+n"
+    pub fn hotkey(&self) -> Result<HotKey, AppError> {
+        let (mut parts, key) = self.combo();
+        parts.push(key);
+        let combo = parts.join("+");
+        combo
+            .parse::<HotKey>()
+            .map_err(|e| AppError::Hotkey(format!("Unsupported key combo {}: {}", combo, e)))
+    }
+}
+
+/// Volume key state: remembers the volume to restore after a mute
+#[derive(Debug, Default)]
+pub struct VolumeKeys {
+    pre_mute: Option<f32>,
+}
+
+impl VolumeKeys {
+    /// New music volume for a volume action pressed at `current`
+    ///
+    /// Returns None for actions that do not change the volume.
+    pub fn target(&mut self, action: HotkeyAction, step: f32, current: f32) -> Option<f32> {
+        match action {
+            HotkeyAction::VolumeUp => {
+                self.pre_mute = None;
+                Some((current + step).min(1.0))
+            }
+            HotkeyAction::VolumeDown => {
+                self.pre_mute = None;
+                Some((current - step).max(0.0))
+            }
+            HotkeyAction::MuteToggle => match self.pre_mute.take() {
+                Some(volume) => Some(volume),
+                None => {
+                    self.pre_mute = Some(current);
+                    Some(0.0)
+                }
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Canonical key combination, see `HotkeyConfig::combo`
+pub type KeyCombo = (Vec<String>, String);
+
+/// Several actions bound to the same combo
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HotkeyConflict {
+    pub modifiers: Vec<String>,
+    pub key: String,
+    pub actions: Vec<HotkeyAction>,
 }
 
 /// Hotkey event
@@ -65,90 +181,397 @@ pub struct HotkeyEvent {
     pub timestamp: std::time::SystemTime,
 }
 
-/// Hotkey manager
-pub struct HotkeyManager {
+/// Bindings and event routing, shared with the OS event listener
+pub(crate) struct Bindings {
     /// Registered hotkeys
     hotkeys: RwLock<HashMap<HotkeyAction, HotkeyConfig>>,
+    /// Action owning each combo, rebuilt from `hotkeys` (locked after it)
+    used_combos: RwLock<HashMap<KeyCombo, HotkeyAction>>,
     /// Event sender
     event_tx: RwLock<Option<flume::Sender<HotkeyEvent>>>,
     /// Is enabled
     enabled: RwLock<bool>,
 }
 
+impl Bindings {
+    fn handle_event(&self, action: HotkeyAction) {
+        if !*self.enabled.read() {
+            return;
+        }
+
+        tracing::debug!("Hotkey triggered: {:?}", action);
+
+        if let Some(tx) = self.event_tx.read().as_ref() {
+            let event = HotkeyEvent {
+                action,
+                timestamp: std::time::SystemTime::now(),
+            };
+            let _ = tx.send(event);
+        }
+    }
+
+    /// Rebuild `used_combos`; a shared combo goes to the first action
+    fn index_combos(&self, hotkeys: &HashMap<HotkeyAction, HotkeyConfig>) {
+        let mut configs: Vec<&HotkeyConfig> = hotkeys.values().collect();
+        configs.sort_by(|a, b| b.action.cmp(&a.action));
+        *self.used_combos.write() = configs.into_iter().map(|c| (c.combo(), c.action.clone())).collect();
+    }
+
+    /// Action whose OS registration has `id`
+    fn action_for(&self, id: u32) -> Option<HotkeyAction> {
+        self.hotkeys.read().values().find(|c| c.id == Some(id)).map(|c| c.action.clone())
+    }
+}
+
+/// Registers key combos with the platform
+pub(crate) trait Registrar: Send + Sync {
+    fn register(&self, hotkey: HotKey) -> Result<(), AppError>;
+    fn unregister(&self, hotkey: HotKey);
+}
+
+thread_local! {
+    /// Platform manager, owned by the thread that created it
+    ///
+    /// The manager is not thread-safe on every platform, so it never leaves
+    /// that thread (the main thread, where the app state is built).
+    static OS_MANAGER: RefCell<Option<GlobalHotKeyManager>> = const { RefCell::new(None) };
+}
+
+/// OS-level registration of the bindings, through the thread's `OS_MANAGER`
+struct OsHotkeys;
+
+impl OsHotkeys {
+    /// Connect to the platform hotkey API and route presses to `bindings`
+    ///
+    /// Returns None when no hotkey API is available; bindings then only
+    /// fire through `HotkeyManager::handle_event`.
+    fn start(bindings: &Arc<Bindings>) -> Option<Self> {
+        // Unit tests run headless
+        if cfg!(test) {
+            return None;
+        }
+
+        if OS_MANAGER.with_borrow(|manager| manager.is_none()) {
+            match GlobalHotKeyManager::new() {
+                Ok(manager) => OS_MANAGER.set(Some(manager)),
+                Err(e) => {
+                    tracing::warn!("Global hotkeys unavailable: {}", e);
+                    return None;
+                }
+            }
+        }
+
+        let bindings = Arc::downgrade(bindings);
+        let spawned = std::thread::Builder::new()
+            .name("global-hotkeys".to_string())
+            .spawn(move || {
+                while let Ok(event) = GlobalHotKeyEvent::receiver().recv() {
+                    let Some(bindings) = bindings.upgrade() else {
+                        break;
+                    };
+                    if event.state != HotKeyState::Pressed {
+                        continue;
+                    }
+                    if let Some(action) = bindings.action_for(event.id) {
+                        bindings.handle_event(action);
+                    }
+                }
+            });
+        if let Err(e) = spawned {
+            tracing::warn!("Global hotkeys will not be handled: {}", e);
+            return None;
+        }
+
+        Some(Self)
+    }
+}
+
+impl Registrar for OsHotkeys {
+    fn register(&self, hotkey: HotKey) -> Result<(), AppError> {
+        OS_MANAGER.with_borrow(|manager| match manager {
+            Some(manager) => manager
+                .register(hotkey)
+                .map_err(|e| AppError::Hotkey(format!("Failed to register {}: {}", hotkey, e))),
+            None => Err(AppError::Hotkey(format!(
+                "Cannot register {} off the thread that owns global hotkeys",
+                hotkey
+            ))),
+        })
+    }
+
+    fn unregister(&self, hotkey: HotKey) {
+        OS_MANAGER.with_borrow(|manager| match manager {
+            Some(manager) => {
+                if let Err(e) = manager.unregister(hotkey) {
+                    tracing::warn!("Failed to unregister {}: {}", hotkey, e);
+                }
+            }
+            None => tracing::warn!("Cannot unregister {} off the thread that owns global hotkeys", hotkey),
+        })
+    }
+}
+
+/// Registrar standing in for the OS in tests
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct FakeRegistrar {
+    /// Ids of the registered hotkeys
+    pub registered: Arc<parking_lot::Mutex<std::collections::HashSet<u32>>>,
+    /// Ids the fake OS refuses to register
+    pub refused: std::collections::HashSet<u32>,
+}
+
+#[cfg(test)]
+impl Registrar for FakeRegistrar {
+    fn register(&self, hotkey: HotKey) -> Result<(), AppError> {
+        if self.refused.contains(&hotkey.id()) || !self.registered.lock().insert(hotkey.id()) {
+            return Err(AppError::Hotkey(format!("Failed to register {}", hotkey)));
+        }
+        Ok(())
+    }
+
+    fn unregister(&self, hotkey: HotKey) {
+        self.registered.lock().remove(&hotkey.id());
+    }
+}
+
+/// Unregister the OS hotkey of `config`, if it has a valid combo
+fn unregister_config(os: &dyn Registrar, config: &HotkeyConfig) {
+    if let Ok(hotkey) = config.hotkey() {
+        os.unregister(hotkey);
+    }
+}
+
+/// Hotkey manager
+pub struct HotkeyManager {
+    bindings: Arc<Bindings>,
+    /// Platform registration (None when unavailable)
+    os: Option<Box<dyn Registrar>>,
+    /// Bumped on every binding change so the OS registration can be refreshed
+    version: AtomicU64,
+}
+
 impl HotkeyManager {
-    /// Create a new hotkey manager
+    /// Create a new hotkey manager registering with the OS
+    ///
+    /// Bindings can only be changed from the thread that first created a
+    /// manager, which for the app is the main thread.
     pub fn new() -> Self {
-        Self {
+        Self::with_registrar(|bindings| OsHotkeys::start(bindings).map(|os| Box::new(os) as Box<dyn Registrar>))
+    }
+
+    /// Create a manager whose bindings are registered by `start(bindings)`
+    pub(crate) fn with_registrar(start: impl FnOnce(&Arc<Bindings>) -> Option<Box<dyn Registrar>>) -> Self {
+        let bindings = Arc::new(Bindings {
             hotkeys: RwLock::new(HashMap::new()),
+            used_combos: RwLock::new(HashMap::new()),
             event_tx: RwLock::new(None),
             enabled: RwLock::new(true),
+        });
+        let os = start(&bindings);
+
+        Self {
+            bindings,
+            os,
+            version: AtomicU64::new(0),
         }
     }
 
-    /// Register a hotkey
-    pub fn register(&self, config: HotkeyConfig) -> Result<(), AppError> {
+    /// Create a manager holding `default_hotkeys()`
+    pub fn with_defaults() -> Self {
+        let manager = Self::new();
+        manager.replace_all(default_hotkeys());
+        manager
+    }
+
+    /// Register a hotkey, replacing the action's previous binding
+    ///
+    /// Fails if a different action already uses the combo.
+    pub fn register(&self, mut config: HotkeyConfig) -> Result<(), AppError> {
         tracing::info!("Registering hotkey: {:?} + {:?}", config.modifiers, config.key);
+        let hotkey = config.hotkey()?;
 
-        let mut hotkeys = self.hotkeys.write();
-        hotkeys.insert(config.action, config);
+        let mut hotkeys = self.bindings.hotkeys.write();
+        if let Some(action) = self.bindings.used_combos.read().get(&config.combo()) {
+            if *action != config.action {
+                return Err(AppError::Hotkey(format!("Combo already assigned to {:?}", action)));
+            }
+        }
+
+        if let Some(os) = &self.os {
+            if let Some(previous) = hotkeys.get(&config.action) {
+                unregister_config(os.as_ref(), previous);
+            }
+            if let Err(e) = os.register(hotkey) {
+                // Keep the previous binding working
+                if let Some(previous) = hotkeys.get(&config.action).and_then(|c| c.hotkey().ok()) {
+                    let _ = os.register(previous);
+                }
+                return Err(e);
+            }
+            config.id = Some(hotkey.id());
+        }
+        hotkeys.insert(config.action.clone(), config);
+        self.bindings.index_combos(&hotkeys);
+        self.version.fetch_add(1, Ordering::Relaxed);
 
         Ok(())
     }
 
     /// Unregister a hotkey
-    pub fn unregister(&self, action: HotkeyAction) {
+    pub fn unregister(&self, action: &HotkeyAction) {
         tracing::info!("Unregistering hotkey: {:?}", action);
-        let mut hotkeys = self.hotkeys.write();
-        hotkeys.remove(&action);
+        let mut hotkeys = self.bindings.hotkeys.write();
+        if let Some(config) = hotkeys.remove(action) {
+            if let Some(os) = &self.os {
+                unregister_config(os.as_ref(), &config);
+            }
+        }
+        self.bindings.index_combos(&hotkeys);
+        self.version.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Replace every binding at once
+    ///
+    /// Bindings the OS refuses are kept, so they still show up and can be
+    /// rebound, but do not fire.
+    pub fn replace_all(&self, configs: Vec<HotkeyConfig>) {
+        let mut hotkeys = self.bindings.hotkeys.write();
+        if let Some(os) = &self.os {
+            for config in hotkeys.values() {
+                unregister_config(os.as_ref(), config);
+            }
+        }
+        hotkeys.clear();
+        for mut config in configs {
+            if let Some(os) = &self.os {
+                match config.hotkey().and_then(|hotkey| os.register(hotkey).map(|_| hotkey)) {
+                    Ok(hotkey) => config.id = Some(hotkey.id()),
+                    Err(e) => tracing::warn!("Hotkey {:?} not active: {}", config.action, e),
+                }
+            }
+            hotkeys.insert(config.action.clone(), config);
+        }
+        self.bindings.index_combos(&hotkeys);
+        self.version.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Store every binding in the settings table
+    pub fn save_hotkeys(&self, pool: &DbPool) -> Result<(), AppError> {
+        let json = serde_json::to_string(&self.get_all_hotkeys())
+            .map_err(|e| AppError::Serialization(e.to_string()))?;
+        Repository::new(pool.clone()).set_setting(HOTKEYS_SETTING, &json)
+    }
+
+    /// Replace the bindings with the stored ones, or the defaults when none were saved
+    ///
+    /// Actions added since the bindings were saved get their default combo
+    /// unless it is already taken. Stored bindings that cannot be registered
+    /// are skipped.
+    pub fn load_hotkeys(&self, pool: &DbPool) -> Result<(), AppError> {
+        let Some(json) = Repository::new(pool.clone()).get_setting(HOTKEYS_SETTING)? else {
+            self.replace_all(default_hotkeys());
+            return Ok(());
+        };
+        let mut hotkeys: Vec<HotkeyConfig> =
+            serde_json::from_str(&json).map_err(|e| AppError::Serialization(e.to_string()))?;
+
+        for default in default_hotkeys() {
+            let bound = hotkeys
+                .iter()
+                .any(|c| c.action == default.action || c.combo() == default.combo());
+            if !bound {
+                hotkeys.push(default);
+            }
+        }
+
+        self.replace_all(Vec::new());
+        for config in hotkeys {
+            let action = config.action.clone();
+            if let Err(e) = self.register(config) {
+                tracing::warn!("Skipping saved hotkey {:?}: {}", action, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Action other than `config.action` already bound to the same combo
+    pub fn conflict(&self, config: &HotkeyConfig) -> Option<HotkeyAction> {
+        let combo = config.combo();
+        self.bindings
+            .hotkeys
+            .read()
+            .values()
+            .find(|c| c.action != config.action && c.combo() == combo)
+            .map(|c| c.action.clone())
+    }
+
+    /// Action bound to `combo`
+    pub fn action_for_combo(&self, combo: &KeyCombo) -> Option<HotkeyAction> {
+        self.bindings.used_combos.read().get(combo).cloned()
+    }
+
+    /// Combos bound to more than one action, e.g. from edited saved settings
+    pub fn conflicts(&self) -> Vec<HotkeyConflict> {
+        let mut by_combo: HashMap<KeyCombo, Vec<HotkeyAction>> = HashMap::new();
+        for config in self.bindings.hotkeys.read().values() {
+            by_combo.entry(config.combo()).or_default().push(config.action.clone());
+        }
+
+        let mut conflicts: Vec<HotkeyConflict> = by_combo
+            .into_iter()
+            .filter(|(_, actions)| actions.len() > 1)
+            .map(|((modifiers, key), mut actions)| {
+                actions.sort();
+                HotkeyConflict { modifiers, key, actions }
+            })
+            .collect();
+        conflicts.sort_by(|a, b| a.actions[0].cmp(&b.actions[0]));
+        conflicts
+    }
+
+    /// Binding version, incremented whenever a hotkey changes
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Relaxed)
     }
 
     /// Set event sender
     pub fn set_event_sender(&self, tx: flume::Sender<HotkeyEvent>) {
-        *self.event_tx.write() = Some(tx);
+        *self.bindings.event_tx.write() = Some(tx);
     }
 
     /// Enable hotkeys
     pub fn enable(&self) {
-        *self.enabled.write() = true;
+        *self.bindings.enabled.write() = true;
         tracing::info!("Hotkeys enabled");
     }
 
     /// Disable hotkeys
     pub fn disable(&self) {
-        *self.enabled.write() = false;
+        *self.bindings.enabled.write() = false;
         tracing::info!("Hotkeys disabled");
     }
 
     /// Check if enabled
     pub fn is_enabled(&self) -> bool {
-        *self.enabled.read()
+        *self.bindings.enabled.read()
     }
 
     /// Get hotkey config
-    pub fn get_hotkey(&self, action: HotkeyAction) -> Option<HotkeyConfig> {
-        self.hotkeys.read().get(&action).cloned()
+    pub fn get_hotkey(&self, action: &HotkeyAction) -> Option<HotkeyConfig> {
+        self.bindings.hotkeys.read().get(action).cloned()
     }
 
-    /// Get all hotkeys
+    /// Get all hotkeys, in action order
     pub fn get_all_hotkeys(&self) -> Vec<HotkeyConfig> {
-        self.hotkeys.read().values().cloned().collect()
+        let mut hotkeys: Vec<HotkeyConfig> = self.bindings.hotkeys.read().values().cloned().collect();
+        hotkeys.sort_by(|a, b| a.action.cmp(&b.action));
+        hotkeys
     }
 
     /// Handle hotkey event
     pub fn handle_event(&self, action: HotkeyAction) {
-        if !self.is_enabled() {
-            return;
-        }
-
-        tracing::debug!("Hotkey triggered: {:?}", action);
-
-        if let Some(tx) = self.event_tx.read().as_ref() {
-            let event = HotkeyEvent {
-                action,
-                timestamp: std::time::SystemTime::now(),
-            };
-            let _ = tx.send(event);
-        }
+        self.bindings.handle_event(action);
     }
 }
 
@@ -173,6 +596,12 @@ pub fn default_hotkeys() -> Vec<HotkeyConfig> {
             .with_modifiers(vec!["ctrl".to_string()]),
         HotkeyConfig::new("escape".to_string(), HotkeyAction::Stop)
             .with_modifiers(vec!["ctrl".to_string()]),
+        HotkeyConfig::new("up".to_string(), HotkeyAction::VolumeUp)
+            .with_modifiers(vec!["ctrl".to_string()]),
+        HotkeyConfig::new("down".to_string(), HotkeyAction::VolumeDown)
+            .with_modifiers(vec!["ctrl".to_string()]),
+        HotkeyConfig::new("0".to_string(), HotkeyAction::MuteToggle)
+            .with_modifiers(vec!["ctrl".to_string()]),
     ]
 }
 
@@ -180,6 +609,17 @@ pub fn default_hotkeys() -> Vec<HotkeyConfig> {
 mod tests {
     use super::*;
 
+    /// Manager registering with a fake OS, returned with the registered ids
+    fn fake_manager(refused: &[&str]) -> (HotkeyManager, Arc<parking_lot::Mutex<std::collections::HashSet<u32>>>) {
+        let registrar = FakeRegistrar {
+            refused: refused.iter().map(|combo| combo.parse::<HotKey>().unwrap().id()).collect(),
+            ..Default::default()
+        };
+        let registered = registrar.registered.clone();
+        let manager = HotkeyManager::with_registrar(|_| Some(Box::new(registrar) as Box<dyn Registrar>));
+        (manager, registered)
+    }
+
     #[test]
     fn test_hotkey_manager() {
         let manager = HotkeyManager::new();
@@ -187,10 +627,95 @@ mod tests {
         let config = HotkeyConfig::new("n".to_string(), HotkeyAction::Next);
         manager.register(config.clone()).unwrap();
 
-        assert!(manager.get_hotkey(HotkeyAction::Next).is_some());
-        assert!(manager.get_hotkey(HotkeyAction::Shift).is_none());
+        assert!(manager.get_hotkey(&HotkeyAction::Next).is_some());
+        assert!(manager.get_hotkey(&HotkeyAction::Shift).is_none());
+
+        manager.unregister(&HotkeyAction::Next);
+        assert!(manager.get_hotkey(&HotkeyAction::Next).is_none());
+    }
+
+    #[test]
+    fn test_os_registration_follows_bindings() {
+        let (manager, registered) = fake_manager(&["ctrl+f2"]);
+        let config = HotkeyConfig::new("f1".to_string(), HotkeyAction::Next);
+        manager.register(config.clone()).unwrap();
+        assert!(registered.lock().contains(&config.hotkey().unwrap().id()));
+
+        // A refused combo keeps the previous binding registered
+        let refused = HotkeyConfig::new("f2".to_string(), HotkeyAction::Next).with_modifiers(vec!["ctrl".to_string()]);
+        assert!(manager.register(refused).is_err());
+        assert_eq!(manager.get_hotkey(&HotkeyAction::Next).unwrap().key, "f1");
+        assert!(registered.lock().contains(&config.hotkey().unwrap().id()));
 
-        manager.unregister(HotkeyAction::Next);
-        assert!(manager.get_hotkey(HotkeyAction::Next).is_none());
+        manager.unregister(&HotkeyAction::Next);
+        assert!(registered.lock().is_empty());
+
+        manager.replace_all(default_hotkeys());
+        assert_eq!(registered.lock().len(), default_hotkeys().len());
+    }
+
+    #[test]
+    fn test_conflict_ignores_modifier_order_and_case() {
+        let manager = HotkeyManager::with_defaults();
+        let version = manager.version();
+
+        let taken = HotkeyConfig::new("N".to_string(), HotkeyAction::Hold)
+            .with_modifiers(vec!["Ctrl".to_string()]);
+        assert_eq!(manager.conflict(&taken), Some(HotkeyAction::Next));
+
+        let rebind = HotkeyConfig::new("n".to_string(), HotkeyAction::Next)
+            .with_modifiers(vec!["shift".to_string(), "ctrl".to_string()]);
+        assert_eq!(manager.conflict(&rebind), None);
+        manager.register(rebind).unwrap();
+        assert!(manager.version() > version);
+
+        let clash = HotkeyConfig::new("n".to_string(), HotkeyAction::Hold)
+            .with_modifiers(vec!["ctrl".to_string(), "shift".to_string()]);
+        let err = manager.register(clash).unwrap_err();
+        assert!(err.to_string().contains("Combo already assigned to Next"));
+        assert_eq!(manager.get_hotkey(&HotkeyAction::Hold).unwrap().key, "h");
+
+        // The old combo is free again once Next moved away from it
+        manager.register(taken).unwrap();
+        assert!(manager.conflicts().is_empty());
+
+        let mut duplicated = default_hotkeys();
+        duplicated[1].key = "n".to_string();
+        manager.replace_all(duplicated);
+        let conflicts = manager.conflicts();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].actions, vec![HotkeyAction::Next, HotkeyAction::Shift]);
+    }
+
+    #[test]
+    fn test_volume_keys_step_and_mute() {
+        let mut config = HotkeyConfig::new("up".to_string(), HotkeyAction::VolumeUp);
+        assert_eq!(config.volume_step(), DEFAULT_VOLUME_STEP);
+        config.metadata = Some(serde_json::json!({ "step": 0.2 }));
+        assert_eq!(config.volume_step(), 0.2);
+
+        let mut keys = VolumeKeys::default();
+        assert_eq!(keys.target(HotkeyAction::VolumeUp, 0.2, 0.9), Some(1.0));
+        assert_eq!(keys.target(HotkeyAction::VolumeDown, 0.05, 0.03), Some(0.0));
+        assert_eq!(keys.target(HotkeyAction::MuteToggle, 0.05, 0.6), Some(0.0));
+        assert_eq!(keys.target(HotkeyAction::MuteToggle, 0.05, 0.0), Some(0.6));
+        assert_eq!(keys.target(HotkeyAction::Next, 0.05, 0.6), None);
+    }
+
+    #[test]
+    fn test_default_hotkeys_convert_to_os_hotkeys() {
+        for config in default_hotkeys() {
+            assert!(config.hotkey().is_ok(), "{:?}", config.action);
+        }
+
+        let shifted = HotkeyConfig::new("N".to_string(), HotkeyAction::Next)
+            .with_modifiers(vec!["Shift".to_string(), "ctrl".to_string()]);
+        let expected = "ctrl+shift+n".parse::<HotKey>().unwrap();
+        assert_eq!(shifted.hotkey().unwrap().id(), expected.id());
+
+        let manager = HotkeyManager::new();
+        let unknown = HotkeyConfig::new("nosuchkey".to_string(), HotkeyAction::Next);
+        assert!(manager.register(unknown).is_err());
+        assert!(manager.get_hotkey(&HotkeyAction::Next).is_none());
     }
 }
@@ -9,6 +9,7 @@
 //! - Pitch estimation (fundamental frequency)
 //! - Energy variance (speech rhythm/stability)
 
+use crate::profile::EmotionBaseline;
 use std::collections::HashMap;
 use thiserror::Error;
 use tracing::{debug, info, warn};
@@ -95,6 +96,7 @@ pub struct AudioFeatures {
 pub struct EmotionAnalyzer {
     initialized: bool,
     sensitivity: f32,  // How much to weight the features (0.0 - 1.0)
+    baseline: Option<EmotionBaseline>,  // Per-GM resting scores
 }
 
 impl EmotionAnalyzer {
@@ -103,6 +105,7 @@ impl EmotionAnalyzer {
         Self {
             initialized: false,
             sensitivity: 0.5,
+            baseline: None,
         }
     }
 
@@ -111,9 +114,15 @@ impl EmotionAnalyzer {
         Self {
             initialized: false,
             sensitivity: sensitivity.clamp(0.0, 1.0),
+            baseline: None,
         }
     }
 
+    /// Set the GM's emotion baseline (None analyzes raw scores)
+    pub fn set_baseline(&mut self, baseline: Option<EmotionBaseline>) {
+        self.baseline = baseline;
+    }
+
     /// Initialize the analyzer
     pub fn init(&mut self) -> Result<(), EmotionError> {
         info!("Initializing emotion analyzer (feature-based)");
@@ -193,16 +202,38 @@ impl EmotionAnalyzer {
         // Disgusted: low energy, low pitch, moderate variance
         let disgusted = (1.0 - energy) * 0.4 + (1.0 - pitch_norm) * 0.3;
 
-        // Normalize scores to sum to ~1
-        let total = neutral + happy + sad + angry + fearful + surprised + disgusted;
+        let raw = [
+            (Emotion::Neutral, neutral),
+            (Emotion::Happy, happy),
+            (Emotion::Sad, sad),
+            (Emotion::Angry, angry),
+            (Emotion::Fearful, fearful),
+            (Emotion::Surprised, surprised),
+            (Emotion::Disgusted, disgusted),
+        ];
+        let total: f32 = raw.iter().map(|(_, score)| score).sum();
+
+        // Subtract the GM's resting baseline (in the same share units) so only
+        // deviations from their normal voice count
+        let adjusted: Vec<(Emotion, f32)> = match &self.baseline {
+            Some(baseline) => raw
+                .iter()
+                .map(|&(emotion, score)| (emotion, (score / total - baseline.score(emotion)).max(0.0)))
+                .collect(),
+            None => raw.to_vec(),
+        };
 
-        scores.insert(Emotion::Neutral, neutral / total);
-        scores.insert(Emotion::Happy, happy / total);
-        scores.insert(Emotion::Sad, sad / total);
-        scores.insert(Emotion::Angry, angry / total);
-        scores.insert(Emotion::Fearful, fearful / total);
-        scores.insert(Emotion::Surprised, surprised / total);
-        scores.insert(Emotion::Disgusted, disgusted / total);
+        // Normalize scores to sum to ~1 (fall back to raw if the baseline cancels everything)
+        let adjusted_total: f32 = adjusted.iter().map(|(_, score)| score).sum();
+        if adjusted_total > 0.0 {
+            for (emotion, score) in adjusted {
+                scores.insert(emotion, score / adjusted_total);
+            }
+        } else {
+            for (emotion, score) in raw {
+                scores.insert(emotion, score / total);
+            }
+        }
 
         scores
     }
@@ -407,6 +438,30 @@ mod tests {
         assert!(result.scores.contains_key(&Emotion::Neutral));
     }
 
+    #[test]
+    fn test_baseline_is_subtracted_before_normalizing() {
+        let mut analyzer = EmotionAnalyzer::new();
+        analyzer.init().unwrap();
+
+        let samples = vec![0.0f32; 16000];
+        let raw = analyzer.analyze(&samples, 16000).unwrap();
+        let baseline = EmotionBaseline::from_results(std::slice::from_ref(&raw));
+        assert!((baseline.score(Emotion::Sad) - raw.scores[&Emotion::Sad]).abs() < 1e-6);
+
+        // Damp sadness only: it should lose share, everything else gains
+        let damped = EmotionBaseline {
+            sad: raw.scores[&Emotion::Sad] * 0.5,
+            ..Default::default()
+        };
+        analyzer.set_baseline(Some(damped));
+        let adjusted = analyzer.analyze(&samples, 16000).unwrap();
+
+        assert!(adjusted.scores[&Emotion::Sad] < raw.scores[&Emotion::Sad]);
+        assert!(adjusted.scores[&Emotion::Neutral] > raw.scores[&Emotion::Neutral]);
+        let total: f32 = adjusted.scores.values().sum();
+        assert!((total - 1.0).abs() < 1e-4);
+    }
+
     #[test]
     fn test_emotion_display() {
         assert_eq!(format!("{}", Emotion::Happy), "happy");
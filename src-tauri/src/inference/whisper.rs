@@ -4,11 +4,20 @@
 //! When the "whisper" feature is enabled, it uses whisper-rs for actual inference.
 //! Without the feature, it provides a placeholder implementation.
 
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::PathBuf;
 use thiserror::Error;
 use tracing::{debug, info, warn};
 
 #[cfg(feature = "whisper")]
 use std::path::Path;
+#[cfg(feature = "whisper")]
+use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters, WhisperState};
+
+/// Sample rate whisper.cpp expects
+pub const WHISPER_SAMPLE_RATE: u32 = 16000;
 
 #[derive(Error, Debug)]
 pub enum WhisperError {
@@ -34,12 +43,56 @@ pub struct Transcription {
     pub confidence: f32,
 }
 
+/// Audio used for language detection, and how much new audio triggers a re-detect
+const LANGUAGE_DETECT_SECS: u32 = 30;
+
+/// Detected language plus how much audio has been transcribed since
+#[derive(Debug, Default)]
+pub struct LanguageCache {
+    language: Option<String>,
+    samples_since_detect: usize,
+}
+
+impl LanguageCache {
+    /// Account for `samples` of new 16 kHz audio; true if the language should be (re)detected
+    pub fn needs_detection(&mut self, samples: usize) -> bool {
+        if self.language.is_none() {
+            return true;
+        }
+        self.samples_since_detect += samples;
+        self.samples_since_detect > (WHISPER_SAMPLE_RATE * LANGUAGE_DETECT_SECS) as usize
+    }
+
+    /// Store a freshly detected language
+    pub fn set(&mut self, language: String) {
+        self.language = Some(language);
+        self.samples_since_detect = 0;
+    }
+
+    /// Last detected language
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+}
+
 /// Whisper inference engine
+///
+/// whisper.cpp contexts must not be shared across threads, so the context
+/// lives on a dedicated worker thread and audio is sent to it over a channel.
 #[cfg(feature = "whisper")]
 pub struct WhisperEngine {
     model_path: Option<String>,
-    context: Option<whisper_rs::WhisperContext>,
-    params: whisper_rs::WhisperParams,
+    worker: Option<flume::Sender<TranscribeJob>>,
+    language: parking_lot::Mutex<LanguageCache>,
+}
+
+/// Audio to transcribe plus the channel to answer on
+#[cfg(feature = "whisper")]
+struct TranscribeJob {
+    pcm: Vec<f32>,
+    /// Known language, or None to detect it from the first 30 seconds
+    language: Option<String>,
+    reply: flume::Sender<Result<Transcription, WhisperError>>,
 }
 
 /// Placeholder Whisper engine when feature is disabled
@@ -53,18 +106,14 @@ pub struct WhisperEngine {
 impl WhisperEngine {
     /// Create a new WhisperEngine instance
     pub fn new() -> Self {
-        let params = whisper_rs::WhisperParams::new()
-            .with_n_threads(4)
-            .with_language(Some("en"));
-
         Self {
             model_path: None,
-            context: None,
-            params,
+            worker: None,
+            language: parking_lot::Mutex::new(LanguageCache::default()),
         }
     }
 
-    /// Initialize with a model file
+    /// Initialize with a model file, loading it on the worker thread
     pub fn init(&mut self, model_path: &str) -> Result<(), WhisperError> {
         info!("Initializing Whisper engine with model: {}", model_path);
 
@@ -72,69 +121,103 @@ impl WhisperEngine {
             return Err(WhisperError::ModelNotFound(model_path.to_string()));
         }
 
-        let context = whisper_rs::WhisperContext::new(model_path)
+        let (job_tx, job_rx) = flume::unbounded::<TranscribeJob>();
+        let (ready_tx, ready_rx) = flume::bounded(1);
+        let path = model_path.to_string();
+
+        std::thread::Builder::new()
+            .name("whisper".to_string())
+            .spawn(move || {
+                let context = match WhisperContext::new_with_params(&path, WhisperContextParameters::default()) {
+                    Ok(context) => {
+                        let _ = ready_tx.send(Ok(()));
+                        context
+                    }
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(WhisperError::ModelLoadError(e.to_string())));
+                        return;
+                    }
+                };
+
+                // Exits when the engine (and its sender) is dropped
+                while let Ok(job) = job_rx.recv() {
+                    let _ = job.reply.send(run_full(&context, &job.pcm, job.language));
+                }
+                debug!("Whisper worker stopped");
+            })
             .map_err(|e| WhisperError::ModelLoadError(e.to_string()))?;
 
+        ready_rx
+            .recv()
+            .map_err(|_| WhisperError::ModelLoadError("whisper worker exited".to_string()))??;
+
         self.model_path = Some(model_path.to_string());
-        self.context = Some(context);
+        self.worker = Some(job_tx);
 
         info!("Whisper engine initialized successfully");
         Ok(())
     }
 
     /// Transcribe audio samples
-    pub fn transcribe(&mut self, samples: &[f32], sample_rate: u32) -> Result<Transcription, WhisperError> {
-        let context = self.context.as_ref().ok_or(WhisperError::NotInitialized)?;
+    pub fn transcribe(&self, samples: &[f32], sample_rate: u32) -> Result<Transcription, WhisperError> {
+        let worker = self.worker.as_ref().ok_or(WhisperError::NotInitialized)?;
 
-        // Convert f32 to i16 (whisper expects 16-bit PCM)
-        let pcm_samples: Vec<i16> = samples
-            .iter()
-            .map(|&s| (s * i16::MAX as f32) as i16)
-            .collect();
+        if samples.is_empty() {
+            return Err(WhisperError::AudioError("No samples to transcribe".to_string()));
+        }
 
         let duration_secs = samples.len() as f32 / sample_rate as f32;
         debug!("Transcribing audio: {:.2}s, {} Hz", duration_secs, sample_rate);
 
-        let mut state = context
-            .create_state()
-            .map_err(|e| WhisperError::InferenceError(e.to_string()))?;
-
-        state
-            .process(&self.params, &pcm_samples)
-            .map_err(|e| WhisperError::InferenceError(e.to_string()))?;
-
-        let segments = state
-            .get_segments()
-            .map_err(|e| WhisperError::InferenceError(e.to_string()))?;
-
-        let mut full_text = String::new();
-        for segment in segments {
-            full_text.push_str(&segment.text);
-        }
-
-        let confidence = if full_text.trim().is_empty() {
-            0.0
+        // whisper.cpp expects 16 kHz mono f32
+        let pcm = if sample_rate == WHISPER_SAMPLE_RATE {
+            samples.to_vec()
         } else {
-            0.85
+            crate::dsp::processing::resample(samples, sample_rate, WHISPER_SAMPLE_RATE)
         };
 
-        let language = state
-            .get_language()
-            .map(|l| l.to_string())
-            .ok();
+        // Re-detect only after 30 seconds of new audio
+        let language = {
+            let mut cache = self.language.lock();
+            if cache.needs_detection(pcm.len()) {
+                None
+            } else {
+                cache.language().map(str::to_string)
+            }
+        };
 
-        debug!("Transcription result: {} chars", full_text.len());
+        let detect = language.is_none();
+
+        let (reply_tx, reply_rx) = flume::bounded(1);
+        worker
+            .send(TranscribeJob {
+                pcm,
+                language,
+                reply: reply_tx,
+            })
+            .map_err(|_| WhisperError::InferenceError("whisper worker not running".to_string()))?;
+
+        let transcription = reply_rx
+            .recv()
+            .map_err(|_| WhisperError::InferenceError("whisper worker not running".to_string()))??;
+
+        if detect {
+            if let Some(language) = &transcription.language {
+                self.language.lock().set(language.clone());
+            }
+        }
 
-        Ok(Transcription {
-            text: full_text.trim().to_string(),
-            language,
-            confidence,
-        })
+        Ok(transcription)
+    }
+
+    /// Last detected language
+    pub fn detected_language(&self) -> Option<String> {
+        self.language.lock().language().map(str::to_string)
     }
 
     /// Check if engine is initialized
     pub fn is_initialized(&self) -> bool {
-        self.context.is_some()
+        self.worker.is_some()
     }
 
     /// Get model path
@@ -143,6 +226,77 @@ impl WhisperEngine {
     }
 }
 
+/// Threads used for language detection
+#[cfg(feature = "whisper")]
+const DETECT_THREADS: usize = 4;
+
+/// Detect the spoken language from the first 30 seconds of audio
+#[cfg(feature = "whisper")]
+fn detect_language(state: &mut WhisperState, pcm: &[f32]) -> Result<String, WhisperError> {
+    let window = pcm.len().min((WHISPER_SAMPLE_RATE * LANGUAGE_DETECT_SECS) as usize);
+
+    state
+        .pcm_to_mel(&pcm[..window], DETECT_THREADS)
+        .map_err(|e| WhisperError::InferenceError(e.to_string()))?;
+    let (lang_id, _) = state
+        .lang_detect(0, DETECT_THREADS)
+        .map_err(|e| WhisperError::InferenceError(e.to_string()))?;
+
+    let language = whisper_rs::get_lang_str(lang_id)
+        .ok_or_else(|| WhisperError::InferenceError(format!("Unknown language id: {}", lang_id)))?;
+    info!("Detected language: {}", language);
+    Ok(language.to_string())
+}
+
+/// Run a full whisper pass on the worker thread
+#[cfg(feature = "whisper")]
+fn run_full(context: &WhisperContext, pcm: &[f32], language: Option<String>) -> Result<Transcription, WhisperError> {
+    let mut state = context
+        .create_state()
+        .map_err(|e| WhisperError::InferenceError(e.to_string()))?;
+
+    let language = match language {
+        Some(language) => language,
+        None => detect_language(&mut state, pcm)?,
+    };
+
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_language(Some(&language));
+    params.set_no_timestamps(true);
+    params.set_print_special(false);
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_timestamps(false);
+
+    state
+        .full(params, pcm)
+        .map_err(|e| WhisperError::InferenceError(e.to_string()))?;
+
+    let mut full_text = String::new();
+    for i in 0..state.full_n_segments() {
+        if let Some(segment) = state.get_segment(i) {
+            let text = segment
+                .to_str_lossy()
+                .map_err(|e| WhisperError::InferenceError(e.to_string()))?;
+            full_text.push_str(&text);
+        }
+    }
+
+    let confidence = if full_text.trim().is_empty() {
+        0.0
+    } else {
+        0.85
+    };
+
+    debug!("Transcription result: {} chars", full_text.len());
+
+    Ok(Transcription {
+        text: full_text.trim().to_string(),
+        language: Some(language),
+        confidence,
+    })
+}
+
 #[cfg(not(feature = "whisper"))]
 impl WhisperEngine {
     /// Create a new WhisperEngine instance (placeholder)
@@ -177,14 +331,19 @@ impl WhisperEngine {
         let duration_secs = samples.len() as f32 / sample_rate as f32;
         debug!("Transcribing audio (placeholder): {:.2}s, {} Hz", duration_secs, sample_rate);
 
-        // Return placeholder text
+        // Return placeholder text (no model, so no language detection)
         Ok(Transcription {
             text: "[Transcription placeholder - enable whisper feature]".to_string(),
-            language: Some("en".to_string()),
+            language: None,
             confidence: 0.0,
         })
     }
 
+    /// Last detected language (never set without the whisper feature)
+    pub fn detected_language(&self) -> Option<String> {
+        None
+    }
+
     /// Check if engine is initialized
     pub fn is_initialized(&self) -> bool {
         self.initialized
@@ -196,48 +355,131 @@ impl WhisperEngine {
     }
 }
 
+impl WhisperEngine {
+    /// Transcribe in overlapping windows, sending each new piece of text to `tx`
+    ///
+    /// Input is split into 3-second windows overlapping by 0.5 seconds. Words
+    /// repeated across a window boundary are dropped before sending. Returns
+    /// the merged transcript.
+    pub fn transcribe_streaming(
+        &self,
+        samples: &[f32],
+        sample_rate: u32,
+        tx: flume::Sender<String>,
+    ) -> Result<String, WhisperError> {
+        let window = (sample_rate as f32 * STREAM_WINDOW_SECS) as usize;
+        let step = window - (sample_rate as f32 * STREAM_OVERLAP_SECS) as usize;
+        if window == 0 || step == 0 {
+            return Err(WhisperError::AudioError(format!("Invalid sample rate: {}", sample_rate)));
+        }
+
+        let mut transcript: Vec<String> = Vec::new();
+        let mut start = 0;
+
+        while start < samples.len() {
+            let end = (start + window).min(samples.len());
+            let result = self.transcribe(&samples[start..end], sample_rate)?;
+
+            let words = drop_overlap(&transcript, &result.text);
+            if !words.is_empty() {
+                let _ = tx.send(words.join(" "));
+                transcript.extend(words);
+            }
+
+            if end == samples.len() {
+                break;
+            }
+            start += step;
+        }
+
+        Ok(transcript.join(" "))
+    }
+}
+
+/// Length of each streaming window
+const STREAM_WINDOW_SECS: f32 = 3.0;
+
+/// Overlap between adjacent streaming windows
+const STREAM_OVERLAP_SECS: f32 = 0.5;
+
+/// Words of `text` left after removing the longest prefix that repeats the
+/// end of `transcript` (compared case- and punctuation-insensitively)
+fn drop_overlap(transcript: &[String], text: &str) -> Vec<String> {
+    let words: Vec<String> = text.split_whitespace().map(str::to_string).collect();
+    let normalize = |w: &str| {
+        w.chars()
+            .filter(|c| c.is_alphanumeric())
+            .collect::<String>()
+            .to_lowercase()
+    };
+
+    let max = transcript.len().min(words.len());
+    let overlap = (1..=max)
+        .rev()
+        .find(|&k| {
+            transcript[transcript.len() - k..]
+                .iter()
+                .zip(&words[..k])
+                .all(|(a, b)| normalize(a) == normalize(b))
+        })
+        .unwrap_or(0);
+
+    words[overlap..].to_vec()
+}
+
 impl Default for WhisperEngine {
     fn default() -> Self {
         Self::new()
     }
 }
 
-/// Get the default model path
-/// Checks in order:
-/// 1. ./assets/models/whisper/tiny.bin (bundled)
-/// 2. ./models/whisper-tiny.bin (local)
-/// 3. ~/.local/share/ttrpg_companion/models/whisper/tiny.bin (user data)
+/// Settings key of the Whisper model size picked by the user
+pub const WHISPER_MODEL_SETTING: &str = "whisper_model";
+
+/// Model size `get_model_path` looks for first
+static SELECTED_MODEL: parking_lot::RwLock<Option<WhisperModelSize>> = parking_lot::RwLock::new(None);
+
+/// Look for `size` before any other model size
+pub fn select_model(size: Option<WhisperModelSize>) {
+    *SELECTED_MODEL.write() = size;
+}
+
+/// Model size picked by the user, if any
+pub fn selected_model() -> Option<WhisperModelSize> {
+    *SELECTED_MODEL.read()
+}
+
+/// Get the model path
+///
+/// Looks for the selected model size first, then for any other size, in:
+/// 1. ./assets/models/whisper/<size>.bin (bundled)
+/// 2. ./models/whisper-<size>.bin (local)
+/// 3. ~/.local/share/ttrpg_companion/models/whisper/<size>.bin (user data, where downloads go)
 pub fn get_model_path() -> std::path::PathBuf {
-    // Check bundled path
-    let bundled = std::path::PathBuf::from("assets")
-        .join("models")
-        .join("whisper")
-        .join("tiny.bin");
-
-    if bundled.exists() {
-        return bundled;
-    }
-
-    // Check local models directory
-    let local = std::path::PathBuf::from("models").join("whisper-tiny.bin");
-    if local.exists() {
-        return local;
-    }
-
-    // Check user data directory
-    if let Some(data_dir) = dirs::data_local_dir() {
-        let user_path = data_dir
-            .join("ttrpg_companion")
-            .join("models")
-            .join("whisper")
-            .join("tiny.bin");
-        if user_path.exists() {
-            return user_path;
+    find_model_path(selected_model(), user_model_dir().as_deref())
+}
+
+/// First existing model file, or where the selected model would be downloaded
+fn find_model_path(selected: Option<WhisperModelSize>, user_dir: Option<&std::path::Path>) -> PathBuf {
+    let others = WhisperModelSize::ALL.into_iter().filter(|size| Some(*size) != selected);
+    for size in selected.into_iter().chain(others) {
+        let file = format!("{}.bin", size.name());
+        let mut candidates = vec![
+            PathBuf::from("assets").join("models").join("whisper").join(&file),
+            PathBuf::from("models").join(format!("whisper-{}", file)),
+        ];
+        candidates.extend(user_dir.map(|dir| dir.join(&file)));
+
+        if let Some(path) = candidates.into_iter().find(|path| path.exists()) {
+            return path;
         }
     }
 
-    // Return default path even if it doesn't exist
-    std::path::PathBuf::from("models/whisper-tiny.bin")
+    // Return a default path even though it doesn't exist
+    match (selected, user_dir) {
+        (Some(size), Some(dir)) => dir.join(format!("{}.bin", size.name())),
+        _ => PathBuf::from("models/whisper-tiny.bin"),
+    }
 }
 
 /// Check if whisper model is available
@@ -256,27 +498,299 @@ pub fn is_whisper_enabled() -> bool {
     false
 }
 
+/// "ggml" magic at the start of whisper.cpp model files
+const GGML_MAGIC: u32 = 0x6767_6d6c;
+
+/// whisper.cpp stores the quantization version multiplied into `ftype`
+const GGML_QNT_VERSION_FACTOR: i32 = 1000;
+
+/// Model file details shown in the UI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhisperModelInfo {
+    /// Model type, e.g. "tiny" or "small"
+    pub name: String,
+    pub size_mb: u64,
+    pub quantized: bool,
+    /// Expected real-time factor on a typical CPU (processing time / audio time)
+    pub expected_rtf: f32,
+}
+
+/// Read the ggml header of a model file to identify its type and quantization
+pub fn read_model_info(path: &std::path::Path) -> Result<WhisperModelInfo, WhisperError> {
+    let mut file = std::fs::File::open(path)
+        .map_err(|_| WhisperError::ModelNotFound(path.display().to_string()))?;
+    let size = file
+        .metadata()
+        .map_err(|e| WhisperError::ModelLoadError(e.to_string()))?
+        .len();
+
+    // Magic followed by 11 i32 hyperparameters, ending with ftype
+    let mut header = [0u8; 48];
+    file.read_exact(&mut header)
+        .map_err(|e| WhisperError::ModelLoadError(format!("Truncated model header: {}", e)))?;
+
+    let field = |i: usize| {
+        let offset = i * 4;
+        i32::from_le_bytes([header[offset], header[offset + 1], header[offset + 2], header[offset + 3]])
+    };
+    if field(0) as u32 != GGML_MAGIC {
+        return Err(WhisperError::ModelLoadError("Not a ggml model file".to_string()));
+    }
+
+    let n_audio_layer = field(5);
+    let n_vocab = field(1);
+    let ftype = field(11) % GGML_QNT_VERSION_FACTOR;
+
+    let (name, rtf) = match n_audio_layer {
+        4 => ("tiny", 0.05),
+        6 => ("base", 0.1),
+        12 => ("small", 0.3),
+        24 => ("medium", 0.8),
+        32 => ("large", 1.5),
+        _ => ("unknown", 1.0),
+    };
+    // English-only models have a smaller vocabulary
+    let name = if n_vocab == 51864 { format!("{}.en", name) } else { name.to_string() };
+
+    // 0 = f32, 1 = f16, anything else is a quantized type
+    let quantized = ftype > 1;
+
+    Ok(WhisperModelInfo {
+        name,
+        size_mb: size / (1024 * 1024),
+        quantized,
+        expected_rtf: if quantized { rtf * 0.7 } else { rtf },
+    })
+}
+
 /// Download URL for tiny.en model
 pub fn get_model_download_url() -> &'static str {
     "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.en.bin"
 }
 
+/// Base URL of the ggml Whisper models on HuggingFace
+const MODEL_BASE_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main";
+
+/// Downloadable Whisper model sizes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WhisperModelSize {
+    Tiny,
+    TinyEn,
+    Base,
+    BaseEn,
+    Small,
+    SmallEn,
+}
+
+impl WhisperModelSize {
+    /// Every size, smallest first
+    pub const ALL: [WhisperModelSize; 6] = [
+        WhisperModelSize::Tiny,
+        WhisperModelSize::TinyEn,
+        WhisperModelSize::Base,
+        WhisperModelSize::BaseEn,
+        WhisperModelSize::Small,
+        WhisperModelSize::SmallEn,
+    ];
+
+    /// Model name as used in the ggml file names, e.g. "tiny.en"
+    pub fn name(&self) -> &'static str {
+        match self {
+            WhisperModelSize::Tiny => "tiny",
+            WhisperModelSize::TinyEn => "tiny.en",
+            WhisperModelSize::Base => "base",
+            WhisperModelSize::BaseEn => "base.en",
+            WhisperModelSize::Small => "small",
+            WhisperModelSize::SmallEn => "small.en",
+        }
+    }
+
+    /// HuggingFace download URL
+    pub fn download_url(&self) -> String {
+        format!("{}/ggml-{}.bin", MODEL_BASE_URL, self.name())
+    }
+
+    /// Expected SHA-256 of the published file
+    fn sha256(&self) -> &'static str {
+        match self {
+            WhisperModelSize::Tiny => "be07e048e1e599ad46341c8d2a135645097a538221678b7acdd1b1919c6e1b21",
+            WhisperModelSize::TinyEn => "921e4cf8686fdd993dcd081a5da5b6c365bfde1162e72b08d75ac75289920b1f",
+            WhisperModelSize::Base => "60ed5bc3dd14eea856493d334349b405782ddcaf0028d4b5df4088345fba2efe",
+            WhisperModelSize::BaseEn => "a03779c86df3323075f5e796cb2ce5029f00ec8869eee3fdfb897afe36c6d002",
+            WhisperModelSize::Small => "1be3a9b2063867b937e64e2ec7483364a79917e157fa98c5d94b5c1fffea987b",
+            WhisperModelSize::SmallEn => "c6138d6d58ecc8322097e0f987c32f1be8bb0a18532a3f88f734d1bbf9c41e5d",
+        }
+    }
+}
+
+/// Download a model into `dest_dir`, reporting progress (0-1) on `progress_tx`
+///
+/// The file is written next to its final name and only moved into place once
+/// its SHA-256 matches, so an interrupted or corrupt download is never loaded.
+pub fn download_model(
+    model_size: WhisperModelSize,
+    dest_dir: &std::path::Path,
+    progress_tx: flume::Sender<f64>,
+) -> Result<PathBuf, AppError> {
+    let url = model_size.download_url();
+    info!("Downloading Whisper model {} from {}", model_size.name(), url);
+
+    std::fs::create_dir_all(dest_dir).map_err(|e| AppError::Io(e.to_string()))?;
+    let dest = dest_dir.join(format!("{}.bin", model_size.name()));
+    let partial = dest.with_extension("bin.part");
+
+    let mut response = reqwest::blocking::get(&url)
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| AppError::Io(format!("Model download failed: {}", e)))?;
+    let total = response.content_length();
+
+    let mut file = std::fs::File::create(&partial).map_err(|e| AppError::Io(e.to_string()))?;
+    let mut hasher = ring::digest::Context::new(&ring::digest::SHA256);
+    let mut chunk = vec![0u8; 64 * 1024];
+    let mut downloaded = 0u64;
+
+    loop {
+        let read = response
+            .read(&mut chunk)
+            .map_err(|e| AppError::Io(format!("Model download failed: {}", e)))?;
+        if read == 0 {
+            break;
+        }
+
+        file.write_all(&chunk[..read]).map_err(|e| AppError::Io(e.to_string()))?;
+        hasher.update(&chunk[..read]);
+        downloaded += read as u64;
+
+        if let Some(total) = total.filter(|t| *t > 0) {
+            let _ = progress_tx.send(downloaded as f64 / total as f64);
+        }
+    }
+    drop(file);
+
+    let digest: String = hasher.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect();
+    if digest != model_size.sha256() {
+        let _ = std::fs::remove_file(&partial);
+        return Err(AppError::Inference(format!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            model_size.name(),
+            model_size.sha256(),
+            digest
+        )));
+    }
+
+    std::fs::rename(&partial, &dest).map_err(|e| AppError::Io(e.to_string()))?;
+    let _ = progress_tx.send(1.0);
+
+    info!("Whisper model saved to {:?} ({} bytes)", dest, downloaded);
+    Ok(dest)
+}
+
+/// Directory downloaded models are stored in
+pub fn user_model_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("ttrpg_companion").join("models").join("whisper"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_read_model_info() {
+        let path = std::env::temp_dir().join(format!("ggml-{}.bin", uuid::Uuid::new_v4()));
+        // tiny.en, q5_1 with quantization version 2
+        let fields: [i32; 11] = [51864, 1500, 384, 6, 4, 448, 384, 6, 4, 80, 2009];
+        let mut bytes = GGML_MAGIC.to_le_bytes().to_vec();
+        for field in fields {
+            bytes.extend(field.to_le_bytes());
+        }
+        std::fs::write(&path, &bytes).unwrap();
+
+        let info = read_model_info(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(info.name, "tiny.en");
+        assert!(info.quantized);
+        assert!(read_model_info(&path).is_err());
+    }
+
+    #[test]
+    fn test_model_size_urls() {
+        let size: WhisperModelSize = serde_json::from_str("\"tiny_en\"").unwrap();
+        assert_eq!(size, WhisperModelSize::TinyEn);
+        assert_eq!(size.download_url(), get_model_download_url());
+        assert_eq!(size.sha256().len(), 64);
+    }
+
     #[test]
     fn test_get_model_path() {
         let path = get_model_path();
         assert!(path.file_name().is_some());
     }
 
+    #[test]
+    fn test_model_path_follows_downloaded_and_selected_size() {
+        let dir = std::env::temp_dir().join(format!("whisper-models-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        assert_eq!(find_model_path(Some(WhisperModelSize::Base), Some(&dir)), dir.join("base.bin"));
+
+        // A download under its own size name is found without a selection
+        std::fs::write(dir.join("small.en.bin"), b"ggml").unwrap();
+        assert_eq!(find_model_path(None, Some(&dir)), dir.join("small.en.bin"));
+        assert_eq!(find_model_path(Some(WhisperModelSize::Base), Some(&dir)), dir.join("small.en.bin"));
+
+        // The selected size wins over smaller downloads
+        std::fs::write(dir.join("tiny.bin"), b"ggml").unwrap();
+        assert_eq!(find_model_path(None, Some(&dir)), dir.join("tiny.bin"));
+        assert_eq!(find_model_path(Some(WhisperModelSize::SmallEn), Some(&dir)), dir.join("small.en.bin"));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
     #[test]
     fn test_engine_creation() {
         let engine = WhisperEngine::new();
         assert!(!engine.is_initialized());
     }
 
+    #[test]
+    fn test_language_cache_redetects_after_30s() {
+        let mut cache = LanguageCache::default();
+        assert!(cache.needs_detection(16000));
+
+        cache.set("de".to_string());
+        assert!(!cache.needs_detection(20 * 16000));
+        assert!(cache.needs_detection(11 * 16000));
+        assert_eq!(cache.language(), Some("de"));
+
+        cache.set("de".to_string());
+        assert!(!cache.needs_detection(16000));
+    }
+
+    #[test]
+    fn test_drop_overlap() {
+        let transcript: Vec<String> = "the dragon attacks the".split(' ').map(str::to_string).collect();
+        assert_eq!(drop_overlap(&transcript, "Attacks the village now"), vec!["village", "now"]);
+        assert_eq!(drop_overlap(&transcript, "roll initiative"), vec!["roll", "initiative"]);
+        assert!(drop_overlap(&transcript, "the").is_empty());
+    }
+
+    #[test]
+    #[cfg(not(feature = "whisper"))]
+    fn test_streaming_sends_deduplicated_partials() {
+        let mut engine = WhisperEngine::new();
+        engine.init("dummy.bin").unwrap();
+
+        // 8 seconds -> windows at 0, 2.5 and 5 seconds
+        let (tx, rx) = flume::unbounded();
+        let text = engine.transcribe_streaming(&vec![0.0f32; 8 * 16000], 16000, tx).unwrap();
+
+        // The placeholder repeats itself every window, so only the first survives
+        let partials: Vec<String> = rx.drain().collect();
+        assert_eq!(partials, vec![text]);
+    }
+
     #[test]
     #[cfg(not(feature = "whisper"))]
     fn test_placeholder_transcribe() {
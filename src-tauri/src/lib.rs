@@ -10,6 +10,8 @@ pub mod dsp;
 pub mod error;
 pub mod hotkeys;
 pub mod inference;
+pub mod library;
+pub mod logging;
 pub mod ml;
 pub mod orchestrator;
 pub mod profile;
@@ -19,15 +21,17 @@ pub mod state;
 use db::Database;
 use error::AppError;
 use state::{AppMode, SessionConfig, SessionState};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
 use tauri::{
     menu::{Menu, MenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
-    Manager,
+    Emitter, Manager,
 };
 use tracing::{error, info, warn};
+use tracing_appender::non_blocking::WorkerGuard;
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
-use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use tracing_subscriber::{fmt, fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 /// Application state shared across Tauri commands
 pub struct AppState {
@@ -38,15 +42,77 @@ pub struct AppState {
     /// Session configuration
     pub config: parking_lot::RwLock<SessionConfig>,
     /// Audio buffer for processing (thread-safe)
-    pub audio_buffer: Arc<parking_lot::RwLock<Vec<f32>>>,
-    /// Current sample rate
+    pub audio_buffer: Arc<parking_lot::RwLock<audio::AudioRingBuffer>>,
+    /// Sample rate of the capture device (set when a session starts)
     pub sample_rate: parking_lot::RwLock<u32>,
+    /// Channel count of the capture device (samples in the buffer are interleaved)
+    pub channels: parking_lot::RwLock<u16>,
+    /// Input device picked with `select_input_device` (None uses the saved or default one)
+    pub active_device_id: parking_lot::RwLock<Option<String>>,
+    /// Microphone gain and clipping indicator (shared with the capture callback)
+    pub input_gain: Arc<parking_lot::RwLock<audio::gain::InputGain>>,
+    /// Peak microphone level in dBFS over the last 60 Hz meter window
+    pub input_peak_db: Arc<parking_lot::RwLock<f32>>,
+    /// Linear RMS level of the latest microphone chunk
+    pub input_level: Arc<parking_lot::RwLock<f32>>,
+    /// Linear RMS level of the music bus (shared with the audio engine)
+    pub music_level: Arc<parking_lot::RwLock<f32>>,
+    /// Tempo detected for each played track, by track id
+    pub track_bpm: Arc<parking_lot::RwLock<HashMap<String, f32>>>,
+    /// Audio playback thread
+    pub audio: audio::AudioController,
+    /// Microphone capture of the running session
+    pub capture: parking_lot::Mutex<Option<audio::capture::CaptureThread>>,
+    /// WAV recording of the running session (written by the capture callback)
+    pub session_recorder: Arc<parking_lot::Mutex<Option<audio::capture::SessionRecorder>>>,
+    /// Database id of the running session
+    pub session_id: parking_lot::RwLock<Option<String>>,
+    /// Live detection pipeline of the running session
+    pub pipeline_thread: parking_lot::Mutex<Option<detection::PipelineThread>>,
+    /// Capture-side frame queue into the pipeline thread (None when not streaming)
+    pub pipeline_feed: Arc<parking_lot::Mutex<Option<detection::FrameSender>>>,
+    /// Frame counters and FSM state of the live pipeline
+    pub pipeline_stats: Arc<detection::StreamStats>,
+    /// Collaborative mode music changes awaiting GM confirmation
+    pub suggestions: parking_lot::Mutex<orchestrator::SuggestionQueue>,
+    /// Detections of the running session, stored with it when it stops
+    pub detection_logger: Arc<parking_lot::Mutex<detection::DetectionLogger>>,
+    /// System audio recorded next to the microphone (`CaptureSource::Both`)
+    pub loopback_buffer: Arc<parking_lot::RwLock<audio::AudioRingBuffer>>,
+    /// Loopback capture of the running session (`CaptureSource::Both` only)
+    pub loopback_capture: parking_lot::Mutex<Option<audio::capture::CaptureThread>>,
     /// Database connection pool
     pub db_pool: parking_lot::RwLock<Option<db::DbPool>>,
     /// Current detected emotion
     pub current_emotion: parking_lot::RwLock<String>,
+    /// Language detected in the last transcription
+    pub detected_language: parking_lot::RwLock<Option<String>>,
+    /// Transcription of the last processed session
+    pub last_transcription: parking_lot::RwLock<Option<String>>,
+    /// Primary emotion of the last processed session
+    pub last_emotion: parking_lot::RwLock<Option<String>>,
     /// Keyword vocabulary version
     pub keyword_version: parking_lot::RwLock<u64>,
+    /// Imported keyword vocabulary (None uses the built-in defaults)
+    pub keyword_vocabulary: parking_lot::RwLock<Option<detection::KeywordVocabulary>>,
+    /// Per-session keyword use counts (shared with the detection pipeline)
+    pub keyword_use_counts: Arc<parking_lot::RwLock<HashMap<String, u32>>>,
+    /// Emotion baseline of the default voice profile
+    pub emotion_baseline: parking_lot::RwLock<Option<profile::EmotionBaseline>>,
+    /// In-progress voice enrollment
+    pub voice_training: parking_lot::RwLock<Option<profile::VoiceTraining>>,
+    /// Microphone capture of the training passage being recorded
+    pub training_capture: parking_lot::Mutex<Option<audio::capture::CaptureThread>>,
+    /// Audio of the training passage being recorded (filled by the capture callback)
+    pub training_samples: Arc<parking_lot::Mutex<Vec<f32>>>,
+    /// Hotkey bindings (defaults until the saved ones are loaded)
+    pub hotkeys: hotkeys::HotkeyManager,
+    /// Input device hot-plug watcher (started once setup completes)
+    pub device_watcher: parking_lot::Mutex<Option<audio::capture::DeviceWatcher>>,
+    /// Music library folder watcher (None until a library path is set)
+    pub library_watcher: parking_lot::Mutex<Option<library::LibraryWatcher>>,
+    /// Startup phase timings
+    pub startup: startup::StartupManager,
     /// Is detection pipeline ready
     pub detection_ready: parking_lot::RwLock<bool>,
     /// Startup complete flag
@@ -55,35 +121,110 @@ pub struct AppState {
 
 impl Default for AppState {
     fn default() -> Self {
+        let music_level = Arc::new(parking_lot::RwLock::new(0.0));
+        let track_bpm = Arc::new(parking_lot::RwLock::new(HashMap::new()));
+        let detection_logger = Arc::new(parking_lot::Mutex::new(detection::DetectionLogger::new(String::new())));
+
         Self {
             session_state: parking_lot::RwLock::new(SessionState::Idle),
             app_mode: parking_lot::RwLock::new(AppMode::default()),
             config: parking_lot::RwLock::new(SessionConfig::default()),
-            audio_buffer: Arc::new(parking_lot::RwLock::new(Vec::new())),
+            audio_buffer: Arc::new(parking_lot::RwLock::new(audio::AudioRingBuffer::new(
+                state::channels::AUDIO_BUFFER_CAPACITY,
+                16000,
+            ))),
             sample_rate: parking_lot::RwLock::new(16000),
+            channels: parking_lot::RwLock::new(1),
+            active_device_id: parking_lot::RwLock::new(None),
+            input_gain: Arc::new(parking_lot::RwLock::new(audio::gain::InputGain::default())),
+            input_peak_db: Arc::new(parking_lot::RwLock::new(audio::meter::SILENCE_DB)),
+            input_level: Arc::new(parking_lot::RwLock::new(0.0)),
+            audio: audio::AudioController::spawn(music_level.clone(), track_bpm.clone(), detection_logger.clone()),
+            music_level,
+            track_bpm,
+            capture: parking_lot::Mutex::new(None),
+            session_recorder: Arc::new(parking_lot::Mutex::new(None)),
+            session_id: parking_lot::RwLock::new(None),
+            pipeline_thread: parking_lot::Mutex::new(None),
+            pipeline_feed: Arc::new(parking_lot::Mutex::new(None)),
+            pipeline_stats: Arc::new(detection::StreamStats::default()),
+            suggestions: parking_lot::Mutex::new(orchestrator::SuggestionQueue::new()),
+            detection_logger,
+            loopback_buffer: Arc::new(parking_lot::RwLock::new(audio::AudioRingBuffer::new(
+                state::channels::AUDIO_BUFFER_CAPACITY,
+                16000,
+            ))),
+            loopback_capture: parking_lot::Mutex::new(None),
             db_pool: parking_lot::RwLock::new(None),
             current_emotion: parking_lot::RwLock::new("neutral".to_string()),
+            detected_language: parking_lot::RwLock::new(None),
+            last_transcription: parking_lot::RwLock::new(None),
+            last_emotion: parking_lot::RwLock::new(None),
             keyword_version: parking_lot::RwLock::new(0),
+            keyword_vocabulary: parking_lot::RwLock::new(None),
+            keyword_use_counts: Arc::new(parking_lot::RwLock::new(HashMap::new())),
+            emotion_baseline: parking_lot::RwLock::new(None),
+            voice_training: parking_lot::RwLock::new(None),
+            training_capture: parking_lot::Mutex::new(None),
+            training_samples: Arc::new(parking_lot::Mutex::new(Vec::new())),
+            hotkeys: hotkeys::HotkeyManager::with_defaults(),
+            device_watcher: parking_lot::Mutex::new(None),
+            library_watcher: parking_lot::Mutex::new(None),
+            startup: startup::StartupManager::new(),
             detection_ready: parking_lot::RwLock::new(false),
             startup_complete: parking_lot::RwLock::new(false),
         }
     }
 }
 
-/// Initialize logging system with file output
-fn init_logging() {
-    let log_dir = dirs::data_local_dir()
+/// Name of the log file; daily rotation appends the date
+pub(crate) const LOG_FILE_PREFIX: &str = "ttrpg_companion.log";
+
+/// Name of the machine-readable log file, one JSON object per line
+pub(crate) const STRUCTURED_LOG_FILE_PREFIX: &str = "ttrpg_companion_structured.log";
+
+/// Flushes the log file on drop, so it lives as long as the app
+static LOG_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+/// Flushes the structured log file on drop
+static STRUCTURED_LOG_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+/// Latest log lines, shown in the diagnostics panel
+static RECENT_LOGS: OnceLock<logging::LogBuffer> = OnceLock::new();
+
+/// Buffer of the latest log lines, filled once logging is initialized
+pub(crate) fn recent_logs() -> &'static logging::LogBuffer {
+    RECENT_LOGS.get_or_init(logging::LogBuffer::default)
+}
+
+/// Directory the log files are written to
+pub(crate) fn log_dir() -> std::path::PathBuf {
+    dirs::data_local_dir()
         .unwrap_or_else(|| std::path::PathBuf::from("."))
         .join("ttrpg_companion")
-        .join("logs");
+        .join("logs")
+}
+
+/// Initialize logging system with file output
+///
+/// Logs go to stdout, a human-readable file and a JSON lines file, both
+/// rotated daily. The latest lines are also kept in memory. The JSON file
+/// also records when each span closes, with its busy and idle time.
+///
+/// `RUST_LOG` overrides the default `info` filter; the detection stage
+/// spans need `RUST_LOG=debug`.
+fn init_logging() {
+    let log_dir = log_dir();
 
     std::fs::create_dir_all(&log_dir).ok();
 
-    let file_appender = RollingFileAppender::new(Rotation::DAILY, log_dir, "ttrpg_companion.log");
-    let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
+    let file_appender = RollingFileAppender::new(Rotation::DAILY, &log_dir, LOG_FILE_PREFIX);
+    let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
+    let _ = LOG_GUARD.set(guard);
 
-    // Keep the guard alive for the lifetime of the application
-    std::mem::forget(_guard);
+    let structured_appender = RollingFileAppender::new(Rotation::DAILY, &log_dir, STRUCTURED_LOG_FILE_PREFIX);
+    let (structured, guard) = tracing_appender::non_blocking(structured_appender);
+    let _ = STRUCTURED_LOG_GUARD.set(guard);
 
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("info"));
@@ -91,14 +232,21 @@ fn init_logging() {
     tracing_subscriber::registry()
         .with(filter)
         .with(fmt::layer().with_writer(non_blocking))
+        .with(fmt::layer().json().with_span_events(FmtSpan::CLOSE).with_writer(structured))
         .with(fmt::layer().with_writer(std::io::stdout))
+        .with(logging::RecentLogsLayer::new(recent_logs().clone(), logging::RECENT_LOG_CAPACITY))
         .init();
 }
 
-/// Initialize database
-fn init_database(app: &tauri::App) -> Result<db::DbPool, AppError> {
+/// Location of the application database file
+pub(crate) fn database_path(app: &tauri::AppHandle) -> Result<std::path::PathBuf, AppError> {
     let app_dir = app.path().app_data_dir().map_err(|e| AppError::Config(e.to_string()))?;
-    let db_path = app_dir.join("ttrpg_companion.db");
+    Ok(app_dir.join("ttrpg_companion.db"))
+}
+
+/// Initialize database
+fn init_database(app: &tauri::AppHandle) -> Result<db::DbPool, AppError> {
+    let db_path = database_path(app)?;
 
     info!("Initializing database at: {:?}", db_path);
 
@@ -106,6 +254,85 @@ fn init_database(app: &tauri::App) -> Result<db::DbPool, AppError> {
     Ok(db.pool().clone())
 }
 
+/// Open the database and restore everything stored in it (UI ready phase)
+pub(crate) fn init_backend(app: &tauri::AppHandle) -> Result<(), AppError> {
+    let pool = init_database(app)?;
+    info!("Database initialized successfully");
+
+    let state = app.state::<AppState>();
+    state.db_pool.write().replace(pool.clone());
+    commands::library::restore_library_watcher(app);
+    commands::training::restore_emotion_baseline(&state);
+    // Embeddings saved before they were encrypted
+    let embeddings = profile::EncryptedStorage::new(profile::ProfileStorage::default_path());
+    if let Err(e) = embeddings.seal_plaintext_embeddings() {
+        warn!("Failed to encrypt stored voice embeddings: {}", e);
+    }
+    commands::keywords::restore_keywords(&state);
+    commands::models::restore_whisper_model(&state);
+    // Before the input gain, whose own setting is saved on every change
+    if let Err(e) = commands::settings::load_session_config(app.state()) {
+        warn!("Failed to load session configuration: {}", e);
+    }
+    commands::session::restore_app_mode(&state);
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(tray_tooltip(*state.app_mode.read())));
+    }
+    commands::settings::restore_input_gain(&state);
+
+    // OS hotkeys must be registered from the main thread
+    let handle = app.clone();
+    if let Err(e) = app.run_on_main_thread(move || commands::hotkeys::restore_hotkeys(&handle.state::<AppState>())) {
+        warn!("Failed to restore hotkeys: {}", e);
+    }
+    restore_playback(&state, db::Repository::new(pool));
+    Ok(())
+}
+
+/// Relay model loading progress to the frontend until the models have loaded
+fn start_startup_progress_relay(app: &tauri::AppHandle) {
+    let (tx, rx) = flume::unbounded::<startup::StartupProgress>();
+    app.state::<AppState>().startup.set_progress_sender(tx);
+
+    let app = app.clone();
+    let spawned = std::thread::Builder::new()
+        .name("startup-progress".to_string())
+        .spawn(move || {
+            for progress in rx.iter() {
+                let _ = app.emit(startup::STARTUP_PROGRESS_EVENT, progress);
+            }
+        });
+
+    if let Err(e) = spawned {
+        warn!("Startup progress will not reach the UI: {}", e);
+    }
+}
+
+/// Connect the audio thread to the database: load the crossfade matrix,
+/// start saving playback snapshots and resume the last track if enabled
+pub(crate) fn restore_playback(state: &AppState, repo: db::Repository) {
+    state.audio.set_repository(repo.clone());
+
+    if let Err(e) = commands::playback::load_crossfade_overrides(state, &repo) {
+        warn!("Failed to load crossfade overrides: {}", e);
+    }
+
+    if !audio::resume::resume_enabled(&repo) {
+        return;
+    }
+    if let Err(e) = state.audio.resume_last_track(&repo) {
+        warn!("Failed to resume last track: {}", e);
+    }
+}
+
+/// Id of the system tray icon
+pub(crate) const TRAY_ID: &str = "main";
+
+/// Tray tooltip showing the current mode
+pub(crate) fn tray_tooltip(mode: AppMode) -> String {
+    format!("TTRPG Companion - {} mode", mode)
+}
+
 /// Main entry point for the Tauri application
 pub fn run() {
     // Initialize logging first
@@ -120,17 +347,7 @@ pub fn run() {
     tauri::Builder::default()
         .setup(|app| {
             info!("Application setup starting");
-
-            // Initialize database
-            match init_database(app) {
-                Ok(pool) => {
-                    info!("Database initialized successfully");
-                    app.state::<AppState>().db_pool.write().replace(pool);
-                }
-                Err(e) => {
-                    warn!("Database initialization failed: {}", e);
-                }
-            }
+            app.state::<AppState>().startup.start();
 
             // Create system tray menu with mood indicator
             let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
@@ -148,9 +365,10 @@ pub fn run() {
             ])?;
 
             // Build system tray
-            let _tray = TrayIconBuilder::new()
+            let app_mode = *app.state::<AppState>().app_mode.read();
+            let _tray = TrayIconBuilder::with_id(TRAY_ID)
                 .menu(&menu)
-                .tooltip("TTRPG Companion - Ready")
+                .tooltip(tray_tooltip(app_mode))
                 .on_menu_event(|app, event| {
                     let state = app.state::<AppState>();
 
@@ -166,16 +384,15 @@ pub fn run() {
                         }
                         "stop_session" => {
                             info!("Stop session requested from system tray");
+                            state.capture.lock().take();
+                            state.loopback_capture.lock().take();
+                            state.pipeline_feed.lock().take();
+                            state.pipeline_thread.lock().take();
                             *state.session_state.write() = SessionState::Idle;
                         }
                         "toggle_mode" => {
-                            let current_mode = *state.app_mode.read();
-                            let new_mode = match current_mode {
-                                AppMode::ModeA => AppMode::ModeB,
-                                AppMode::ModeB => AppMode::ModeA,
-                            };
-                            *state.app_mode.write() = new_mode;
-                            info!("Mode toggled to: {:?}", new_mode);
+                            let new_mode = state.app_mode.read().toggled();
+                            commands::session::apply_app_mode(app, new_mode);
                         }
                         _ => {}
                     }
@@ -196,8 +413,14 @@ pub fn run() {
                 })
                 .build(app)?;
 
-            // Mark startup as complete
-            *app.state::<AppState>().startup_complete.write() = true;
+            commands::session::start_device_watcher(app.handle());
+            commands::hotkeys::start_hotkey_dispatcher(app.handle());
+            start_startup_progress_relay(app.handle());
+
+            // Database and models load in the background; the tray already
+            // exists so the restored mode reaches its tooltip. Dropping the
+            // handle detaches the task; phase errors are kept on the startup state
+            let _startup = app.state::<AppState>().startup.run_phases_async(app.handle().clone());
 
             info!("Application setup complete");
             Ok(())
@@ -205,17 +428,105 @@ pub fn run() {
         .manage(AppState::default())
         .invoke_handler(tauri::generate_handler![
             commands::session::start_session,
+            commands::session::start_session_with_device,
             commands::session::stop_session,
             commands::session::get_session_status,
             commands::session::get_available_devices,
+            commands::session::select_input_device,
+            commands::session::set_input_gain,
+            commands::session::set_latency_mode,
             commands::session::get_tracks,
             commands::session::set_app_mode,
             commands::session::get_app_mode,
             commands::session::set_detection_enabled,
+            commands::session::keyword_use_counts,
+            commands::session::simulate_detection,
+            commands::session::get_audio_levels,
+            commands::session::get_audio_peak,
+            commands::session::test_microphone,
+            commands::session::get_session_history,
+            commands::session::get_session_detail,
+            commands::session::delete_session,
+            commands::session::search_session_events,
+            commands::session::search_transcripts,
+            commands::session::get_detection_events,
+            commands::session::count_detection_events,
+            commands::session::get_pipeline_stats,
+            commands::session::get_pipeline_metrics,
+            commands::database::vacuum_database,
+            commands::database::check_database_integrity,
+            commands::database::backup_database,
+            commands::database::restore_database,
+            commands::diagnostics::get_diagnostics,
+            commands::diagnostics::get_startup_diagnostics,
+            commands::diagnostics::log_dir,
+            commands::diagnostics::get_recent_logs,
+            commands::export::export_session,
+            commands::hotkeys::get_hotkeys,
+            commands::hotkeys::set_hotkey,
+            commands::hotkeys::reset_hotkeys,
+            commands::hotkeys::list_hotkey_conflicts,
+            commands::hotkeys::assign_sfx_hotkey,
+            commands::hotkeys::unassign_sfx_hotkey,
+            commands::settings::get_setting,
+            commands::settings::set_setting,
+            commands::settings::get_all_settings,
+            commands::settings::save_session_config,
+            commands::settings::load_session_config,
+            commands::keywords::import_keywords,
+            commands::keywords::export_keywords,
+            commands::keywords::get_keywords,
+            commands::keywords::add_keyword,
+            commands::keywords::update_keyword,
+            commands::keywords::delete_keyword,
+            commands::keywords::set_keyword_active,
+            commands::keywords::get_genre_mappings,
+            commands::keywords::set_genre_mapping,
+            commands::keywords::delete_genre_mapping,
+            commands::library::import_tracks,
+            commands::library::update_track,
+            commands::library::delete_track,
+            commands::library::set_library_path,
+            commands::library::rescan_library,
+            commands::media::scan_media_directory,
+            commands::models::get_whisper_model_info,
+            commands::models::download_whisper_model,
+            commands::playback::set_resume_last_track,
+            commands::playback::get_last_playback,
+            commands::playback::preview_track,
+            commands::playback::stop_preview,
+            commands::playback::get_crossfade_overrides,
+            commands::playback::set_crossfade_override,
+            commands::playback::get_track_stats,
+            commands::playback::get_top_tracks,
+            commands::playback::get_mood_mappings,
+            commands::playback::set_mood_mapping,
+            commands::playback::delete_mood_mapping,
+            commands::playlists::get_playlists,
+            commands::playlists::create_playlist,
+            commands::playlists::add_track_to_playlist,
+            commands::playlists::remove_track_from_playlist,
+            commands::playlists::reorder_playlist,
+            commands::playlists::get_playlist_tracks,
+            commands::sfx::get_sfx,
+            commands::sfx::import_sfx,
+            commands::sfx::play_sfx_by_id,
+            commands::sfx::delete_sfx,
+            commands::suggestions::get_pending_suggestions,
+            commands::suggestions::accept_suggestion,
+            commands::suggestions::reject_suggestion,
             commands::training::get_training_passages,
             commands::training::get_training_status,
+            commands::training::start_training_recording,
+            commands::training::stop_training_recording,
+            commands::training::get_training_progress,
             commands::training::save_voice_profile,
+            commands::training::get_voice_profiles,
+            commands::training::set_default_voice_profile,
             commands::training::delete_voice_profile,
+            commands::training::calibrate_speaker_threshold,
+            commands::training::calibrate_emotion_baseline,
+            commands::training::revoke_consent,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
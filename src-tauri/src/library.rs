@@ -0,0 +1,253 @@
+//! Music library sync - keeps the `tracks` table in step with a folder on disk
+//!
+//! A full diff adds new audio files, soft-deletes rows whose file is gone and
+//! restores rows whose file came back. Files that fail to decode are stored
+//! with an error flag so later scans skip them.
+
+use crate::audio::metadata;
+use crate::db::{Repository, Track};
+use crate::error::AppError;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Settings key holding the library root directory
+pub const LIBRARY_PATH_SETTING: &str = "library_path";
+
+/// Event emitted to the frontend when the library changes
+pub const LIBRARY_CHANGED_EVENT: &str = "library://changed";
+
+/// File extensions treated as music
+const AUDIO_EXTENSIONS: &[&str] = &["mp3", "ogg", "wav", "flac", "m4a", "aac"];
+
+/// Quiet period before a burst of filesystem events triggers a rescan
+const WATCH_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Changes applied by a library scan
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibraryDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub failed: Vec<String>,
+}
+
+impl LibraryDiff {
+    /// Check if the scan changed nothing
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// Check if a path looks like a music file
+pub fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| AUDIO_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Recursively list audio files under a directory
+pub fn scan_directory(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if is_audio_file(&path) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Diff the directory against the database and apply the changes
+pub fn sync_library(repo: &Repository, root: &Path) -> Result<LibraryDiff, AppError> {
+    let mut diff = LibraryDiff::default();
+
+    let on_disk: HashSet<String> = scan_directory(root)
+        .into_iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+
+    let entries = repo.get_library_entries()?;
+    let known: HashSet<&str> = entries.iter().map(|e| e.file_path.as_str()).collect();
+
+    for entry in &entries {
+        if entry.is_excluded || !Path::new(&entry.file_path).starts_with(root) {
+            continue;
+        }
+
+        let exists = on_disk.contains(&entry.file_path);
+        if !exists && !entry.is_deleted && repo.soft_delete_track_by_path(&entry.file_path)? {
+            diff.removed.push(entry.file_path.clone());
+        } else if exists && entry.is_deleted && repo.restore_track_by_path(&entry.file_path)? {
+            diff.added.push(entry.file_path.clone());
+        }
+    }
+
+    let mut new_files: Vec<&String> = on_disk.iter().filter(|p| !known.contains(p.as_str())).collect();
+    new_files.sort();
+
+    for path in new_files {
+        match metadata::probe_track(Path::new(path)) {
+            Ok(meta) => {
+                let mut track = Track::new(uuid::Uuid::new_v4().to_string(), meta.title, path.clone());
+                track.duration_ms = meta.duration_ms.map(|ms| ms as i64);
+                repo.insert_track(&track)?;
+                if !meta.warnings.is_empty() {
+                    warn!("Import warnings for {}: {}", path, meta.warnings.join(", "));
+                    repo.set_track_import_warnings(&track.id, &meta.warnings)?;
+                }
+                diff.added.push(path.clone());
+            }
+            Err(e) => {
+                warn!("Library file failed to decode {}: {}", path, e);
+                let name = Path::new(path)
+                    .file_stem()
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.clone());
+                let mut track = Track::new(uuid::Uuid::new_v4().to_string(), name, path.clone());
+                track.duration_ms = Some(0);
+                repo.insert_track(&track)?;
+                repo.set_track_decode_error(&track.id, &e.to_string())?;
+                diff.failed.push(path.clone());
+            }
+        }
+    }
+
+    info!(
+        "Library sync: {} added, {} removed, {} failed",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.failed.len()
+    );
+
+    Ok(diff)
+}
+
+/// Watches the library folder and resyncs after filesystem changes
+pub struct LibraryWatcher {
+    _watcher: RecommendedWatcher,
+    root: PathBuf,
+}
+
+impl LibraryWatcher {
+    /// Start watching `root`; `on_change` runs after each sync that changed something
+    pub fn start<F>(root: PathBuf, repo: Repository, on_change: F) -> Result<Self, AppError>
+    where
+        F: Fn(&LibraryDiff) + Send + 'static,
+    {
+        let (tx, rx) = flume::unbounded::<()>();
+
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            if let Ok(event) = res {
+                if event.paths.iter().any(|p| is_audio_file(p) || p.is_dir()) {
+                    let _ = tx.send(());
+                }
+            }
+        })
+        .map_err(|e| AppError::Io(e.to_string()))?;
+
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| AppError::Io(e.to_string()))?;
+
+        let sync_root = root.clone();
+        std::thread::spawn(move || {
+            // Exits when the watcher (and its sender) is dropped
+            while rx.recv().is_ok() {
+                // Let copies finish before decoding new files
+                while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+
+                match sync_library(&repo, &sync_root) {
+                    Ok(diff) if !diff.is_empty() => on_change(&diff),
+                    Ok(_) => debug!("Library change produced no diff"),
+                    Err(e) => warn!("Library sync failed: {}", e),
+                }
+            }
+            debug!("Library watcher stopped");
+        });
+
+        info!("Watching music library at {:?}", root);
+
+        Ok(Self {
+            _watcher: watcher,
+            root,
+        })
+    }
+
+    /// Get the watched directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Database;
+
+    #[test]
+    fn test_sync_library_diff() {
+        let root = std::env::temp_dir().join(format!("library-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&root).unwrap();
+
+        let good = root.join("good.wav");
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 8000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(&good, spec).unwrap();
+        writer.write_sample(0i16).unwrap();
+        writer.finalize().unwrap();
+        std::fs::write(root.join("broken.mp3"), b"not audio").unwrap();
+
+        let db = Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+
+        let diff = sync_library(&repo, &root).unwrap();
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.failed.len(), 1);
+
+        // Broken files are not retried, unchanged files are not re-added
+        assert!(sync_library(&repo, &root).unwrap().is_empty());
+        assert_eq!(repo.get_all_tracks().unwrap().len(), 1);
+
+        let content = std::fs::read(&good).unwrap();
+        std::fs::remove_file(&good).unwrap();
+        let diff = sync_library(&repo, &root).unwrap();
+        assert_eq!(diff.removed, vec![good.to_string_lossy().to_string()]);
+        assert!(repo.get_all_tracks().unwrap().is_empty());
+
+        std::fs::write(&good, content).unwrap();
+        assert_eq!(sync_library(&repo, &root).unwrap().added.len(), 1);
+
+        // A track the user removed stays removed while its file exists
+        let track = repo.get_all_tracks().unwrap().remove(0);
+        assert!(repo.delete_track(&track.id).unwrap());
+        assert!(sync_library(&repo, &root).unwrap().is_empty());
+        assert!(repo.get_all_tracks().unwrap().is_empty());
+
+        // A sibling folder sharing the root's name as a prefix is not part of it
+        let sibling = format!("{}-old/track.ogg", root.to_string_lossy());
+        repo.insert_track(&Track::new("sibling".to_string(), "Sibling".to_string(), sibling)).unwrap();
+        assert!(sync_library(&repo, &root).unwrap().removed.is_empty());
+        assert!(repo.get_track("sibling").unwrap().is_some());
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
@@ -0,0 +1,113 @@
+//! In-memory buffer of recent log lines
+//!
+//! A tracing layer keeps the latest formatted events so the diagnostics
+//! panel can show them without reading the log files.
+
+use parking_lot::RwLock;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::Arc;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Number of log lines kept in memory
+pub const RECENT_LOG_CAPACITY: usize = 500;
+
+/// Shared buffer of formatted log lines, oldest first
+pub type LogBuffer = Arc<RwLock<VecDeque<String>>>;
+
+/// Layer pushing every event to a bounded buffer
+pub struct RecentLogsLayer {
+    buffer: LogBuffer,
+    capacity: usize,
+}
+
+impl RecentLogsLayer {
+    /// Create a layer writing to `buffer`, dropping the oldest lines beyond `capacity`
+    pub fn new(buffer: LogBuffer, capacity: usize) -> Self {
+        Self { buffer, capacity }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for RecentLogsLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut visitor = LineVisitor::default();
+        event.record(&mut visitor);
+
+        let line = format!(
+            "{} {:>5} {}: {}{}",
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            metadata.level(),
+            metadata.target(),
+            visitor.message,
+            visitor.fields
+        );
+
+        let mut buffer = self.buffer.write();
+        while buffer.len() >= self.capacity {
+            buffer.pop_front();
+        }
+        buffer.push_back(line);
+    }
+}
+
+/// Collects the message and the other fields of an event
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for LineVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            let _ = write!(self.fields, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Last `lines` lines of the buffer, oldest first
+pub fn recent_lines(buffer: &LogBuffer, lines: usize) -> Vec<String> {
+    let buffer = buffer.read();
+    buffer.iter().skip(buffer.len().saturating_sub(lines)).cloned().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_buffer_keeps_latest_lines() {
+        let buffer = LogBuffer::default();
+        let subscriber = tracing_subscriber::registry().with(RecentLogsLayer::new(buffer.clone(), 3));
+
+        tracing::subscriber::with_default(subscriber, || {
+            for i in 0..5 {
+                tracing::info!(device = "mic", "line {}", i);
+            }
+            tracing::warn!("last");
+        });
+
+        let lines = recent_lines(&buffer, 10);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("INFO") && lines[0].ends_with("line 3 device=mic"));
+        assert!(lines[2].contains("WARN") && lines[2].ends_with("last"));
+
+        let tail = recent_lines(&buffer, 1);
+        assert_eq!(tail, vec![lines[2].clone()]);
+        assert!(recent_lines(&buffer, 0).is_empty());
+    }
+}
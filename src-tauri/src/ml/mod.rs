@@ -22,6 +22,7 @@ pub struct ModelPaths {
     pub vad_model: Option<String>,
     pub speaker_model: Option<String>,
     pub emotion_model: Option<String>,
+    pub whisper_model: Option<String>,
 }
 
 impl Default for ModelPaths {
@@ -30,6 +31,7 @@ impl Default for ModelPaths {
             vad_model: Some("models/silero_vad.onnx".to_string()),
             speaker_model: Some("models/resemblyzer.onnx".to_string()),
             emotion_model: Some("models/emotion2vec.onnx".to_string()),
+            whisper_model: Some(crate::inference::whisper::get_model_path().to_string_lossy().to_string()),
         }
     }
 }
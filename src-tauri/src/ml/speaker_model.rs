@@ -1,7 +1,32 @@
 //! Resemblyzer speaker model integration
+//!
+//! With the `ort` feature enabled, embeddings come from the Resemblyzer
+//! ONNX model: 16kHz audio is turned into 40-band mel frames, split into
+//! 160-frame partials, run as one batch and averaged into a unit vector.
 
 use crate::error::AppError;
 
+#[cfg(feature = "ort")]
+use crate::dsp::{mel, processing};
+#[cfg(feature = "ort")]
+use ort::{session::Session, value::Tensor};
+
+/// Sample rate expected by Resemblyzer
+pub const RESEMBLYZER_SAMPLE_RATE: u32 = 16000;
+/// Resemblyzer embedding size
+pub const RESEMBLYZER_EMBEDDING_DIM: usize = 256;
+
+#[cfg(feature = "ort")]
+const MEL_WINDOW: usize = 400; // 25ms
+#[cfg(feature = "ort")]
+const MEL_HOP: usize = 160; // 10ms
+#[cfg(feature = "ort")]
+const MEL_BANDS: usize = 40;
+#[cfg(feature = "ort")]
+const PARTIAL_FRAMES: usize = 160;
+#[cfg(feature = "ort")]
+const PARTIAL_STEP: usize = 80;
+
 /// Speaker embedding (256-512 dimensions)
 #[derive(Debug, Clone)]
 pub struct SpeakerEmbedding {
@@ -50,7 +75,10 @@ pub struct VerificationResult {
 
 /// Resemblyzer speaker model
 pub struct SpeakerModel {
-    /// Placeholder for ONNX session
+    /// ONNX session (None falls back to a placeholder embedding)
+    #[cfg(feature = "ort")]
+    session: Option<Session>,
+    #[cfg(not(feature = "ort"))]
     session: Option<()>,
     threshold: f32,
 }
@@ -68,8 +96,10 @@ impl SpeakerModel {
     pub fn load(&mut self, model_path: &str) -> Result<(), AppError> {
         tracing::info!("Loading Resemblyzer model from: {}", model_path);
 
-        // In production:
-        // self.session = Some(ort::Session::from_file(model_path)?);
+        #[cfg(feature = "ort")]
+        {
+            self.session = Some(Session::builder()?.commit_from_file(model_path)?);
+        }
 
         tracing::info!("Resemblyzer model loaded");
         Ok(())
@@ -80,12 +110,15 @@ impl SpeakerModel {
         self.threshold = threshold.clamp(0.0, 1.0);
     }
 
-    /// Extract embedding from audio
-    pub fn extract_embedding(&self, audio: &[f32], sample_rate: u32) -> Result<SpeakerEmbedding, AppError> {
-        // Placeholder: Generate random embedding
-        // In production, run ONNX inference to get embedding
+    /// Extract embedding from mono audio
+    pub fn extract_embedding(&mut self, audio: &[f32], sample_rate: u32) -> Result<SpeakerEmbedding, AppError> {
+        #[cfg(feature = "ort")]
+        if let Some(session) = self.session.as_mut() {
+            return Self::run_resemblyzer(session, audio, sample_rate);
+        }
 
-        let dimension = 256;
+        // Placeholder embedding when no model is loaded
+        let dimension = RESEMBLYZER_EMBEDDING_DIM;
         let mut data = vec![0.0f32; dimension];
 
         // Simple feature extraction as placeholder
@@ -118,6 +151,63 @@ impl SpeakerModel {
     pub fn is_loaded(&self) -> bool {
         self.session.is_some()
     }
+
+    /// Run Resemblyzer over all partial windows and average the result
+    #[cfg(feature = "ort")]
+    fn run_resemblyzer(
+        session: &mut Session,
+        audio: &[f32],
+        sample_rate: u32,
+    ) -> Result<SpeakerEmbedding, AppError> {
+        let samples = if sample_rate != RESEMBLYZER_SAMPLE_RATE {
+            processing::resample(audio, sample_rate, RESEMBLYZER_SAMPLE_RATE)
+        } else {
+            audio.to_vec()
+        };
+
+        let frames = mel::mel_spectrogram(&samples, RESEMBLYZER_SAMPLE_RATE, MEL_WINDOW, MEL_HOP, MEL_BANDS);
+        if frames.is_empty() {
+            return Err(AppError::Inference("Audio too short for speaker embedding".to_string()));
+        }
+
+        // Split into overlapping partials, zero-padding the tail
+        let n_partials = 1 + frames.len().saturating_sub(PARTIAL_FRAMES).div_ceil(PARTIAL_STEP);
+        let mut batch = Vec::with_capacity(n_partials * PARTIAL_FRAMES * MEL_BANDS);
+        for p in 0..n_partials {
+            let start = p * PARTIAL_STEP;
+            for f in start..start + PARTIAL_FRAMES {
+                match frames.get(f) {
+                    Some(frame) => batch.extend_from_slice(frame),
+                    None => batch.resize(batch.len() + MEL_BANDS, 0.0),
+                }
+            }
+        }
+
+        let input = Tensor::from_array(([n_partials, PARTIAL_FRAMES, MEL_BANDS], batch))?;
+        let outputs = session.run(ort::inputs![input])?;
+        let (_, output) = outputs[0].try_extract_tensor::<f32>()?;
+
+        let dimension = output.len() / n_partials;
+        let mut data = vec![0.0f32; dimension];
+        for partial in output.chunks_exact(dimension) {
+            for (d, v) in data.iter_mut().zip(partial) {
+                *d += v / n_partials as f32;
+            }
+        }
+
+        Ok(SpeakerEmbedding::new(l2_normalize(data)))
+    }
+}
+
+/// Scale a vector to unit length
+pub fn l2_normalize(mut data: Vec<f32>) -> Vec<f32> {
+    let norm = data.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for v in data.iter_mut() {
+            *v /= norm;
+        }
+    }
+    data
 }
 
 impl Default for SpeakerModel {
@@ -125,3 +215,16 @@ impl Default for SpeakerModel {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_l2_normalize() {
+        let data = l2_normalize(vec![3.0, 4.0]);
+        assert!((data[0] - 0.6).abs() < 1e-6);
+        assert!((data[1] - 0.8).abs() < 1e-6);
+        assert_eq!(l2_normalize(vec![0.0; 4]), vec![0.0; 4]);
+    }
+}
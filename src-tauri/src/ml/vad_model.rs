@@ -1,6 +1,20 @@
 //! Silero VAD model integration
+//!
+//! With the `ort` feature enabled, the Silero V4 ONNX model is run through
+//! ONNX Runtime. Otherwise inference falls back to RMS energy.
 
 use crate::error::AppError;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+#[cfg(feature = "ort")]
+use ort::{session::Session, value::Tensor};
+
+/// Sample rate expected by Silero VAD
+pub const SILERO_SAMPLE_RATE: u32 = 16000;
+
+/// Size of each Silero LSTM state tensor (shape [2, 1, 64])
+const SILERO_STATE_LEN: usize = 2 * 64;
 
 /// Voice Activity Detection result
 #[derive(Debug, Clone)]
@@ -9,10 +23,38 @@ pub struct VadOutput {
     pub probability: f32,
 }
 
+/// Silero recurrent state carried between inference calls
+#[derive(Debug, Clone)]
+pub struct SileroState {
+    pub h: Vec<f32>,
+    pub c: Vec<f32>,
+}
+
+impl SileroState {
+    /// Create a zeroed state
+    pub fn new() -> Self {
+        Self {
+            h: vec![0.0; SILERO_STATE_LEN],
+            c: vec![0.0; SILERO_STATE_LEN],
+        }
+    }
+}
+
+impl Default for SileroState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Silero VAD model
 pub struct VadModel {
-    /// Placeholder for ONNX session
+    /// ONNX session (None falls back to energy detection)
+    #[cfg(feature = "ort")]
+    session: Option<Mutex<Session>>,
+    #[cfg(not(feature = "ort"))]
     session: Option<()>,
+    /// Hidden state shared across calls
+    state: Arc<Mutex<SileroState>>,
     threshold: f32,
 }
 
@@ -21,6 +63,7 @@ impl VadModel {
     pub fn new() -> Self {
         Self {
             session: None,
+            state: Arc::new(Mutex::new(SileroState::new())),
             threshold: 0.5,
         }
     }
@@ -29,26 +72,37 @@ impl VadModel {
     pub fn load(&mut self, model_path: &str) -> Result<(), AppError> {
         tracing::info!("Loading Silero VAD model from: {}", model_path);
 
-        // In production:
-        // self.session = Some(ort::Session::from_file(model_path)?);
+        #[cfg(feature = "ort")]
+        {
+            let session = Session::builder()?.commit_from_file(model_path)?;
+            self.session = Some(Mutex::new(session));
+            self.reset_state();
+        }
 
         tracing::info!("Silero VAD model loaded");
         Ok(())
     }
 
+    /// Clear the recurrent state (call between sessions)
+    pub fn reset_state(&self) {
+        *self.state.lock() = SileroState::new();
+    }
+
     /// Set detection threshold
     pub fn set_threshold(&mut self, threshold: f32) {
         self.threshold = threshold.clamp(0.0, 1.0);
     }
 
-    /// Run inference on audio frame
+    /// Run inference on audio frame (16kHz mono)
     pub fn infer(&self, audio: &[f32]) -> Result<VadOutput, AppError> {
-        // Placeholder implementation
-        // In production, run ONNX inference:
-
-        // let input = Tensor::from_slice(audio)?;
-        // let output = self.session.run(input)?;
-        // let probability = output[0].as_slice()[0];
+        #[cfg(feature = "ort")]
+        if let Some(session) = &self.session {
+            let probability = self.run_silero(&mut session.lock(), audio)?;
+            return Ok(VadOutput {
+                is_speech: probability > self.threshold,
+                probability,
+            });
+        }
 
         // Energy-based fallback
         let energy = if audio.is_empty() {
@@ -71,6 +125,35 @@ impl VadModel {
     pub fn is_loaded(&self) -> bool {
         self.session.is_some()
     }
+
+    /// Run one Silero V4 step, updating the recurrent state
+    #[cfg(feature = "ort")]
+    fn run_silero(&self, session: &mut Session, audio: &[f32]) -> Result<f32, AppError> {
+        let mut state = self.state.lock();
+
+        let input = Tensor::from_array(([1, audio.len()], audio.to_vec()))?;
+        let sr = Tensor::from_array(([1], vec![SILERO_SAMPLE_RATE as i64]))?;
+        let h = Tensor::from_array(([2, 1, 64], state.h.clone()))?;
+        let c = Tensor::from_array(([2, 1, 64], state.c.clone()))?;
+
+        let outputs = session.run(ort::inputs![
+            "input" => input,
+            "sr" => sr,
+            "h" => h,
+            "c" => c,
+        ])?;
+
+        let (_, probability) = outputs[0].try_extract_tensor::<f32>()?;
+        let (_, hn) = outputs[1].try_extract_tensor::<f32>()?;
+        let (_, cn) = outputs[2].try_extract_tensor::<f32>()?;
+
+        if hn.len() == SILERO_STATE_LEN && cn.len() == SILERO_STATE_LEN {
+            state.h.copy_from_slice(hn);
+            state.c.copy_from_slice(cn);
+        }
+
+        Ok(probability.first().copied().unwrap_or(0.0))
+    }
 }
 
 impl Default for VadModel {
@@ -82,7 +165,7 @@ impl Default for VadModel {
 /// Convert audio to model input format
 pub fn prepare_input(samples: &[f32], sample_rate: u32) -> Vec<f32> {
     // Silero expects 16kHz mono audio
-    let target_rate = 16000;
+    let target_rate = SILERO_SAMPLE_RATE;
 
     if sample_rate == target_rate {
         return samples.to_vec();
@@ -99,3 +182,17 @@ pub fn prepare_input(samples: &[f32], sample_rate: u32) -> Vec<f32> {
         .copied()
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reset_state_clears_hidden_state() {
+        let model = VadModel::new();
+        model.state.lock().h[0] = 1.0;
+        model.reset_state();
+        assert!(model.state.lock().h.iter().all(|&v| v == 0.0));
+        assert_eq!(model.state.lock().c.len(), SILERO_STATE_LEN);
+    }
+}
@@ -0,0 +1,159 @@
+//! Autonomous playback - turns a confirmed dual signal into a track
+//!
+//! The keyword's category is mapped to a genre through the keyword genre
+//! mappings. Tracks explicitly mapped to the detected emotion through the mood
+//! mappings win; among them, those of the keyword's genre are preferred.
+//! Without a mood mapping, the track is picked at random among those matching
+//! both the genre and the emotion as mood. When nothing matches both, the
+//! genre alone is tried, then the mood alone.
+
+use crate::db::{Repository, Track};
+use crate::detection::keyword::KeywordVocabulary;
+use crate::error::AppError;
+use rand::seq::SliceRandom;
+
+/// Genre mapped to the category of `keyword`, if any
+pub fn genre_for_keyword(
+    repo: &Repository,
+    vocabulary: &KeywordVocabulary,
+    keyword: &str,
+) -> Result<Option<String>, AppError> {
+    let Some(keyword) = vocabulary.get(keyword) else {
+        return Ok(None);
+    };
+    repo.get_genre_for_category(&keyword.category)
+}
+
+/// Tracks mapped to `mood`, from the highest priority that resolves to any
+pub fn mapped_tracks(repo: &Repository, mood: &str) -> Result<Vec<Track>, AppError> {
+    let mut tracks: Vec<Track> = Vec::new();
+    let mut priority = None;
+
+    for mapping in repo.get_mood_mappings_for(mood)? {
+        if priority != Some(mapping.priority) && !tracks.is_empty() {
+            break;
+        }
+        priority = Some(mapping.priority);
+
+        let resolved = match (&mapping.track_id, &mapping.genre) {
+            (Some(track_id), _) => repo.get_track(track_id)?.into_iter().collect(),
+            (None, Some(genre)) => repo.get_tracks_by_genre(genre)?,
+            (None, None) => Vec::new(),
+        };
+        for track in resolved {
+            if !tracks.iter().any(|t| t.id == track.id) {
+                tracks.push(track);
+            }
+        }
+    }
+
+    Ok(tracks)
+}
+
+/// SFX of the highest-priority mapping for `mood` that has one
+pub fn mapped_sfx(repo: &Repository, mood: &str) -> Result<Option<String>, AppError> {
+    Ok(repo.get_mood_mappings_for(mood)?.into_iter().find_map(|m| m.sfx_id))
+}
+
+/// Pick a random track for a genre and mood, relaxing the match if needed
+pub fn pick_track(repo: &Repository, genre: Option<&str>, mood: &str) -> Result<Option<Track>, AppError> {
+    let mapped = mapped_tracks(repo, mood)?;
+    if !mapped.is_empty() {
+        let in_genre: Vec<&Track> = mapped
+            .iter()
+            .filter(|t| genre.is_some() && t.genre.as_deref() == genre)
+            .collect();
+        let track = match in_genre.choose(&mut rand::thread_rng()) {
+            Some(track) => Some(*track),
+            None => mapped.choose(&mut rand::thread_rng()),
+        };
+        return Ok(track.cloned());
+    }
+
+    let mut tracks = Vec::new();
+    if let Some(genre) = genre {
+        tracks = repo.get_tracks_by_genre_and_mood(genre, mood)?;
+        if tracks.is_empty() {
+            tracks = repo.get_tracks_by_genre(genre)?;
+        }
+    }
+    if tracks.is_empty() {
+        tracks = repo.get_tracks_by_mood(mood)?;
+    }
+
+    Ok(tracks.choose(&mut rand::thread_rng()).cloned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{Database, KeywordGenreMapping, MoodMapping};
+    use crate::detection::keyword::Keyword;
+
+    fn insert(repo: &Repository, id: &str, genre: &str, mood: &str) {
+        let mut track = Track::new(id.to_string(), id.to_string(), format!("/music/{}.ogg", id));
+        track.duration_ms = Some(120_000);
+        track.genre = Some(genre.to_string());
+        track.mood = Some(mood.to_string());
+        repo.insert_track(&track).unwrap();
+    }
+
+    #[test]
+    fn test_dual_signal_picks_genre_and_mood_track() {
+        let db = Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+        insert(&repo, "boss-angry", "battle", "angry");
+        insert(&repo, "boss-sad", "battle", "sad");
+        insert(&repo, "town-happy", "town", "happy");
+        repo.upsert_genre_mapping(&KeywordGenreMapping::new("combat".to_string(), "battle".to_string(), 1))
+            .unwrap();
+
+        let mut vocabulary = KeywordVocabulary::new();
+        vocabulary.add_keyword(Keyword::new("dragon".to_string(), "combat".to_string()));
+
+        let genre = genre_for_keyword(&repo, &vocabulary, "Dragon").unwrap();
+        assert_eq!(genre.as_deref(), Some("battle"));
+        assert_eq!(genre_for_keyword(&repo, &vocabulary, "tavern").unwrap(), None);
+
+        let track = pick_track(&repo, genre.as_deref(), "angry").unwrap().unwrap();
+        assert_eq!(track.id, "boss-angry");
+
+        // No battle track is fearful: fall back to any battle track
+        let track = pick_track(&repo, Some("battle"), "fearful").unwrap().unwrap();
+        assert_eq!(track.genre.as_deref(), Some("battle"));
+
+        // Unmapped keyword: mood alone
+        let track = pick_track(&repo, None, "happy").unwrap().unwrap();
+        assert_eq!(track.id, "town-happy");
+        assert!(pick_track(&repo, None, "fearful").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_mood_mappings_take_precedence() {
+        let db = Database::in_memory().unwrap();
+        let repo = Repository::new(db.pool().clone());
+        insert(&repo, "boss-angry", "battle", "angry");
+        insert(&repo, "duel", "battle", "calm");
+        insert(&repo, "chase", "action", "calm");
+
+        let mut duel = MoodMapping::new("m1".to_string(), "angry".to_string());
+        duel.track_id = Some("duel".to_string());
+        duel.priority = 5;
+        repo.upsert_mood_mapping(&duel).unwrap();
+        let mut action = MoodMapping::new("m2".to_string(), "angry".to_string());
+        action.genre = Some("action".to_string());
+        repo.upsert_mood_mapping(&action).unwrap();
+
+        // Mapped tracks win over mood-tagged ones, highest priority first
+        let ids: Vec<String> = mapped_tracks(&repo, "angry").unwrap().into_iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec!["duel"]);
+        assert_eq!(pick_track(&repo, None, "angry").unwrap().unwrap().id, "duel");
+
+        // Deleting the track drops its mapping; the lower priority genre takes over
+        repo.delete_track("duel").unwrap();
+        assert_eq!(pick_track(&repo, Some("battle"), "angry").unwrap().unwrap().id, "chase");
+
+        repo.delete_mood_mapping("m2").unwrap();
+        assert_eq!(pick_track(&repo, None, "angry").unwrap().unwrap().id, "boss-angry");
+    }
+}
@@ -1,5 +1,10 @@
 //! Session orchestrator - state machine management
 
+pub mod autoplay;
+pub mod playlist;
 pub mod state;
+pub mod suggestions;
 
+pub use playlist::MoodPlaylist;
 pub use state::SessionOrchestrator;
+pub use suggestions::SuggestionQueue;
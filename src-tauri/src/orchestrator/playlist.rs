@@ -0,0 +1,239 @@
+//! Mood playlist - rotates background music within the locked mood
+//!
+//! Tracks mapped to the current mood (or tagged with it) are shuffled and
+//! played in turn.
+//! Non-looping tracks advance when they finish; looping tracks advance
+//! after `rotation_minutes`. The same track is never played twice in a row.
+//! The audio thread ticks the playlist; rotation stops as soon as the music
+//! is stopped or changed by anything else.
+
+use crate::audio::engine::{AudioEngine, Track};
+use crate::db::Repository;
+use crate::error::AppError;
+use crate::orchestrator::autoplay;
+use rand::seq::SliceRandom;
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Default rotation interval for looping tracks
+pub const DEFAULT_ROTATION_MINUTES: u32 = 5;
+
+/// Wait before trying the next track after a failed crossfade
+const RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Background music scheduler for a single mood
+pub struct MoodPlaylist {
+    repository: Repository,
+    mood: Option<String>,
+    tracks: Vec<Track>,
+    queue: Vec<Track>,
+    current: Option<Track>,
+    started_at: Option<Instant>,
+    retry_at: Option<Instant>,
+    rotation_minutes: u32,
+}
+
+impl MoodPlaylist {
+    /// Create a new playlist backed by the track repository
+    pub fn new(repository: Repository) -> Self {
+        Self {
+            repository,
+            mood: None,
+            tracks: Vec::new(),
+            queue: Vec::new(),
+            current: None,
+            started_at: None,
+            retry_at: None,
+            rotation_minutes: DEFAULT_ROTATION_MINUTES,
+        }
+    }
+
+    /// Get the current mood
+    pub fn current_mood(&self) -> Option<&str> {
+        self.mood.as_deref()
+    }
+
+    /// Switch to a new mood, reloading its tracks
+    ///
+    /// Tracks come from the mood mappings first, then tracks tagged with the
+    /// mood, then the genre of the same name. Setting the mood that is
+    /// already active keeps the current rotation.
+    pub fn set_mood(&mut self, mood: &str) -> Result<(), AppError> {
+        if self.mood.as_deref() == Some(mood) {
+            return Ok(());
+        }
+
+        let mut tracks = autoplay::mapped_tracks(&self.repository, mood)?;
+        if tracks.is_empty() {
+            tracks = self.repository.get_tracks_by_mood(mood)?;
+        }
+        if tracks.is_empty() {
+            tracks = self.repository.get_tracks_by_genre(mood)?;
+        }
+
+        info!("Mood playlist set to {} ({} tracks)", mood, tracks.len());
+
+        self.mood = Some(mood.to_string());
+        self.tracks = tracks.iter().map(Track::from).collect();
+        self.queue.clear();
+        self.started_at = None;
+        Ok(())
+    }
+
+    /// Crossfade to `track` and keep rotating the tracks of `mood` after it
+    ///
+    /// The track still plays if the mood's tracks cannot be loaded; the
+    /// rotation is stopped instead.
+    pub fn play(&mut self, mood: &str, track: &Track, engine: &mut AudioEngine) -> Result<(), AppError> {
+        engine.crossfade_to(track)?;
+        match self.set_mood(mood) {
+            Ok(()) => self.advanced(track.clone()),
+            Err(e) => {
+                warn!("Mood playlist unavailable for {}: {}", mood, e);
+                self.clear();
+            }
+        }
+        Ok(())
+    }
+
+    /// Stop rotating
+    pub fn clear(&mut self) {
+        self.mood = None;
+        self.tracks.clear();
+        self.queue.clear();
+        self.current = None;
+        self.started_at = None;
+        self.retry_at = None;
+    }
+
+    /// Set the rotation interval for looping tracks
+    pub fn set_rotation_minutes(&mut self, minutes: u32) {
+        self.rotation_minutes = minutes.max(1);
+    }
+
+    /// Currently scheduled track
+    pub fn current_track(&self) -> Option<&Track> {
+        self.current.as_ref()
+    }
+
+    /// Advance the playlist if the current track finished or its rotation elapsed
+    ///
+    /// Call periodically; crossfades the engine to the next track when needed.
+    /// The current track only changes once the crossfade succeeded.
+    pub fn tick(&mut self, engine: &mut AudioEngine) -> Result<(), AppError> {
+        if self.mood.is_none() || self.tracks.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(current) = &self.current {
+            let playing = engine.current_track().map(|playing| playing.track.id);
+            if playing.as_deref() != Some(current.id.as_str()) {
+                debug!("Music changed outside the mood playlist, stopping rotation");
+                self.clear();
+                return Ok(());
+            }
+        }
+
+        if self.retry_at.is_some_and(|at| Instant::now() < at) || !self.should_advance(engine.is_playing()) {
+            return Ok(());
+        }
+
+        let Some(track) = self.next_track() else {
+            return Ok(());
+        };
+        debug!("Mood playlist advancing to {}", track.name);
+        if let Err(e) = engine.crossfade_to(&track) {
+            self.retry_at = Some(Instant::now() + RETRY_DELAY);
+            return Err(e);
+        }
+        self.advanced(track);
+        Ok(())
+    }
+
+    /// Record `track` as playing from now
+    fn advanced(&mut self, track: Track) {
+        self.current = Some(track);
+        self.started_at = Some(Instant::now());
+        self.retry_at = None;
+    }
+
+    /// Decide whether the current track should be replaced
+    fn should_advance(&self, is_playing: bool) -> bool {
+        let (Some(current), Some(started_at)) = (&self.current, self.started_at) else {
+            return true;
+        };
+
+        if current.is_looping {
+            started_at.elapsed() >= Duration::from_secs(self.rotation_minutes as u64 * 60)
+        } else {
+            !is_playing
+        }
+    }
+
+    /// Pick the next track, reshuffling when the queue runs out
+    fn next_track(&mut self) -> Option<Track> {
+        if self.queue.is_empty() {
+            self.queue = self.tracks.clone();
+            self.queue.shuffle(&mut rand::thread_rng());
+
+            // Avoid replaying the track that just finished
+            let last_id = self.current.as_ref().map(|t| t.id.clone());
+            let end = self.queue.len() - 1;
+            if end > 0 && self.queue.last().map(|t| &t.id) == last_id.as_ref() {
+                self.queue.swap(0, end);
+            }
+        }
+
+        self.queue.pop()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{self, Database};
+
+    #[test]
+    fn test_rotation_never_repeats_track() {
+        let database = Database::in_memory().unwrap();
+        let repository = Repository::new(database.pool().clone());
+        for (id, name) in [("a", "Alpha"), ("b", "Bravo"), ("c", "Charlie")] {
+            let mut track = db::Track::new(id.to_string(), name.to_string(), format!("{}.ogg", id));
+            track.mood = Some("tense".to_string());
+            track.duration_ms = Some(180_000);
+            repository.insert_track(&track).unwrap();
+        }
+
+        let mut playlist = MoodPlaylist::new(Repository::new(database.pool().clone()));
+        playlist.set_mood("tense").unwrap();
+        assert_eq!(playlist.current_mood(), Some("tense"));
+
+        let mut last = String::new();
+        for _ in 0..30 {
+            let track = playlist.next_track().unwrap();
+            assert_ne!(track.id, last);
+            last = track.id.clone();
+            playlist.advanced(track);
+        }
+    }
+
+    #[test]
+    fn test_failed_crossfade_keeps_current_track() {
+        let database = Database::in_memory().unwrap();
+        let repository = Repository::new(database.pool().clone());
+        let mut track = db::Track::new("a".to_string(), "Alpha".to_string(), "missing.ogg".to_string());
+        track.mood = Some("calm".to_string());
+        repository.insert_track(&track).unwrap();
+
+        let mut playlist = MoodPlaylist::new(repository);
+        playlist.set_mood("calm").unwrap();
+        let mut engine = AudioEngine::default();
+        assert!(playlist.tick(&mut engine).is_err());
+        assert!(playlist.current_track().is_none());
+        assert!(playlist.started_at.is_none());
+
+        // Waits before trying again
+        assert!(playlist.tick(&mut engine).is_ok());
+        assert_eq!(playlist.current_mood(), Some("calm"));
+    }
+}
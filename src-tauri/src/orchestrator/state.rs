@@ -6,9 +6,12 @@ pub use crate::state::SessionState;
 pub use crate::state::SessionConfig;
 
 use crate::audio::capture::AudioCapture;
+use crate::audio::AudioRingBuffer;
 use crate::dsp::processing;
+use crate::error::AppError;
 use crate::inference::emotion::{EmotionAnalyzer, EmotionResult};
-use crate::inference::whisper::{Transcription, WhisperEngine};
+use crate::inference::whisper::{self, Transcription, WhisperEngine};
+use crate::state::channels::AUDIO_BUFFER_CAPACITY;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
@@ -23,6 +26,21 @@ pub enum OrchestratorError {
     AudioError(String),
     #[error("Inference error: {0}")]
     InferenceError(String),
+    #[error("Live detection problem: {0}")]
+    DetectionError(String),
+}
+
+impl From<AppError> for OrchestratorError {
+    fn from(error: AppError) -> Self {
+        match error {
+            AppError::Audio(message) => OrchestratorError::AudioError(message),
+            AppError::Inference(message) => OrchestratorError::InferenceError(message),
+            AppError::State(message) => OrchestratorError::InvalidState(message),
+            AppError::Detection(message) => OrchestratorError::DetectionError(message),
+            // Anything else reaching the orchestrator came out of the pipeline
+            other => OrchestratorError::DetectionError(other.to_string()),
+        }
+    }
 }
 
 /// Audio buffer for processing
@@ -51,7 +69,7 @@ pub struct SessionOrchestrator {
     capture: AudioCapture,
     whisper: WhisperEngine,
     emotion: EmotionAnalyzer,
-    audio_buffer: Arc<Mutex<Vec<f32>>>,  // Thread-safe buffer
+    audio_buffer: Arc<Mutex<AudioRingBuffer>>,  // Thread-safe buffer
     event_tx: Option<mpsc::Sender<SessionEvent>>,
 }
 
@@ -64,7 +82,7 @@ impl SessionOrchestrator {
             capture: AudioCapture::new(),
             whisper: WhisperEngine::new(),
             emotion: EmotionAnalyzer::new(),
-            audio_buffer: Arc::new(Mutex::new(Vec::new())),
+            audio_buffer: Arc::new(Mutex::new(AudioRingBuffer::new(AUDIO_BUFFER_CAPACITY, 16000))),
             event_tx: None,
         }
     }
@@ -74,7 +92,7 @@ impl SessionOrchestrator {
         info!("Initializing session orchestrator");
 
         // Initialize whisper (placeholder model path)
-        if let Err(e) = self.whisper.init("models/whisper-tiny.bin") {
+        if let Err(e) = self.whisper.init(&whisper::get_model_path().to_string_lossy()) {
             warn!("Whisper init warning: {}", e);
         }
 
@@ -111,11 +129,17 @@ impl SessionOrchestrator {
         self.capture
             .start_recording(move |samples| {
                 if let Ok(mut buffer) = buffer.lock() {
-                    buffer.extend_from_slice(&samples);
+                    buffer.push_slice(&samples);
                 }
             })
             .map_err(|e| OrchestratorError::AudioError(e.to_string()))?;
 
+        // Snapshots are measured in interleaved samples of the real device
+        let rate = self.capture.sample_rate() * self.capture.channels() as u32;
+        if let Ok(mut buffer) = self.audio_buffer.lock() {
+            buffer.set_sample_rate(rate);
+        }
+
         self.state = SessionState::Recording;
         info!("Session started, state: {}", self.state);
 
@@ -169,8 +193,8 @@ impl SessionOrchestrator {
     fn process_audio(&self) -> Result<SessionResult, OrchestratorError> {
         // Get samples from the thread-safe buffer
         let samples = {
-            let buffer = self.audio_buffer.lock().unwrap();
-            buffer.clone()
+            let mut buffer = self.audio_buffer.lock().unwrap();
+            buffer.drain_all()
         };
 
         info!("Processing audio buffer ({} samples)", samples.len());
@@ -242,3 +266,18 @@ pub struct SessionResult {
     pub transcription: Option<Transcription>,
     pub emotion: Option<EmotionResult>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detection_failures_convert_to_detection_error() {
+        let error = OrchestratorError::from(AppError::Detection("Transcription failed".to_string()));
+        assert!(matches!(error, OrchestratorError::DetectionError(ref m) if m == "Transcription failed"));
+        assert_eq!(error.to_string(), "Live detection problem: Transcription failed");
+
+        let error = OrchestratorError::from(AppError::Audio("device gone".to_string()));
+        assert!(matches!(error, OrchestratorError::AudioError(_)));
+    }
+}
@@ -0,0 +1,168 @@
+//! Suggestion queue - music changes awaiting GM confirmation
+//!
+//! In collaborative mode a confirmed dual signal does not change the music by
+//! itself. It queues a suggestion that the GM accepts or rejects; suggestions
+//! nobody answers expire after their TTL. A keyword that fires again within
+//! the cooldown window is not queued twice.
+//!
+//! This is the collaborative-mode queue: nothing in it touches the audio
+//! engine until the GM accepts it.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How long a suggestion stays pending
+pub const SUGGESTION_TTL_MS: u64 = 60_000;
+
+/// Minimum gap before the same keyword may queue another suggestion
+pub const SUGGESTION_COOLDOWN_MS: u64 = 30_000;
+
+/// Action of a suggestion that crossfades to its track
+pub const CROSSFADE_ACTION: &str = "crossfade";
+
+/// A proposed switch to the music of a mood
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Suggestion {
+    pub id: String,
+    pub session_id: String,
+    /// What accepting does (only `CROSSFADE_ACTION` so far)
+    pub action_type: String,
+    /// Mood whose music would be played
+    pub mood: String,
+    /// Track picked for the mood (None if no track is tagged with it)
+    pub track_id: Option<String>,
+    pub track_name: Option<String>,
+    /// Keyword and emotion that confirmed the dual signal
+    pub keyword: String,
+    pub emotion: String,
+    pub confidence: f32,
+    /// Wall-clock time in milliseconds
+    pub suggested_at_ms: u64,
+    pub expires_at_ms: u64,
+    /// Queued by a simulated detection
+    #[serde(default)]
+    pub simulated: bool,
+}
+
+impl Suggestion {
+    /// Check whether the suggestion has outlived its TTL at `now_ms`
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        now_ms >= self.expires_at_ms
+    }
+}
+
+/// Pending suggestions, oldest first
+pub struct SuggestionQueue {
+    pending: Vec<Suggestion>,
+    /// When each keyword last queued a suggestion
+    last_queued: HashMap<String, u64>,
+    cooldown_ms: u64,
+}
+
+impl SuggestionQueue {
+    /// Create a queue with the default cooldown
+    pub fn new() -> Self {
+        Self {
+            pending: Vec::new(),
+            last_queued: HashMap::new(),
+            cooldown_ms: SUGGESTION_COOLDOWN_MS,
+        }
+    }
+
+    /// Queue a suggestion unless its keyword is still cooling down
+    ///
+    /// Returns true if the suggestion was queued.
+    pub fn push(&mut self, suggestion: Suggestion) -> bool {
+        let keyword = suggestion.keyword.to_lowercase();
+        if let Some(&last) = self.last_queued.get(&keyword) {
+            if suggestion.suggested_at_ms.saturating_sub(last) < self.cooldown_ms {
+                return false;
+            }
+        }
+
+        self.last_queued.insert(keyword, suggestion.suggested_at_ms);
+        self.pending.push(suggestion);
+        true
+    }
+
+    /// Remove a suggestion to accept or reject it
+    pub fn take(&mut self, id: &str) -> Option<Suggestion> {
+        let index = self.pending.iter().position(|s| s.id == id)?;
+        Some(self.pending.remove(index))
+    }
+
+    /// Remove and return every suggestion expired at `now_ms`
+    pub fn expire(&mut self, now_ms: u64) -> Vec<Suggestion> {
+        let (expired, pending) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|s| s.is_expired(now_ms));
+        self.pending = pending;
+        expired
+    }
+
+    /// Suggestions still waiting for an answer
+    pub fn pending(&self) -> &[Suggestion] {
+        &self.pending
+    }
+
+    /// Drop everything, e.g. when a session ends
+    pub fn clear(&mut self) -> Vec<Suggestion> {
+        self.last_queued.clear();
+        std::mem::take(&mut self.pending)
+    }
+}
+
+impl Default for SuggestionQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn suggestion(id: &str, keyword: &str, at_ms: u64) -> Suggestion {
+        Suggestion {
+            id: id.to_string(),
+            session_id: "s1".to_string(),
+            action_type: CROSSFADE_ACTION.to_string(),
+            mood: "fearful".to_string(),
+            track_id: None,
+            track_name: None,
+            keyword: keyword.to_string(),
+            emotion: "fearful".to_string(),
+            confidence: 0.8,
+            suggested_at_ms: at_ms,
+            expires_at_ms: at_ms + SUGGESTION_TTL_MS,
+            simulated: false,
+        }
+    }
+
+    #[test]
+    fn test_same_keyword_is_deduplicated_within_cooldown() {
+        let mut queue = SuggestionQueue::new();
+        assert!(queue.push(suggestion("a", "dragon", 0)));
+        assert!(!queue.push(suggestion("b", "Dragon", 10_000)));
+        assert!(queue.push(suggestion("c", "trap", 10_000)));
+        assert!(queue.push(suggestion("d", "dragon", SUGGESTION_COOLDOWN_MS)));
+
+        let ids: Vec<_> = queue.pending().iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "c", "d"]);
+    }
+
+    #[test]
+    fn test_take_and_expire() {
+        let mut queue = SuggestionQueue::new();
+        queue.push(suggestion("a", "dragon", 0));
+        queue.push(suggestion("b", "trap", 5_000));
+
+        assert_eq!(queue.take("a").unwrap().keyword, "dragon");
+        assert!(queue.take("a").is_none());
+
+        assert!(queue.expire(SUGGESTION_TTL_MS).is_empty());
+        let expired = queue.expire(5_000 + SUGGESTION_TTL_MS);
+        assert_eq!(expired.len(), 1);
+        assert!(queue.pending().is_empty());
+    }
+}
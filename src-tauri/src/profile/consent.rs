@@ -0,0 +1,63 @@
+//! Consent gating for biometric voice data
+//!
+//! Speaker embeddings are biometric data; nothing that stores them may run
+//! unless the GM explicitly opted in.
+
+use crate::error::AppError;
+use crate::profile::{ProfileStorage, VoiceProfile};
+use tracing::{info, warn};
+
+/// Refuse to proceed unless consent was given for this profile
+pub fn require_consent(profile_id: &str, consent_given: bool) -> Result<(), AppError> {
+    if consent_given {
+        info!("Consent confirmed for voice profile {}", profile_id);
+        Ok(())
+    } else {
+        warn!("Refusing to store biometric data for voice profile {}: consent not given", profile_id);
+        Err(AppError::Profile("consent not given".to_string()))
+    }
+}
+
+/// Profile storage that only saves profiles with consent
+pub struct ConsentGuard {
+    storage: ProfileStorage,
+}
+
+impl ConsentGuard {
+    /// Wrap a profile storage
+    pub fn new(storage: ProfileStorage) -> Self {
+        Self { storage }
+    }
+
+    /// Save a profile if consent was given
+    pub fn save_profile(&self, profile: &VoiceProfile) -> Result<(), AppError> {
+        require_consent(&profile.id, profile.consent_given)?;
+        self.storage.save_profile(profile)
+    }
+
+    /// Access the wrapped storage
+    pub fn storage(&self) -> &ProfileStorage {
+        &self.storage
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard_refuses_without_consent() {
+        let dir = std::env::temp_dir().join(format!("consent-{}", uuid::Uuid::new_v4()));
+        let guard = ConsentGuard::new(ProfileStorage::new(dir.clone()));
+
+        let mut profile = VoiceProfile::new("gm".to_string(), "GM".to_string());
+        assert!(matches!(guard.save_profile(&profile), Err(AppError::Profile(_))));
+        assert!(guard.storage().load_profile("gm").unwrap().is_none());
+
+        profile.consent_given = true;
+        guard.save_profile(&profile).unwrap();
+        assert!(guard.storage().load_profile("gm").unwrap().is_some());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -2,8 +2,10 @@
 //!
 //! Provides GM voice profile management with encrypted storage.
 
+pub mod consent;
 pub mod voice;
 pub mod storage;
 
+pub use consent::*;
 pub use voice::*;
 pub use storage::*;
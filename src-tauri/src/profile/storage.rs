@@ -1,6 +1,8 @@
 //! Profile storage with encryption
 
 use crate::error::AppError;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -15,6 +17,14 @@ impl ProfileStorage {
         Self { storage_path }
     }
 
+    /// Default profile directory under the local data dir
+    pub fn default_path() -> PathBuf {
+        dirs::data_local_dir()
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("ttrpg_companion")
+            .join("profiles")
+    }
+
     /// Get storage path
     pub fn path(&self) -> &PathBuf {
         &self.storage_path
@@ -65,29 +75,48 @@ impl ProfileStorage {
         let mut ids = Vec::new();
 
         for entry in entries {
-            let entry = entry?;
-            if let Some(name) = entry.path().file_stem() {
-                ids.push(name.to_string_lossy().to_string());
+            let path = entry?.path();
+            // Embedding blobs share the profile's stem
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(name) = path.file_stem() {
+                    ids.push(name.to_string_lossy().to_string());
+                }
             }
         }
 
+        ids.sort();
         Ok(ids)
     }
 
-    /// Delete profile
+    /// Delete profile and its embedding blob
     pub fn delete_profile(&self, id: &str) -> Result<(), AppError> {
-        let profile_path = self.storage_path.join(format!("{}.json", id));
-
-        if profile_path.exists() {
-            std::fs::remove_file(&profile_path)?;
-            tracing::info!("Deleted voice profile: {}", id);
+        for file_name in [format!("{}.json", id), format!("{}.emb", id)] {
+            let path = self.storage_path.join(file_name);
+            if path.exists() {
+                std::fs::remove_file(&path)?;
+            }
         }
+        tracing::info!("Deleted voice profile: {}", id);
 
         Ok(())
     }
 }
 
+/// Keychain service holding the embedding key
+const KEYRING_SERVICE: &str = "ttrpg_companion";
+
+/// Keychain account holding the embedding key
+const KEYRING_USER: &str = "embedding_key";
+
+/// Marks a sealed embedding file; files without it predate encryption
+const SEALED_MAGIC: &[u8; 4] = b"EMB1";
+
 /// Encrypted blob storage
+///
+/// Embeddings are sealed with AES-256-GCM under a random nonce stored in
+/// front of the ciphertext. The key lives in the OS keychain and is
+/// generated on first use. Plaintext files from before encryption are
+/// sealed the first time they are loaded.
 pub struct EncryptedStorage {
     storage: ProfileStorage,
 }
@@ -102,13 +131,13 @@ impl EncryptedStorage {
 
     /// Store encrypted embedding
     pub fn store_embedding(&self, profile_id: &str, embedding: &[u8]) -> Result<(), AppError> {
-        // In production, encrypt with AES-256-GCM using OS keychain
-        // For now, store as base64
+        let key = load_or_create_key()?;
+        let sealed = seal(&key, profile_id, embedding)?;
 
         self.storage.ensure_dir()?;
 
         let emb_path = self.storage.path().join(format!("{}.emb", profile_id));
-        std::fs::write(&emb_path, embedding)?;
+        std::fs::write(&emb_path, sealed)?;
 
         Ok(())
     }
@@ -122,6 +151,137 @@ impl EncryptedStorage {
         }
 
         let data = std::fs::read(&emb_path)?;
-        Ok(Some(data))
+        if !data.starts_with(SEALED_MAGIC) {
+            self.store_embedding(profile_id, &data)?;
+            tracing::info!("Encrypted plaintext embedding of profile {}", profile_id);
+            return Ok(Some(data));
+        }
+
+        let key = load_or_create_key()?;
+        Ok(Some(open(&key, profile_id, data)?))
+    }
+
+    /// Seal every embedding file still stored in plaintext
+    ///
+    /// Returns how many were sealed.
+    pub fn seal_plaintext_embeddings(&self) -> Result<usize, AppError> {
+        if !self.storage.path().exists() {
+            return Ok(0);
+        }
+
+        let mut sealed = 0;
+        for entry in std::fs::read_dir(self.storage.path())? {
+            let path = entry?.path();
+            let is_embedding = path.extension().is_some_and(|ext| ext == "emb");
+            if is_embedding && std::fs::read(&path)?.starts_with(SEALED_MAGIC) {
+                continue;
+            }
+            if let Some(profile_id) = path.file_stem().filter(|_| is_embedding) {
+                self.load_embedding(&profile_id.to_string_lossy())?;
+                sealed += 1;
+            }
+        }
+        Ok(sealed)
+    }
+}
+
+/// Fetch the embedding key from the OS keychain, generating it on first use
+fn load_or_create_key() -> Result<[u8; 32], AppError> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER)
+        .map_err(|e| AppError::Profile(format!("Keychain unavailable: {}", e)))?;
+
+    match entry.get_secret() {
+        Ok(secret) => secret
+            .try_into()
+            .map_err(|_| AppError::Profile("Embedding key in keychain is not 256 bits".to_string())),
+        Err(keyring::Error::NoEntry) => {
+            let mut key = [0u8; 32];
+            SystemRandom::new()
+                .fill(&mut key)
+                .map_err(|_| AppError::Profile("Failed to generate embedding key".to_string()))?;
+
+            entry
+                .set_secret(&key)
+                .map_err(|e| AppError::Profile(format!("Failed to store embedding key: {}", e)))?;
+            tracing::info!("Generated new embedding key in OS keychain");
+
+            Ok(key)
+        }
+        Err(e) => Err(AppError::Profile(format!("Keychain unavailable: {}", e))),
+    }
+}
+
+fn cipher(key: &[u8; 32]) -> Result<LessSafeKey, AppError> {
+    UnboundKey::new(&AES_256_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| AppError::Profile("Invalid embedding key".to_string()))
+}
+
+/// Encrypt `plaintext` under a fresh random nonce, binding it to the profile ID
+///
+/// The output is `SEALED_MAGIC`, the nonce, then the ciphertext and tag.
+fn seal(key: &[u8; 32], profile_id: &str, plaintext: &[u8]) -> Result<Vec<u8>, AppError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| AppError::Profile("Failed to generate embedding nonce".to_string()))?;
+
+    let mut ciphertext = plaintext.to_vec();
+    cipher(key)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(profile_id.as_bytes()),
+            &mut ciphertext,
+        )
+        .map_err(|_| AppError::Profile("Failed to encrypt embedding".to_string()))?;
+
+    let mut data = Vec::with_capacity(SEALED_MAGIC.len() + NONCE_LEN + ciphertext.len());
+    data.extend_from_slice(SEALED_MAGIC);
+    data.extend_from_slice(&nonce);
+    data.extend_from_slice(&ciphertext);
+    Ok(data)
+}
+
+/// Decrypt data written by `seal` for the same profile
+fn open(key: &[u8; 32], profile_id: &str, data: Vec<u8>) -> Result<Vec<u8>, AppError> {
+    let malformed = || AppError::Profile("Failed to decrypt embedding".to_string());
+    let body = data.strip_prefix(SEALED_MAGIC.as_slice()).ok_or_else(malformed)?;
+    if body.len() < NONCE_LEN {
+        return Err(malformed());
+    }
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| malformed())?;
+
+    let mut plaintext = ciphertext.to_vec();
+    let len = cipher(key)?
+        .open_in_place(nonce, Aad::from(profile_id.as_bytes()), &mut plaintext)
+        .map_err(|_| malformed())?
+        .len();
+    plaintext.truncate(len);
+    Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_round_trip_is_bound_to_profile() {
+        let key = [7u8; 32];
+        let embedding: Vec<u8> = (0..=255).collect();
+
+        let sealed = seal(&key, "profile-a", &embedding).unwrap();
+        assert!(!sealed.windows(embedding.len()).any(|w| w == embedding.as_slice()));
+        assert_eq!(open(&key, "profile-a", sealed.clone()).unwrap(), embedding);
+
+        // Sealing the same profile again never reuses the nonce
+        let resealed = seal(&key, "profile-a", &embedding).unwrap();
+        let nonce = SEALED_MAGIC.len()..SEALED_MAGIC.len() + NONCE_LEN;
+        assert_ne!(sealed[nonce.clone()], resealed[nonce]);
+        assert_eq!(open(&key, "profile-a", resealed).unwrap(), embedding);
+        assert!(open(&key, "profile-a", embedding.clone()).is_err());
+
+        assert!(open(&key, "profile-b", sealed.clone()).is_err());
+        assert!(open(&[8u8; 32], "profile-a", sealed).is_err());
     }
 }
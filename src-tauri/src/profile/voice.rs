@@ -1,7 +1,16 @@
 //! Voice profile module
 
+use crate::detection::speaker::SpeakerEmbedding;
+use crate::detection::vad::VoiceActivityDetector;
+use crate::dsp::processing;
+use crate::error::AppError;
+use crate::inference::emotion::{Emotion, EmotionAnalyzer, EmotionResult};
+use crate::ml::speaker_model::SpeakerModel;
 use serde::{Deserialize, Serialize};
 
+/// Fewest recorded passages a profile can be enrolled from
+pub const MIN_ENROLLMENT_PASSAGES: usize = 5;
+
 /// Voice profile for a GM
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VoiceProfile {
@@ -42,6 +51,51 @@ pub struct EmotionBaseline {
     pub disgusted: f32,
 }
 
+impl EmotionBaseline {
+    /// Baseline confidence for one emotion
+    pub fn score(&self, emotion: Emotion) -> f32 {
+        match emotion {
+            Emotion::Neutral => self.neutral,
+            Emotion::Happy => self.happy,
+            Emotion::Sad => self.sad,
+            Emotion::Angry => self.angry,
+            Emotion::Fearful => self.fearful,
+            Emotion::Surprised => self.surprised,
+            Emotion::Disgusted => self.disgusted,
+        }
+    }
+
+    fn score_mut(&mut self, emotion: Emotion) -> &mut f32 {
+        match emotion {
+            Emotion::Neutral => &mut self.neutral,
+            Emotion::Happy => &mut self.happy,
+            Emotion::Sad => &mut self.sad,
+            Emotion::Angry => &mut self.angry,
+            Emotion::Fearful => &mut self.fearful,
+            Emotion::Surprised => &mut self.surprised,
+            Emotion::Disgusted => &mut self.disgusted,
+        }
+    }
+
+    /// Per-emotion mean confidence over a set of analysis results
+    pub fn from_results(results: &[EmotionResult]) -> Self {
+        let mut baseline = Self::default();
+        if results.is_empty() {
+            return baseline;
+        }
+
+        for emotion in Emotion::all() {
+            let total: f32 = results
+                .iter()
+                .map(|r| r.scores.get(&emotion).copied().unwrap_or(0.0))
+                .sum();
+            *baseline.score_mut(emotion) = total / results.len() as f32;
+        }
+
+        baseline
+    }
+}
+
 impl VoiceProfile {
     /// Create a new voice profile
     pub fn new(id: String, name: String) -> Self {
@@ -127,16 +181,36 @@ pub fn default_training_passages() -> Vec<TrainingPassage> {
 pub struct VoiceTraining {
     passages: Vec<TrainingPassage>,
     current_passage: usize,
-    recordings: Vec<Vec<f32>>,
+    /// Accepted recording of each passage, at `SAMPLE_RATE`
+    recordings: Vec<Option<Vec<f32>>>,
+}
+
+/// Speaker embeddings and emotion baseline computed from a training session
+#[derive(Debug, Clone)]
+pub struct Enrollment {
+    /// One embedding per recorded passage
+    pub embeddings: Vec<SpeakerEmbedding>,
+    pub emotion_baseline: EmotionBaseline,
+}
+
+impl Enrollment {
+    /// Average of the per-passage embeddings, stored as the profile embedding
+    pub fn mean_embedding(&self) -> SpeakerEmbedding {
+        SpeakerEmbedding::mean(&self.embeddings)
+    }
 }
 
 impl VoiceTraining {
+    /// Sample rate of training recordings
+    pub const SAMPLE_RATE: u32 = 16000;
+
     /// Create a new training session
     pub fn new() -> Self {
+        let passages = default_training_passages();
         Self {
-            passages: default_training_passages(),
+            recordings: vec![None; passages.len()],
+            passages,
             current_passage: 0,
-            recordings: Vec::new(),
         }
     }
 
@@ -145,9 +219,56 @@ impl VoiceTraining {
         self.passages.get(self.current_passage)
     }
 
-    /// Add recording for current passage
+    /// Select the passage to record next (e.g. to redo one); false if out of range
+    pub fn select_passage(&mut self, index: usize) -> bool {
+        if index < self.passages.len() {
+            self.current_passage = index;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Add recording for current passage, replacing an earlier take
     pub fn add_recording(&mut self, audio: Vec<f32>) {
-        self.recordings.push(audio);
+        if let Some(slot) = self.recordings.get_mut(self.current_passage) {
+            *slot = Some(audio);
+        }
+    }
+
+    /// Recordings collected so far (at most one per passage)
+    pub fn recordings(&self) -> impl Iterator<Item = &[f32]> {
+        self.recordings.iter().flatten().map(|r| r.as_slice())
+    }
+
+    /// Extract a speaker embedding and emotion scores from every recording
+    ///
+    /// Fails if fewer than `MIN_ENROLLMENT_PASSAGES` passages were recorded.
+    pub fn enroll(&self, model: &mut SpeakerModel, analyzer: &EmotionAnalyzer) -> Result<Enrollment, AppError> {
+        let (recorded, _) = self.progress();
+        if recorded < MIN_ENROLLMENT_PASSAGES {
+            return Err(AppError::Profile(format!(
+                "recorded {} of the {} passages needed to enroll",
+                recorded, MIN_ENROLLMENT_PASSAGES
+            )));
+        }
+
+        let mut embeddings = Vec::with_capacity(recorded);
+        let mut results = Vec::with_capacity(recorded);
+        for recording in self.recordings() {
+            let embedding = model.extract_embedding(recording, Self::SAMPLE_RATE)?;
+            embeddings.push(SpeakerEmbedding::new(embedding.data));
+
+            match analyzer.analyze(recording, Self::SAMPLE_RATE) {
+                Ok(result) => results.push(result),
+                Err(e) => tracing::warn!("Skipping passage in emotion baseline: {}", e),
+            }
+        }
+
+        Ok(Enrollment {
+            embeddings,
+            emotion_baseline: EmotionBaseline::from_results(&results),
+        })
     }
 
     /// Move to next passage
@@ -162,12 +283,70 @@ impl VoiceTraining {
 
     /// Check if training is complete
     pub fn is_complete(&self) -> bool {
-        self.recordings.len() >= self.passages.len()
+        self.recordings.iter().all(|r| r.is_some())
     }
 
-    /// Get progress
+    /// Get progress as (recorded passages, total passages)
     pub fn progress(&self) -> (usize, usize) {
-        (self.recordings.len(), self.passages.len())
+        (self.recordings().count(), self.passages.len())
+    }
+}
+
+/// Share of a passage's estimated duration a take must last
+const MIN_PASSAGE_DURATION_RATIO: f32 = 0.5;
+
+/// Quietest accepted take level (RMS, dBFS)
+const MIN_PASSAGE_LEVEL_DB: f32 = -40.0;
+
+/// VAD frame length for the quality check
+const QUALITY_FRAME_MS: u32 = 30;
+
+/// Outcome of the quality check on one take
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingQuality {
+    pub passed: bool,
+    /// Why the take should be redone (empty if it passed)
+    pub reasons: Vec<String>,
+    pub duration_ms: u64,
+    pub level_db: f32,
+    pub speech_detected: bool,
+}
+
+/// Check that a mono take of `passage` is long and loud enough and contains speech
+pub fn check_recording(samples: &[f32], sample_rate: u32, passage: &TrainingPassage) -> RecordingQuality {
+    let duration_ms = samples.len() as u64 * 1000 / sample_rate.max(1) as u64;
+    let level_db = processing::calculate_db(samples);
+
+    let mut vad = VoiceActivityDetector::new();
+    vad.set_sample_rate(sample_rate);
+    let frame = ((sample_rate * QUALITY_FRAME_MS / 1000) as usize).max(1);
+    let speech_detected = samples
+        .chunks(frame)
+        .enumerate()
+        .any(|(i, chunk)| vad.process_frame(chunk, i as u64 * QUALITY_FRAME_MS as u64).is_speech);
+
+    let mut reasons = Vec::new();
+    let min_duration_ms = (passage.duration_secs as f32 * MIN_PASSAGE_DURATION_RATIO * 1000.0) as u64;
+    if duration_ms < min_duration_ms {
+        reasons.push(format!(
+            "Recording too short ({:.1} s, need at least {:.1} s)",
+            duration_ms as f32 / 1000.0,
+            min_duration_ms as f32 / 1000.0
+        ));
+    }
+    if level_db < MIN_PASSAGE_LEVEL_DB {
+        reasons.push(format!("Recording too quiet ({:.0} dBFS); speak up or raise the input gain", level_db));
+    }
+    if !speech_detected {
+        reasons.push("No speech detected".to_string());
+    }
+
+    RecordingQuality {
+        passed: reasons.is_empty(),
+        reasons,
+        duration_ms,
+        level_db,
+        speech_detected,
     }
 }
 
@@ -176,3 +355,62 @@ impl Default for VoiceTraining {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enroll_requires_minimum_passages() {
+        let mut analyzer = EmotionAnalyzer::new();
+        analyzer.init().unwrap();
+        let mut model = SpeakerModel::new();
+
+        let mut training = VoiceTraining::new();
+        let tone = |freq: f32| -> Vec<f32> {
+            (0..VoiceTraining::SAMPLE_RATE)
+                .map(|i| (i as f32 * freq * std::f32::consts::TAU / VoiceTraining::SAMPLE_RATE as f32).sin() * 0.3)
+                .collect()
+        };
+
+        for i in 0..MIN_ENROLLMENT_PASSAGES - 1 {
+            training.add_recording(tone(150.0 + i as f32 * 20.0));
+            training.next_passage();
+        }
+        assert!(matches!(training.enroll(&mut model, &analyzer), Err(AppError::Profile(_))));
+
+        // A retake replaces the passage instead of counting twice
+        training.select_passage(0);
+        training.add_recording(tone(300.0));
+        assert_eq!(training.progress().0, MIN_ENROLLMENT_PASSAGES - 1);
+
+        training.select_passage(MIN_ENROLLMENT_PASSAGES - 1);
+        training.add_recording(tone(300.0));
+        let enrollment = training.enroll(&mut model, &analyzer).unwrap();
+        assert_eq!(enrollment.embeddings.len(), MIN_ENROLLMENT_PASSAGES);
+        assert!(!enrollment.mean_embedding().data.is_empty());
+
+        let baseline = &enrollment.emotion_baseline;
+        let total: f32 = Emotion::all().into_iter().map(|e| baseline.score(e)).sum();
+        assert!(total > 0.0);
+    }
+
+    #[test]
+    fn test_check_recording_reports_each_problem() {
+        let passage = &default_training_passages()[0];
+        let rate = VoiceTraining::SAMPLE_RATE;
+
+        let silence = vec![0.0f32; rate as usize];
+        let quality = check_recording(&silence, rate, passage);
+        assert!(!quality.passed);
+        assert_eq!(quality.reasons.len(), 3);
+
+        // Quiet lead-in, then voiced bursts for the rest of the passage
+        let take: Vec<f32> = (0..rate as usize * passage.duration_secs as usize)
+            .map(|i| if i < rate as usize { 0.001 } else { (i as f32 * 0.05).sin() * 0.9 })
+            .collect();
+        let quality = check_recording(&take, rate, passage);
+        assert!(quality.passed, "{:?}", quality.reasons);
+        assert!(quality.speech_detected);
+    }
+}
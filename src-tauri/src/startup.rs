@@ -3,13 +3,64 @@
 //! Implements a two-phase startup process:
 //! 1. UI Ready (≤3s) - Fast window display
 //! 2. Detection Ready (≤15s) - ML models loaded
+//!
+//! `StartupManager::run_phases_async` drives both phases in the background.
+//! Checking the model files reports `StartupProgress` on the channel given
+//! to `StartupManager::set_progress_sender`, and every model missing from
+//! disk is reported with `MODEL_MISSING_EVENT`.
 
 use crate::error::AppError;
+use crate::ml::{init_onnx, ModelPaths};
 use crate::state::constants::{DETECTION_READY_TIMEOUT_MS, UI_READY_TIMEOUT_MS};
+use crate::AppState;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use parking_lot::RwLock;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::task::JoinHandle;
+
+/// Event emitted when a startup phase finishes
+pub const STARTUP_PHASE_COMPLETE_EVENT: &str = "startup_phase_complete";
+
+/// Event emitted when a startup phase runs past its timeout
+pub const STARTUP_TIMEOUT_EVENT: &str = "startup_timeout";
+
+/// Payload of `STARTUP_PHASE_COMPLETE_EVENT`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseComplete {
+    pub phase: String,
+    /// Time since startup began
+    pub elapsed_ms: u64,
+}
+
+/// Payload of `STARTUP_TIMEOUT_EVENT`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseTimeout {
+    pub phase: String,
+}
+
+/// Event relaying `StartupProgress` to the frontend
+pub const STARTUP_PROGRESS_EVENT: &str = "startup_progress";
+
+/// A step of the detection ready phase finished
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StartupProgress {
+    pub step: String,
+    pub steps_completed: u32,
+    pub steps_total: u32,
+}
+
+/// Event emitted for each model file missing at startup
+pub const MODEL_MISSING_EVENT: &str = "model_missing";
+
+/// Payload of `MODEL_MISSING_EVENT`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelMissing {
+    pub path: String,
+}
 
 /// Startup phase
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd)]
@@ -41,13 +92,26 @@ impl std::fmt::Display for StartupPhase {
     }
 }
 
+/// Which detection model files were found usable at startup
+///
+/// The files are only checked; each model is loaded where it is used.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelsVerified {
+    pub vad: bool,
+    pub speaker: bool,
+    pub emotion: bool,
+    pub whisper: bool,
+}
+
 /// Startup state
 pub struct StartupState {
     phase: RwLock<StartupPhase>,
     start_time: RwLock<Option<Instant>>,
     ui_ready_time: RwLock<Option<Duration>>,
     detection_ready_time: RwLock<Option<Duration>>,
-    error: RwLock<Option<String>>,
+    errors: RwLock<Vec<String>>,
+    models_verified: RwLock<ModelsVerified>,
+    missing_models: RwLock<Vec<PathBuf>>,
 }
 
 impl StartupState {
@@ -58,7 +122,9 @@ impl StartupState {
             start_time: RwLock::new(None),
             ui_ready_time: RwLock::new(None),
             detection_ready_time: RwLock::new(None),
-            error: RwLock::new(None),
+            errors: RwLock::new(Vec::new()),
+            models_verified: RwLock::new(ModelsVerified::default()),
+            missing_models: RwLock::new(Vec::new()),
         }
     }
 
@@ -97,9 +163,8 @@ impl StartupState {
 
     /// Mark error
     pub fn mark_error(&self, error: String) {
-        let error_clone = error.clone();
-        *self.error.write() = Some(error);
-        tracing::error!("Startup error: {}", error_clone);
+        tracing::error!("Startup error: {}", error);
+        self.errors.write().push(error);
     }
 
     /// Get current phase
@@ -132,9 +197,29 @@ impl StartupState {
         *self.detection_ready_time.read()
     }
 
-    /// Get error if any
+    /// Get the latest error if any
     pub fn error(&self) -> Option<String> {
-        self.error.read().clone()
+        self.errors.read().last().cloned()
+    }
+
+    /// Every error and missing model reported during startup, oldest first
+    pub fn errors(&self) -> Vec<String> {
+        self.errors.read().clone()
+    }
+
+    /// Model files found usable by the detection ready phase; all false until it ran
+    pub fn models_verified(&self) -> ModelsVerified {
+        *self.models_verified.read()
+    }
+
+    /// Model files found missing by the detection ready phase
+    pub fn missing_models(&self) -> Vec<PathBuf> {
+        self.missing_models.read().clone()
+    }
+
+    /// Time since startup began (zero before `StartupManager::start`)
+    fn elapsed(&self) -> Duration {
+        self.start_time.read().map(|start| start.elapsed()).unwrap_or_default()
     }
 }
 
@@ -149,6 +234,7 @@ pub struct StartupManager {
     state: Arc<StartupState>,
     timeout_ui_ms: u64,
     timeout_detection_ms: u64,
+    progress_tx: RwLock<Option<flume::Sender<StartupProgress>>>,
 }
 
 impl StartupManager {
@@ -158,6 +244,7 @@ impl StartupManager {
             state: Arc::new(StartupState::new()),
             timeout_ui_ms: UI_READY_TIMEOUT_MS,
             timeout_detection_ms: DETECTION_READY_TIMEOUT_MS,
+            progress_tx: RwLock::new(None),
         }
     }
 
@@ -167,6 +254,7 @@ impl StartupManager {
             state: Arc::new(StartupState::new()),
             timeout_ui_ms,
             timeout_detection_ms,
+            progress_tx: RwLock::new(None),
         }
     }
 
@@ -175,29 +263,212 @@ impl StartupManager {
         &self.state
     }
 
+    /// Set the sender model loading reports progress on
+    ///
+    /// The sender is dropped once the models have loaded.
+    pub fn set_progress_sender(&self, tx: flume::Sender<StartupProgress>) {
+        *self.progress_tx.write() = Some(tx);
+    }
+
     /// Start the startup timer
     pub fn start(&self) {
         *self.state.start_time.write() = Some(Instant::now());
         tracing::info!("Startup started");
     }
 
-    /// Check UI timeout
-    pub fn check_ui_timeout(&self) -> bool {
-        if let Some(start) = *self.state.start_time.read() {
-            start.elapsed().as_millis() as u64 > self.timeout_ui_ms
-        } else {
-            false
+    /// Fail with `AppError::Timeout` if the UI is still not ready past its timeout
+    pub fn check_ui_timeout(&self) -> Result<(), AppError> {
+        if self.state.is_ui_ready() {
+            return Ok(());
+        }
+        check_timeout(&self.state, StartupPhase::UiReady, Duration::from_millis(self.timeout_ui_ms))
+    }
+
+    /// Fail with `AppError::Timeout` if detection is still not ready past its timeout
+    pub fn check_detection_timeout(&self) -> Result<(), AppError> {
+        if self.state.is_detection_ready() {
+            return Ok(());
+        }
+        check_timeout(&self.state, StartupPhase::DetectionReady, Duration::from_millis(self.timeout_detection_ms))
+    }
+
+    /// Run both startup phases in the background
+    ///
+    /// The database is opened and everything stored in it restored (UI
+    /// ready) while the model files are checked in a second task (detection
+    /// ready). Each phase emits `STARTUP_PHASE_COMPLETE_EVENT` when it
+    /// finishes and `STARTUP_TIMEOUT_EVENT` if it is still running at its
+    /// timeout; a late phase is still waited for. Resolves to the first phase
+    /// error.
+    pub fn run_phases_async(&self, app_handle: AppHandle) -> JoinHandle<Result<(), AppError>> {
+        if self.state.start_time.read().is_none() {
+            self.start();
+        }
+        let state = self.state.clone();
+        let ui_timeout = Duration::from_millis(self.timeout_ui_ms);
+        let detection_timeout = Duration::from_millis(self.timeout_detection_ms);
+        let progress_tx = self.progress_tx.write().take();
+
+        tauri::async_runtime::handle().inner().spawn(async move {
+            let models = {
+                let state = state.clone();
+                tokio::task::spawn_blocking(move || check_models(&state, progress_tx))
+            };
+            let database = {
+                let app = app_handle.clone();
+                tokio::task::spawn_blocking(move || crate::init_backend(&app))
+            };
+
+            let on_timeout = |phase: StartupPhase| {
+                let _ = app_handle.emit(STARTUP_TIMEOUT_EVENT, PhaseTimeout { phase: phase.to_string() });
+            };
+
+            // The app runs without a database, so the UI is ready either way
+            let ui_result = await_phase(&state, StartupPhase::UiReady, ui_timeout, database, on_timeout).await;
+            state.mark_ui_ready();
+            emit_phase_complete(&app_handle, &state, StartupPhase::UiReady);
+
+            let detection_result =
+                await_phase(&state, StartupPhase::DetectionReady, detection_timeout, models, on_timeout).await;
+            if detection_result.is_ok() {
+                state.mark_detection_ready();
+                emit_phase_complete(&app_handle, &state, StartupPhase::DetectionReady);
+            }
+            for path in state.missing_models() {
+                let _ = app_handle.emit(MODEL_MISSING_EVENT, ModelMissing { path: path.display().to_string() });
+            }
+
+            state.mark_complete();
+            *app_handle.state::<AppState>().startup_complete.write() = true;
+            ui_result.and(detection_result)
+        })
+    }
+}
+
+/// `AppError::Timeout` if more than `timeout` passed since startup began
+fn check_timeout(state: &StartupState, phase: StartupPhase, timeout: Duration) -> Result<(), AppError> {
+    if state.start_time.read().is_some() && state.elapsed() > timeout {
+        return Err(AppError::Timeout(format!("Startup phase {} exceeded {}ms", phase, timeout.as_millis())));
+    }
+    Ok(())
+}
+
+/// Wait for a phase task, calling `on_timeout` if it outlives `timeout` since startup
+///
+/// Errors, including the timeout, are recorded on `state`.
+async fn await_phase(
+    state: &StartupState,
+    phase: StartupPhase,
+    timeout: Duration,
+    mut task: JoinHandle<Result<(), AppError>>,
+    on_timeout: impl Fn(StartupPhase),
+) -> Result<(), AppError> {
+    let remaining = timeout.saturating_sub(state.elapsed());
+    let joined = match tokio::time::timeout(remaining, &mut task).await {
+        Ok(joined) => joined,
+        Err(_) => {
+            if let Err(e) = check_timeout(state, phase, timeout) {
+                tracing::warn!("{}", e);
+                state.errors.write().push(e.to_string());
+            }
+            on_timeout(phase);
+            task.await
         }
+    };
+
+    let result = joined
+        .map_err(|e| AppError::State(format!("Startup phase {} failed: {}", phase, e)))
+        .and_then(|result| result);
+    if let Err(e) = &result {
+        state.mark_error(e.to_string());
     }
+    result
+}
+
+fn emit_phase_complete(app: &AppHandle, state: &StartupState, phase: StartupPhase) {
+    let payload = PhaseComplete {
+        phase: phase.to_string(),
+        elapsed_ms: state.elapsed().as_millis() as u64,
+    };
+    let _ = app.emit(STARTUP_PHASE_COMPLETE_EVENT, payload);
+}
 
-    /// Check detection timeout
-    pub fn check_detection_timeout(&self) -> bool {
-        if let Some(start) = *self.state.start_time.read() {
-            start.elapsed().as_millis() as u64 > self.timeout_detection_ms
-        } else {
-            false
+/// Check every configured model is on disk
+///
+/// Returns an `AppError::ModelNotFound` for each missing model, so they can
+/// all be reported at once.
+pub fn verify_models(paths: &ModelPaths) -> Vec<AppError> {
+    [&paths.vad_model, &paths.speaker_model, &paths.emotion_model, &paths.whisper_model]
+        .into_iter()
+        .flatten()
+        .map(PathBuf::from)
+        .filter(|path| !path.exists())
+        .map(AppError::ModelNotFound)
+        .collect()
+}
+
+/// Start the inference runtime and check the detection model files
+///
+/// Each of the VAD, Resemblyzer and emotion files is checked to open and not
+/// be empty, without loading the model; usable files are recorded on
+/// `state`, missing ones as errors and in `StartupState::missing_models`.
+/// Progress is reported on `progress_tx` as each check starts and finishes.
+fn check_models(state: &StartupState, progress_tx: Option<flume::Sender<StartupProgress>>) -> Result<(), AppError> {
+    init_onnx()?;
+
+    let paths = ModelPaths::default();
+    let mut missing = Vec::new();
+    for error in verify_models(&paths) {
+        tracing::warn!("{}", error);
+        state.errors.write().push(error.to_string());
+        if let AppError::ModelNotFound(path) = error {
+            missing.push(path);
+        }
+    }
+    let on_disk = |model: &String| !missing.iter().any(|m| *m == Path::new(model));
+
+    let mut models = ModelsVerified::default();
+    let steps: [(&str, Option<String>, &mut bool); 3] = [
+        ("vad_check", paths.vad_model, &mut models.vad),
+        ("resemblyzer_check", paths.speaker_model, &mut models.speaker),
+        ("emotion_check", paths.emotion_model, &mut models.emotion),
+    ];
+    let steps_total = steps.len() as u32;
+    let report = |step: &str, steps_completed: usize| {
+        if let Some(tx) = &progress_tx {
+            let _ = tx.send(StartupProgress {
+                step: step.to_string(),
+                steps_completed: steps_completed as u32,
+                steps_total,
+            });
         }
+    };
+    for (i, (step, model, verified)) in steps.into_iter().enumerate() {
+        report(step, i);
+        if let Some(model) = model.filter(on_disk) {
+            match check_model_file(&model) {
+                Ok(()) => *verified = true,
+                Err(e) => {
+                    tracing::warn!("Unusable model file {}: {}", model, e);
+                    state.errors.write().push(e.to_string());
+                }
+            }
+        }
+        report(step, i + 1);
     }
+
+    models.whisper = paths.whisper_model.as_ref().is_some_and(on_disk);
+    *state.models_verified.write() = models;
+    *state.missing_models.write() = missing;
+    Ok(())
+}
+
+/// Check a model file opens and is not empty, without loading it
+fn check_model_file(path: &str) -> Result<(), AppError> {
+    if std::fs::File::open(path)?.metadata()?.len() == 0 {
+        return Err(AppError::Inference(format!("Model file is empty: {}", path)));
+    }
+    Ok(())
 }
 
 impl Default for StartupManager {
@@ -225,4 +496,101 @@ mod tests {
         state.mark_complete();
         assert!(state.is_complete());
     }
+
+    #[test]
+    fn test_timeout_checks() {
+        let manager = StartupManager::with_timeouts(0, 60_000);
+        assert!(manager.check_ui_timeout().is_ok());
+
+        manager.start();
+        std::thread::sleep(Duration::from_millis(5));
+        assert!(matches!(manager.check_ui_timeout(), Err(AppError::Timeout(_))));
+        assert!(manager.check_detection_timeout().is_ok());
+
+        manager.state().mark_ui_ready();
+        assert!(manager.check_ui_timeout().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_await_phase_reports_timeout_and_waits() {
+        let state = StartupState::new();
+        *state.start_time.write() = Some(Instant::now());
+        let timed_out = AtomicBool::new(false);
+        let on_timeout = |phase: StartupPhase| {
+            assert_eq!(phase, StartupPhase::UiReady);
+            timed_out.store(true, Ordering::SeqCst);
+        };
+
+        let slow = tokio::spawn(async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(())
+        });
+        let result = await_phase(&state, StartupPhase::UiReady, Duration::from_millis(10), slow, on_timeout).await;
+        assert!(result.is_ok());
+        assert!(timed_out.load(Ordering::SeqCst));
+        assert!(state.error().unwrap().starts_with("Timeout"));
+
+        let failing = tokio::spawn(async { Err(AppError::Database("locked".to_string())) });
+        let result = await_phase(&state, StartupPhase::UiReady, Duration::from_secs(5), failing, |_| {}).await;
+        assert!(result.is_err());
+        assert!(state.error().unwrap().contains("locked"));
+    }
+
+    #[test]
+    fn test_check_models_reports_each_model() {
+        let state = StartupState::new();
+        let (tx, rx) = flume::unbounded();
+        check_models(&state, Some(tx)).unwrap();
+
+        // Each model is reported as its check starts and once it is done
+        let progress: Vec<StartupProgress> = rx.iter().collect();
+        let steps: Vec<(&str, u32)> = progress.iter().map(|p| (p.step.as_str(), p.steps_completed)).collect();
+        assert_eq!(
+            steps,
+            vec![
+                ("vad_check", 0),
+                ("vad_check", 1),
+                ("resemblyzer_check", 1),
+                ("resemblyzer_check", 2),
+                ("emotion_check", 2),
+                ("emotion_check", 3)
+            ]
+        );
+        assert!(progress.iter().all(|p| p.steps_total == 3));
+
+        // Every model not found is reported
+        let verified = state.models_verified();
+        let missing = [verified.vad, verified.speaker, verified.emotion, verified.whisper]
+            .into_iter()
+            .filter(|verified| !verified)
+            .count();
+        assert_eq!(state.errors().len(), missing);
+        assert_eq!(state.missing_models().len(), missing);
+        assert!(state.errors().iter().all(|e| e.starts_with("Model not found")));
+    }
+
+    #[test]
+    fn test_verify_models_reports_every_missing_model() {
+        let present = std::env::temp_dir().join(format!("model-{}.onnx", uuid::Uuid::new_v4()));
+        std::fs::write(&present, b"onnx").unwrap();
+        let paths = ModelPaths {
+            vad_model: Some(present.to_string_lossy().to_string()),
+            speaker_model: Some("missing/resemblyzer.onnx".to_string()),
+            emotion_model: None,
+            whisper_model: Some("missing/whisper.bin".to_string()),
+        };
+
+        let errors = verify_models(&paths);
+        let missing: Vec<String> = errors
+            .iter()
+            .map(|e| match e {
+                AppError::ModelNotFound(path) => path.to_string_lossy().to_string(),
+                other => panic!("unexpected error: {}", other),
+            })
+            .collect();
+        assert_eq!(missing, vec!["missing/resemblyzer.onnx", "missing/whisper.bin"]);
+        assert_eq!(errors[0].to_string(), "Model not found: missing/resemblyzer.onnx");
+
+        std::fs::remove_file(present).unwrap();
+    }
 }
@@ -1,11 +1,130 @@
 //! Application state management
 
+use crate::audio::capture::{CaptureSource, LatencyMode, INPUT_DEVICE_SETTING};
+use crate::audio::gain::{MAX_INPUT_GAIN, MIN_INPUT_GAIN};
+use crate::audio::resume::RESUME_LAST_TRACK_SETTING;
 use crate::detection::fsm::DetectionMode;
 use crate::db::DbPool;
+use crate::inference::whisper::WHISPER_SAMPLE_RATE;
+use crate::library::LIBRARY_PATH_SETTING;
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::sync::Arc;
 
+/// Settings key holding the last selected application mode
+pub const APP_MODE_SETTING: &str = "app_mode";
+
+/// Settings key holding the microphone gain
+pub const INPUT_GAIN_SETTING: &str = "input_gain";
+
+/// Settings key holding the session configuration as JSON
+pub const SESSION_CONFIG_SETTING: &str = "session_config";
+
+/// User-facing settings the frontend may read and write
+///
+/// Values are exchanged as JSON and stored as text in the `settings` table.
+/// Internal keys such as the last playback snapshot are not part of the schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingKey {
+    /// Music library folder (text, unset by default)
+    LibraryPath,
+    /// Preferred capture device id (text, unset by default)
+    InputDevice,
+    /// Resume the last track at startup (bool)
+    ResumeLastTrack,
+    /// Microphone gain (number within the accepted gain range)
+    InputGain,
+    /// "autonomous" or "collaborative"
+    AppMode,
+}
+
+impl SettingKey {
+    /// Every known setting
+    pub const ALL: [SettingKey; 5] = [
+        SettingKey::LibraryPath,
+        SettingKey::InputDevice,
+        SettingKey::ResumeLastTrack,
+        SettingKey::InputGain,
+        SettingKey::AppMode,
+    ];
+
+    /// Look up a key by its stored name
+    pub fn parse(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|k| k.as_str() == key)
+    }
+
+    /// Name of the key in the settings table
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SettingKey::LibraryPath => LIBRARY_PATH_SETTING,
+            SettingKey::InputDevice => INPUT_DEVICE_SETTING,
+            SettingKey::ResumeLastTrack => RESUME_LAST_TRACK_SETTING,
+            SettingKey::InputGain => INPUT_GAIN_SETTING,
+            SettingKey::AppMode => APP_MODE_SETTING,
+        }
+    }
+
+    /// Value reported when nothing is stored
+    pub fn default_value(self) -> Value {
+        match self {
+            SettingKey::LibraryPath | SettingKey::InputDevice => Value::Null,
+            SettingKey::ResumeLastTrack => Value::Bool(false),
+            SettingKey::InputGain => Value::from(1.0),
+            SettingKey::AppMode => Value::from(AppMode::default().to_string()),
+        }
+    }
+
+    /// Check a value from the frontend and convert it to its stored text
+    pub fn encode(self, value: &Value) -> Result<String, String> {
+        match (self, value) {
+            (SettingKey::LibraryPath | SettingKey::InputDevice, Value::String(text)) => Ok(text.clone()),
+            (SettingKey::ResumeLastTrack, Value::Bool(enabled)) => Ok(enabled.to_string()),
+            (SettingKey::InputGain, Value::Number(number)) => {
+                let gain = number.as_f64().unwrap_or(f64::NAN) as f32;
+                if !(MIN_INPUT_GAIN..=MAX_INPUT_GAIN).contains(&gain) {
+                    return Err(format!(
+                        "{} must be between {} and {}",
+                        self.as_str(),
+                        MIN_INPUT_GAIN,
+                        MAX_INPUT_GAIN
+                    ));
+                }
+                Ok(gain.to_string())
+            }
+            (SettingKey::AppMode, Value::String(name)) => AppMode::parse(name)
+                .map(|mode| mode.to_string())
+                .ok_or_else(|| format!("{} must be 'autonomous' or 'collaborative'", self.as_str())),
+            _ => Err(format!("{} must be {}, got {}", self.as_str(), self.type_name(), value)),
+        }
+    }
+
+    /// Convert stored text back to JSON, falling back to the default if it
+    /// doesn't parse
+    pub fn decode(self, stored: &str) -> Value {
+        match self {
+            SettingKey::LibraryPath | SettingKey::InputDevice => Value::from(stored),
+            SettingKey::ResumeLastTrack => Value::Bool(stored == "true"),
+            SettingKey::InputGain => stored
+                .parse::<f32>()
+                .map(|gain| Value::from(gain as f64))
+                .unwrap_or_else(|_| self.default_value()),
+            SettingKey::AppMode => AppMode::parse(stored)
+                .map(|mode| Value::from(mode.to_string()))
+                .unwrap_or_else(|| self.default_value()),
+        }
+    }
+
+    /// Expected JSON type, for error messages
+    fn type_name(self) -> &'static str {
+        match self {
+            SettingKey::LibraryPath | SettingKey::InputDevice | SettingKey::AppMode => "a string",
+            SettingKey::ResumeLastTrack => "a boolean",
+            SettingKey::InputGain => "a number",
+        }
+    }
+}
+
 /// Session states
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -43,6 +162,33 @@ impl Default for AppMode {
     }
 }
 
+impl AppMode {
+    /// Parse the name used by the frontend and the settings table
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "autonomous" => Some(AppMode::ModeA),
+            "collaborative" => Some(AppMode::ModeB),
+            _ => None,
+        }
+    }
+
+    /// The other mode
+    pub fn toggled(self) -> Self {
+        match self {
+            AppMode::ModeA => AppMode::ModeB,
+            AppMode::ModeB => AppMode::ModeA,
+        }
+    }
+
+    /// Detection FSM behaviour for this mode
+    pub fn detection_mode(self) -> DetectionMode {
+        match self {
+            AppMode::ModeA => DetectionMode::Autonomous,
+            AppMode::ModeB => DetectionMode::Collaborative,
+        }
+    }
+}
+
 impl std::fmt::Display for AppMode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -53,10 +199,15 @@ impl std::fmt::Display for AppMode {
 }
 
 /// Session configuration
-#[derive(Debug, Clone)]
+///
+/// Fields missing from stored JSON take their default, so configurations
+/// saved by older versions still load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SessionConfig {
     pub sample_rate: u32,
-    pub buffer_size_ms: u32,
+    /// Device buffer size requested when capture starts
+    pub latency_mode: LatencyMode,
     pub silence_threshold: f32,
     pub enable_transcription: bool,
     pub enable_emotion_analysis: bool,
@@ -66,13 +217,21 @@ pub struct SessionConfig {
     pub crossfade_duration_ms: u32,
     pub sfx_volume: f32,
     pub music_volume: f32,
+    /// Microphone gain applied before audio is buffered (0.1-4.0)
+    pub input_gain: f32,
+    /// Attempts to re-attach a lost input device before giving up
+    pub device_recovery_retries: u32,
+    /// Microphone, system audio (loopback) or both
+    pub capture_source: CaptureSource,
+    /// Write the raw session audio to a WAV file
+    pub record_session: bool,
 }
 
 impl Default for SessionConfig {
     fn default() -> Self {
         Self {
             sample_rate: 16000,
-            buffer_size_ms: 100,
+            latency_mode: LatencyMode::default(),
             silence_threshold: 0.01,
             enable_transcription: true,
             enable_emotion_analysis: true,
@@ -82,10 +241,39 @@ impl Default for SessionConfig {
             crossfade_duration_ms: 2000,
             sfx_volume: 0.8,
             music_volume: 0.6,
+            input_gain: 1.0,
+            device_recovery_retries: 10,
+            capture_source: CaptureSource::default(),
+            record_session: false,
         }
     }
 }
 
+impl SessionConfig {
+    /// Check the values a saved configuration may not take
+    pub fn validate(&self) -> Result<(), String> {
+        if self.sample_rate != WHISPER_SAMPLE_RATE {
+            return Err(format!("sample_rate must be {} Hz, got {}", WHISPER_SAMPLE_RATE, self.sample_rate));
+        }
+        if !(MIN_INPUT_GAIN..=MAX_INPUT_GAIN).contains(&self.input_gain) {
+            return Err(format!("input_gain must be between {} and {}", MIN_INPUT_GAIN, MAX_INPUT_GAIN));
+        }
+        for (name, value) in [
+            ("silence_threshold", self.silence_threshold),
+            ("sfx_volume", self.sfx_volume),
+            ("music_volume", self.music_volume),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{} must be between 0 and 1, got {}", name, value));
+            }
+        }
+        if self.crossfade_duration_ms > constants::CROSSFADE_MAX_MS {
+            return Err(format!("crossfade_duration_ms must be at most {}", constants::CROSSFADE_MAX_MS));
+        }
+        Ok(())
+    }
+}
+
 /// Currently playing track info
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlayingTrack {
@@ -138,12 +326,15 @@ impl Default for AppState {
 
 /// Channel capacities for internal communication
 pub mod channels {
-    /// Audio buffer capacity (number of frames)
-    pub const AUDIO_BUFFER_CAPACITY: usize = 16000 * 60; // 1 minute at 16kHz
+    /// Audio buffer capacity (number of samples); older audio is overwritten
+    pub const AUDIO_BUFFER_CAPACITY: usize = 48000 * 2 * 120; // 2 minutes at 48kHz stereo
 
     /// Detection event queue capacity
     pub const DETECTION_QUEUE_CAPACITY: usize = 100;
 
+    /// Audio frames queued between capture and the detection pipeline
+    pub const AUDIO_FRAME_QUEUE_CAPACITY: usize = 64;
+
     /// Max transcription text length
     pub const MAX_TRANSCRIPTION_LENGTH: usize = 4096;
 }
@@ -173,8 +364,69 @@ pub mod constants {
     pub const CROSSFADE_QUICK_MS: u32 = 500;
     pub const CROSSFADE_MUSICAL_MS: u32 = 2000;
     pub const CROSSFADE_LONG_MS: u32 = 5000;
+    /// Longest crossfade a session configuration may ask for
+    pub const CROSSFADE_MAX_MS: u32 = 30000;
 
     /// Two-phase startup timeouts (ms)
     pub const UI_READY_TIMEOUT_MS: u64 = 3000;
     pub const DETECTION_READY_TIMEOUT_MS: u64 = 15000;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_config_json_round_trip() {
+        let config = SessionConfig {
+            enable_vad: false,
+            detection_mode: DetectionMode::Collaborative,
+            capture_source: CaptureSource::Both,
+            input_gain: 2.5,
+            ..SessionConfig::default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        let loaded: SessionConfig = serde_json::from_str(&json).unwrap();
+        assert!(!loaded.enable_vad);
+        assert_eq!(loaded.detection_mode, DetectionMode::Collaborative);
+        assert_eq!(loaded.capture_source, CaptureSource::Both);
+        assert_eq!(loaded.input_gain, 2.5);
+
+        // Saved before `record_session` existed
+        let loaded: SessionConfig = serde_json::from_str(r#"{"sample_rate": 48000}"#).unwrap();
+        assert_eq!(loaded.sample_rate, 48000);
+        assert!(!loaded.record_session);
+        assert_eq!(loaded.crossfade_duration_ms, 2000);
+
+        assert!(SessionConfig::default().validate().is_ok());
+        assert!(loaded.validate().unwrap_err().contains("sample_rate"));
+        let loud = SessionConfig { music_volume: 1.5, ..SessionConfig::default() };
+        assert!(loud.validate().unwrap_err().contains("music_volume"));
+    }
+
+    #[test]
+    fn test_app_mode_name_round_trip() {
+        for mode in [AppMode::ModeA, AppMode::ModeB] {
+            assert_eq!(AppMode::parse(&mode.to_string()), Some(mode));
+            assert_eq!(mode.toggled().toggled(), mode);
+        }
+        assert_eq!(AppMode::ModeB.detection_mode(), DetectionMode::Collaborative);
+        assert_eq!(AppMode::parse("mode_b"), None);
+    }
+
+    #[test]
+    fn test_setting_values_are_validated_and_round_trip() {
+        let gain = SettingKey::parse("input_gain").unwrap();
+        assert_eq!(gain.decode(&gain.encode(&Value::from(2.5)).unwrap()), Value::from(2.5));
+        assert!(gain.encode(&Value::from(10.0)).unwrap_err().contains("between"));
+        assert!(gain.encode(&Value::from("loud")).unwrap_err().contains("a number"));
+
+        let resume = SettingKey::ResumeLastTrack;
+        assert_eq!(resume.encode(&Value::Bool(true)).unwrap(), "true");
+        assert_eq!(resume.decode("true"), Value::Bool(true));
+
+        assert!(SettingKey::AppMode.encode(&Value::from("chaos")).is_err());
+        assert_eq!(SettingKey::AppMode.decode("garbage"), Value::from("autonomous"));
+        assert_eq!(SettingKey::parse("last_playback"), None);
+    }
+}
@@ -33,6 +33,7 @@ async function init() {
 
     // Set up event listeners
     setupEventListeners();
+    await listenForSessionEvents();
 
     // Get initial session status
     await checkSessionStatus();
@@ -66,7 +67,7 @@ async function loadAudioDevices() {
 
         log('info', `Found ${devices.length} audio device(s)`);
     } catch (error) {
-        log('error', `Failed to load devices: ${error}`);
+        log('error', `Failed to load devices: ${errorMessage(error)}`);
         // Fallback to default option
         elements.audioDeviceSelect.innerHTML = '<option value="default">Default Device</option>';
     }
@@ -87,6 +88,30 @@ function setupEventListeners() {
     elements.stopBtn.addEventListener('click', stopRecording);
 }
 
+// Follow the processing that runs after a session stops
+async function listenForSessionEvents() {
+    try {
+        await window.__TAURI__.event.listen('session://processing_progress', (event) => {
+            const { stage, percent } = event.payload;
+            elements.statusText.textContent = `Processing... ${stage} (${percent}%)`;
+        });
+
+        await window.__TAURI__.event.listen('session://complete', (event) => {
+            const status = event.payload;
+            state.isRecording = status.is_recording;
+
+            elements.resultsSection.classList.remove('hidden');
+            elements.transcriptionResult.textContent = status.transcription || '-';
+            elements.emotionResult.textContent = status.emotion || '-';
+
+            updateUIState('idle');
+            log('success', 'Recording stopped and processed');
+        });
+    } catch (error) {
+        log('error', `Failed to listen for session events: ${errorMessage(error)}`);
+    }
+}
+
 // Start recording session
 async function startRecording() {
     try {
@@ -102,7 +127,7 @@ async function startRecording() {
             log('error', `Failed to start: ${response.message}`);
         }
     } catch (error) {
-        log('error', `Error starting session: ${error}`);
+        log('error', `Error starting session: ${errorMessage(error)}`);
     }
 }
 
@@ -118,27 +143,14 @@ async function stopRecording() {
         state.isRecording = false;
 
         if (response.success) {
-            // Show results
-            elements.resultsSection.classList.remove('hidden');
-
-            // Parse message for results
-            const lines = response.message.split('\n');
-            lines.forEach(line => {
-                if (line.startsWith('Transcription:')) {
-                    elements.transcriptionResult.textContent = line.replace('Transcription: ', '');
-                } else if (line.startsWith('Emotion:')) {
-                    elements.emotionResult.textContent = line.replace('Emotion: ', '');
-                }
-            });
-
-            updateUIState('idle');
-            log('success', 'Recording stopped and processed');
+            // Results arrive with the session://complete event
+            log('info', response.message);
         } else {
             updateUIState('error');
             log('error', `Failed to stop: ${response.message}`);
         }
     } catch (error) {
-        log('error', `Error stopping session: ${error}`);
+        log('error', `Error stopping session: ${errorMessage(error)}`);
         updateUIState('error');
     }
 }
@@ -155,7 +167,7 @@ async function checkSessionStatus() {
             updateUIState('idle');
         }
     } catch (error) {
-        log('error', `Error checking status: ${error}`);
+        log('error', `Error checking status: ${errorMessage(error)}`);
     }
 }
 
@@ -194,6 +206,11 @@ function updateUIState(sessionState) {
     }
 }
 
+// Message of a rejected command: commands reject with { code, message, details }
+function errorMessage(error) {
+    return error && error.message ? error.message : String(error);
+}
+
 // Add log entry
 function log(level, message) {
     const entry = document.createElement('div');